@@ -0,0 +1,222 @@
+//! The daemon's D-Bus interface on Linux.
+//!
+//! The daemon claims `org.aishot.Capture` on the session bus and serves the
+//! `org.aishot.Capture` interface at `/org/aishot/Capture`, so GNOME and KDE
+//! shell extensions and scripts can drive it without the trigger socket:
+//!
+//! - `TriggerInteractive()`: capture the screen and open the overlay, like
+//!   the select hotkey.
+//! - `CaptureRegion(x, y, width, height, prompt) -> id`: ask about an area
+//!   of the desktop, in the pointer's coordinates, in the background. An
+//!   empty prompt picks one for what the area shows.
+//! - `AnalyzeLast(prompt) -> id`: ask about the most recent selection in the
+//!   history again; an empty prompt repeats its question.
+//!
+//! Answers arrive as `AnalysisCompleted(id, prompt, answer)` signals, and
+//! failures as `AnalysisFailed(id, error)`, carrying the id the call
+//! returned. The answers are added to the history like any other. Requests
+//! share the daemon's [`TaskScheduler`] with its hotkeys, so a burst of calls
+//! waits for a slot instead of all being sent at once.
+//!
+//! ```text
+//! gdbus call --session --dest org.aishot.Capture --object-path /org/aishot/Capture \
+//!     --method org.aishot.Capture.CaptureRegion 100 100 640 480 "What is this?"
+//! dbus-monitor --session "type='signal',interface='org.aishot.Capture'"
+//! ```
+
+use ai_shot_core::history::{HistoryEntry, HistoryStore};
+use ai_shot_core::hotkey::HotkeyAction;
+use ai_shot_core::scheduler::{self, TaskScheduler};
+use ai_shot_core::{analysis, AiShot, Analysis, Config};
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use zbus::fdo;
+use zbus::object_server::SignalEmitter;
+
+/// Well-known name the daemon claims on the session bus.
+const BUS_NAME: &str = "org.aishot.Capture";
+
+/// Path of the object serving the interface.
+const OBJECT_PATH: &str = "/org/aishot/Capture";
+
+/// Runs a hotkey action in the daemon, as the trigger socket does.
+type Trigger = Box<dyn Fn(HotkeyAction) -> Result<()> + Send + Sync>;
+
+/// The `org.aishot.Capture` interface.
+pub struct CaptureService {
+    app: Arc<Mutex<AiShot>>,
+    paused: Arc<AtomicBool>,
+    trigger: Trigger,
+    /// Id of the next background analysis
+    next_id: AtomicU32,
+}
+
+impl CaptureService {
+    /// Creates the interface for the daemon's state; `trigger` runs hotkey
+    /// actions and reports why one was refused.
+    pub fn new(
+        app: Arc<Mutex<AiShot>>,
+        paused: Arc<AtomicBool>,
+        trigger: impl Fn(HotkeyAction) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            app,
+            paused,
+            trigger: Box::new(trigger),
+            next_id: AtomicU32::new(1),
+        }
+    }
+
+    /// Returns the daemon's configuration, unless it is paused.
+    fn config(&self) -> Result<Config> {
+        if self.paused.load(Ordering::Relaxed) {
+            anyhow::bail!("Paused (resume with `ai-shot daemon resume`)");
+        }
+        let app = self.app.lock().ok().context("Daemon state is unavailable")?;
+        Ok(app.config().clone())
+    }
+
+    /// Asks about `image` on the runtime and signals the answer; returns the
+    /// id the signal carries.
+    fn spawn_analysis(
+        &self,
+        emitter: SignalEmitter<'_>,
+        config: Config,
+        image: DynamicImage,
+        prompt: Option<String>,
+    ) -> u32 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let emitter = emitter.into_owned();
+        tokio::spawn(async move {
+            let signaled = match ask(config, image, prompt).await {
+                Ok(analysis) => {
+                    println!("D-Bus request {} answered", id);
+                    Self::analysis_completed(&emitter, id, &analysis.prompt, &analysis.text).await
+                }
+                Err(e) => {
+                    eprintln!("❌ D-Bus request {} failed: {:#}", id, e);
+                    Self::analysis_failed(&emitter, id, &format!("{:#}", e)).await
+                }
+            };
+            if let Err(e) = signaled {
+                eprintln!("Warning: Failed to send the D-Bus signal: {}", e);
+            }
+        });
+        id
+    }
+}
+
+#[zbus::interface(name = "org.aishot.Capture")]
+impl CaptureService {
+    /// Captures the screen and opens the overlay, like the select hotkey.
+    fn trigger_interactive(&self) -> fdo::Result<()> {
+        (self.trigger)(HotkeyAction::Select).map_err(failed)
+    }
+
+    /// Asks about an area of the desktop in the background and returns the
+    /// id of the answer's signal.
+    async fn capture_region(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+        prompt: String,
+    ) -> fdo::Result<u32> {
+        let config = self.config().map_err(failed)?;
+        let image = self
+            .app
+            .lock()
+            .ok()
+            .context("Daemon state is unavailable")
+            .and_then(|app| Ok(app.capture_desktop_area(x, y, width, height)?))
+            .map_err(failed)?;
+        println!("D-Bus: asking about {}x{} pixels at {},{}...", width, height, x, y);
+        let prompt = Some(prompt).filter(|prompt| !prompt.trim().is_empty());
+        Ok(self.spawn_analysis(emitter, config, image, prompt))
+    }
+
+    /// Asks about the most recent selection in the history again and
+    /// returns the id of the answer's signal.
+    async fn analyze_last(
+        &self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+        prompt: String,
+    ) -> fdo::Result<u32> {
+        let config = self.config().map_err(failed)?;
+        let (entry, image) = last_selection().map_err(failed)?;
+        let prompt = if prompt.trim().is_empty() { entry.prompt } else { prompt };
+        println!("D-Bus: asking about the last selection again...");
+        Ok(self.spawn_analysis(emitter, config, image, Some(prompt)))
+    }
+
+    /// An answer to `CaptureRegion` or `AnalyzeLast` is complete.
+    #[zbus(signal)]
+    async fn analysis_completed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        prompt: &str,
+        answer: &str,
+    ) -> zbus::Result<()>;
+
+    /// A `CaptureRegion` or `AnalyzeLast` request failed.
+    #[zbus(signal)]
+    async fn analysis_failed(
+        emitter: &SignalEmitter<'_>,
+        id: u32,
+        error: &str,
+    ) -> zbus::Result<()>;
+}
+
+/// Claims [`BUS_NAME`] on the session bus and serves `service`, until the
+/// returned connection is dropped.
+///
+/// # Errors
+///
+/// Fails if there is no session bus or another process owns the name.
+pub async fn register(service: CaptureService) -> Result<zbus::Connection> {
+    zbus::connection::Builder::session()
+        .and_then(|builder| builder.name(BUS_NAME))
+        .and_then(|builder| builder.serve_at(OBJECT_PATH, service))
+        .context("Failed to set up the D-Bus interface")?
+        .build()
+        .await
+        .with_context(|| format!("Failed to claim {} on the session bus", BUS_NAME))
+}
+
+/// Returns the newest history entry and its image.
+fn last_selection() -> Result<(HistoryEntry, DynamicImage)> {
+    let mut store = HistoryStore::open_default().context("No data directory found")?;
+    let entry = store
+        .entries()?
+        .into_iter()
+        .next()
+        .context("The history is empty; ask about a selection first")?;
+    let image = store
+        .image(&entry.id)
+        .context("The image of the last selection is missing")?;
+    Ok((entry, (*image).clone()))
+}
+
+/// Asks about `image` and adds the answer to the history.
+async fn ask(mut config: Config, image: DynamicImage, prompt: Option<String>) -> Result<Analysis> {
+    crate::require_credentials(&mut config)?;
+    let crop = image.clone();
+    let analysis = TaskScheduler::shared()
+        .run(
+            scheduler::provider(&config),
+            analysis::analyze_region(&config, image, None, prompt.as_deref(), |_| {}),
+        )
+        .await
+        .context("Analysis failed")?;
+    crate::record_history(&config, &analysis, &crop);
+    Ok(analysis)
+}
+
+/// Turns an error into the `org.freedesktop.DBus.Error.Failed` reply.
+fn failed(error: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(format!("{:#}", error))
+}
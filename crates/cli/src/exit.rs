@@ -0,0 +1,75 @@
+//! Process exit codes.
+//!
+//! Scripts branch on these, so existing codes must not change meaning:
+//!
+//! | Code | Meaning                                              |
+//! |------|------------------------------------------------------|
+//! | 0    | Success                                              |
+//! | 1    | Any other failure                                    |
+//! | 2    | Invalid command line (reported by clap)              |
+//! | 3    | Configuration error (missing API key, bad setting)   |
+//! | 4    | Screen capture failed                                |
+//! | 5    | The API request failed, timed out, or was throttled  |
+//! | 6    | Cancelled: the overlay was closed without a question |
+
+use ai_shot_core::AppError;
+use std::fmt;
+use std::process::ExitCode;
+
+/// Kind of failure, as reported by the exit code.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Failure {
+    Other = 1,
+    Config = 3,
+    Capture = 4,
+    Api = 5,
+    Cancelled = 6,
+}
+
+impl Failure {
+    /// Classifies an error by the first cause that has a known kind.
+    pub fn of(error: &anyhow::Error) -> Self {
+        error
+            .chain()
+            .find_map(|cause| {
+                if cause.is::<Cancelled>() {
+                    return Some(Self::Cancelled);
+                }
+                cause.downcast_ref::<AppError>().and_then(Self::of_app_error)
+            })
+            .unwrap_or(Self::Other)
+    }
+
+    fn of_app_error(error: &AppError) -> Option<Self> {
+        match error {
+            AppError::Config(_) | AppError::MissingEnvVar(_) | AppError::Secrets(_) => {
+                Some(Self::Config)
+            }
+            AppError::ScreenCapture(_) | AppError::ScreenNotFound(_) => Some(Self::Capture),
+            AppError::GeminiApi(_)
+            | AppError::Timeout(_)
+            | AppError::OpenAi(_)
+            | AppError::RateLimited
+            | AppError::QueueFull(_) => Some(Self::Api),
+            _ => None,
+        }
+    }
+}
+
+impl From<Failure> for ExitCode {
+    fn from(failure: Failure) -> Self {
+        ExitCode::from(failure as u8)
+    }
+}
+
+/// The user closed the overlay without asking anything.
+#[derive(Debug)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -0,0 +1,144 @@
+//! Recognizing the daemon's hotkeys in global keyboard events.
+//!
+//! rdev reports individual key presses and releases; [`Matcher`] keeps track
+//! of the held modifiers and turns each press of another key into a
+//! [`Hotkey`] that can be compared with the configured one, and its release
+//! into the same [`Hotkey`] for actions that last while it is held.
+//!
+//! rdev only works on X11, Windows and macOS. Under Wayland the daemon
+//! registers its hotkeys with the desktop portal instead (see
+//! [`crate::portal`]); [`Backend::detect`] picks one.
+
+use ai_shot_core::hotkey::{Hotkey, Key, Modifiers};
+use rdev::EventType;
+use std::env;
+
+/// Where global hotkeys come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// Reading the keyboard with rdev (X11, Windows, macOS).
+    Rdev,
+    /// The GlobalShortcuts desktop portal (Wayland).
+    Portal,
+}
+
+impl Backend {
+    /// Picks the portal in Wayland sessions and rdev elsewhere.
+    ///
+    /// `AI_SHOT_HOTKEY_BACKEND=rdev` or `portal` overrides the choice, e.g.
+    /// to keep rdev on a Wayland desktop whose portal lacks global shortcuts.
+    pub fn detect() -> Self {
+        match env::var("AI_SHOT_HOTKEY_BACKEND").as_deref() {
+            Ok("rdev") => return Backend::Rdev,
+            Ok("portal") => return Backend::Portal,
+            Ok(other) => eprintln!(
+                "Warning: Unknown AI_SHOT_HOTKEY_BACKEND '{}', expected 'rdev' or 'portal'",
+                other
+            ),
+            Err(_) => {}
+        }
+
+        let wayland = env::var_os("WAYLAND_DISPLAY").is_some()
+            || env::var("XDG_SESSION_TYPE").is_ok_and(|session| session == "wayland");
+        if cfg!(target_os = "linux") && wayland {
+            Backend::Portal
+        } else {
+            Backend::Rdev
+        }
+    }
+}
+
+/// A key combination going down or coming back up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyEvent {
+    /// The key went down with these modifiers held.
+    Pressed(Hotkey),
+    /// The key was released; the modifiers are those held when it went down,
+    /// whether or not they were let go first.
+    Released(Hotkey),
+}
+
+/// Tracks held modifiers across keyboard events.
+#[derive(Default)]
+pub struct Matcher {
+    held: Modifiers,
+    /// Key currently held down, to ignore auto-repeated presses, and the
+    /// combination it was pressed in
+    down: Option<(rdev::Key, Option<Hotkey>)>,
+}
+
+impl Matcher {
+    /// Feeds an event; returns the combination when a key other than a
+    /// modifier goes down or comes back up.
+    pub fn feed(&mut self, event: &EventType) -> Option<KeyEvent> {
+        match *event {
+            EventType::KeyPress(key) => {
+                if self.set_modifier(key, true) || self.down.is_some_and(|(down, _)| down == key) {
+                    return None;
+                }
+                let hotkey = convert(key).map(|key| Hotkey {
+                    modifiers: self.held,
+                    key,
+                });
+                self.down = Some((key, hotkey));
+                hotkey.map(KeyEvent::Pressed)
+            }
+            EventType::KeyRelease(key) => {
+                self.set_modifier(key, false);
+                let (_, hotkey) = self.down.take_if(|(down, _)| *down == key)?;
+                hotkey.map(KeyEvent::Released)
+            }
+            _ => None,
+        }
+    }
+
+    /// Records a modifier going down or up; returns whether `key` is one.
+    fn set_modifier(&mut self, key: rdev::Key, held: bool) -> bool {
+        use rdev::Key::*;
+
+        let modifier = match key {
+            ControlLeft | ControlRight => &mut self.held.ctrl,
+            Alt | AltGr => &mut self.held.alt,
+            ShiftLeft | ShiftRight => &mut self.held.shift,
+            MetaLeft | MetaRight => &mut self.held.super_key,
+            _ => return false,
+        };
+        *modifier = held;
+        true
+    }
+}
+
+/// Converts an rdev key, or returns `None` for keys hotkeys can't use.
+fn convert(key: rdev::Key) -> Option<Key> {
+    use rdev::Key::*;
+
+    let named = match key {
+        PrintScreen => Key::PrintScreen,
+        Space => Key::Space,
+        Return => Key::Enter,
+        Tab => Key::Tab,
+        Escape => Key::Escape,
+        Insert => Key::Insert,
+        Delete => Key::Delete,
+        Home => Key::Home,
+        End => Key::End,
+        PageUp => Key::PageUp,
+        PageDown => Key::PageDown,
+        UpArrow => Key::Up,
+        DownArrow => Key::Down,
+        LeftArrow => Key::Left,
+        RightArrow => Key::Right,
+        // Letters, digits and function keys are named KeyA, Num1 and F1
+        other => {
+            let name = format!("{:?}", other);
+            if let Some(letter) = name.strip_prefix("Key") {
+                return letter.chars().next().map(Key::Char);
+            }
+            if let Some(digit) = name.strip_prefix("Num").filter(|d| d.len() == 1) {
+                return digit.chars().next().map(Key::Char);
+            }
+            return name.strip_prefix('F').and_then(|n| n.parse().ok()).map(Key::F);
+        }
+    };
+    Some(named)
+}
@@ -0,0 +1,255 @@
+//! Talking to the running daemon over a local socket, and handing its
+//! captures to the overlay process.
+//!
+//! The daemon listens on [`paths::daemon_socket`] (a Unix socket, or a
+//! loopback TCP port written to that file on Windows). Binding it doubles as
+//! the single-instance guard: a second daemon finds the first one answering
+//! and refuses to start instead of fighting over the hotkeys.
+//!
+//! Each connection sends one [`Request`] line and reads back `ok`,
+//! `ok: <message>` or `error: <message>`. `ai-shot trigger [action]` sends
+//! an action's name, so window managers can bind their own keys to the
+//! daemon instead of relying on its keyboard listener; `ai-shot daemon
+//! pause` and `resume` send `pause` and `resume`:
+//!
+//! ```text
+//! # sway
+//! bindsym Print exec ai-shot trigger select
+//! ```
+//!
+//! The overlay runs in a process of its own, so a crash or a closed window
+//! never takes the daemon down. The daemon writes each capture to the new
+//! process's stdin ([`send_capture`]) rather than to a file that other
+//! users or programs could read.
+
+use ai_shot_core::hotkey::HotkeyAction;
+use ai_shot_core::paths;
+use anyhow::{Context, Result};
+use image::{DynamicImage, RgbaImage};
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+
+/// How long either side waits for the other's line.
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a client asks the daemon for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Request {
+    /// Act as if the action's hotkey was pressed.
+    Action(HotkeyAction),
+    /// Ignore hotkeys and triggers until resumed.
+    Pause,
+    /// Handle hotkeys and triggers again.
+    Resume,
+    /// Report whether the daemon is paused.
+    Status,
+}
+
+impl FromStr for Request {
+    type Err = ai_shot_core::AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.trim() {
+            "pause" => Ok(Request::Pause),
+            "resume" => Ok(Request::Resume),
+            "status" => Ok(Request::Status),
+            action => action.parse().map(Request::Action),
+        }
+    }
+}
+
+impl fmt::Display for Request {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Request::Action(action) => action.fmt(f),
+            Request::Pause => f.write_str("pause"),
+            Request::Resume => f.write_str("resume"),
+            Request::Status => f.write_str("status"),
+        }
+    }
+}
+
+/// The daemon's end of the socket.
+pub struct Server {
+    listener: platform::Listener,
+}
+
+impl Server {
+    /// Claims the socket for this daemon.
+    ///
+    /// # Errors
+    ///
+    /// Fails if another daemon already answers on the socket, or the socket
+    /// can't be created.
+    pub fn bind() -> Result<Self> {
+        let path = socket_path()?;
+        if platform::connect(&path).is_ok() {
+            anyhow::bail!(
+                "Another ai-shot daemon is already running; use `ai-shot trigger` to reach it"
+            );
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        // Whatever is left at the path belongs to a daemon that is gone
+        let listener = platform::bind(&path)
+            .with_context(|| format!("Failed to listen on {}", path.display()))?;
+        Ok(Self { listener })
+    }
+
+    /// Answers requests on a background thread with `handle`, which returns
+    /// a message for the client (empty for none) or why it failed.
+    pub fn serve(self, handle: impl Fn(Request) -> Result<String> + Send + 'static) -> Result<()> {
+        thread::Builder::new()
+            .name("ai-shot-ipc".to_string())
+            .spawn(move || {
+                for stream in self.listener.incoming() {
+                    let Ok(mut stream) = stream else {
+                        continue;
+                    };
+                    let _ = stream.set_read_timeout(Some(TIMEOUT));
+                    let mut line = String::new();
+                    let answered = BufReader::new(&mut stream)
+                        .read_line(&mut line)
+                        .map_err(anyhow::Error::from)
+                        .and_then(|_| handle(line.parse()?));
+                    let reply = match answered {
+                        Ok(message) if message.is_empty() => "ok".to_string(),
+                        Ok(message) => format!("ok: {}", message),
+                        Err(e) => format!("error: {:#}", e),
+                    };
+                    let _ = writeln!(stream, "{}", reply);
+                }
+            })
+            .context("Failed to start the trigger listener")?;
+        Ok(())
+    }
+}
+
+/// Asks the running daemon to perform `action`.
+///
+/// # Errors
+///
+/// Fails if no daemon is running or it rejects the action (e.g. because
+/// it is paused).
+pub fn trigger(action: HotkeyAction) -> Result<()> {
+    send(Request::Action(action)).map(|_| ())
+}
+
+/// Sends `request` to the running daemon and returns its message (empty
+/// for none).
+///
+/// # Errors
+///
+/// Fails if no daemon is running or the request fails.
+pub fn send(request: Request) -> Result<String> {
+    let path = socket_path()?;
+    let mut stream = platform::connect(&path)
+        .context("No ai-shot daemon is running; start one with `ai-shot daemon`")?;
+    let _ = stream.set_read_timeout(Some(TIMEOUT));
+    writeln!(stream, "{}", request).context("Failed to reach the daemon")?;
+
+    let mut reply = String::new();
+    BufReader::new(&mut stream)
+        .read_line(&mut reply)
+        .context("The daemon did not answer")?;
+    match reply.trim() {
+        "ok" => Ok(String::new()),
+        reply => match reply.strip_prefix("ok: ") {
+            Some(message) => Ok(message.to_string()),
+            None => anyhow::bail!(
+                "The daemon refused: {}",
+                reply.strip_prefix("error: ").unwrap_or(reply)
+            ),
+        },
+    }
+}
+
+/// Writes a capture for [`receive_capture`]: its width and height as
+/// little-endian `u32`s, then the raw RGBA pixels.
+///
+/// Raw pixels skip encoding, which would take longer than the copy.
+pub fn send_capture(mut writer: impl Write, image: &RgbaImage) -> std::io::Result<()> {
+    writer.write_all(&image.width().to_le_bytes())?;
+    writer.write_all(&image.height().to_le_bytes())?;
+    writer.write_all(image.as_raw())?;
+    writer.flush()
+}
+
+/// Reads a capture written by [`send_capture`].
+///
+/// # Errors
+///
+/// Fails if the stream ends early or the size is implausible.
+pub fn receive_capture(mut reader: impl Read) -> Result<DynamicImage> {
+    let mut header = [0u8; 8];
+    reader
+        .read_exact(&mut header)
+        .context("Failed to read the capture size")?;
+    let [w0, w1, w2, w3, h0, h1, h2, h3] = header;
+    let width = u32::from_le_bytes([w0, w1, w2, w3]);
+    let height = u32::from_le_bytes([h0, h1, h2, h3]);
+
+    let len = (width as usize)
+        .checked_mul(height as usize)
+        .and_then(|pixels| pixels.checked_mul(4))
+        .context("Capture size is too large")?;
+    let mut pixels = vec![0; len];
+    reader
+        .read_exact(&mut pixels)
+        .context("Failed to read the capture")?;
+    let image = RgbaImage::from_raw(width, height, pixels).context("Capture size is invalid")?;
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+fn socket_path() -> Result<PathBuf> {
+    paths::daemon_socket().context("No runtime or cache directory found for this user")
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::io;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    pub type Listener = UnixListener;
+
+    pub fn connect(path: &Path) -> io::Result<UnixStream> {
+        UnixStream::connect(path)
+    }
+
+    pub fn bind(path: &Path) -> io::Result<UnixListener> {
+        let _ = std::fs::remove_file(path);
+        UnixListener::bind(path)
+    }
+}
+
+#[cfg(not(unix))]
+mod platform {
+    use std::fs;
+    use std::io;
+    use std::net::{Ipv4Addr, TcpListener, TcpStream};
+    use std::path::Path;
+
+    pub type Listener = TcpListener;
+
+    pub fn connect(path: &Path) -> io::Result<TcpStream> {
+        let port: u16 = fs::read_to_string(path)?
+            .trim()
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        TcpStream::connect((Ipv4Addr::LOCALHOST, port))
+    }
+
+    pub fn bind(path: &Path) -> io::Result<TcpListener> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        fs::write(path, listener.local_addr()?.port().to_string())?;
+        Ok(listener)
+    }
+}
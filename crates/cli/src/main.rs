@@ -3,66 +3,818 @@
 //! A command-line tool for capturing screenshots and analyzing them with
 //! Google's Gemini AI.
 
-use ai_shot_core::{init, AiShot, Config};
+use ai_shot_core::archive::Archive;
+use ai_shot_core::history::{HistoryEntry, HistoryQuery, HistoryStore};
+use ai_shot_core::hotkey::HotkeyAction;
+use ai_shot_core::ocr::{self, OcrEngine};
+use ai_shot_core::privacy;
+use ai_shot_core::profiles::Profiles;
+use ai_shot_core::rules::{self, Rule};
+use ai_shot_core::scheduler::{self, SchedulerConfig, TaskScheduler};
+use ai_shot_core::ui::AVAILABLE_MODELS;
+use ai_shot_core::usage::{self, BudgetStatus, UsageLog};
+use ai_shot_core::{
+    analysis, init, models, paths, AiShot, Analysis, AppError, Config, DaemonMonitor,
+    ImageProcessor, MonitorSelector, PixelRegion, SystemPromptPreset,
+};
 use anyhow::{Context, Result};
-use clap::Parser;
-use std::process::Command;
+use clap::builder::{PossibleValue, TypedValueParser};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use image::{DynamicImage, ImageFormat};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitCode};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(target_os = "linux")]
+mod dbus;
+mod exit;
+mod hotkeys;
+mod ipc;
+mod mcp;
+mod notify;
+#[cfg(target_os = "linux")]
+mod portal;
+mod service;
+
+use exit::{Cancelled, Failure};
+
+/// Set by `--quiet` to silence status messages.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Prints a status message to stderr unless `--quiet` is given.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        }
+    };
+}
 
 /// AI-powered screenshot analysis tool using Google Gemini.
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    after_help = "Exit codes: 0 success, 1 other failure, 2 invalid arguments, 3 configuration, \
+                  4 screen capture, 5 API request, 6 cancelled"
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     /// Prompt to send to Gemini (optional, uses default if empty)
     #[arg(trailing_var_arg = true)]
     prompt: Vec<String>,
 
-    /// Override the model defined in .env
-    #[arg(short, long)]
+    /// Override the model defined in .env (abbreviations like "flash-lite"
+    /// are expanded; see `ai-shot models`)
+    #[arg(short, long, global = true, value_parser = ModelParser, hide_possible_values = true)]
     model: Option<String>,
 
+    /// Profile from profiles.toml to use (overrides AI_SHOT_PROFILE and the
+    /// file's default; see `ai-shot config profiles`)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+
+    /// Keep settings, history and logs in this directory instead of the
+    /// user's config and data directories (portable mode; also
+    /// AI_SHOT_DATA_DIR)
+    #[arg(long, global = true, value_name = "DIR")]
+    data_dir: Option<PathBuf>,
+
+    /// Built-in system prompt preset (code-reviewer, translator, ui-ux-critic,
+    /// accessibility-auditor, data-extractor)
+    #[arg(long, global = true)]
+    preset: Option<SystemPromptPreset>,
+
+    /// Language for answers, regardless of the screenshot's language
+    /// (e.g. "de", "German", "Español")
+    #[arg(long, global = true)]
+    lang: Option<String>,
+
     /// Copy the result to clipboard automatically
-    #[arg(short, long, default_value_t = false)]
+    #[arg(short, long, default_value_t = false, global = true)]
     copy: bool,
 
-    /// Select which monitor to capture (0-indexed)
-    #[arg(long, default_value_t = 0)]
-    monitor: usize,
+    /// Read a custom system prompt from a file (combined with --preset)
+    #[arg(long, global = true)]
+    system_prompt_file: Option<PathBuf>,
+
+    /// Save each answered crop to this path template (strftime fields like
+    /// %Y%m%d-%H%M%S, plus {monitor} and {model})
+    #[arg(long, global = true, value_name = "TEMPLATE")]
+    save_image: Option<String>,
+
+    /// Save each answer to this path template (same fields as --save-image)
+    #[arg(long, global = true, value_name = "TEMPLATE")]
+    save_answer: Option<String>,
+
+    /// Only print results and errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log progress to stderr (-vv for debug, -vvv for trace output)
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Monitor to capture: a 0-based index, "cursor" for the one under the
+    /// pointer, or an output name like "DP-2"
+    #[arg(long, default_value_t = MonitorSelector::default())]
+    monitor: MonitorSelector,
 
     /// List available monitors and exit
     #[arg(long)]
     list_monitors: bool,
 
-    /// Run in background mode, listening for Ctrl+Alt+X hotkey
+    /// Run in background mode, listening for the hotkey (Ctrl+Alt+X unless
+    /// set with `hotkey` in the settings or config.toml, or AI_SHOT_HOTKEY)
+    /// and those bound to actions in the [hotkeys] table of config.toml
     #[arg(long)]
     daemon: bool,
 
     /// Load image from path instead of capturing (internal use)
     #[arg(long)]
     image_path: Option<String>,
+
+    /// Read a raw capture from stdin, as the daemon sends it (internal use)
+    #[arg(long, hide = true, conflicts_with = "image_path")]
+    image_pipe: bool,
+
+    /// Monitor the --image-path or --image-pipe image shows, or "all" for
+    /// the whole desktop (internal use)
+    #[arg(long, hide = true)]
+    image_monitor: Option<DaemonMonitor>,
+}
+
+/// Commands that run without the interactive overlay.
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Capture a screen region, ask Gemini about it, and print the answer
+    /// (no GUI needed)
+    Analyze(AnalyzeArgs),
+
+    /// Save a screenshot of a monitor or region to a file (no AI involved)
+    Shoot(ShootArgs),
+
+    /// Select a region on screen and print it as "X,Y WxH" (like slurp),
+    /// for use with --region or other tools
+    Select(SelectArgs),
+
+    /// Ask the same question about every image in a directory and write
+    /// the answers as JSON lines
+    Batch(BatchArgs),
+
+    /// Print the text in a screen region or image (locally with Tesseract,
+    /// or with the vision model)
+    Ocr(OcrArgs),
+
+    /// Compare two screenshots, highlight what changed, and optionally ask
+    /// the model about it
+    Diff(DiffArgs),
+
+    /// Show or change the saved settings (model, API key, system prompt,
+    /// hotkey, ...)
+    #[command(subcommand)]
+    Config(ConfigCommand),
+
+    /// List, search, show or export earlier answers
+    #[command(subcommand)]
+    History(HistoryCommand),
+
+    /// Show requests, tokens and estimated spend per model, and the monthly
+    /// budget
+    Stats {
+        /// Only count requests this recent: e.g. 24h, 7d, 4w, or "month" for
+        /// the current calendar month (all of them if omitted)
+        #[arg(long)]
+        since: Option<Since>,
+
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+
+    /// List the models available with your credentials and their capabilities
+    Models {
+        /// Only show models whose name contains this text
+        filter: Option<String>,
+    },
+
+    /// Run the hotkey daemon, or manage it as a service started at login
+    Daemon {
+        #[command(subcommand)]
+        action: Option<DaemonAction>,
+    },
+
+    /// Make the running daemon act as if a hotkey was pressed, so window
+    /// managers can bind their own keys (select, active-window, ocr,
+    /// repeat-prompt)
+    Trigger {
+        /// Action to perform
+        #[arg(default_value_t = HotkeyAction::Select)]
+        action: HotkeyAction,
+    },
+
+    /// Serve capture_screen, capture_region and analyze_screenshot to agents
+    /// (Claude Desktop, IDE assistants) as a Model Context Protocol server on
+    /// stdin and stdout
+    Mcp,
+
+    /// Print a shell completion script (e.g. `ai-shot completions bash`)
+    Completions {
+        /// Shell to generate the script for
+        shell: clap_complete::Shell,
+    },
+}
+
+/// Subcommands of `daemon`.
+#[derive(Subcommand, Debug)]
+enum DaemonAction {
+    /// Register the daemon as a user service (systemd, launchd, or Task
+    /// Scheduler) that starts at login, passing on --profile, --copy and
+    /// --save-* flags
+    Install,
+    /// Stop the service and remove it
+    Uninstall,
+    /// Show whether the service is installed and running, and whether the
+    /// daemon is paused
+    Status,
+    /// Make the running daemon ignore hotkeys and triggers ("do not
+    /// disturb") until resumed
+    Pause,
+    /// Make a paused daemon handle hotkeys and triggers again
+    Resume,
+}
+
+/// Parses `--model`, expanding abbreviated names of known models.
+///
+/// Known models are also offered for shell completion; other names (new
+/// models, gateway model strings) are passed through unchanged.
+#[derive(Clone)]
+struct ModelParser;
+
+impl TypedValueParser for ModelParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &std::ffi::OsStr,
+    ) -> std::result::Result<String, clap::Error> {
+        let name = clap::builder::NonEmptyStringValueParser::new().parse_ref(cmd, arg, value)?;
+        Ok(models::closest_known(&name).map_or(name, str::to_string))
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(AVAILABLE_MODELS.iter().copied().map(PossibleValue::new)))
+    }
+}
+
+/// Subcommands of `config`.
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Change a setting
+    Set {
+        /// Setting name as in settings.json (e.g. model, api_key, system_prompt, hotkey)
+        key: String,
+        /// New value; JSON for non-text settings (e.g. true, 14)
+        value: String,
+    },
+    /// Print a setting
+    Get {
+        /// Setting name as in settings.json
+        key: String,
+    },
+    /// Print all settings (the API key is masked)
+    List,
+    /// Print the path of the settings file
+    Path,
+    /// List the profiles in profiles.toml
+    Profiles,
+    /// Print the effective configuration and where each value comes from
+    /// (config.toml, saved settings, environment, profile, or flags)
+    Show,
+    /// Check the configuration for problems such as a missing API key, an
+    /// unknown model or an unreachable proxy
+    Check,
+}
+
+/// Subcommands of `history`.
+#[derive(Subcommand, Debug)]
+enum HistoryCommand {
+    /// List the newest answers with their ids
+    List {
+        /// Most answers to list
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+        /// Output format: one line per answer, or a JSON array
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Find answers whose question, answer or model contain every word of
+    /// the query, ignoring case (e.g. `ai-shot history search regex --days 7`)
+    Search {
+        /// Words to look for
+        #[arg(required = true)]
+        query: Vec<String>,
+        #[command(flatten)]
+        filter: HistoryFilter,
+        /// Most answers to list
+        #[arg(long, short = 'n', default_value_t = 20)]
+        limit: usize,
+        /// Output format: one line per answer, or a JSON array
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Print an answer with its question, model, time and image path
+    Show {
+        /// Id of the answer, as printed by `list` and `search`
+        id: String,
+        /// Output format: readable text, or a JSON object including the thoughts
+        #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+        output: OutputFormat,
+    },
+    /// Write answers, oldest first, as JSON lines or a Markdown document
+    Export {
+        /// Only export answers containing every word of this text
+        #[arg(long)]
+        search: Option<String>,
+        #[command(flatten)]
+        filter: HistoryFilter,
+        /// File to write instead of stdout
+        #[arg(long, short)]
+        out: Option<PathBuf>,
+        /// Format of the export
+        #[arg(long, value_enum, default_value_t = HistoryExportFormat::Jsonl)]
+        format: HistoryExportFormat,
+    },
+}
+
+/// Options narrowing the answers `history search` and `history export` find.
+#[derive(clap::Args, Debug)]
+struct HistoryFilter {
+    /// Only answers by models whose name contains this text
+    #[arg(long)]
+    model: Option<String>,
+    /// Only answers from the last N days
+    #[arg(long)]
+    days: Option<u64>,
+}
+
+impl HistoryFilter {
+    /// Returns the query for these options and the search text.
+    fn query(&self, text: Option<String>) -> HistoryQuery {
+        let since = self.days.map(|days| {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+            now.as_secs().saturating_sub(days * 24 * 60 * 60)
+        });
+        HistoryQuery {
+            text,
+            model: self.model.clone(),
+            since,
+            ..HistoryQuery::default()
+        }
+    }
+}
+
+/// Format of `history export`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum HistoryExportFormat {
+    /// One JSON object per line, as `history show --output json` prints them
+    Jsonl,
+    /// A Markdown document with the questions, answers and linked images
+    Markdown,
+}
+
+/// How far back `stats` counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Since {
+    /// Requests this many seconds ago or later
+    Ago(u64),
+    /// Requests since the start of the current month
+    Month,
+}
+
+impl Since {
+    /// Returns the Unix timestamp the period starts at.
+    fn start(self) -> u64 {
+        match self {
+            Self::Ago(seconds) => {
+                let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+                now.as_secs().saturating_sub(seconds)
+            }
+            Self::Month => usage::month_start(),
+        }
+    }
+}
+
+impl std::str::FromStr for Since {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim().to_ascii_lowercase();
+        if s == "month" {
+            return Ok(Self::Month);
+        }
+        let unit_at = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(unit_at);
+        let seconds = match unit {
+            "h" => 60 * 60,
+            "d" | "" => 24 * 60 * 60,
+            "w" => 7 * 24 * 60 * 60,
+            _ => return Err(format!("Unknown unit '{}' (expected h, d, w or month)", unit)),
+        };
+        let number: u64 = number
+            .parse()
+            .map_err(|_| format!("Expected a period like 24h, 7d or 4w, not '{}'", s))?;
+        Ok(Self::Ago(number.saturating_mul(seconds)))
+    }
+}
+
+/// Arguments of the `analyze` command.
+#[derive(clap::Args, Debug)]
+struct AnalyzeArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    #[command(flatten)]
+    prompt: PromptArgs,
+
+    /// Output format: the answer as it streams in, or one JSON object at the end
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Capture and prepare the request, then print what would be sent instead of sending it
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Don't add the crop and the answer to the history
+    #[arg(long)]
+    no_history: bool,
+}
+
+/// Arguments of the `ocr` command.
+#[derive(clap::Args, Debug)]
+struct OcrArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+
+    /// How to extract the text
+    #[arg(long, value_enum, default_value_t = TextEngine::Auto)]
+    engine: TextEngine,
+}
+
+/// How `ocr` extracts text.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TextEngine {
+    /// Tesseract if installed, otherwise the vision model
+    Auto,
+    /// Tesseract only; nothing leaves the machine
+    Local,
+    /// The configured vision model
+    Model,
+}
+
+/// Where a headless command gets its image.
+#[derive(clap::Args, Debug)]
+struct SourceArgs {
+    /// Region to use as X,Y,WxH (or as printed by `select`) in monitor (or
+    /// image) pixels (all of it if omitted)
+    #[arg(long)]
+    region: Option<PixelRegion>,
+
+    /// Monitor to capture: index, "cursor", or output name
+    #[arg(long, default_value_t = MonitorSelector::default())]
+    monitor: MonitorSelector,
+
+    /// Use an image file instead of capturing the screen
+    #[arg(long, conflicts_with_all = ["stdin", "monitor"])]
+    file: Option<PathBuf>,
+
+    /// Read the image (e.g. PNG bytes) from stdin instead of capturing
+    #[arg(long, conflicts_with = "monitor")]
+    stdin: bool,
+
+    /// Use the image on the clipboard (e.g. copied by another screenshot
+    /// tool) instead of capturing
+    #[arg(long, conflicts_with_all = ["file", "stdin", "monitor"])]
+    clipboard: bool,
+
+    /// Convert to grayscale, stretch the contrast and sharpen before sending,
+    /// for screenshots of small or faint text and code
+    #[arg(long)]
+    sharpen_text: bool,
+
+    /// Find email addresses, API keys and card numbers with Tesseract and
+    /// mask them before sending
+    #[arg(long)]
+    mask_sensitive: bool,
+}
+
+impl SourceArgs {
+    /// Applies the preprocessing chosen with the flags to a loaded image.
+    ///
+    /// # Errors
+    ///
+    /// Fails if sensitive text should be masked but Tesseract can't run.
+    fn preprocess(&self, mut image: DynamicImage) -> Result<DynamicImage> {
+        if self.mask_sensitive {
+            let findings = privacy::scan(&OcrEngine::from_env(), &image)
+                .context("Failed to check the image for sensitive text")?;
+            if !findings.is_empty() {
+                privacy::mask(&mut image, &findings);
+                status!("Masked {}", privacy::summary(&findings));
+            }
+        }
+        if self.sharpen_text {
+            image = ImageProcessor::enhance_text(&image);
+        }
+        Ok(image)
+    }
+}
+
+/// Where the question of a headless request comes from.
+#[derive(clap::Args, Debug)]
+struct PromptArgs {
+    /// Question to ask (a default for the detected content if omitted)
+    #[arg(short, long)]
+    prompt: Option<String>,
+
+    /// Read the question from a file
+    #[arg(long, conflicts_with = "prompt")]
+    prompt_file: Option<PathBuf>,
+
+    /// Attach the text currently on the clipboard to the question
+    #[arg(long)]
+    append_clipboard: bool,
+}
+
+impl PromptArgs {
+    /// Builds the question, or `None` to use the default for the content.
+    fn resolve(&self) -> Result<Option<String>> {
+        let mut prompt = match &self.prompt_file {
+            Some(path) => Some(read_prompt_file(path)?),
+            None => self.prompt.clone(),
+        };
+        if self.append_clipboard {
+            let text = arboard::Clipboard::new()
+                .and_then(|mut clipboard| clipboard.get_text())
+                .context("Failed to read text from the clipboard")?;
+            let attachment = format!("Clipboard text:\n```\n{}\n```", text.trim_end());
+            prompt = Some(match prompt {
+                Some(prompt) => format!("{}\n\n{}", prompt, attachment),
+                None => attachment,
+            });
+        }
+        Ok(prompt)
+    }
+}
+
+/// Reads a prompt from a file, dropping the trailing newline.
+fn read_prompt_file(path: &Path) -> Result<String> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read prompt file: {}", path.display()))?;
+    Ok(text.trim_end().to_string())
+}
+
+/// Arguments of the `shoot` command.
+#[derive(clap::Args, Debug)]
+struct ShootArgs {
+    /// Monitor to capture: index, "cursor", or output name
+    #[arg(long, default_value_t = MonitorSelector::default())]
+    monitor: MonitorSelector,
+
+    /// File to write the screenshot to
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Region to keep as X,Y,WxH in monitor pixels (whole monitor if omitted)
+    #[arg(long)]
+    region: Option<PixelRegion>,
+
+    /// Image format (inferred from the --out extension if omitted, else PNG)
+    #[arg(long, value_enum)]
+    format: Option<ShotFormat>,
+
+    /// Leave out the time/host/note stamp enabled in the settings
+    #[arg(long)]
+    no_stamp: bool,
+}
+
+/// Arguments of the `select` command.
+#[derive(clap::Args, Debug)]
+struct SelectArgs {
+    /// Monitor to select on: index, "cursor", or output name
+    #[arg(long, default_value_t = MonitorSelector::default())]
+    monitor: MonitorSelector,
+
+    /// Print desktop coordinates in logical pixels (as slurp does) instead of
+    /// monitor pixels
+    #[arg(long)]
+    desktop: bool,
+}
+
+/// Arguments of the `batch` command.
+#[derive(clap::Args, Debug)]
+struct BatchArgs {
+    /// Directory with the images (PNG, JPEG, WebP) to analyze
+    #[arg(long)]
+    dir: PathBuf,
+
+    #[command(flatten)]
+    prompt: PromptArgs,
+
+    /// JSONL file to write, one result object per image
+    #[arg(long)]
+    out: PathBuf,
+
+    /// Number of requests in flight at once
+    #[arg(long, default_value_t = 2, value_parser = clap::value_parser!(u32).range(1..))]
+    concurrency: u32,
+
+    /// Maximum requests started per minute (unlimited if omitted)
+    #[arg(long)]
+    rate_limit: Option<f64>,
+}
+
+/// Arguments of the `diff` command.
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// Image before the change
+    before: PathBuf,
+
+    /// Image after the change
+    after: PathBuf,
+
+    /// File to write the highlighted differences to (format inferred from
+    /// the extension, else PNG)
+    #[arg(long)]
+    out: Option<PathBuf>,
+
+    /// Ask the model what changed, sending both images and the highlighted
+    /// differences (implied by --prompt)
+    #[arg(long)]
+    ask: bool,
+
+    #[command(flatten)]
+    prompt: PromptArgs,
+}
+
+/// Question `diff --ask` sends with the before, after and difference images.
+const DIFF_PROMPT: &str = "The first image was taken before a change and the second one after \
+    it; the third shows the second faded, with the pixels that changed in red. \
+    Describe what changed.";
+
+/// File format written by `shoot`.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ShotFormat {
+    /// Lossless PNG
+    Png,
+    /// JPEG (smaller, lossy, no transparency)
+    #[value(alias = "jpeg")]
+    Jpg,
+    /// Lossless WebP
+    Webp,
+}
+
+impl ShotFormat {
+    /// Infers the format from a file extension.
+    fn from_path(path: &Path) -> Option<Self> {
+        match ImageFormat::from_path(path).ok()? {
+            ImageFormat::Png => Some(Self::Png),
+            ImageFormat::Jpeg => Some(Self::Jpg),
+            ImageFormat::WebP => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// The matching `image` crate format.
+    fn image_format(self) -> ImageFormat {
+        match self {
+            Self::Png => ImageFormat::Png,
+            Self::Jpg => ImageFormat::Jpeg,
+            Self::Webp => ImageFormat::WebP,
+        }
+    }
+}
+
+/// How `analyze` prints its result.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// The answer text, streamed as it arrives
+    Text,
+    /// A JSON object with the answer, usage, timing and finish reason
+    Json,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     // Initialize environment and parse arguments
     init();
     let args = Args::parse();
+    QUIET.store(args.quiet, Ordering::Relaxed);
+    if let Some(dir) = &args.data_dir {
+        paths::set_data_dir(dir);
+    }
+    let console_level = match args.verbose {
+        0 => None,
+        1 => Some(tracing::Level::INFO),
+        2 => Some(tracing::Level::DEBUG),
+        _ => Some(tracing::Level::TRACE),
+    };
+    // The daemon keeps a log of every run, since it mostly runs unattended
+    let daemon = args.daemon || matches!(args.command, Some(Commands::Daemon { action: None }));
+    let _log_guard = if daemon {
+        ai_shot_core::logging::init_run_log("daemon", console_level)
+    } else {
+        ai_shot_core::logging::init_with_console(console_level)
+    };
 
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let failure = Failure::of(&e);
+            if failure != Failure::Cancelled {
+                eprintln!("Error: {:?}", e);
+            }
+            failure.into()
+        }
+    }
+}
+
+/// Runs the command line; errors decide the exit code.
+async fn run(args: Args) -> Result<()> {
     // Handle daemon mode separately (blocking operation)
-    if args.daemon {
-        return run_daemon();
+    match &args.command {
+        Some(Commands::Daemon { action: None }) => {
+            return run_daemon(build_config(&args)?, overlay_args(&args));
+        }
+        Some(Commands::Daemon { action: Some(action) }) => {
+            return match action {
+                DaemonAction::Install => service::install(&overlay_args(&args)),
+                DaemonAction::Uninstall => service::uninstall(),
+                DaemonAction::Status => {
+                    service::status()?;
+                    if let Ok(state) = ipc::send(ipc::Request::Status) {
+                        println!("Daemon: {}", state);
+                    }
+                    Ok(())
+                }
+                DaemonAction::Pause => ipc::send(ipc::Request::Pause).map(|_| ()),
+                DaemonAction::Resume => ipc::send(ipc::Request::Resume).map(|_| ()),
+            };
+        }
+        Some(Commands::Trigger { action }) => return ipc::trigger(*action),
+        _ if args.daemon => return run_daemon(build_config(&args)?, overlay_args(&args)),
+        _ => {}
     }
 
     // Build configuration, applying CLI overrides
     let config = build_config(&args)?;
 
+    match &args.command {
+        Some(Commands::Analyze(analyze)) => return run_analyze(config, analyze).await,
+        Some(Commands::Shoot(shoot)) => return run_shoot(config, shoot),
+        Some(Commands::Select(select)) => return run_select(config, select),
+        Some(Commands::Batch(batch)) => return run_batch(config, batch).await,
+        Some(Commands::Ocr(ocr)) => return run_ocr(config, ocr).await,
+        Some(Commands::Diff(diff)) => return run_diff(config, diff).await,
+        Some(Commands::Config(command)) => return run_config(&config, command),
+        Some(Commands::History(command)) => return run_history(command),
+        Some(Commands::Stats { since, output }) => return run_stats(&config, *since, *output),
+        Some(Commands::Mcp) => return mcp::serve(config).await,
+        Some(Commands::Models { filter }) => return run_models(config, filter.as_deref()).await,
+        Some(Commands::Completions { shell }) => {
+            clap_complete::generate(*shell, &mut Args::command(), "ai-shot", &mut std::io::stdout());
+            return Ok(());
+        }
+        Some(Commands::Daemon { .. } | Commands::Trigger { .. }) | None => {}
+    }
+
     // Create the application instance
     let app = AiShot::with_config(config).context("Failed to initialize ai-shot")?;
 
-    // Handle --image-path (Internal fast-path)
-    if let Some(path) = args.image_path {
-        let img = image::open(&path)
-            .with_context(|| format!("Failed to load image from path: {}", path))?;
-        app.run_interactive_with_image(img)?;
+    // Handle --image-path and --image-pipe (Internal fast-path)
+    let image = match &args.image_path {
+        Some(path) => Some(
+            image::open(path)
+                .with_context(|| format!("Failed to load image from path: {}", path))?,
+        ),
+        None if args.image_pipe => Some(ipc::receive_capture(std::io::stdin().lock())?),
+        None => None,
+    };
+    if let Some(img) = image {
+        let monitor = match &args.image_monitor {
+            Some(DaemonMonitor::All) => None,
+            Some(DaemonMonitor::Monitor(selector)) => Some(app.resolve_monitor(selector)?),
+            None => Some(0),
+        };
+        app.run_interactive_with_image_on(img, monitor)?.ok_or(Cancelled)?;
         return Ok(());
     }
 
@@ -76,8 +828,10 @@ async fn main() -> Result<()> {
     }
 
     // Run the interactive selection UI
-    app.run_interactive(args.monitor)
-        .context("Failed to run interactive mode. Try --list-monitors to check available indices")?;
+    let monitor = app.resolve_monitor(&args.monitor)?;
+    app.run_interactive(monitor)
+        .context("Failed to run interactive mode. Try --list-monitors to check available indices")?
+        .ok_or(Cancelled)?;
 
     Ok(())
 }
@@ -86,50 +840,1154 @@ async fn main() -> Result<()> {
 fn build_config(args: &Args) -> Result<Config> {
     let mut builder = Config::builder();
 
+    if let Some(ref profile) = args.profile {
+        builder = builder.with_profile(profile);
+    }
+
     if let Some(ref model) = args.model {
         builder = builder.with_model(model);
     }
 
+    if let Some(preset) = args.preset {
+        builder = builder.with_preset(preset);
+    }
+
+    if let Some(ref lang) = args.lang {
+        builder = builder.with_response_language(lang);
+    }
+
+    if let Some(ref path) = args.system_prompt_file {
+        builder = builder.with_system_prompt(read_prompt_file(path)?);
+    }
+
+    builder = builder.with_copy_answers(args.copy).with_archive(Archive {
+        image: args.save_image.clone(),
+        answer: args.save_answer.clone(),
+    });
+
     builder.build().context(
         "Failed to load configuration.",
     )
 }
 
-/// Runs the background daemon that listens for the Ctrl+Alt+X hotkey.
-fn run_daemon() -> Result<()> {
-    use rdev::{listen, EventType, Key};
-    use std::sync::Arc;
+/// Captures a region, asks about it, and streams the answer to stdout.
+async fn run_analyze(mut config: Config, args: &AnalyzeArgs) -> Result<()> {
+    if args.dry_run {
+        return run_dry_run(&config, args);
+    }
+
+    let copy = config.copy_answers;
+    require_credentials(&mut config)?;
+
+    let streaming = args.output == OutputFormat::Text;
+    let mut stdout = std::io::stdout();
+    let on_text = |text: &str| {
+        if streaming {
+            let _ = stdout.write_all(text.as_bytes());
+            let _ = stdout.flush();
+        }
+    };
+    let prompt = args.prompt.resolve()?;
+    let prompt = prompt.as_deref();
+
+    let (crop, monitor) = load_source(&config, &args.source)?;
+    let archive = config.archive.for_monitor(&monitor);
+    let kept_crop = archive.is_enabled().then(|| crop.clone());
+    let crop = args.source.preprocess(crop)?;
+    let history_crop = (!args.no_history).then(|| crop.clone());
+    let analysis = analysis::analyze_region(&config, crop, None, prompt, on_text)
+        .await
+        .context("Analysis failed")?;
+
+    match args.output {
+        OutputFormat::Text if !analysis.text.ends_with('\n') => println!(),
+        OutputFormat::Text => {}
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&analysis_json(&analysis))?),
+    }
+
+    // Confirmations go to stderr so stdout stays just the answer
+    if copy {
+        copy_to_clipboard(&analysis.text)?;
+        status!("Answer copied to clipboard");
+    }
+    if let Some(crop) = kept_crop {
+        let crop = ai_shot_core::ui::Settings::load(&config.model_name).stamp().apply(&crop);
+        for path in archive.save(&crop, &analysis.text, &analysis.model)? {
+            status!("Saved {}", path.display());
+        }
+    }
+    if let Some(crop) = history_crop {
+        record_history(&config, &analysis, &crop);
+    }
+    warn_about_budget(&config);
+    Ok(())
+}
+
+/// Warns once the monthly budget is nearly or fully spent.
+fn warn_about_budget(config: &Config) {
+    match usage::budget_status(&config.budget) {
+        Ok(status) => {
+            if let Some(warning) = status.warning() {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+        Err(e) => eprintln!("Warning: Failed to read the usage log: {}", e),
+    }
+}
+
+/// Adds an answered analysis to the history and applies the retention
+/// policy, warning if either fails.
+fn record_history(config: &Config, analysis: &Analysis, crop: &DynamicImage) {
+    if analysis.text.trim().is_empty() {
+        return;
+    }
+    let Some(mut store) = HistoryStore::open_default() else {
+        return;
+    };
+    let entry = HistoryEntry::new(&analysis.prompt, &analysis.model, &analysis.text)
+        .with_thoughts(&analysis.thoughts)
+        .with_usage(analysis.input_tokens, analysis.output_tokens);
+    let result = store
+        .record(&entry, crop)
+        .and_then(|()| store.apply_retention(&config.history));
+    if let Err(e) = result {
+        eprintln!("Warning: Failed to save history: {}", e);
+    }
+}
+
+/// Prints what `analyze` would send, without needing an API key.
+fn run_dry_run(config: &Config, args: &AnalyzeArgs) -> Result<()> {
+    let prompt = args.prompt.resolve()?;
+    let (crop, _) = load_source(config, &args.source)?;
+    let crop = args.source.preprocess(crop)?;
+    let preview = analysis::preview_request(config, &crop, prompt.as_deref())
+        .context("Failed to prepare the request")?;
+
+    match args.output {
+        OutputFormat::Text => {
+            println!("Model:            {}", preview.model);
+            let mut image = format!("{}x{}", preview.width, preview.height);
+            if let Some((width, height)) = preview.downscaled_from {
+                image.push_str(&format!(" (downscaled from {}x{})", width, height));
+            }
+            if let Some((width, height)) = preview.upscaled_from {
+                image.push_str(&format!(" (enlarged from {}x{})", width, height));
+            }
+            println!("Image:            {}", image);
+            println!(
+                "Encoded size:     {:.1} MB ({})",
+                preview.encoded_bytes as f64 / (1024.0 * 1024.0),
+                config.image_encoding
+            );
+            println!("Estimated tokens: ~{}", preview.estimated_tokens);
+            println!("Prompt:           {}", preview.prompt);
+            if !preview.system_prompt.is_empty() {
+                println!("System prompt:    {}", preview.system_prompt);
+            }
+        }
+        OutputFormat::Json => {
+            let json = serde_json::json!({
+                "model": preview.model,
+                "prompt": preview.prompt,
+                "systemPrompt": preview.system_prompt,
+                "image": {
+                    "width": preview.width,
+                    "height": preview.height,
+                    "encodedBytes": preview.encoded_bytes,
+                    "downscaledFrom": preview.downscaled_from.map(|(width, height)| {
+                        serde_json::json!({ "width": width, "height": height })
+                    }),
+                    "upscaledFrom": preview.upscaled_from.map(|(width, height)| {
+                        serde_json::json!({ "width": width, "height": height })
+                    }),
+                    "mimeType": preview.mime_type,
+                },
+                "estimatedTokens": preview.estimated_tokens,
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+    Ok(())
+}
+
+/// Loads the image a headless command works on, cropped to `--region`.
+///
+/// Returns the image and the `{monitor}` label for archive templates.
+/// Files and stdin skip capture, so no display is needed at all.
+fn load_source(config: &Config, source: &SourceArgs) -> Result<(DynamicImage, String)> {
+    let (image, monitor) = match read_input_image(source)? {
+        Some(image) if source.stdin => (image, "stdin".to_string()),
+        Some(image) if source.clipboard => (image, "clipboard".to_string()),
+        Some(image) => (image, "file".to_string()),
+        None => {
+            let app = AiShot::with_config(config.clone()).context("Failed to initialize ai-shot")?;
+            let monitor = app.resolve_monitor(&source.monitor)?;
+            let image = app.capture(monitor).context("Failed to capture screen")?;
+            (image, monitor.to_string())
+        }
+    };
+    let image = match source.region {
+        Some(region) => ImageProcessor::crop_region(&image, region).context("Invalid --region")?,
+        None => image,
+    };
+    Ok((image, monitor))
+}
+
+/// Extracts the text of a region or image and prints it.
+async fn run_ocr(config: Config, args: &OcrArgs) -> Result<()> {
+    let (image, _) = load_source(&config, &args.source)?;
+    let image = args.source.preprocess(image)?;
+    let copy = config.copy_answers;
+    let text = extract_text(config, &image, args.engine).await?;
+
+    println!("{}", text);
+    if copy {
+        copy_to_clipboard(&text)?;
+        status!("Text copied to clipboard");
+    }
+    Ok(())
+}
+
+/// Extracts the text of `image` with `engine`, without trailing whitespace.
+async fn extract_text(
+    mut config: Config,
+    image: &DynamicImage,
+    engine: TextEngine,
+) -> Result<String> {
+    let ocr_engine = OcrEngine::from_env();
+    let local = match engine {
+        TextEngine::Local => true,
+        TextEngine::Model => false,
+        TextEngine::Auto => ocr_engine.is_available(),
+    };
+    let text = if local {
+        ocr_engine.extract_text(image).context("Local OCR failed")?
+    } else {
+        if engine == TextEngine::Auto {
+            status!("Tesseract not found, extracting text with {}", config.model_name);
+        }
+        require_credentials(&mut config)?;
+        // A preset or answer language would reword the transcription
+        config.preset = None;
+        config.system_prompt = None;
+        config.response_language = None;
+        analysis::analyze_image(&config, image, ocr::MODEL_EXTRACTION_PROMPT, |_| {})
+            .await
+            .context("Text extraction failed")?
+            .text
+    };
+    Ok(text.trim_end().to_string())
+}
+
+/// Puts `text` on the clipboard.
+fn copy_to_clipboard(text: &str) -> Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .context("Failed to copy to the clipboard")
+}
+
+/// Makes sure a backend is configured for headless requests.
+///
+/// Falls back to the key saved in the overlay's settings.
+fn require_credentials(config: &mut Config) -> Result<()> {
+    if config.gemini_api_key.is_empty() && config.vertex.is_none() && config.openai.is_none() {
+        config.gemini_api_key = ai_shot_core::ui::Settings::load(&config.model_name).api_key;
+    }
+    if config.gemini_api_key.is_empty() && config.vertex.is_none() && config.openai.is_none() {
+        return Err(AppError::config(
+            "No API key configured. Set GEMINI_API_KEY or save one in the overlay settings",
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Analyzes every image in a directory and writes one JSON line per image.
+///
+/// Results are written as they complete, so the file's order may differ
+/// from the directory's. Failed images get an `error` entry and do not stop
+/// the batch.
+///
+/// Requests go through the shared scheduler, with `--concurrency` as the
+/// provider's limit and `--rate-limit` spacing their starts.
+async fn run_batch(mut config: Config, args: &BatchArgs) -> Result<()> {
+    use futures::stream::{self, StreamExt};
+    use std::time::Duration;
+
+    require_credentials(&mut config)?;
+
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&args.dir)
+        .with_context(|| format!("Failed to read directory: {}", args.dir.display()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && ShotFormat::from_path(path).is_some())
+        .collect();
+    paths.sort();
+    if paths.is_empty() {
+        anyhow::bail!("No PNG, JPEG or WebP images in {}", args.dir.display());
+    }
+
+    let provider = scheduler::provider(&config);
+    let mut limits =
+        SchedulerConfig::default().with_provider_limit(provider, args.concurrency as usize);
+    if let Some(per_minute) = args.rate_limit {
+        let interval = Some(per_minute)
+            .filter(|per_minute| *per_minute > 0.0)
+            .and_then(|per_minute| Duration::try_from_secs_f64(60.0 / per_minute).ok())
+            .filter(|interval| *interval <= scheduler::MAX_INTERVAL)
+            .context("--rate-limit must be at least one request a day (0.0007 per minute)")?;
+        limits = limits.with_provider_interval(provider, interval);
+    }
+    // Running requests no longer count against the queue
+    let in_flight = args.concurrency as usize + limits.max_queue_depth;
+    TaskScheduler::configure_shared(limits);
+    let shared = &TaskScheduler::shared();
+
+    let mut out = std::fs::File::create(&args.out)
+        .with_context(|| format!("Failed to create {}", args.out.display()))?;
+
+    // The scheduler decides when requests start; images are only loaded
+    // once theirs does, and no more are submitted than it runs and queues
+    let config = &config;
+    let prompt = args.prompt.resolve()?;
+    let prompt = prompt.as_deref();
+    let mut results = stream::iter(&paths)
+        .map(|path| async move {
+            let result = shared
+                .run(provider, async {
+                    let image = image::open(path).context("Failed to load image")?;
+                    let analysis =
+                        analysis::analyze_region(config, image, None, prompt, |_| {}).await?;
+                    Ok::<_, anyhow::Error>(analysis)
+                })
+                .await;
+            (path, result)
+        })
+        .buffer_unordered(in_flight);
+
+    let mut failed = 0;
+    while let Some((path, result)) = results.next().await {
+        let line = match result {
+            Ok(analysis) => {
+                let mut line = analysis_json(&analysis);
+                line["file"] = path.display().to_string().into();
+                line
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("❌ {}: {:#}", path.display(), e);
+                serde_json::json!({ "file": path.display().to_string(), "error": format!("{:#}", e) })
+            }
+        };
+        writeln!(out, "{}", line).context("Failed to write results")?;
+        out.flush().context("Failed to write results")?;
+    }
+
+    status!(
+        "Analyzed {} images ({} failed), results in {}",
+        paths.len(),
+        failed,
+        args.out.display()
+    );
+    if failed == paths.len() {
+        anyhow::bail!("Every image failed");
+    }
+    Ok(())
+}
+
+/// Shows or changes the settings shared with the overlay.
+fn run_config(config: &Config, command: &ConfigCommand) -> Result<()> {
+    use ai_shot_core::ui::Settings;
+
+    let path = Settings::path().context("No config directory found for this user")?;
+    let mut settings = Settings::load(&config.model_name);
+    match command {
+        ConfigCommand::Set { key, value } => {
+            settings.set(key, value)?;
+            settings
+                .save()
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        ConfigCommand::Get { key } => {
+            let value = settings
+                .get(key)
+                .with_context(|| format!("Unknown setting '{}'", key))?;
+            println!("{}", value);
+        }
+        ConfigCommand::List => {
+            for (key, value) in settings.entries() {
+                let value = if key == "api_key" && !value.is_empty() {
+                    mask_secret(&value)
+                } else {
+                    value
+                };
+                println!("{} = {}", key, value);
+            }
+        }
+        ConfigCommand::Path => println!("{}", path.display()),
+        ConfigCommand::Show => show_config(&config.with_settings(&settings).build()?),
+        ConfigCommand::Check => check_config(&config.with_settings(&settings).build()?)?,
+        ConfigCommand::Profiles => {
+            let profiles = Profiles::load()?;
+            if profiles.is_empty()
+                && let Some(path) = Profiles::path()
+            {
+                status!("No profiles defined in {}", path.display());
+            }
+            for name in profiles.names() {
+                let active = config.profile.as_deref() == Some(name);
+                println!("{}{}", if active { "* " } else { "  " }, name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lists, searches, shows or exports the history.
+fn run_history(command: &HistoryCommand) -> Result<()> {
+    let store = HistoryStore::open_default().context("No data directory found for this user")?;
+    match command {
+        HistoryCommand::List { limit, output } => {
+            let query = HistoryQuery {
+                limit: Some(*limit),
+                ..HistoryQuery::default()
+            };
+            print_history(&store, &store.query(&query)?, *output)?;
+        }
+        HistoryCommand::Search {
+            query,
+            filter,
+            limit,
+            output,
+        } => {
+            let query = HistoryQuery {
+                limit: Some(*limit),
+                ..filter.query(Some(query.join(" ")))
+            };
+            let entries = store.query(&query)?;
+            if entries.is_empty() {
+                status!("No answers match");
+            }
+            print_history(&store, &entries, *output)?;
+        }
+        HistoryCommand::Show { id, output } => {
+            let entry = store
+                .entries()?
+                .into_iter()
+                .find(|entry| entry.id == *id)
+                .with_context(|| format!("No answer with id '{}' in the history", id))?;
+            match output {
+                OutputFormat::Text => {
+                    println!("Id:       {}", entry.id);
+                    println!("Time:     {}", entry.time());
+                    println!("Model:    {}", entry.model);
+                    if let Some(usage) = entry.usage {
+                        println!(
+                            "Tokens:   {} in, {} out",
+                            usage.input_tokens, usage.output_tokens
+                        );
+                    }
+                    let image = store.image_path(&entry.id);
+                    if image.exists() {
+                        println!("Image:    {}", image.display());
+                    }
+                    println!("Prompt:   {}", entry.prompt);
+                    println!();
+                    println!("{}", entry.answer.trim_end());
+                }
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string_pretty(&history_json(&store, &entry))?);
+                }
+            }
+        }
+        HistoryCommand::Export {
+            search,
+            filter,
+            out,
+            format,
+        } => {
+            let mut entries = store.query(&filter.query(search.clone()))?;
+            entries.reverse();
+            let text = match format {
+                HistoryExportFormat::Jsonl => {
+                    let mut text = String::new();
+                    for entry in &entries {
+                        text.push_str(&serde_json::to_string(&history_json(&store, entry))?);
+                        text.push('\n');
+                    }
+                    text
+                }
+                HistoryExportFormat::Markdown => history_markdown(&store, &entries),
+            };
+            match out {
+                Some(path) => {
+                    std::fs::write(path, text)
+                        .with_context(|| format!("Failed to write {}", path.display()))?;
+                    status!("Exported {} answers to {}", entries.len(), path.display());
+                }
+                None => print!("{}", text),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the requests, tokens and estimated spend per model, and how much
+/// of the monthly budget is spent.
+fn run_stats(config: &Config, since: Option<Since>, output: OutputFormat) -> Result<()> {
+    let log = UsageLog::open_default().context("No data directory found for this user")?;
+    let start = since.map(Since::start);
+    let models = log.summary(start.unwrap_or(0))?;
+    let budget = log.budget_status(&config.budget)?;
+    let total_cost: f64 = models.iter().filter_map(|model| model.cost).sum();
+
+    match output {
+        OutputFormat::Text => {
+            if models.is_empty() {
+                status!("No requests recorded in this period");
+            } else {
+                let row = |model: &str, requests: u64, input: u64, output: u64, cost: &str| {
+                    println!(
+                        "{:<32} {:>8} {:>13} {:>13} {:>10}",
+                        model, requests, input, output, cost
+                    );
+                };
+                println!(
+                    "{:<32} {:>8} {:>13} {:>13} {:>10}",
+                    "Model", "Requests", "Input tokens", "Output tokens", "Est. cost"
+                );
+                for model in &models {
+                    let cost = model.cost.map_or("?".to_string(), |cost| format!("${:.2}", cost));
+                    row(
+                        &model.model,
+                        model.requests,
+                        model.input_tokens,
+                        model.output_tokens,
+                        &cost,
+                    );
+                }
+                if models.len() > 1 {
+                    row(
+                        "Total",
+                        models.iter().map(|model| model.requests).sum(),
+                        models.iter().map(|model| model.input_tokens).sum(),
+                        models.iter().map(|model| model.output_tokens).sum(),
+                        &format!("${:.2}", total_cost),
+                    );
+                }
+                if models.iter().any(|model| model.cost.is_none()) {
+                    status!("Models marked ? have no known price and are left out of the total");
+                }
+            }
+            if let Some((spent, limit)) = budget.spent() {
+                println!();
+                println!(
+                    "Monthly budget: ${:.2} of ${:.2} spent ({:.0}%){}",
+                    spent,
+                    limit,
+                    spent / limit * 100.0,
+                    if config.budget.hard_limit { ", hard limit" } else { "" }
+                );
+            }
+            if let Some(warning) = budget.warning() {
+                eprintln!("Warning: {}", warning);
+            }
+        }
+        OutputFormat::Json => {
+            let json = serde_json::json!({
+                "since": start,
+                "models": models
+                    .iter()
+                    .map(|model| {
+                        serde_json::json!({
+                            "model": model.model,
+                            "requests": model.requests,
+                            "inputTokens": model.input_tokens,
+                            "outputTokens": model.output_tokens,
+                            "estimatedCost": model.cost,
+                        })
+                    })
+                    .collect::<Vec<_>>(),
+                "estimatedCost": total_cost,
+                "budget": budget.spent().map(|(spent, limit)| {
+                    serde_json::json!({
+                        "monthly": limit,
+                        "spent": spent,
+                        "hardLimit": config.budget.hard_limit,
+                        "exceeded": matches!(budget, BudgetStatus::Exceeded { .. }),
+                    })
+                }),
+            });
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+    }
+    Ok(())
+}
+
+/// Prints history entries as one line each or as a JSON array.
+fn print_history(
+    store: &HistoryStore,
+    entries: &[HistoryEntry],
+    output: OutputFormat,
+) -> Result<()> {
+    match output {
+        OutputFormat::Text => {
+            for entry in entries {
+                let prompt = entry.prompt.lines().next().unwrap_or_default();
+                let prompt = match prompt.char_indices().nth(60) {
+                    Some((end, _)) => format!("{}…", &prompt[..end]),
+                    None => prompt.to_string(),
+                };
+                println!("{}  {}  {:<24}  {}", entry.id, entry.time(), entry.model, prompt);
+            }
+        }
+        OutputFormat::Json => {
+            let entries: Vec<_> = entries.iter().map(|entry| history_json(store, entry)).collect();
+            println!("{}", serde_json::to_string_pretty(&entries)?);
+        }
+    }
+    Ok(())
+}
+
+/// Builds the JSON object for a history entry, in the style of `analyze --output json`.
+fn history_json(store: &HistoryStore, entry: &HistoryEntry) -> serde_json::Value {
+    let image = store.image_path(&entry.id);
+    serde_json::json!({
+        "id": entry.id,
+        "createdAt": entry.created_at,
+        "model": entry.model,
+        "prompt": entry.prompt,
+        "answer": entry.answer,
+        "thoughts": entry.thoughts,
+        "usage": entry.usage.map(|usage| serde_json::json!({
+            "inputTokens": usage.input_tokens,
+            "outputTokens": usage.output_tokens,
+        })),
+        "image": image.exists().then(|| image.display().to_string()),
+    })
+}
+
+/// Renders history entries as a Markdown document linking their images.
+fn history_markdown(store: &HistoryStore, entries: &[HistoryEntry]) -> String {
+    let mut text = String::from("# AI Shot history\n");
+    for entry in entries {
+        text.push_str(&format!("\n## {}\n\n", entry.prompt.trim()));
+        text.push_str(&format!("*{} · {}*\n\n", entry.time(), entry.model));
+        let image = store.image_path(&entry.id);
+        if image.exists() {
+            text.push_str(&format!("![Selection](<{}>)\n\n", image.display()));
+        }
+        text.push_str(entry.answer.trim());
+        text.push('\n');
+    }
+    text
+}
+
+/// Prints the effective configuration with the source of each value.
+fn show_config(config: &Config) {
+    let text = |value: Option<&str>| value.unwrap_or_default().to_string();
+    let seconds = |duration: Option<std::time::Duration>| {
+        duration.map_or_else(|| "off".to_string(), |d| format!("{}s", d.as_secs()))
+    };
+    let api_key = match config.gemini_api_key.as_str() {
+        "" => String::new(),
+        key => mask_secret(key),
+    };
+    let vertex = config
+        .vertex
+        .as_ref()
+        .map(|vertex| format!("{} ({})", vertex.project, vertex.location));
+    let hotkeys: Vec<String> = config
+        .hotkeys
+        .iter()
+        .map(|(hotkey, action)| format!("{} = {}", hotkey, action))
+        .collect();
+    let timeouts = &config.timeouts;
+    let network = &config.network;
+    let history = &config.history;
+    let plugins: Vec<&str> = config.plugins.iter().map(|plugin| plugin.name.as_str()).collect();
+    let rules: Vec<String> = config.rules.iter().map(Rule::label).collect();
+
+    let rows = [
+        ("model", config.model_name.clone()),
+        ("api_key", api_key),
+        ("vertex", vertex.unwrap_or_default()),
+        ("openai", text(config.openai.as_ref().map(|openai| openai.base_url.as_str()))),
+        ("preset", config.preset.map(|preset| preset.to_string()).unwrap_or_default()),
+        ("system_prompt", text(config.system_prompt.as_deref())),
+        ("response_language", text(config.response_language.as_deref())),
+        ("overlay_mode", format!("{:?}", config.overlay_mode).to_lowercase()),
+        ("hotkey", config.hotkey.to_string()),
+        ("hotkeys", hotkeys.join(", ")),
+        ("daemon_monitor", config.daemon_monitor.to_string()),
+        ("image_encoding", config.image_encoding.to_string()),
+        (
+            "max_image_pixels",
+            config.max_image_pixels.map(|pixels| pixels.to_string()).unwrap_or_default(),
+        ),
+        (
+            "upscale_min_edge",
+            config.upscale_min_edge.map(|edge| edge.to_string()).unwrap_or_default(),
+        ),
+        ("upscale_filter", config.upscale_filter.to_string()),
+        ("color_profile", config.color_profile.to_string()),
+        ("timeouts.connect", seconds(Some(timeouts.connect))),
+        ("timeouts.read", seconds(Some(timeouts.read))),
+        ("timeouts.deadline", seconds(timeouts.deadline)),
+        ("timeouts.keepalive", seconds(timeouts.keepalive)),
+        ("network.proxy", text(network.proxy.as_deref())),
+        ("network.no_proxy", text(network.no_proxy.as_deref())),
+        (
+            "network.ca_bundle",
+            network.ca_bundle.as_ref().map(|path| path.display().to_string()).unwrap_or_default(),
+        ),
+        (
+            "history.max_age_days",
+            history
+                .max_age
+                .map(|age| (age.as_secs() / (24 * 60 * 60)).to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "history.max_entries",
+            history.max_entries.map(|entries| entries.to_string()).unwrap_or_default(),
+        ),
+        ("plugins", plugins.join(", ")),
+        ("rules", rules.join(", ")),
+        (
+            "upload",
+            config
+                .upload
+                .as_ref()
+                .map(|upload| upload.destination.name().to_string())
+                .unwrap_or_default(),
+        ),
+        (
+            "usage.monthly_budget",
+            config.budget.monthly.map(|budget| format!("{:.2}", budget)).unwrap_or_default(),
+        ),
+        ("usage.hard_limit", config.budget.hard_limit.to_string()),
+    ];
+
+    if let Some(path) = Config::file_path() {
+        status!("Config file: {}", path.display());
+    }
+    for (key, value) in rows {
+        println!("{} = {}  ({})", key, value, config.source(key));
+    }
+}
+
+/// Prints each problem found in the configuration with a hint on fixing it.
+///
+/// Fails if any problem would make requests fail.
+fn check_config(config: &Config) -> Result<()> {
+    use ai_shot_core::diagnostics::Severity;
+
+    let diagnostics = config.validate();
+    if diagnostics.is_empty() {
+        status!("No problems found");
+        return Ok(());
+    }
+    for diagnostic in &diagnostics {
+        println!("{}: {}: {}", diagnostic.severity, diagnostic.key, diagnostic.message);
+        if let Some(hint) = &diagnostic.hint {
+            println!("  hint: {}", hint);
+        }
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        return Err(AppError::config(format!(
+            "Found {} configuration error{}",
+            errors,
+            if errors == 1 { "" } else { "s" }
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Prints the provider's models as a table of capabilities.
+async fn run_models(mut config: Config, filter: Option<&str>) -> Result<()> {
+    require_credentials(&mut config)?;
+    let mut models = models::list_models(&config)
+        .await
+        .context("Failed to list models")?;
+    if let Some(filter) = filter {
+        let filter = filter.to_lowercase();
+        models.retain(|model| model.name.to_lowercase().contains(&filter));
+    }
+
+    let flag = |value: Option<bool>| match value {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "?",
+    };
+    let width = models.iter().map(|model| model.name.len()).max().unwrap_or(0).max(5);
+    println!("{:width$}  VISION  THINKING  CONTEXT", "MODEL");
+    for model in &models {
+        let context = model
+            .context_length
+            .map_or_else(|| "?".to_string(), |tokens| tokens.to_string());
+        println!(
+            "{:width$}  {:6}  {:8}  {}",
+            model.name,
+            flag(model.vision),
+            flag(model.thinking),
+            context
+        );
+    }
+    Ok(())
+}
+
+/// Hides all but the last four characters of a secret.
+fn mask_secret(secret: &str) -> String {
+    let tail = secret
+        .char_indices()
+        .rev()
+        .nth(3)
+        .map_or(secret, |(start, _)| &secret[start..]);
+    format!("****{}", tail)
+}
+
+/// Captures a monitor (optionally cropped) and saves it to a file.
+fn run_shoot(config: Config, args: &ShootArgs) -> Result<()> {
+    let format = args
+        .format
+        .or_else(|| ShotFormat::from_path(&args.out))
+        .unwrap_or(ShotFormat::Png);
+    let stamp = ai_shot_core::ui::Settings::load(&config.model_name).stamp();
+
+    let app = AiShot::with_config(config).context("Failed to initialize ai-shot")?;
+    let monitor = app.resolve_monitor(&args.monitor)?;
+    let mut image = app.capture(monitor).context("Failed to capture screen")?;
+    if let Some(region) = args.region {
+        image = ImageProcessor::crop_region(&image, region).context("Invalid --region")?;
+    }
+    if !args.no_stamp {
+        image = stamp.apply(&image);
+    }
+
+    ImageProcessor::save(&image, &args.out, format.image_format())?;
+    status!("Saved {}x{} screenshot to {}", image.width(), image.height(), args.out.display());
+    Ok(())
+}
+
+/// Compares two images, and asks the model what changed if requested.
+async fn run_diff(mut config: Config, args: &DiffArgs) -> Result<()> {
+    let open = |path: &Path| {
+        image::open(path)
+            .with_context(|| format!("Failed to load image from path: {}", path.display()))
+    };
+    let before = open(&args.before)?;
+    let after = open(&args.after)?;
+    let diff = ImageProcessor::diff(&before, &after);
+
+    if let Some(out) = &args.out {
+        let format = ShotFormat::from_path(out).unwrap_or(ShotFormat::Png);
+        ImageProcessor::save(&diff.image, out, format.image_format())?;
+        status!("Saved differences to {}", out.display());
+    }
+
+    let prompt = args.prompt.resolve()?;
+    if !args.ask && prompt.is_none() {
+        print!("{:.2}% of pixels changed", diff.changed_percent());
+        match diff.changed_region {
+            Some(region) => println!(", within {}", region),
+            None => println!(),
+        }
+        return Ok(());
+    }
+
+    status!("{:.2}% of pixels changed", diff.changed_percent());
+    if diff.changed_pixels == 0 {
+        return Ok(());
+    }
+    require_credentials(&mut config)?;
+    let mut stdout = std::io::stdout();
+    let on_text = |text: &str| {
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+    };
+    let images = [before, after, diff.image];
+    let prompt = prompt.as_deref().unwrap_or(DIFF_PROMPT);
+    let analysis = analysis::analyze_images(&config, &images, prompt, on_text)
+        .await
+        .context("Analysis failed")?;
+    if !analysis.text.ends_with('\n') {
+        println!();
+    }
+    Ok(())
+}
+
+/// Lets the user pick a region and prints its geometry.
+fn run_select(config: Config, args: &SelectArgs) -> Result<()> {
+    let app = AiShot::with_config(config).context("Failed to initialize ai-shot")?;
+    let monitor = app.resolve_monitor(&args.monitor)?;
+    let region = app.pick_region(monitor)?.ok_or(Cancelled)?;
+
+    if args.desktop {
+        let screen = app.screen_geometry(monitor)?;
+        let logical = |pixels: u32| (pixels as f32 / screen.scale_factor).round() as i64;
+        println!(
+            "{},{} {}x{}",
+            screen.x as i64 + logical(region.x),
+            screen.y as i64 + logical(region.y),
+            logical(region.width),
+            logical(region.height)
+        );
+    } else {
+        println!("{},{} {}x{}", region.x, region.y, region.width, region.height);
+    }
+    Ok(())
+}
+
+/// Builds the `--output json` object for an analysis.
+fn analysis_json(analysis: &Analysis) -> serde_json::Value {
+    serde_json::json!({
+        "model": analysis.model,
+        "prompt": analysis.prompt,
+        "answer": analysis.text,
+        "thoughts": analysis.thoughts,
+        "usage": {
+            "inputTokens": analysis.input_tokens,
+            "outputTokens": analysis.output_tokens,
+        },
+        "timing": {
+            "firstTextMs": analysis.first_text_after.map(|d| d.as_millis() as u64),
+            "totalMs": analysis.elapsed.as_millis() as u64,
+        },
+        "finishReason": analysis.finish_reason,
+    })
+}
+
+/// Loads the image given with `--file`, `--stdin` or `--clipboard`, if any.
+fn read_input_image(args: &SourceArgs) -> Result<Option<DynamicImage>> {
+    if let Some(path) = &args.file {
+        let image = image::open(path)
+            .with_context(|| format!("Failed to load image from path: {}", path.display()))?;
+        return Ok(Some(image));
+    }
+    if args.stdin {
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .context("Failed to read image from stdin")?;
+        let image = image::load_from_memory(&bytes).context("Failed to decode image from stdin")?;
+        return Ok(Some(image));
+    }
+    if args.clipboard {
+        return Ok(Some(ImageProcessor::from_clipboard()?));
+    }
+    Ok(None)
+}
+
+/// Flags the daemon passes on to the overlays it opens.
+fn overlay_args(args: &Args) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    if let Some(dir) = &args.data_dir {
+        forwarded.extend(["--data-dir".to_string(), dir.display().to_string()]);
+    }
+    if let Some(profile) = &args.profile {
+        forwarded.extend(["--profile".to_string(), profile.clone()]);
+    }
+    if args.copy {
+        forwarded.push("--copy".to_string());
+    }
+    if let Some(template) = &args.save_image {
+        forwarded.extend(["--save-image".to_string(), template.clone()]);
+    }
+    if let Some(template) = &args.save_answer {
+        forwarded.extend(["--save-answer".to_string(), template.clone()]);
+    }
+    forwarded
+}
+
+/// Runs the background daemon that listens for the configured hotkeys
+/// (Ctrl+Alt+X by default).
+///
+/// `overlay_args` are passed on to each overlay process it opens. The
+/// hotkeys, model and API key are reloaded when the config files change.
+fn run_daemon(config: Config, overlay_args: Vec<String>) -> Result<()> {
+    use ai_shot_core::ui::Settings;
+    use std::sync::{Arc, Mutex};
+
+    // The hotkey can be set in the overlay's settings as well
+    let with_settings =
+        |config: &Config| config.with_settings(&Settings::load(&config.model_name)).build();
+    let config = with_settings(&config)?;
+    let server = ipc::Server::bind()?;
+    warn_hotkey_conflicts(&config);
 
     println!("AI-Shot Daemon Started");
-    println!("   Press Ctrl+Alt+X to capture a screenshot");
+    print_hotkeys(&config);
     println!("   Press Ctrl+C to exit");
 
+    // Actions without the overlay run on the runtime `main` started
+    let runtime = tokio::runtime::Handle::current();
+
     // Initialize core once to warm up screens
-    let app = Arc::new(AiShot::new().context("Failed to initialize daemon context")?);
-    
-    let mut ctrl_pressed = false;
-    let mut alt_pressed = false;
+    let updates = config.subscribe()?;
+    let app = AiShot::with_config(config).context("Failed to initialize daemon context")?;
+    let app = Arc::new(Mutex::new(app));
 
-    // Listen for global keyboard events
-    let listen_result = listen(move |event| {
-        match event.event_type {
-            EventType::KeyPress(key) => {
-                match key {
-                    Key::ControlLeft | Key::ControlRight => ctrl_pressed = true,
-                    Key::Alt | Key::AltGr => alt_pressed = true,
-                    Key::KeyX => {
-                        if ctrl_pressed && alt_pressed {
-                            capture_and_spawn(app.clone());
-                        }
+    // Pick up hotkey, model and API key changes without restarting the daemon
+    let watched = app.clone();
+    std::thread::spawn(move || {
+        for config in updates {
+            let config = match with_settings(&config) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Warning: Ignoring configuration change: {}", e);
+                    continue;
+                }
+            };
+            let Ok(mut app) = watched.lock() else {
+                return;
+            };
+            if app.config().hotkey_bindings() != config.hotkey_bindings() {
+                println!("Hotkeys changed:");
+                print_hotkeys(&config);
+                warn_hotkey_conflicts(&config);
+            }
+            println!("Configuration changed, now using {}", config.model_name);
+            *app.config_mut() = config;
+        }
+    });
+
+    // "Do not disturb": set by the toggle-pause hotkey or `daemon pause`
+    let paused = Arc::new(AtomicBool::new(false));
+    // Where the pointer was when the hold-select hotkey went down, with the
+    // rule that applies
+    let hold_start = Arc::new(Mutex::new(None));
+    let run_action = {
+        let app = app.clone();
+        let paused = paused.clone();
+        let hold_start = hold_start.clone();
+        let runtime = runtime.clone();
+        move |action: HotkeyAction| -> Result<()> {
+            if action == HotkeyAction::TogglePause {
+                let now_paused = !paused.fetch_xor(true, Ordering::Relaxed);
+                println!("{}", if now_paused { "Paused" } else { "Resumed" });
+                return Ok(());
+            }
+            if paused.load(Ordering::Relaxed) {
+                anyhow::bail!("Paused; ignoring {} (resume with `ai-shot daemon resume`)", action);
+            }
+            let fullscreen = app
+                .lock()
+                .ok()
+                .filter(|app| app.config().pause_in_fullscreen)
+                .and_then(|app| app.fullscreen_window());
+            if let Some(title) = fullscreen {
+                anyhow::bail!("'{}' is fullscreen; ignoring {}", title, action);
+            }
+
+            // The first rule for the focused app and the monitor adjusts the capture
+            let rule = app
+                .lock()
+                .ok()
+                .filter(|app| !app.config().rules.is_empty())
+                .and_then(|app| {
+                    let context = app.rule_context(action);
+                    rules::first_match(&app.config().rules, &context).cloned()
+                });
+            if let Some(rule) = &rule {
+                println!("Applying rule '{}'", rule.label());
+            }
+
+            match action {
+                HotkeyAction::Select => capture_and_spawn(&app, &overlay_args, rule.as_ref()),
+                HotkeyAction::HoldSelect => {
+                    let start = app.lock().ok().and_then(|app| app.pointer_position()).context(
+                        "The pointer position is unavailable; use the select hotkey instead",
+                    )?;
+                    if let Ok(mut hold_start) = hold_start.lock() {
+                        *hold_start = Some((start, rule));
                     }
-                    _ => {}
                 }
+                action => run_hotkey_action(&app, action, &runtime, rule.as_ref()),
             }
-            EventType::KeyRelease(key) => {
-                match key {
-                    Key::ControlLeft | Key::ControlRight => ctrl_pressed = false,
-                    Key::Alt | Key::AltGr => alt_pressed = false,
-                    _ => {}
+            Ok(())
+        }
+    };
+    let on_hotkey = {
+        let run_action = run_action.clone();
+        move |action: HotkeyAction| {
+            if let Err(e) = run_action(action) {
+                println!("{}", e);
+            }
+        }
+    };
+
+    // Shell extensions and scripts can reach the daemon over D-Bus too
+    #[cfg(target_os = "linux")]
+    let _bus = {
+        let service = dbus::CaptureService::new(app.clone(), paused.clone(), run_action.clone());
+        match tokio::task::block_in_place(|| runtime.block_on(dbus::register(service))) {
+            Ok(connection) => Some(connection),
+            Err(e) => {
+                eprintln!("Warning: {:#}; the D-Bus interface is unavailable", e);
+                None
+            }
+        }
+    };
+
+    server.serve(move |request| match request {
+        ipc::Request::Action(HotkeyAction::HoldSelect) => {
+            anyhow::bail!("hold-select needs a hotkey that is held; bind one in config.toml")
+        }
+        ipc::Request::Action(action) => run_action(action).map(|()| String::new()),
+        ipc::Request::Pause | ipc::Request::Resume => {
+            let pause = request == ipc::Request::Pause;
+            paused.store(pause, Ordering::Relaxed);
+            println!("{}", if pause { "Paused" } else { "Resumed" });
+            Ok(String::new())
+        }
+        ipc::Request::Status => {
+            let state = if paused.load(Ordering::Relaxed) { "paused" } else { "running" };
+            Ok(state.to_string())
+        }
+    })?;
+
+    // Wayland hides the keyboard from rdev; the compositor reports shortcuts
+    #[cfg(target_os = "linux")]
+    if hotkeys::Backend::detect() == hotkeys::Backend::Portal {
+        let bindings = app
+            .lock()
+            .map(|app| app.config().hotkey_bindings())
+            .unwrap_or_default();
+        let listened = tokio::task::block_in_place(|| {
+            runtime.block_on(portal::listen(&bindings, &on_hotkey))
+        });
+        match listened {
+            Ok(()) => return Ok(()),
+            Err(e) => eprintln!(
+                "Warning: {:#}. Falling back to the X11 listener, which only sees keys \
+                 pressed in X11 windows",
+                e
+            ),
+        }
+    }
+
+    // Listen for global keyboard events
+    let mut matcher = hotkeys::Matcher::default();
+    let listen_result = rdev::listen(move |event| {
+        let (hotkey, pressed) = match matcher.feed(&event.event_type) {
+            Some(hotkeys::KeyEvent::Pressed(hotkey)) => (hotkey, true),
+            Some(hotkeys::KeyEvent::Released(hotkey)) => (hotkey, false),
+            None => return,
+        };
+        let action = app.lock().ok().and_then(|app| app.config().hotkey_action(hotkey));
+        match action {
+            Some(action) if pressed => on_hotkey(action),
+            // Letting go of hold-select ends the selection
+            Some(HotkeyAction::HoldSelect) => {
+                let start = hold_start.lock().ok().and_then(|mut start| start.take());
+                if let Some((start, rule)) = start {
+                    finish_hold_select(&app, start, rule.as_ref(), &runtime);
                 }
             }
             _ => {}
@@ -143,41 +2001,336 @@ fn run_daemon() -> Result<()> {
     Ok(())
 }
 
-/// Captures the screen immediately and spawns the UI process.
-fn capture_and_spawn(app: std::sync::Arc<AiShot>) {
+/// Lists the daemon's hotkeys and what each one does.
+fn print_hotkeys(config: &Config) {
+    for (hotkey, action) in config.hotkey_bindings() {
+        println!("   Press {} to {}", hotkey, action.description());
+    }
+}
+
+/// Warns about hotkeys the desktop or other programs usually take.
+fn warn_hotkey_conflicts(config: &Config) {
+    for (hotkey, _) in config.hotkey_bindings() {
+        if let Some(owner) = hotkey.conflicts() {
+            eprintln!(
+                "Warning: {} is usually taken by {}; the daemon may never see it or may \
+                 steal it from other programs. Bind another in config.toml or with \
+                 `ai-shot config set hotkey ...`",
+                hotkey, owner
+            );
+        }
+    }
+}
+
+/// Runs an action on the active window in the background, printing its
+/// result and copying it to the clipboard.
+fn run_hotkey_action(
+    app: &std::sync::Mutex<AiShot>,
+    action: HotkeyAction,
+    runtime: &tokio::runtime::Handle,
+    rule: Option<&Rule>,
+) {
+    println!("Hotkey triggered! Going to {}...", action.description());
+    tracing::info!(%action, "running hotkey action");
+
+    let Ok(app) = app.lock() else {
+        eprintln!("❌ Daemon state is unavailable");
+        return;
+    };
+    // Capture right away, while the window still has the focus
+    let image = match app.capture_active_window() {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("❌ Failed to capture the active window: {}", e);
+            let message = format!("Failed to capture the active window: {}", e);
+            notify::failure("Capture failed", &message);
+            return;
+        }
+    };
+    let mut config = app.config().clone();
+    drop(app);
+    if let Some(rule) = rule {
+        rule.apply(&mut config);
+    }
+    spawn_hotkey_action(config, action, image, runtime);
+}
+
+/// Smallest area, in either direction, hold-select asks about; anything
+/// smaller was most likely a plain key press.
+const MIN_HOLD_SELECTION: u32 = 8;
+
+/// Ends a hold-select that started with the pointer at `start`: asks the
+/// default prompt about the area between there and the pointer's position
+/// now, in the background.
+fn finish_hold_select(
+    app: &std::sync::Mutex<AiShot>,
+    start: (i32, i32),
+    rule: Option<&Rule>,
+    runtime: &tokio::runtime::Handle,
+) {
+    let Ok(app) = app.lock() else {
+        eprintln!("❌ Daemon state is unavailable");
+        return;
+    };
+    let Some(end) = app.pointer_position() else {
+        eprintln!("❌ The pointer position is unavailable");
+        return;
+    };
+    let (width, height) = (start.0.abs_diff(end.0), start.1.abs_diff(end.1));
+    if width < MIN_HOLD_SELECTION || height < MIN_HOLD_SELECTION {
+        println!("Selection too small; move the pointer across the area while holding the keys");
+        return;
+    }
+
+    println!("Hotkey released! Going to ask about {}x{} pixels...", width, height);
+    tracing::info!(width, height, "running hold-select");
+    let x = start.0.min(end.0);
+    let y = start.1.min(end.1);
+    let image = match app.capture_desktop_area(x, y, width, height) {
+        Ok(image) => image,
+        Err(e) => {
+            eprintln!("❌ Failed to capture the selection: {}", e);
+            let message = format!("Failed to capture the selection: {}", e);
+            notify::failure("Capture failed", &message);
+            return;
+        }
+    };
+    let mut config = app.config().clone();
+    drop(app);
+    if let Some(rule) = rule {
+        rule.apply(&mut config);
+    }
+    spawn_hotkey_action(config, HotkeyAction::HoldSelect, image, runtime);
+}
+
+/// Runs an action on a capture on the runtime, printing its result and
+/// copying it to the clipboard.
+fn spawn_hotkey_action(
+    config: Config,
+    action: HotkeyAction,
+    image: DynamicImage,
+    runtime: &tokio::runtime::Handle,
+) {
+    runtime.spawn(async move {
+        let copied = hotkey_action_text(config, action, image).await.and_then(|text| {
+            println!("{}", text);
+            copy_to_clipboard(&text)
+        });
+        match copied {
+            Ok(()) => println!("Copied to clipboard"),
+            Err(e) => {
+                eprintln!("❌ {:#}", e);
+                let summary = format!("Failed to {}", action.description());
+                notify::failure(&summary, &format!("{:#}", e));
+            }
+        }
+    });
+}
+
+/// Produces the text an action on `image` copies: the extracted text or
+/// an answer.
+async fn hotkey_action_text(
+    mut config: Config,
+    action: HotkeyAction,
+    image: DynamicImage,
+) -> Result<String> {
+    let prompt = match action {
+        HotkeyAction::Ocr => return extract_text(config, &image, TextEngine::Auto).await,
+        HotkeyAction::RepeatPrompt => {
+            let prompt = ai_shot_core::ui::Settings::load(&config.model_name).last_prompt;
+            if prompt.trim().is_empty() {
+                anyhow::bail!("No prompt has been typed in the overlay yet");
+            }
+            Some(prompt)
+        }
+        HotkeyAction::Select
+        | HotkeyAction::ActiveWindow
+        | HotkeyAction::TogglePause
+        | HotkeyAction::HoldSelect => None,
+    };
+
+    require_credentials(&mut config)?;
+    let archive = config.archive.for_monitor(&action.to_string());
+    let kept_image = archive.is_enabled().then(|| image.clone());
+    let analysis = TaskScheduler::shared()
+        .run(
+            scheduler::provider(&config),
+            analysis::analyze_region(&config, image, None, prompt.as_deref(), |_| {}),
+        )
+        .await
+        .context("Analysis failed")?;
+    if let Some(image) = kept_image {
+        let image = ai_shot_core::ui::Settings::load(&config.model_name).stamp().apply(&image);
+        for path in archive.save(&image, &analysis.text, &analysis.model)? {
+            println!("Saved {}", path.display());
+        }
+    }
+    Ok(analysis.text)
+}
+
+/// Captures the screen immediately and spawns the UI process, with the
+/// flags of the rule that applies.
+fn capture_and_spawn(
+    app: &std::sync::Mutex<AiShot>,
+    overlay_args: &[String],
+    rule: Option<&Rule>,
+) {
     println!("Hotkey triggered! Capturing...");
+    tracing::info!("capturing for the overlay");
     
     // Capture immediately in this process (fast, no startup overhead)
-    // We capture the primary monitor (0) for now.
-    match app.capture(0) {
+    let Ok(app) = app.lock() else {
+        eprintln!("❌ Daemon state is unavailable");
+        return;
+    };
+    let (captured, monitor) = match &app.config().daemon_monitor {
+        DaemonMonitor::All => (app.capture_desktop(), DaemonMonitor::All),
+        DaemonMonitor::Monitor(selector) => {
+            // The pointer position is unavailable on Wayland
+            let index = app.resolve_monitor(selector).unwrap_or_else(|e| {
+                eprintln!("Warning: {}; capturing monitor 0", e);
+                0
+            });
+            (app.capture(index), DaemonMonitor::Monitor(MonitorSelector::Index(index)))
+        }
+    };
+    drop(app);
+    match captured {
         Ok(screenshot) => {
-            // Save to temporary file
-            let temp_path = std::env::temp_dir().join("ai_shot_rapid_capture.png");
-            match screenshot.save(&temp_path) {
-                Ok(_) => {
-                    spawn_process_with_image(&temp_path);
-                }
-                Err(e) => eprintln!("❌ Failed to save temp image: {}", e),
-            }
+            let overlay_args = match rule {
+                Some(rule) => with_rule_args(overlay_args, rule),
+                None => overlay_args.to_vec(),
+            };
+            spawn_process_with_image(screenshot.into_rgba8(), &monitor, &overlay_args)
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to capture screen: {}", e);
+            notify::failure("Capture failed", &format!("Failed to capture the screen: {}", e));
         }
-        Err(e) => eprintln!("❌ Failed to capture screen: {}", e),
     }
 }
 
-/// Spawns the main process processing the saved image
-fn spawn_process_with_image(path: &std::path::Path) {
-    match std::env::current_exe() {
-        Ok(exe_path) => {
-            if let Err(e) = Command::new(exe_path)
-                .arg("--image-path")
-                .arg(path)
-                .spawn() 
-            {
-                eprintln!("❌ Failed to spawn UI process: {}", e);
+/// Returns the overlay's flags with those of a rule's actions, which replace
+/// the daemon's own.
+fn with_rule_args(overlay_args: &[String], rule: &Rule) -> Vec<String> {
+    let then = &rule.then;
+    let mut rule_args = Vec::new();
+    if let Some(model) = &then.model {
+        rule_args.extend(["--model".to_string(), model.clone()]);
+    }
+    if let Some(preset) = then.preset {
+        rule_args.extend(["--preset".to_string(), preset.id().to_string()]);
+    }
+    if let Some(template) = &then.save_image {
+        rule_args.extend(["--save-image".to_string(), template.clone()]);
+    }
+    if let Some(template) = &then.save_answer {
+        rule_args.extend(["--save-answer".to_string(), template.clone()]);
+    }
+    if then.copy == Some(true) {
+        rule_args.push("--copy".to_string());
+    }
+
+    // Flags may only be given once
+    let mut args = Vec::new();
+    let mut forwarded = overlay_args.iter();
+    while let Some(arg) = forwarded.next() {
+        if arg == "--copy" {
+            if then.copy.is_none() {
+                args.push(arg.clone());
             }
+        } else if arg.starts_with("--") && rule_args.contains(arg) {
+            forwarded.next();
+        } else {
+            args.push(arg.clone());
         }
+    }
+    args.extend(rule_args);
+    args
+}
+
+/// Spawns the overlay process and pipes the capture to it
+fn spawn_process_with_image(
+    image: image::RgbaImage,
+    monitor: &DaemonMonitor,
+    overlay_args: &[String],
+) {
+    let exe_path = match std::env::current_exe() {
+        Ok(exe_path) => exe_path,
         Err(e) => {
             eprintln!("❌ Failed to get executable path: {}", e);
+            return;
+        }
+    };
+    let spawned = Command::new(exe_path)
+        .arg("--image-pipe")
+        .arg("--image-monitor")
+        .arg(monitor.to_string())
+        .args(overlay_args)
+        .stdin(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn();
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("❌ Failed to spawn UI process: {}", e);
+            notify::failure("Failed to open the overlay", &e.to_string());
+            return;
+        }
+    };
+    tracing::info!(pid = child.id(), "overlay started");
+
+    // Write from another thread so the hotkey listener isn't held up, then
+    // watch the process until the overlay closes
+    std::thread::spawn(move || {
+        if let Some(stdin) = child.stdin.take()
+            && let Err(e) = ipc::send_capture(std::io::BufWriter::new(stdin), &image)
+        {
+            eprintln!("❌ Failed to hand the capture to the UI process: {}", e);
+        }
+        supervise_overlay(child);
+    });
+}
+
+/// Lines of the overlay's stderr shown when it crashes.
+const OVERLAY_ERROR_LINES: usize = 3;
+
+/// Waits for the overlay process, passing its stderr on to the log and
+/// notifying about crashes with the last lines it wrote.
+fn supervise_overlay(mut child: std::process::Child) {
+    use std::io::BufRead;
+
+    let pid = child.id();
+    let mut last_lines = std::collections::VecDeque::with_capacity(OVERLAY_ERROR_LINES);
+    if let Some(stderr) = child.stderr.take() {
+        for line in std::io::BufReader::new(stderr).lines().map_while(Result::ok) {
+            eprintln!("{}", line);
+            tracing::warn!(pid, "overlay: {}", line);
+            if last_lines.len() == OVERLAY_ERROR_LINES {
+                last_lines.pop_front();
+            }
+            last_lines.push_back(line);
         }
     }
+
+    let status = match child.wait() {
+        Ok(status) => status,
+        Err(e) => {
+            tracing::error!(pid, error = %e, "failed to wait for the overlay");
+            return;
+        }
+    };
+    // Closing the overlay without a question isn't a failure
+    if status.success() || status.code() == Some(Failure::Cancelled as i32) {
+        tracing::info!(pid, %status, "overlay closed");
+        return;
+    }
+
+    let ended = match status.code() {
+        Some(code) => format!("exited with code {}", code),
+        None => "was killed (e.g. by a crash)".to_string(),
+    };
+    eprintln!("❌ The UI process {}", ended);
+    let output = Vec::from(last_lines).join("\n");
+    notify::failure("The ai-shot overlay failed", &format!("It {}.\n{}", ended, output));
 }
\ No newline at end of file
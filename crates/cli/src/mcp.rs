@@ -0,0 +1,288 @@
+//! A Model Context Protocol server on stdin and stdout.
+//!
+//! `ai-shot mcp` lets agents such as Claude Desktop or an IDE assistant take
+//! screenshots and ask about them, with three tools:
+//!
+//! - `capture_screen`: a screenshot of a monitor
+//! - `capture_region`: a screenshot of a rectangle of a monitor, in the
+//!   monitor's pixels
+//! - `analyze_screenshot`: the configured model's answer to a question about
+//!   a monitor or a rectangle of it
+//!
+//! Messages are JSON-RPC 2.0, one per line. Screenshots are scaled down to
+//! `max_image_pixels` and encoded with `image_encoding` like uploads, and
+//! come with the size of the capture so agents can pick regions of it. Only
+//! protocol messages are written to stdout; warnings go to stderr.
+//!
+//! ```text
+//! // claude_desktop_config.json
+//! { "mcpServers": { "ai-shot": { "command": "ai-shot", "args": ["mcp"] } } }
+//! ```
+
+use ai_shot_core::scheduler::{self, TaskScheduler};
+use ai_shot_core::{analysis, AiShot, Config, ImageProcessor, MonitorSelector, PixelRegion};
+use anyhow::{Context, Result};
+use image::DynamicImage;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+
+/// Protocol revision answered when the client asks for one not listed in
+/// [`SUPPORTED_VERSIONS`].
+const PROTOCOL_VERSION: &str = "2025-06-18";
+
+/// Protocol revisions whose tool messages this server speaks.
+const SUPPORTED_VERSIONS: [&str; 3] = ["2024-11-05", "2025-03-26", "2025-06-18"];
+
+// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC error code and message.
+type RpcError = (i64, String);
+
+/// Answers requests from stdin until it is closed.
+///
+/// Requests are handled one at a time, in order.
+///
+/// # Errors
+///
+/// Returns an error if stdin cannot be read or stdout cannot be written.
+pub async fn serve(config: Config) -> Result<()> {
+    let server = Server { config };
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        if let Some(response) = server.handle(&line).await {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+}
+
+struct Server {
+    config: Config,
+}
+
+impl Server {
+    /// Handles one message; returns the response, or `None` for
+    /// notifications.
+    async fn handle(&self, line: &str) -> Option<Value> {
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => return Some(error_response(Value::Null, (PARSE_ERROR, e.to_string()))),
+        };
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            let id = message.get("id")?.clone();
+            return Some(error_response(id, (INVALID_REQUEST, "Missing method".to_string())));
+        };
+        // Notifications (`notifications/initialized`, cancellations) need no answer
+        let id = message.get("id")?.clone();
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+        let result = match method {
+            "initialize" => Ok(initialize(&params)),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tools() })),
+            "tools/call" => self.call_tool(&params).await,
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method '{}'", method))),
+        };
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    /// Runs a tool and returns its result.
+    ///
+    /// Failures of the tool itself (no such monitor, no API key, a failed
+    /// request) are results marked as errors, so the agent can read them.
+    async fn call_tool(&self, params: &Value) -> std::result::Result<Value, RpcError> {
+        let name = params
+            .get("name")
+            .and_then(Value::as_str)
+            .ok_or((INVALID_PARAMS, "Missing tool name".to_string()))?;
+        let arguments = params.get("arguments").cloned().unwrap_or_else(|| json!({}));
+
+        let content = match name {
+            "capture_screen" => self.capture(&arguments, false),
+            "capture_region" => self.capture(&arguments, true),
+            "analyze_screenshot" => self.analyze(&arguments).await,
+            _ => return Err((INVALID_PARAMS, format!("Unknown tool '{}'", name))),
+        };
+        Ok(match content {
+            Ok(content) => json!({ "content": content, "isError": false }),
+            Err(e) => json!({
+                "content": [{ "type": "text", "text": format!("{:#}", e) }],
+                "isError": true,
+            }),
+        })
+    }
+
+    /// Returns a screenshot as image content, with its size as text.
+    fn capture(&self, arguments: &Value, region_required: bool) -> Result<Value> {
+        if region_required && region(arguments)?.is_none() {
+            anyhow::bail!("capture_region needs x, y, width and height");
+        }
+        let image = self.screenshot(arguments)?;
+        let (width, height) = (image.width(), image.height());
+        let image = self
+            .config
+            .max_image_pixels
+            .and_then(|max_pixels| ImageProcessor::downscale_to_pixels(&image, max_pixels))
+            .unwrap_or(image);
+        let encoded = ImageProcessor::encode_image(&image, self.config.image_encoding)?;
+        let mut note = format!("Screenshot of {}x{} pixels", width, height);
+        if (encoded.width, encoded.height) != (width, height) {
+            note.push_str(&format!(", scaled to {}x{}", encoded.width, encoded.height));
+        }
+        Ok(json!([
+            { "type": "image", "data": encoded.data, "mimeType": encoded.mime_type },
+            { "type": "text", "text": note },
+        ]))
+    }
+
+    /// Asks the model about a screenshot and returns the answer as text.
+    async fn analyze(&self, arguments: &Value) -> Result<Value> {
+        let mut config = self.config.clone();
+        crate::require_credentials(&mut config)?;
+        let prompt = arguments
+            .get("prompt")
+            .and_then(Value::as_str)
+            .filter(|prompt| !prompt.trim().is_empty());
+
+        let image = self.screenshot(arguments)?;
+        let crop = image.clone();
+        let analysis = TaskScheduler::shared()
+            .run(
+                scheduler::provider(&config),
+                analysis::analyze_region(&config, image, None, prompt, |_| {}),
+            )
+            .await
+            .context("Analysis failed")?;
+        crate::record_history(&config, &analysis, &crop);
+        Ok(json!([{ "type": "text", "text": analysis.text }]))
+    }
+
+    /// Captures the monitor and region the arguments name.
+    fn screenshot(&self, arguments: &Value) -> Result<DynamicImage> {
+        let monitor = match arguments.get("monitor") {
+            None | Some(Value::Null) => MonitorSelector::default(),
+            Some(Value::Number(index)) => {
+                let index = index.as_u64().context("monitor must be a non-negative index")?;
+                MonitorSelector::Index(index as usize)
+            }
+            Some(Value::String(name)) => name.parse()?,
+            Some(_) => anyhow::bail!("monitor must be an index or a name"),
+        };
+        let app = AiShot::with_config(self.config.clone()).context("Failed to initialize ai-shot")?;
+        let index = app.resolve_monitor(&monitor)?;
+        let image = app.capture(index).context("Failed to capture screen")?;
+        match region(arguments)? {
+            Some(region) => ImageProcessor::crop_region(&image, region).context("Invalid region"),
+            None => Ok(image),
+        }
+    }
+}
+
+/// Reads the region from `x`, `y`, `width` and `height`, which are either
+/// all given or all left out.
+fn region(arguments: &Value) -> Result<Option<PixelRegion>> {
+    let keys = ["x", "y", "width", "height"];
+    let given = |key: &str| arguments.get(key).is_some_and(|value| !value.is_null());
+    if !keys.iter().any(|key| given(key)) {
+        return Ok(None);
+    }
+    let mut values = [0u32; 4];
+    for (value, key) in values.iter_mut().zip(keys) {
+        *value = arguments
+            .get(key)
+            .and_then(Value::as_u64)
+            .and_then(|number| u32::try_from(number).ok())
+            .with_context(|| format!("A region needs {} as a non-negative integer", key))?;
+    }
+    let [x, y, width, height] = values;
+    Ok(Some(PixelRegion {
+        x,
+        y,
+        width,
+        height,
+    }))
+}
+
+/// Answers `initialize` with the protocol revision and the server's tools
+/// capability.
+fn initialize(params: &Value) -> Value {
+    let version = params
+        .get("protocolVersion")
+        .and_then(Value::as_str)
+        .filter(|version| SUPPORTED_VERSIONS.contains(version))
+        .unwrap_or(PROTOCOL_VERSION);
+    json!({
+        "protocolVersion": version,
+        "capabilities": { "tools": {} },
+        "serverInfo": { "name": "ai-shot", "version": env!("CARGO_PKG_VERSION") },
+    })
+}
+
+/// Describes the tools for `tools/list`.
+fn tools() -> Value {
+    let monitor = json!({
+        "type": ["integer", "string"],
+        "description": "Monitor index, \"cursor\" for the one with the mouse pointer, or an \
+                        output name like DP-2; the first monitor if left out",
+    });
+    let pixels = |description: &str| {
+        json!({ "type": "integer", "minimum": 0, "description": description })
+    };
+    let region = json!({
+        "monitor": monitor.clone(),
+        "x": pixels("Left edge, in pixels of the monitor"),
+        "y": pixels("Top edge, in pixels of the monitor"),
+        "width": pixels("Width in pixels"),
+        "height": pixels("Height in pixels"),
+    });
+    let mut analyze = region.clone();
+    analyze["prompt"] = json!({
+        "type": "string",
+        "description": "The question; if left out, one suited to what the screenshot shows \
+                        (e.g. explain the code, transcribe the text)",
+    });
+
+    json!([
+        {
+            "name": "capture_screen",
+            "description": "Take a screenshot of a monitor",
+            "inputSchema": { "type": "object", "properties": { "monitor": monitor } },
+        },
+        {
+            "name": "capture_region",
+            "description": "Take a screenshot of a rectangle of a monitor",
+            "inputSchema": {
+                "type": "object",
+                "properties": region,
+                "required": ["x", "y", "width", "height"],
+            },
+        },
+        {
+            "name": "analyze_screenshot",
+            "description": "Take a screenshot of a monitor, or of a rectangle of it when x, y, \
+                            width and height are given, and ask the vision model about it",
+            "inputSchema": { "type": "object", "properties": analyze },
+        },
+    ])
+}
+
+/// Builds a JSON-RPC error response.
+fn error_response(id: Value, (code, message): RpcError) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
@@ -0,0 +1,20 @@
+//! Desktop notifications for failures in the daemon.
+//!
+//! The daemon usually runs without a visible terminal (e.g. as a service),
+//! so a failed capture or analysis would otherwise go unnoticed. Each
+//! failure is also logged; see `ai_shot_core::logging::init_run_log`.
+
+use notify_rust::Notification;
+
+/// Shows a notification that something failed, logging it as well.
+pub fn failure(summary: &str, body: &str) {
+    tracing::error!(summary, body, "daemon failure");
+    let shown = Notification::new()
+        .appname("ai-shot")
+        .summary(summary)
+        .body(body)
+        .show();
+    if let Err(e) = shown {
+        tracing::debug!(error = %e, "failed to show notification");
+    }
+}
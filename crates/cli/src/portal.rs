@@ -0,0 +1,137 @@
+//! Global hotkeys on Wayland through the desktop portal.
+//!
+//! Wayland compositors don't let programs read the keyboard globally, so
+//! `rdev` sees nothing there. Instead the daemon asks the
+//! `org.freedesktop.portal.GlobalShortcuts` portal to register one shortcut
+//! per [`HotkeyAction`], suggesting the hotkeys from the configuration, and
+//! the compositor reports when one is pressed.
+//!
+//! The desktop decides the final keys:
+//!
+//! - **KDE Plasma** (5.27+) binds the suggested keys right away; change them
+//!   in System Settings > Keyboard > Shortcuts under "ai-shot".
+//! - **GNOME** (48+) shows a dialog on the first start to confirm the
+//!   shortcuts; change them later in Settings > Apps > ai-shot.
+//! - **Hyprland** registers the shortcuts without keys; bind them in
+//!   `hyprland.conf`, e.g. `bind = CTRL ALT, X, global, :select`.
+//!
+//! Desktops remember the bindings, so editing hotkeys in `config.toml`
+//! afterwards only changes the suggestion; use the desktop's settings.
+//!
+//! `hold-select` is left out: Wayland doesn't tell programs where the
+//! pointer is.
+
+use ai_shot_core::hotkey::{Hotkey, HotkeyAction, Key};
+use anyhow::{Context, Result};
+use ashpd::desktop::global_shortcuts::{GlobalShortcuts, NewShortcut};
+use futures::StreamExt;
+
+/// Registers the shortcuts and calls `on_action` each time one is pressed.
+///
+/// Runs until the portal goes away.
+///
+/// # Errors
+///
+/// Fails if no portal implements GlobalShortcuts (e.g. GNOME before 48) or
+/// the user declines the shortcuts.
+pub async fn listen(
+    bindings: &[(Hotkey, HotkeyAction)],
+    mut on_action: impl FnMut(HotkeyAction),
+) -> Result<()> {
+    let portal = GlobalShortcuts::new()
+        .await
+        .context("The desktop portal does not support global shortcuts")?;
+    let session = portal
+        .create_session()
+        .await
+        .context("Failed to open a global shortcuts session")?;
+
+    // Every action is registered, so those without a hotkey can still be
+    // bound in the desktop's settings
+    let shortcuts: Vec<NewShortcut> = HotkeyAction::all()
+        .filter(|action| *action != HotkeyAction::HoldSelect)
+        .map(|action| {
+            let preferred = bindings
+                .iter()
+                .find(|(_, bound)| *bound == action)
+                .map(|(hotkey, _)| trigger(*hotkey));
+            NewShortcut::new(action.to_string(), capitalize(action.description()))
+                .preferred_trigger(preferred.as_deref())
+        })
+        .collect();
+    let bound = portal
+        .bind_shortcuts(&session, &shortcuts, None)
+        .await
+        .and_then(|request| request.response())
+        .context("The global shortcuts were not registered")?;
+
+    println!("   Shortcuts registered with the desktop portal:");
+    for shortcut in bound.shortcuts() {
+        let trigger = match shortcut.trigger_description() {
+            "" => "not bound yet, see the desktop's shortcut settings",
+            trigger => trigger,
+        };
+        println!("   {}: {}", shortcut.description(), trigger);
+    }
+
+    let mut activated = portal
+        .receive_activated()
+        .await
+        .context("Failed to listen for global shortcuts")?;
+    while let Some(event) = activated.next().await {
+        match event.shortcut_id().parse() {
+            Ok(action) => on_action(action),
+            Err(e) => tracing::warn!(error = %e, "ignoring unknown global shortcut"),
+        }
+    }
+    Ok(())
+}
+
+/// Writes a hotkey in the portal's trigger format, e.g. `CTRL+ALT+x`.
+fn trigger(hotkey: Hotkey) -> String {
+    let modifiers = [
+        (hotkey.modifiers.ctrl, "CTRL"),
+        (hotkey.modifiers.alt, "ALT"),
+        (hotkey.modifiers.shift, "SHIFT"),
+        (hotkey.modifiers.super_key, "LOGO"),
+    ];
+    let mut trigger = String::new();
+    for (held, name) in modifiers {
+        if held {
+            trigger.push_str(name);
+            trigger.push('+');
+        }
+    }
+
+    // Keys are XKB keysym names
+    let key = match hotkey.key {
+        Key::Char(c) => c.to_ascii_lowercase().to_string(),
+        Key::F(number) => format!("F{}", number),
+        Key::PrintScreen => "Print".to_string(),
+        Key::Space => "space".to_string(),
+        Key::Enter => "Return".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::Escape => "Escape".to_string(),
+        Key::Insert => "Insert".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "Page_Up".to_string(),
+        Key::PageDown => "Page_Down".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+    };
+    trigger.push_str(&key);
+    trigger
+}
+
+/// Upper-cases the first letter, for descriptions shown by the desktop.
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
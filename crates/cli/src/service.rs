@@ -0,0 +1,284 @@
+//! Registering the daemon to start at login.
+//!
+//! `ai-shot daemon install` writes and enables a per-user service that runs
+//! `ai-shot --daemon` with the flags given at install time:
+//!
+//! - **Linux**: a systemd user unit, `~/.config/systemd/user/ai-shot.service`
+//! - **macOS**: a LaunchAgent, `~/Library/LaunchAgents/com.ai-shot.daemon.plist`
+//! - **Windows**: a Task Scheduler task run at logon, `AI-Shot Daemon`
+//!
+//! Services don't see variables exported in shell profiles, so the API key
+//! should be saved with `ai-shot config set api_key …` or in a `.env` file.
+
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// Writes and enables the service, then starts it.
+pub fn install(overlay_args: &[String]) -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to get executable path")?;
+    let mut command = vec![exe.display().to_string(), "--daemon".to_string()];
+    command.extend(overlay_args.iter().cloned());
+    platform::install(&command)
+}
+
+/// Stops and removes the service.
+pub fn uninstall() -> Result<()> {
+    platform::uninstall()
+}
+
+/// Prints whether the service is installed and running.
+pub fn status() -> Result<()> {
+    platform::status()
+}
+
+/// Runs a service manager command, failing with its output if it fails.
+fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns the user's home directory.
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn home_dir() -> Result<std::path::PathBuf> {
+    std::env::var_os("HOME")
+        .map(std::path::PathBuf::from)
+        .context("Could not determine the home directory")
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::{home_dir, run};
+    use anyhow::{Context, Result};
+    use std::path::PathBuf;
+
+    const UNIT: &str = "ai-shot.service";
+
+    fn unit_path() -> Result<PathBuf> {
+        let config_dir = match std::env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => home_dir()?.join(".config"),
+        };
+        Ok(config_dir.join("systemd/user").join(UNIT))
+    }
+
+    /// Quotes an argument for `ExecStart`, escaping systemd specifiers
+    /// (`%Y` in archive templates) and variable expansion.
+    fn quote(arg: &str) -> String {
+        let escaped = arg
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+            .replace('$', "$$");
+        format!("\"{}\"", escaped)
+    }
+
+    pub fn install(command: &[String]) -> Result<()> {
+        let path = unit_path()?;
+        let exec: Vec<String> = command.iter().map(|arg| quote(arg)).collect();
+        let unit = format!(
+            "[Unit]\n\
+             Description=AI-Shot screenshot hotkey daemon\n\
+             PartOf=graphical-session.target\n\
+             After=graphical-session.target\n\
+             \n\
+             [Service]\n\
+             ExecStart={}\n\
+             Restart=on-failure\n\
+             \n\
+             [Install]\n\
+             WantedBy=graphical-session.target\n",
+            exec.join(" ")
+        );
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, unit).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        run("systemctl", &["--user", "daemon-reload"])?;
+        run("systemctl", &["--user", "enable", "--now", UNIT])?;
+        println!("Installed and started {}", path.display());
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<()> {
+        let path = unit_path()?;
+        if !path.exists() {
+            println!("Not installed");
+            return Ok(());
+        }
+        run("systemctl", &["--user", "disable", "--now", UNIT])?;
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        run("systemctl", &["--user", "daemon-reload"])?;
+        println!("Removed {}", path.display());
+        Ok(())
+    }
+
+    pub fn status() -> Result<()> {
+        let path = unit_path()?;
+        if !path.exists() {
+            println!("Not installed");
+            return Ok(());
+        }
+        // `is-active` exits non-zero for inactive units, so read its output regardless
+        let state = std::process::Command::new("systemctl")
+            .args(["--user", "is-active", UNIT])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap_or_else(|_| "unknown".to_string());
+        println!("Installed: {}", path.display());
+        println!("State: {}", state);
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::{home_dir, run};
+    use anyhow::{Context, Result};
+    use std::path::PathBuf;
+
+    const LABEL: &str = "com.ai-shot.daemon";
+
+    fn plist_path() -> Result<PathBuf> {
+        Ok(home_dir()?.join("Library/LaunchAgents").join(format!("{}.plist", LABEL)))
+    }
+
+    fn escape_xml(text: &str) -> String {
+        text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+    }
+
+    pub fn install(command: &[String]) -> Result<()> {
+        let path = plist_path()?;
+        let arguments: String = command
+            .iter()
+            .map(|arg| format!("        <string>{}</string>\n", escape_xml(arg)))
+            .collect();
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+             \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20   <key>Label</key>\n\
+             \x20   <string>{}</string>\n\
+             \x20   <key>ProgramArguments</key>\n\
+             \x20   <array>\n\
+             {}\
+             \x20   </array>\n\
+             \x20   <key>RunAtLoad</key>\n\
+             \x20   <true/>\n\
+             \x20   <key>KeepAlive</key>\n\
+             \x20   <true/>\n\
+             </dict>\n\
+             </plist>\n",
+            LABEL, arguments
+        );
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        std::fs::write(&path, plist).with_context(|| format!("Failed to write {}", path.display()))?;
+
+        let path_arg = path.display().to_string();
+        run("launchctl", &["load", "-w", &path_arg])?;
+        println!("Installed and started {}", path.display());
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<()> {
+        let path = plist_path()?;
+        if !path.exists() {
+            println!("Not installed");
+            return Ok(());
+        }
+        let path_arg = path.display().to_string();
+        run("launchctl", &["unload", "-w", &path_arg])?;
+        std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        println!("Removed {}", path.display());
+        Ok(())
+    }
+
+    pub fn status() -> Result<()> {
+        let path = plist_path()?;
+        if !path.exists() {
+            println!("Not installed");
+            return Ok(());
+        }
+        let running = run("launchctl", &["list", LABEL]).is_ok();
+        println!("Installed: {}", path.display());
+        println!("State: {}", if running { "loaded" } else { "not loaded" });
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::run;
+    use anyhow::Result;
+
+    const TASK: &str = "AI-Shot Daemon";
+
+    pub fn install(command: &[String]) -> Result<()> {
+        let task: Vec<String> = command
+            .iter()
+            .map(|arg| format!("\"{}\"", arg.replace('"', "\\\"")))
+            .collect();
+        let task = task.join(" ");
+        run(
+            "schtasks",
+            &["/Create", "/TN", TASK, "/TR", &task, "/SC", "ONLOGON", "/RL", "LIMITED", "/F"],
+        )?;
+        run("schtasks", &["/Run", "/TN", TASK])?;
+        println!("Installed and started scheduled task '{}'", TASK);
+        Ok(())
+    }
+
+    pub fn uninstall() -> Result<()> {
+        if run("schtasks", &["/Query", "/TN", TASK]).is_err() {
+            println!("Not installed");
+            return Ok(());
+        }
+        // Ending fails if the task isn't running, which is fine
+        let _ = run("schtasks", &["/End", "/TN", TASK]);
+        run("schtasks", &["/Delete", "/TN", TASK, "/F"])?;
+        println!("Removed scheduled task '{}'", TASK);
+        Ok(())
+    }
+
+    pub fn status() -> Result<()> {
+        match run("schtasks", &["/Query", "/TN", TASK, "/FO", "LIST"]) {
+            Ok(details) => print!("{}", details),
+            Err(_) => println!("Not installed"),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+mod platform {
+    use anyhow::Result;
+
+    pub fn install(_command: &[String]) -> Result<()> {
+        anyhow::bail!("Installing the daemon is not supported on this platform")
+    }
+
+    pub fn uninstall() -> Result<()> {
+        anyhow::bail!("Installing the daemon is not supported on this platform")
+    }
+
+    pub fn status() -> Result<()> {
+        anyhow::bail!("Installing the daemon is not supported on this platform")
+    }
+}
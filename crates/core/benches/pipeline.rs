@@ -0,0 +1,103 @@
+//! Benchmarks for the capture → convert → crop → encode pipeline.
+//!
+//! Run with `cargo bench -p ai-shot-core`. Each stage is measured across
+//! representative monitor resolutions so performance-oriented changes can be
+//! validated without regressions. The capture benchmark is skipped when no
+//! display is available (e.g., on CI).
+//!
+//! Results are Criterion's own reports under `target/criterion`; nothing in
+//! the app reads them.
+
+use ai_shot_core::image_processing::ImageProcessor;
+use ai_shot_core::ScreenCapturer;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use eframe::egui;
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::hint::black_box;
+
+/// Representative screen resolutions: 1080p, 1440p, and 4K.
+const RESOLUTIONS: &[(u32, u32)] = &[(1920, 1080), (2560, 1440), (3840, 2160)];
+
+/// Builds a synthetic screenshot with enough structure to be realistic for JPEG.
+fn synthetic_screenshot(width: u32, height: u32) -> DynamicImage {
+    let image = RgbaImage::from_fn(width, height, |x, y| {
+        let stripe = if (y / 16) % 2 == 0 { 30 } else { 45 };
+        let glyph = if (x % 9 < 5) && (y % 16 < 11) && ((x / 9 + y / 16) % 3 != 0) {
+            180
+        } else {
+            stripe
+        };
+        Rgba([glyph, glyph, (glyph as u32 * 3 / 4) as u8, 255])
+    });
+    DynamicImage::ImageRgba8(image)
+}
+
+/// A selection covering the centered quarter of the screen, in UI coordinates.
+fn quarter_selection(ui_size: egui::Vec2) -> egui::Rect {
+    egui::Rect::from_center_size(
+        egui::pos2(ui_size.x / 2.0, ui_size.y / 2.0),
+        ui_size / 2.0,
+    )
+}
+
+fn bench_capture(c: &mut Criterion) {
+    let Ok(capturer) = ScreenCapturer::new() else {
+        eprintln!("Skipping capture benchmark: no display available");
+        return;
+    };
+
+    let mut group = c.benchmark_group("capture");
+    group.sample_size(10);
+    group.bench_function("primary_screen", |b| {
+        b.iter(|| black_box(capturer.capture_screen()))
+    });
+    group.finish();
+}
+
+fn bench_color_image(c: &mut Criterion) {
+    let mut group = c.benchmark_group("rgba_to_color_image");
+    for &(width, height) in RESOLUTIONS {
+        let screenshot = synthetic_screenshot(width, height);
+        group.throughput(Throughput::Elements(u64::from(width) * u64::from(height)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", width, height)),
+            &screenshot,
+            |b, img| b.iter(|| black_box(ImageProcessor::to_color_image(img))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_crop(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crop_selection");
+    for &(width, height) in RESOLUTIONS {
+        let screenshot = synthetic_screenshot(width, height);
+        // Simulate a 2x HiDPI display where UI coordinates are half the pixels
+        let ui_size = egui::vec2(width as f32 / 2.0, height as f32 / 2.0);
+        let selection = quarter_selection(ui_size);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", width, height)),
+            &screenshot,
+            |b, img| b.iter(|| black_box(ImageProcessor::crop_selection(img, selection, ui_size))),
+        );
+    }
+    group.finish();
+}
+
+fn bench_encode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("encode_jpeg");
+    group.sample_size(20);
+    for &(width, height) in RESOLUTIONS {
+        let screenshot = synthetic_screenshot(width, height);
+        group.throughput(Throughput::Elements(u64::from(width) * u64::from(height)));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", width, height)),
+            &screenshot,
+            |b, img| b.iter(|| black_box(ImageProcessor::encode_to_base64_jpeg(img))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_capture, bench_color_image, bench_crop, bench_encode);
+criterion_main!(benches);
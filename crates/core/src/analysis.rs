@@ -0,0 +1,318 @@
+//! Headless analysis of images, without the overlay.
+//!
+//! Sends an image and a question to the configured backend (the Gemini API,
+//! Vertex AI, or an OpenAI-compatible gateway) and collects the streamed
+//! answer. This is what the CLI's `analyze` command uses, so scripts and SSH
+//! sessions on a virtual display never need a GUI.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::analysis;
+//!
+//! let analysis = analysis::analyze_image(&config, &image, "What is this?", |text| {
+//!     print!("{}", text);
+//! })
+//! .await?;
+//! eprintln!("{} output tokens", analysis.output_tokens);
+//! ```
+
+use crate::classifier;
+use crate::config::Config;
+use crate::error::Result;
+use crate::gemini::{GeminiClient, GeminiEventStream, GeminiStreamEvent};
+use crate::image_processing::{ImageProcessor, PixelRegion};
+use crate::openai::OpenAiClient;
+use crate::presets::{merge_system_prompt, with_response_language};
+use crate::usage;
+use futures::StreamExt;
+use image::DynamicImage;
+use std::time::{Duration, Instant};
+
+/// The complete answer to a headless request.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Analysis {
+    /// Model that answered.
+    pub model: String,
+    /// The question that was asked.
+    pub prompt: String,
+    /// The answer text.
+    pub text: String,
+    /// Thinking content, if the model produced any.
+    pub thoughts: String,
+    /// Input (prompt and image) tokens, or 0 if not reported.
+    pub input_tokens: u32,
+    /// Output tokens, or 0 if not reported.
+    pub output_tokens: u32,
+    /// Why the model stopped (e.g. `STOP`, `MAX_TOKENS`), if reported.
+    pub finish_reason: Option<String>,
+    /// Time from sending the request to the first answer text.
+    pub first_text_after: Option<Duration>,
+    /// Time from sending the request to the end of the answer.
+    pub elapsed: Duration,
+}
+
+/// What a request would send, without sending it (`--dry-run`).
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestPreview {
+    /// Model the request would go to.
+    pub model: String,
+    /// The question.
+    pub prompt: String,
+    /// The assembled system prompt (empty if none).
+    pub system_prompt: String,
+    /// Width of the uploaded image in pixels.
+    pub width: u32,
+    /// Height of the uploaded image in pixels.
+    pub height: u32,
+    /// Size of the image before [`Config::max_image_pixels`] downscaled it,
+    /// if it did.
+    pub downscaled_from: Option<(u32, u32)>,
+    /// Size of the image before [`Config::upscale_min_edge`] enlarged it,
+    /// if it did.
+    pub upscaled_from: Option<(u32, u32)>,
+    /// Encoded image size in bytes (before base64).
+    pub encoded_bytes: usize,
+    /// MIME type the image is uploaded as.
+    pub mime_type: &'static str,
+    /// Rough input token count of the image and text.
+    pub estimated_tokens: u32,
+}
+
+/// Image tokens per tile, and the tile size Gemini splits larger images into.
+const TOKENS_PER_TILE: u32 = 258;
+const TILE_SIZE: u32 = 768;
+
+/// Images whose sides are all at most this size count as a single tile.
+const SMALL_IMAGE_SIZE: u32 = 384;
+
+/// Assembles a request about `image` without sending it.
+///
+/// Runs the same encoding and prompt assembly as [`analyze_image`], so the
+/// reported sizes match what would be uploaded.
+///
+/// # Errors
+///
+/// Returns an error if the image cannot be encoded.
+pub fn preview_request(
+    config: &Config,
+    image: &DynamicImage,
+    prompt: Option<&str>,
+) -> Result<RequestPreview> {
+    let prompt = prompt.unwrap_or_else(|| classifier::classify(image).default_prompt());
+    let downscaled = downscale_for_upload(config, image);
+    let downscaled_from = downscaled.as_ref().map(|_| (image.width(), image.height()));
+    let image = downscaled.as_ref().unwrap_or(image);
+    let upscaled = upscale_for_upload(config, image);
+    let upscaled_from = upscaled.as_ref().map(|_| (image.width(), image.height()));
+    let image = upscaled.as_ref().unwrap_or(image);
+    let encoded = ImageProcessor::encode_image(image, config.image_encoding)?;
+    let system_prompt = system_prompt(config);
+    let text_chars = prompt.chars().count() + system_prompt.chars().count();
+
+    Ok(RequestPreview {
+        model: config.model_name.clone(),
+        prompt: prompt.to_string(),
+        estimated_tokens: estimate_image_tokens(encoded.width, encoded.height)
+            + text_chars.div_ceil(4) as u32,
+        system_prompt,
+        width: encoded.width,
+        height: encoded.height,
+        downscaled_from,
+        upscaled_from,
+        encoded_bytes: encoded.byte_len,
+        mime_type: encoded.mime_type,
+    })
+}
+
+/// Shrinks `image` to [`Config::max_image_pixels`], or returns `None` if
+/// it fits.
+fn downscale_for_upload(config: &Config, image: &DynamicImage) -> Option<DynamicImage> {
+    config
+        .max_image_pixels
+        .and_then(|max_pixels| ImageProcessor::downscale_to_pixels(image, max_pixels))
+}
+
+/// Enlarges `image` to [`Config::upscale_min_edge`], or returns `None` if
+/// it is large enough or no minimum is set.
+fn upscale_for_upload(config: &Config, image: &DynamicImage) -> Option<DynamicImage> {
+    config.upscale_min_edge.and_then(|min_edge| {
+        ImageProcessor::upscale_to_min_edge(image, min_edge, config.upscale_filter)
+    })
+}
+
+/// Estimates the input tokens of an image the way Gemini bills them.
+///
+/// Small images are one tile; larger ones are split into 768×768 tiles.
+pub fn estimate_image_tokens(width: u32, height: u32) -> u32 {
+    if width <= SMALL_IMAGE_SIZE && height <= SMALL_IMAGE_SIZE {
+        return TOKENS_PER_TILE;
+    }
+    width.div_ceil(TILE_SIZE) * height.div_ceil(TILE_SIZE) * TOKENS_PER_TILE
+}
+
+/// Builds the system prompt from the preset, custom prompt and language.
+pub(crate) fn system_prompt(config: &Config) -> String {
+    with_response_language(
+        merge_system_prompt(config.preset, config.system_prompt.as_deref().unwrap_or_default()),
+        config.response_language.as_deref().unwrap_or_default(),
+    )
+}
+
+/// Asks about a region of `image`, from a capture, a file, or stdin.
+///
+/// # Arguments
+/// * `config` - Configuration selecting the backend, model and credentials
+/// * `image` - The full image
+/// * `region` - Region in image pixels (the whole image if `None`)
+/// * `prompt` - The question (a default for the detected content if `None`)
+/// * `on_text` - Called with each piece of answer text as it arrives
+///
+/// # Errors
+///
+/// Returns an error if the region lies outside the image or the request fails.
+pub async fn analyze_region(
+    config: &Config,
+    image: DynamicImage,
+    region: Option<PixelRegion>,
+    prompt: Option<&str>,
+    on_text: impl FnMut(&str),
+) -> Result<Analysis> {
+    let image = match region {
+        Some(region) => ImageProcessor::crop_region(&image, region)?,
+        None => image,
+    };
+    let prompt = prompt.unwrap_or_else(|| classifier::classify(&image).default_prompt());
+    analyze_image(config, &image, prompt, on_text).await
+}
+
+/// Asks `prompt` about `image` and waits for the whole answer.
+///
+/// The system prompt is built from [`Config::preset`],
+/// [`Config::system_prompt`] and [`Config::response_language`]. Images
+/// larger than [`Config::max_image_pixels`] are downscaled first, and
+/// those smaller than [`Config::upscale_min_edge`] enlarged.
+///
+/// # Arguments
+/// * `config` - Configuration selecting the backend, model and credentials
+/// * `image` - The image to ask about
+/// * `prompt` - The question
+/// * `on_text` - Called with each piece of answer text as it arrives
+///
+/// # Errors
+///
+/// Returns an error if the image cannot be encoded, the monthly budget's
+/// hard limit is reached, the client cannot be created, or the request or
+/// stream fails.
+pub async fn analyze_image(
+    config: &Config,
+    image: &DynamicImage,
+    prompt: &str,
+    on_text: impl FnMut(&str),
+) -> Result<Analysis> {
+    let downscaled = downscale_for_upload(config, image);
+    let image = downscaled.as_ref().unwrap_or(image);
+    let upscaled = upscale_for_upload(config, image);
+    let image = upscaled.as_ref().unwrap_or(image);
+    let encoded = ImageProcessor::encode_image(image, config.image_encoding)?;
+    let system_prompt = system_prompt(config);
+    usage::enforce_budget(&config.budget)?;
+
+    let started = Instant::now();
+    let stream: GeminiEventStream = if config.openai.is_some() {
+        OpenAiClient::new(config)?
+            .continue_image_stream(encoded, &[], prompt.to_string(), system_prompt)
+            .await?
+    } else {
+        GeminiClient::connect(config)
+            .await?
+            .analyze_image_stream(encoded, prompt.to_string(), system_prompt, false, false)
+            .await?
+    };
+    collect_answer(config, prompt, started, stream, on_text).await
+}
+
+/// Asks `prompt` about several images at once and waits for the whole
+/// answer.
+///
+/// The images are attached in order, so the prompt can refer to "the first
+/// image" and so on. Each is prepared like in [`analyze_image`].
+///
+/// # Errors
+///
+/// Returns an error if an image cannot be encoded, the monthly budget's
+/// hard limit is reached, the client cannot be created, or the request or
+/// stream fails.
+pub async fn analyze_images(
+    config: &Config,
+    images: &[DynamicImage],
+    prompt: &str,
+    on_text: impl FnMut(&str),
+) -> Result<Analysis> {
+    let encoded = images
+        .iter()
+        .map(|image| {
+            let downscaled = downscale_for_upload(config, image);
+            let image = downscaled.as_ref().unwrap_or(image);
+            let upscaled = upscale_for_upload(config, image);
+            let image = upscaled.as_ref().unwrap_or(image);
+            ImageProcessor::encode_image(image, config.image_encoding)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let system_prompt = system_prompt(config);
+    usage::enforce_budget(&config.budget)?;
+
+    let started = Instant::now();
+    let stream: GeminiEventStream = if config.openai.is_some() {
+        OpenAiClient::new(config)?
+            .continue_images_stream(encoded, &[], prompt.to_string(), system_prompt)
+            .await?
+    } else {
+        GeminiClient::connect(config)
+            .await?
+            .continue_images_stream(encoded, &[], prompt.to_string(), system_prompt, false, false)
+            .await?
+    };
+    collect_answer(config, prompt, started, stream, on_text).await
+}
+
+/// Reads a response stream to the end, passing answer text to `on_text`.
+async fn collect_answer(
+    config: &Config,
+    prompt: &str,
+    started: Instant,
+    mut stream: GeminiEventStream,
+    mut on_text: impl FnMut(&str),
+) -> Result<Analysis> {
+    let mut analysis = Analysis {
+        model: config.model_name.clone(),
+        prompt: prompt.to_string(),
+        ..Analysis::default()
+    };
+    while let Some(events) = stream.next().await {
+        for event in events? {
+            match event {
+                GeminiStreamEvent::Text(text) => {
+                    analysis.first_text_after.get_or_insert_with(|| started.elapsed());
+                    on_text(&text);
+                    analysis.text.push_str(&text);
+                }
+                GeminiStreamEvent::Thought(thought) => analysis.thoughts.push_str(&thought),
+                GeminiStreamEvent::ToolCall { .. } => {}
+                GeminiStreamEvent::Finish(reason) => analysis.finish_reason = Some(reason),
+                GeminiStreamEvent::Usage {
+                    input_tokens,
+                    output_tokens,
+                } => {
+                    analysis.input_tokens = input_tokens;
+                    analysis.output_tokens = output_tokens;
+                }
+            }
+        }
+    }
+    analysis.elapsed = started.elapsed();
+    if let Err(e) = usage::record(&analysis.model, analysis.input_tokens, analysis.output_tokens) {
+        tracing::warn!(error = %e, "failed to record usage");
+    }
+    Ok(analysis)
+}
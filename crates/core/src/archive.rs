@@ -0,0 +1,109 @@
+//! Archiving each analysis to templated file names.
+//!
+//! With `--save-image` and `--save-answer`, every answered selection writes
+//! its crop and its answer to paths built from templates. Templates accept
+//! `strftime`-style fields (`%Y-%m-%d_%H%M%S`, in local time) and the
+//! placeholders `{monitor}` (index of the captured monitor, `file`,
+//! `stdin` or `clipboard`, or the daemon hotkey's action for captures
+//! without the overlay) and `{model}`. Missing directories are created.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::archive::Archive;
+//!
+//! let archive = Archive {
+//!     image: Some("~/shots/%Y-%m-%d_%H%M%S-{monitor}.png".into()),
+//!     answer: Some("~/shots/%Y-%m-%d_%H%M%S-{model}.md".into()),
+//! }
+//! .for_monitor("0");
+//! let written = archive.save(&crop, &answer, "gemini-2.5-pro")?;
+//! ```
+
+use crate::error::{AppError, Result};
+use crate::image_processing::ImageProcessor;
+use crate::paths;
+use chrono::Local;
+use image::{DynamicImage, ImageFormat};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// File name templates for archived crops and answers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Archive {
+    /// Template for the cropped image; the extension picks the format
+    /// (PNG if unknown).
+    pub image: Option<String>,
+    /// Template for the answer text.
+    pub answer: Option<String>,
+}
+
+impl Archive {
+    /// Returns whether anything is archived.
+    pub fn is_enabled(&self) -> bool {
+        self.image.is_some() || self.answer.is_some()
+    }
+
+    /// Fills in the `{monitor}` placeholder.
+    pub fn for_monitor(&self, monitor: &str) -> Self {
+        let bind = |template: &Option<String>| {
+            template
+                .as_ref()
+                .map(|template| template.replace("{monitor}", monitor))
+        };
+        Self {
+            image: bind(&self.image),
+            answer: bind(&self.answer),
+        }
+    }
+
+    /// Writes the crop and the answer to their rendered paths.
+    ///
+    /// Returns the paths written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a template has an invalid time field or a file
+    /// cannot be written.
+    pub fn save(&self, crop: &DynamicImage, answer: &str, model: &str) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        if let Some(template) = &self.image {
+            let path = render(template, model)?;
+            create_parent(&path)?;
+            let format = ImageFormat::from_path(&path).unwrap_or(ImageFormat::Png);
+            ImageProcessor::save(crop, &path, format)?;
+            written.push(path);
+        }
+        if let Some(template) = &self.answer {
+            let path = render(template, model)?;
+            create_parent(&path)?;
+            fs::write(&path, answer)?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+/// Renders a template for the current local time.
+///
+/// Placeholders are filled in after the time fields, so a `%` in a model
+/// name is kept as is. `/` in model names (gateway model strings) would
+/// create directories and is replaced by `-`.
+fn render(template: &str, model: &str) -> Result<PathBuf> {
+    let mut path = String::new();
+    write!(path, "{}", Local::now().format(template))
+        .map_err(|_| AppError::config(format!("Invalid time field in '{}'", template)))?;
+    let path = path
+        .replace("{model}", &model.replace('/', "-"))
+        .replace("{monitor}", "unknown");
+    Ok(paths::expand_home(&path))
+}
+
+/// Creates the directory a file will be written to.
+fn create_parent(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => Ok(fs::create_dir_all(dir)?),
+        _ => Ok(()),
+    }
+}
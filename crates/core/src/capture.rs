@@ -6,7 +6,7 @@
 //! # Example
 //!
 //! ```ignore
-//! use ai_shot_core::capture::ScreenCapturer;
+//! use ai_shot_core::capture::{MonitorSelector, ScreenCapturer};
 //!
 //! let capturer = ScreenCapturer::new()?;
 //!
@@ -17,11 +17,170 @@
 //!
 //! // Capture the primary screen
 //! let screenshot = capturer.capture_screen()?;
+//!
+//! // Or the one under the mouse pointer
+//! let index = capturer.resolve_monitor(&MonitorSelector::Cursor)?;
+//! let screenshot = capturer.capture_screen_by_index(index)?;
 //! ```
 
+use crate::color::{DisplayProfile, ProfileSource};
 use crate::error::{AppError, Result};
 use image::DynamicImage;
+use mouse_position::mouse_position::Mouse;
 use screenshots::Screen;
+use std::fmt;
+use std::str::FromStr;
+
+/// Geometry of a top-level window, used to snap selections to windows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WindowInfo {
+    /// Window title (may be empty).
+    pub title: String,
+    /// Left edge in captured-image pixels, relative to the screen.
+    pub x: i32,
+    /// Top edge in captured-image pixels, relative to the screen.
+    pub y: i32,
+    /// Width in captured-image pixels.
+    pub width: u32,
+    /// Height in captured-image pixels.
+    pub height: u32,
+}
+
+/// The window that has the keyboard focus.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FocusedWindow {
+    /// Name of the application, e.g. `code` or `firefox` (may be empty).
+    pub app_name: String,
+    /// Window title (may be empty).
+    pub title: String,
+}
+
+/// Position and size of a screen on the desktop, in logical pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenGeometry {
+    /// Left edge on the virtual desktop.
+    pub x: i32,
+    /// Top edge on the virtual desktop.
+    pub y: i32,
+    /// Width in logical pixels.
+    pub width: u32,
+    /// Height in logical pixels.
+    pub height: u32,
+    /// Ratio of captured (physical) to logical pixels.
+    pub scale_factor: f32,
+}
+
+/// A monitor as listed by [`ScreenCapturer::monitors`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorInfo {
+    /// Zero-based index used by the capture functions.
+    pub index: usize,
+    /// Output name reported by the system (e.g. `DP-2`), if known.
+    pub name: Option<String>,
+    /// Desktop geometry of the monitor.
+    pub geometry: ScreenGeometry,
+    /// Whether this is the primary monitor.
+    pub is_primary: bool,
+}
+
+impl fmt::Display for MonitorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Monitor {}", self.index)?;
+        match (&self.name, self.is_primary) {
+            (Some(name), true) => write!(f, " ({}, primary)", name)?,
+            (Some(name), false) => write!(f, " ({})", name)?,
+            (None, true) => write!(f, " (primary)")?,
+            (None, false) => {}
+        }
+        write!(
+            f,
+            ": {}x{} (scale: {})",
+            self.geometry.width, self.geometry.height, self.geometry.scale_factor
+        )
+    }
+}
+
+/// Which monitor to capture, as given on the command line.
+///
+/// Parses from an index (`1`), `cursor`, or an output name (`DP-2`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MonitorSelector {
+    /// Zero-based index.
+    Index(usize),
+    /// The monitor containing the mouse pointer.
+    Cursor,
+    /// Output name, matched case-insensitively (a unique prefix suffices).
+    Name(String),
+}
+
+impl Default for MonitorSelector {
+    fn default() -> Self {
+        Self::Index(0)
+    }
+}
+
+impl FromStr for MonitorSelector {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(AppError::config("Monitor must be an index, 'cursor', or a name"));
+        }
+        Ok(match s.parse() {
+            Ok(index) => Self::Index(index),
+            Err(_) if s.eq_ignore_ascii_case("cursor") => Self::Cursor,
+            Err(_) => Self::Name(s.to_string()),
+        })
+    }
+}
+
+impl fmt::Display for MonitorSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "{}", index),
+            Self::Cursor => f.write_str("cursor"),
+            Self::Name(name) => f.write_str(name),
+        }
+    }
+}
+
+/// What the daemon captures when its hotkey is pressed.
+///
+/// Parses from `all` or anything a [`MonitorSelector`] parses from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DaemonMonitor {
+    /// One monitor; by default the one containing the mouse pointer.
+    Monitor(MonitorSelector),
+    /// Every monitor, with the overlay spanning the whole desktop.
+    All,
+}
+
+impl Default for DaemonMonitor {
+    fn default() -> Self {
+        Self::Monitor(MonitorSelector::Cursor)
+    }
+}
+
+impl FromStr for DaemonMonitor {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if s.trim().eq_ignore_ascii_case("all") {
+            return Ok(Self::All);
+        }
+        s.parse().map(Self::Monitor)
+    }
+}
+
+impl fmt::Display for DaemonMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Monitor(selector) => selector.fmt(f),
+            Self::All => f.write_str("all"),
+        }
+    }
+}
 
 /// Screen capturer that provides multi-monitor screenshot capabilities.
 ///
@@ -32,8 +191,13 @@ use screenshots::Screen;
 ///
 /// The capturer can be used from multiple threads, but each capture operation
 /// must complete before another can begin on the same screen.
+///
+/// Captures are converted to sRGB from the screens' color profiles set with
+/// [`Self::with_color_profile`].
 pub struct ScreenCapturer {
     screens: Vec<Screen>,
+    /// Color profile of each screen, by index, if its captures are converted
+    profiles: Vec<Option<DisplayProfile>>,
 }
 
 impl ScreenCapturer {
@@ -52,7 +216,23 @@ impl ScreenCapturer {
             return Err(AppError::capture("No screens detected"));
         }
 
-        Ok(Self { screens })
+        Ok(Self {
+            screens,
+            profiles: Vec::new(),
+        })
+    }
+
+    /// Converts captures to sRGB from the color profiles `source` gives for
+    /// each screen (see [`crate::color`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Config`] if a profile file cannot be read or used.
+    pub fn with_color_profile(mut self, source: &ProfileSource) -> Result<Self> {
+        self.profiles = (0..self.screens.len())
+            .map(|index| source.profile_for(index))
+            .collect::<Result<_>>()?;
+        Ok(self)
     }
 
     /// Lists available screens with their dimensions and metadata.
@@ -60,18 +240,103 @@ impl ScreenCapturer {
     /// Returns a vector of human-readable screen descriptions including
     /// resolution and scale factor.
     pub fn list_screen(&self) -> Vec<String> {
+        self.monitors().iter().map(ToString::to_string).collect()
+    }
+
+    /// Lists available monitors with their names and geometry.
+    ///
+    /// Names come from the windowing system and are `None` where it doesn't
+    /// report them.
+    pub fn monitors(&self) -> Vec<MonitorInfo> {
+        // Output names are only available through xcap; match its monitors by position
+        let named: Vec<(i32, i32, String)> = xcap::Monitor::all()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|monitor| {
+                Some((monitor.x().ok()?, monitor.y().ok()?, monitor.name().ok()?))
+            })
+            .collect();
+
         self.screens
             .iter()
             .enumerate()
-            .map(|(i, s)| {
-                format!(
-                    "Monitor {}: {}x{} (scale: {})",
-                    i, s.display_info.width, s.display_info.height, s.display_info.scale_factor
-                )
+            .map(|(index, screen)| {
+                let info = screen.display_info;
+                let name = named
+                    .iter()
+                    .find(|(x, y, _)| *x == info.x && *y == info.y)
+                    .map(|(_, _, name)| name.clone())
+                    .filter(|name| !name.is_empty());
+                MonitorInfo {
+                    index,
+                    name,
+                    geometry: ScreenGeometry {
+                        x: info.x,
+                        y: info.y,
+                        width: info.width,
+                        height: info.height,
+                        scale_factor: info.scale_factor,
+                    },
+                    is_primary: info.is_primary,
+                }
             })
             .collect()
     }
 
+    /// Resolves a [`MonitorSelector`] to a monitor index.
+    ///
+    /// # Errors
+    ///
+    /// Returns:
+    /// - [`AppError::ScreenNotFound`] if an index is out of bounds
+    /// - [`AppError::ScreenCapture`] if the pointer position is unavailable
+    ///   (e.g. on Wayland) or no single monitor matches a name
+    pub fn resolve_monitor(&self, selector: &MonitorSelector) -> Result<usize> {
+        match selector {
+            MonitorSelector::Index(index) if *index < self.screens.len() => Ok(*index),
+            MonitorSelector::Index(index) => Err(AppError::ScreenNotFound(*index)),
+            MonitorSelector::Cursor => {
+                let Some((x, y)) = self.pointer_position() else {
+                    return Err(AppError::capture(
+                        "Pointer position is unavailable; select the monitor by index or name",
+                    ));
+                };
+                self.screens
+                    .iter()
+                    .position(|screen| {
+                        let info = screen.display_info;
+                        x >= info.x
+                            && y >= info.y
+                            && x < info.x + info.width as i32
+                            && y < info.y + info.height as i32
+                    })
+                    .ok_or_else(|| {
+                        AppError::capture(format!("No monitor contains the pointer at {},{}", x, y))
+                    })
+            }
+            MonitorSelector::Name(query) => {
+                let monitors = self.monitors();
+                let name_of =
+                    |monitor: &MonitorInfo| monitor.name.as_deref().unwrap_or_default().to_lowercase();
+                let query = query.to_lowercase();
+                if let Some(exact) = monitors.iter().find(|monitor| name_of(monitor) == query) {
+                    return Ok(exact.index);
+                }
+                let mut matches = monitors
+                    .iter()
+                    .filter(|monitor| name_of(monitor).starts_with(&query));
+                match (matches.next(), matches.next()) {
+                    (Some(monitor), None) => Ok(monitor.index),
+                    (Some(_), Some(_)) => Err(AppError::capture(format!(
+                        "Monitor name '{}' is ambiguous",
+                        query
+                    ))),
+                    (None, _) => Err(AppError::capture(format!("No monitor named '{}'", query))),
+                }
+            }
+        }
+    }
+
     /// Captures the primary screen (first detected screen).
     ///
     /// # Errors
@@ -109,7 +374,105 @@ impl ScreenCapturer {
         let img_buffer = image::ImageBuffer::from_raw(width, height, rgba_data)
             .ok_or_else(|| AppError::capture("Failed to create image buffer"))?;
 
-        Ok(DynamicImage::ImageRgba8(img_buffer))
+        self.to_srgb(index, DynamicImage::ImageRgba8(img_buffer))
+    }
+
+    /// Returns the mouse pointer's position on the desktop, or `None` where
+    /// it is unavailable (e.g. on Wayland).
+    pub fn pointer_position(&self) -> Option<(i32, i32)> {
+        match Mouse::get_mouse_position() {
+            Mouse::Position { x, y } => Some((x, y)),
+            Mouse::Error => None,
+        }
+    }
+
+    /// Captures an area of the desktop, given in desktop coordinates like
+    /// [`Self::pointer_position`].
+    ///
+    /// The area is captured from the screen containing its top-left corner
+    /// and cut off at that screen's edges.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ScreenCapture`] if no screen contains the corner
+    /// or the capture fails.
+    pub fn capture_desktop_area(
+        &self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Result<DynamicImage> {
+        let index = self
+            .screen_at(x, y)
+            .ok_or_else(|| AppError::capture(format!("No monitor contains {},{}", x, y)))?;
+        let info = self.screens[index].display_info;
+        let image = self.capture_screen_by_index(index)?;
+
+        let scale = info.scale_factor;
+        let left = (((x - info.x) as f32 * scale) as u32).min(image.width());
+        let top = (((y - info.y) as f32 * scale) as u32).min(image.height());
+        let width = ((width as f32 * scale) as u32).min(image.width() - left);
+        let height = ((height as f32 * scale) as u32).min(image.height() - top);
+        Ok(image.crop_imm(left, top, width, height))
+    }
+
+    /// Captures every screen into one image laid out like the desktop.
+    ///
+    /// The image covers [`Self::desktop_geometry`] at the primary screen's
+    /// scale; screens with another scale are resized to match, and gaps
+    /// between screens stay transparent.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ScreenCapture`] if capturing any screen fails.
+    pub fn capture_desktop(&self) -> Result<DynamicImage> {
+        let desktop = self.desktop_geometry();
+        let scale = desktop.scale_factor;
+        let physical = |logical: u32| (logical as f32 * scale).round() as u32;
+        let mut canvas = image::RgbaImage::new(physical(desktop.width), physical(desktop.height));
+
+        for (index, screen) in self.screens.iter().enumerate() {
+            let info = screen.display_info;
+            let mut captured = self.capture_screen_by_index(index)?.to_rgba8();
+            let (width, height) = (physical(info.width), physical(info.height));
+            if captured.dimensions() != (width, height) {
+                captured = image::imageops::resize(
+                    &captured,
+                    width,
+                    height,
+                    image::imageops::FilterType::Triangle,
+                );
+            }
+            let x = ((info.x - desktop.x) as f32 * scale).round() as i64;
+            let y = ((info.y - desktop.y) as f32 * scale).round() as i64;
+            image::imageops::replace(&mut canvas, &captured, x, y);
+        }
+
+        Ok(DynamicImage::ImageRgba8(canvas))
+    }
+
+    /// Returns the bounding box of all screens with the primary screen's
+    /// scale factor.
+    pub fn desktop_geometry(&self) -> ScreenGeometry {
+        let infos: Vec<_> = self.screens.iter().map(|screen| screen.display_info).collect();
+        let left = infos.iter().map(|info| info.x).min().unwrap_or(0);
+        let top = infos.iter().map(|info| info.y).min().unwrap_or(0);
+        let right = infos.iter().map(|info| info.x + info.width as i32).max().unwrap_or(0);
+        let bottom = infos.iter().map(|info| info.y + info.height as i32).max().unwrap_or(0);
+        let scale_factor = infos
+            .iter()
+            .find(|info| info.is_primary)
+            .or(infos.first())
+            .map_or(1.0, |info| info.scale_factor);
+
+        ScreenGeometry {
+            x: left,
+            y: top,
+            width: (right - left) as u32,
+            height: (bottom - top) as u32,
+            scale_factor,
+        }
     }
 
     /// Captures a rectangular region from the primary screen.
@@ -142,7 +505,169 @@ impl ScreenCapturer {
         let img_buffer = image::ImageBuffer::from_raw(img_width, img_height, rgba_data)
             .ok_or_else(|| AppError::capture("Failed to create image buffer"))?;
 
-        Ok(DynamicImage::ImageRgba8(img_buffer))
+        self.to_srgb(0, DynamicImage::ImageRgba8(img_buffer))
+    }
+
+    /// Returns the desktop geometry of a screen.
+    ///
+    /// Used to place the overlay exactly over the captured screen when a
+    /// fullscreen window cannot be relied on.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ScreenNotFound`] if the index is out of bounds.
+    pub fn screen_geometry(&self, index: usize) -> Result<ScreenGeometry> {
+        let info = self
+            .screens
+            .get(index)
+            .ok_or(AppError::ScreenNotFound(index))?
+            .display_info;
+
+        Ok(ScreenGeometry {
+            x: info.x,
+            y: info.y,
+            width: info.width,
+            height: info.height,
+            scale_factor: info.scale_factor,
+        })
+    }
+
+    /// Lists visible windows on a screen, frontmost first.
+    ///
+    /// Coordinates are relative to the screen and scaled to the pixel
+    /// coordinates of images captured from it, so they can be mapped onto a
+    /// screenshot directly. Minimized and off-screen windows are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns:
+    /// - [`AppError::ScreenNotFound`] if the index is out of bounds
+    /// - [`AppError::ScreenCapture`] if windows cannot be enumerated
+    pub fn windows_on_screen(&self, index: usize) -> Result<Vec<WindowInfo>> {
+        let info = self
+            .screens
+            .get(index)
+            .ok_or(AppError::ScreenNotFound(index))?
+            .display_info;
+
+        let windows = xcap::Window::all()
+            .map_err(|e| AppError::capture(format!("Failed to enumerate windows: {}", e)))?;
+
+        // Window geometry is in logical pixels; captures are physical
+        let scale = info.scale_factor;
+        let screen_width = (info.width as f32 * scale) as i32;
+        let screen_height = (info.height as f32 * scale) as i32;
+
+        Ok(windows
+            .iter()
+            .filter(|window| !window.is_minimized().unwrap_or(true))
+            .filter_map(|window| {
+                let x = ((window.x().ok()? - info.x) as f32 * scale) as i32;
+                let y = ((window.y().ok()? - info.y) as f32 * scale) as i32;
+                let width = (window.width().ok()? as f32 * scale) as u32;
+                let height = (window.height().ok()? as f32 * scale) as u32;
+
+                let on_screen = width > 0
+                    && height > 0
+                    && x < screen_width
+                    && y < screen_height
+                    && x + width as i32 > 0
+                    && y + height as i32 > 0;
+                on_screen.then(|| WindowInfo {
+                    title: window.title().unwrap_or_default(),
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+            })
+            .collect())
+    }
+
+    /// Captures the window that has the keyboard focus.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ScreenCapture`] if windows cannot be enumerated,
+    /// none has the focus (e.g. the desktop does), or the capture fails.
+    pub fn capture_active_window(&self) -> Result<DynamicImage> {
+        let windows = xcap::Window::all()
+            .map_err(|e| AppError::capture(format!("Failed to enumerate windows: {}", e)))?;
+        let window = windows
+            .iter()
+            .find(|window| window.is_focused().unwrap_or(false))
+            .ok_or_else(|| AppError::capture("No window has the focus"))?;
+
+        let captured = window
+            .capture_image()
+            .map_err(|e| AppError::capture(format!("Failed to capture window: {}", e)))?;
+
+        // Converted with the profile of the screen showing its top left corner
+        let index = match (window.x(), window.y()) {
+            (Ok(x), Ok(y)) => self.screen_at(x, y).unwrap_or(0),
+            _ => 0,
+        };
+        self.to_srgb(index, DynamicImage::ImageRgba8(captured))
+    }
+
+    /// Returns the title of the focused window if it covers a whole screen,
+    /// as games and video players in fullscreen do.
+    ///
+    /// Returns `None` if windows can't be enumerated.
+    pub fn fullscreen_window(&self) -> Option<String> {
+        let windows = xcap::Window::all().ok()?;
+        let window = windows
+            .iter()
+            .find(|window| window.is_focused().unwrap_or(false))?;
+        if window.is_minimized().unwrap_or(true) {
+            return None;
+        }
+        let (x, y) = (window.x().ok()?, window.y().ok()?);
+        let (width, height) = (window.width().ok()?, window.height().ok()?);
+
+        // Allow for a pixel of rounding in the reported geometry
+        let fullscreen = self.screens.iter().any(|screen| {
+            let info = screen.display_info;
+            (x - info.x).abs() <= 1
+                && (y - info.y).abs() <= 1
+                && width.abs_diff(info.width) <= 1
+                && height.abs_diff(info.height) <= 1
+        });
+        fullscreen.then(|| window.title().unwrap_or_default())
+    }
+
+    /// Returns the application name and title of the focused window.
+    ///
+    /// Returns `None` if windows can't be enumerated or none has the focus.
+    pub fn focused_window(&self) -> Option<FocusedWindow> {
+        let windows = xcap::Window::all().ok()?;
+        let window = windows
+            .iter()
+            .find(|window| window.is_focused().unwrap_or(false))?;
+        Some(FocusedWindow {
+            app_name: window.app_name().unwrap_or_default(),
+            title: window.title().unwrap_or_default(),
+        })
+    }
+
+    /// Returns the index of the screen containing a desktop position.
+    fn screen_at(&self, x: i32, y: i32) -> Option<usize> {
+        self.screens.iter().position(|screen| {
+            let info = screen.display_info;
+            x >= info.x
+                && y >= info.y
+                && x < info.x + info.width as i32
+                && y < info.y + info.height as i32
+        })
+    }
+
+    /// Converts a capture of the screen at `index` to sRGB if a color
+    /// profile is set for it.
+    fn to_srgb(&self, index: usize, image: DynamicImage) -> Result<DynamicImage> {
+        match self.profiles.get(index).and_then(Option::as_ref) {
+            Some(profile) => profile.to_srgb(image),
+            None => Ok(image),
+        }
     }
 
     /// Returns the number of available screens.
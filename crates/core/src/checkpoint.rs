@@ -0,0 +1,183 @@
+//! Crash-safe checkpointing of in-progress responses.
+//!
+//! Very long generations can take minutes. To avoid losing most of an answer
+//! to a crash or power loss, the partial transcript is periodically written to
+//! a checkpoint file in the `checkpoints/` directory of the user's data
+//! directory. The checkpoint is cleared when the stream completes.
+//!
+//! Several overlays can stream at once (the daemon starts one per hotkey
+//! press, and a pinned answer stays open), so each process writes its own
+//! `<pid>.json` and holds a lock on `<pid>.lock` while it runs. Only
+//! checkpoints whose owner no longer holds its lock are offered for
+//! recovery; those of overlays that are still streaming are left alone.
+//!
+//! Writes go to a temporary file that is then renamed over the checkpoint, so
+//! a crash mid-write never leaves a truncated file behind.
+
+use crate::error::Result;
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A snapshot of a response that was still streaming.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// The prompt that produced the response.
+    pub prompt: String,
+    /// Model used for the request.
+    pub model: String,
+    /// Response text received so far.
+    pub text: String,
+    /// Thinking output received so far.
+    #[serde(default)]
+    pub thoughts: String,
+    /// Unix timestamp (seconds) of the last update.
+    pub updated_at: u64,
+    /// Process that wrote the checkpoint, as its file is named.
+    #[serde(skip)]
+    owner: String,
+}
+
+impl Checkpoint {
+    /// Creates an empty checkpoint for a new request.
+    pub fn new(prompt: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            model: model.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Directory of checkpoints, one per overlay process.
+pub struct CheckpointStore {
+    dir: PathBuf,
+    /// Name of this process's files, its process id.
+    id: String,
+    /// Lock file held while this process runs, so other processes leave
+    /// its checkpoint alone.
+    lock: Option<File>,
+}
+
+impl CheckpointStore {
+    /// Opens the store in the default data directory.
+    ///
+    /// Returns `None` if the platform data directory cannot be determined.
+    pub fn open_default() -> Option<Self> {
+        paths::checkpoint_dir().map(Self::in_dir)
+    }
+
+    /// Opens a store in a specific directory, claiming this process's
+    /// checkpoint for as long as the store lives.
+    pub fn in_dir(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let id = std::process::id().to_string();
+        let lock = fs::create_dir_all(&dir)
+            .and_then(|()| File::create(dir.join(format!("{}.lock", id))))
+            .and_then(|file| file.lock().map(|()| file));
+        let lock = match lock {
+            Ok(file) => Some(file),
+            Err(e) => {
+                tracing::warn!(error = %e, "failed to lock the response checkpoint");
+                None
+            }
+        };
+        Self { dir, id, lock }
+    }
+
+    /// Atomically writes the checkpoint, stamping the update time.
+    ///
+    /// # Errors
+    /// Returns an error if serialization or file writing fails.
+    pub fn save(&self, checkpoint: &mut Checkpoint) -> Result<()> {
+        checkpoint.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        fs::create_dir_all(&self.dir)?;
+        let path = self.checkpoint_path(&self.id);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_vec(checkpoint)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+
+    /// Loads the newest checkpoint left by an interrupted response, if any.
+    ///
+    /// Checkpoints of other processes that are still running are skipped.
+    pub fn load(&self) -> Option<Checkpoint> {
+        let entries = fs::read_dir(&self.dir).ok()?;
+        entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                if path.extension()? != "json" {
+                    return None;
+                }
+                let owner = path.file_stem()?.to_str()?.to_string();
+                if self.is_running(&owner) {
+                    return None;
+                }
+                let bytes = fs::read(&path).ok()?;
+                let mut checkpoint: Checkpoint = serde_json::from_slice(&bytes).ok()?;
+                checkpoint.owner = owner;
+                Some(checkpoint)
+            })
+            .max_by_key(|checkpoint| checkpoint.updated_at)
+    }
+
+    /// Removes a recovered checkpoint once it has been dismissed.
+    pub fn discard(&self, checkpoint: &Checkpoint) {
+        if checkpoint.owner.is_empty() || self.is_running(&checkpoint.owner) {
+            return;
+        }
+        let _ = fs::remove_file(self.checkpoint_path(&checkpoint.owner));
+        if checkpoint.owner != self.id {
+            let _ = fs::remove_file(self.lock_path(&checkpoint.owner));
+        }
+    }
+
+    /// Removes this process's checkpoint once the response has completed
+    /// or been cancelled.
+    pub fn clear(&self) {
+        let _ = fs::remove_file(self.checkpoint_path(&self.id));
+    }
+
+    /// Returns whether the process that owns `id`'s checkpoint still runs.
+    ///
+    /// A checkpoint with this process's id is left over from an earlier
+    /// process that had the same id, since this one has not written yet
+    /// when recovering. Where locks can't be checked, the owner is assumed
+    /// to run, so a live stream is never taken over.
+    fn is_running(&self, id: &str) -> bool {
+        if id == self.id {
+            return false;
+        }
+        let Ok(file) = File::open(self.lock_path(id)) else {
+            return false;
+        };
+        // Held by the owner (`WouldBlock`), or unsupported here
+        file.try_lock().is_err()
+    }
+
+    /// Returns the path of `id`'s checkpoint.
+    fn checkpoint_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Returns the path of the file `id` holds locked while it runs.
+    fn lock_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.lock", id))
+    }
+}
+
+impl Drop for CheckpointStore {
+    fn drop(&mut self) {
+        // An unfinished checkpoint stays behind for the next overlay to offer
+        if self.lock.take().is_some() {
+            let _ = fs::remove_file(self.lock_path(&self.id));
+        }
+    }
+}
@@ -0,0 +1,122 @@
+//! Lightweight content classification for captured selections.
+//!
+//! This module inspects the pixels of a selection and guesses what kind of
+//! content it shows (source code, a chart, prose, a photo). The result is used
+//! to pick a sensible default prompt when the user leaves the prompt box empty.
+//!
+//! # Heuristics
+//!
+//! Classification is purely statistical and runs locally in a few milliseconds:
+//!
+//! - **Background dominance**: screenshots of code and text are mostly a single
+//!   background color, while photos spread across many colors.
+//! - **Accent coverage**: syntax highlighting produces thin, sparse strokes of
+//!   saturated color, whereas charts contain large filled colored areas.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::classifier::{classify, ContentKind};
+//!
+//! let kind = classify(&cropped);
+//! println!("Suggested prompt: {}", kind.default_prompt());
+//! ```
+
+use image::DynamicImage;
+use std::collections::HashMap;
+
+/// Longest edge of the downsampled image used for classification.
+const SAMPLE_SIZE: u32 = 128;
+
+/// Minimum channel spread for a pixel to count as a saturated accent color.
+const SATURATION_THRESHOLD: u8 = 60;
+
+/// Broad category of content detected in a selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentKind {
+    /// Source code or terminal output.
+    Code,
+    /// A chart, graph, or plot.
+    Chart,
+    /// Prose, documents, or web pages.
+    Text,
+    /// A photograph or other natural image.
+    Photo,
+    /// Content that could not be classified confidently.
+    #[default]
+    General,
+}
+
+impl ContentKind {
+    /// Returns the prompt used when the user submits without typing one.
+    pub fn default_prompt(self) -> &'static str {
+        match self {
+            Self::Code => "Explain this code.",
+            Self::Chart => "Summarize this chart.",
+            Self::Text => "Summarize this text.",
+            Self::Photo => "Describe this image.",
+            Self::General => "Explain this image in detail.",
+        }
+    }
+}
+
+/// Classifies the content of an image using color statistics.
+///
+/// The image is downsampled before analysis, so this is cheap enough to run
+/// every time a selection is finalized.
+pub fn classify(image: &DynamicImage) -> ContentKind {
+    if image.width() == 0 || image.height() == 0 {
+        return ContentKind::General;
+    }
+
+    let sample = image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgb8();
+    let total = (sample.width() * sample.height()) as f32;
+
+    // Quantize to 4 bits per channel and find the dominant (background) color
+    let mut buckets: HashMap<u16, u32> = HashMap::new();
+    for pixel in sample.pixels() {
+        let [r, g, b] = pixel.0;
+        let key = ((r as u16 >> 4) << 8) | ((g as u16 >> 4) << 4) | (b as u16 >> 4);
+        *buckets.entry(key).or_insert(0) += 1;
+    }
+
+    let (background, background_count) = buckets
+        .iter()
+        .max_by_key(|(_, count)| **count)
+        .map(|(key, count)| (*key, *count))
+        .unwrap_or((0, 0));
+    let dominance = background_count as f32 / total;
+
+    // Ignore buckets that only contain antialiasing noise
+    let noise_floor = (total / 500.0).max(1.0) as u32;
+    let distinct_colors = buckets.values().filter(|c| **c > noise_floor).count();
+
+    let accent_coverage = sample
+        .pixels()
+        .filter(|p| {
+            let [r, g, b] = p.0;
+            r.max(g).max(b) - r.min(g).min(b) > SATURATION_THRESHOLD
+        })
+        .count() as f32
+        / total;
+
+    // Background luminance from the quantized bucket (0-15 per channel)
+    let bg_luma = 0.299 * ((background >> 8) & 0xF) as f32
+        + 0.587 * ((background >> 4) & 0xF) as f32
+        + 0.114 * (background & 0xF) as f32;
+    let dark_background = bg_luma < 5.0;
+
+    if dominance < 0.35 && distinct_colors > 150 {
+        ContentKind::Photo
+    } else if dominance >= 0.5 {
+        if accent_coverage > 0.12 {
+            ContentKind::Chart
+        } else if accent_coverage > 0.01 || dark_background {
+            ContentKind::Code
+        } else {
+            ContentKind::Text
+        }
+    } else {
+        ContentKind::General
+    }
+}
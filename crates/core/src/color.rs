@@ -0,0 +1,199 @@
+//! Converting captures from the monitor's color profile to sRGB.
+//!
+//! Screenshots hold the values sent to the monitor. On a wide-gamut monitor
+//! with a color-managed desktop these are in the monitor's color space, and
+//! since models (like image viewers) read untagged images as sRGB, uploaded
+//! crops come out oversaturated. [`DisplayProfile::to_srgb`] converts them
+//! with the monitor's ICC profile, so the model sees the colors the user
+//! sees.
+//!
+//! Which profile is used is set by [`ProfileSource`] (`color_profile` in
+//! `config.toml` or `AI_SHOT_COLOR_PROFILE`):
+//!
+//! - `auto` (the default): the profile the desktop assigned to the monitor,
+//!   where it can be read. On X11 this is the `_ICC_PROFILE` property that
+//!   colord and the desktops' color settings set; elsewhere captures are
+//!   left as they are.
+//! - `off`: captures are never converted.
+//! - The path of an `.icc` or `.icm` file, used for every monitor.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::color::ProfileSource;
+//!
+//! if let Some(profile) = ProfileSource::Auto.profile_for(0)? {
+//!     screenshot = profile.to_srgb(screenshot)?;
+//! }
+//! ```
+
+use crate::error::{AppError, Result};
+use image::{DynamicImage, RgbaImage};
+use moxcms::{ColorProfile, DataColorSpace, Layout, Transform8BitExecutor, TransformOptions};
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::Arc;
+
+/// Where the color profile of a monitor comes from.
+///
+/// Parses from `auto`, `off` (or `none`), or the path of a profile.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ProfileSource {
+    /// The profile the desktop assigned to the monitor, if it can be read.
+    #[default]
+    Auto,
+    /// No conversion.
+    Off,
+    /// An ICC profile file, for every monitor.
+    File(PathBuf),
+}
+
+impl ProfileSource {
+    /// Returns the profile of the screen at `screen_index`, or `None` if
+    /// captures of it are left as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Config`] if a profile file cannot be read or is
+    /// not an RGB display profile.
+    pub fn profile_for(&self, screen_index: usize) -> Result<Option<DisplayProfile>> {
+        match self {
+            ProfileSource::Auto => Ok(DisplayProfile::detect(screen_index)),
+            ProfileSource::Off => Ok(None),
+            ProfileSource::File(path) => DisplayProfile::load(path).map(Some),
+        }
+    }
+}
+
+impl FromStr for ProfileSource {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "" => Err(AppError::config("Color profile must be 'auto', 'off', or a file path")),
+            "auto" => Ok(ProfileSource::Auto),
+            "off" | "none" => Ok(ProfileSource::Off),
+            _ => Ok(ProfileSource::File(PathBuf::from(s))),
+        }
+    }
+}
+
+impl fmt::Display for ProfileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileSource::Auto => f.write_str("auto"),
+            ProfileSource::Off => f.write_str("off"),
+            ProfileSource::File(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// A monitor's ICC profile, ready to convert captures to sRGB.
+#[derive(Clone)]
+pub struct DisplayProfile {
+    transform: Arc<Transform8BitExecutor>,
+}
+
+impl DisplayProfile {
+    /// Reads a profile from the bytes of an ICC file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if the profile is malformed or
+    /// not an RGB profile.
+    pub fn from_icc(icc: &[u8]) -> Result<Self> {
+        Self::parse(icc).map_err(AppError::image)
+    }
+
+    /// Reads a profile from an ICC file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Config`] if the file cannot be read or holds no
+    /// usable profile.
+    pub fn load(path: &Path) -> Result<Self> {
+        let icc = fs::read(path).map_err(|e| {
+            AppError::config(format!("Failed to read color profile {}: {}", path.display(), e))
+        })?;
+        Self::parse(&icc)
+            .map_err(|e| AppError::config(format!("Color profile {}: {}", path.display(), e)))
+    }
+
+    /// Returns the profile the desktop assigned to the screen at
+    /// `screen_index`, or `None` if there is none or it can't be read.
+    pub fn detect(screen_index: usize) -> Option<Self> {
+        let icc = x11_profile(screen_index)?;
+        match Self::parse(&icc) {
+            Ok(profile) => Some(profile),
+            Err(e) => {
+                tracing::warn!(
+                    screen = screen_index,
+                    error = %e,
+                    "ignoring the monitor's color profile"
+                );
+                None
+            }
+        }
+    }
+
+    /// Converts a capture from the monitor's colors to sRGB, keeping its
+    /// transparency.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if the conversion fails.
+    pub fn to_srgb(&self, image: DynamicImage) -> Result<DynamicImage> {
+        let source = image.into_rgba8();
+        let mut converted = RgbaImage::new(source.width(), source.height());
+        self.transform
+            .transform(source.as_raw(), &mut converted)
+            .map_err(|e| AppError::image(format!("Color conversion failed: {}", e)))?;
+        Ok(DynamicImage::ImageRgba8(converted))
+    }
+
+    fn parse(icc: &[u8]) -> std::result::Result<Self, String> {
+        let profile =
+            ColorProfile::new_from_slice(icc).map_err(|e| format!("Invalid ICC profile: {}", e))?;
+        if profile.color_space != DataColorSpace::Rgb {
+            return Err("Not an RGB display profile".to_string());
+        }
+        let transform = profile
+            .create_transform_8bit(
+                Layout::Rgba,
+                &ColorProfile::new_srgb(),
+                Layout::Rgba,
+                TransformOptions::default(),
+            )
+            .map_err(|e| format!("Unsupported ICC profile: {}", e))?;
+        Ok(Self {
+            transform: Arc::from(transform),
+        })
+    }
+}
+
+/// Reads the X11 root window property holding a screen's profile:
+/// `_ICC_PROFILE` for the first screen, `_ICC_PROFILE_1` for the second
+/// and so on.
+fn x11_profile(screen_index: usize) -> Option<Vec<u8>> {
+    if !cfg!(target_os = "linux") || env::var_os("DISPLAY").is_none() {
+        return None;
+    }
+    let atom = match screen_index {
+        0 => "_ICC_PROFILE".to_string(),
+        index => format!("_ICC_PROFILE_{}", index),
+    };
+    let output = Command::new("xprop").args(["-root", "-notype", atom.as_str()]).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    // Printed as `_ICC_PROFILE = 0, 0, 12, 72, ...`, or "not found" if unset
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (_, bytes) = text.split_once('=')?;
+    bytes.split(',').map(|byte| byte.trim().parse().ok()).collect()
+}
@@ -1,10 +1,92 @@
 //! Configuration management for ai-shot.
 //!
-//! This module handles loading configuration from environment variables
-//! and `.env` files, with a builder pattern for flexible initialization.
+//! Each value is taken from the highest of these layers that sets it:
+//!
+//! 1. Built-in defaults
+//! 2. `config.toml` in the config directory (see [`Config::file_path`])
+//! 3. The overlay's saved settings, when given to
+//!    [`ConfigBuilder::with_settings`]
+//! 4. Environment variables, including `.env` files
+//! 5. The active profile (see [`crate::profiles`])
+//! 6. Values set on the [`ConfigBuilder`], e.g. from command-line flags
+//!
+//! The overlay makes every request with [`Config::with_settings`], so a
+//! model picked in its settings window is used unless `GEMINI_MODEL`, a
+//! profile or `--model` says otherwise, and likewise for the API key,
+//! preset, system prompt and response language.
+//!
+//! [`Config::source`] reports which layer each value came from, and
+//! [`Config::subscribe`] reports changes to the files while running.
+//!
+//! ```toml
+//! model = "gemini-2.5-pro"
+//! response_language = "de"
+//! overlay_mode = "borderless"
+//! hotkey = "ctrl+shift+s"
+//! daemon_monitor = "all"
+//! pause_in_fullscreen = false
+//! image_encoding = "png"
+//! max_image_pixels = 4000000
+//! upscale_min_edge = 256
+//! upscale_filter = "nearest"
+//! color_profile = "/usr/share/color/icc/monitor.icc"
+//!
+//! [hotkeys]
+//! "ctrl+alt+o" = "ocr"
+//!
+//! [timeouts]
+//! read = 300
+//!
+//! [history]
+//! max_age_days = 90
+//! max_entries = 1000
+//!
+//! [network]
+//! proxy = "http://proxy.corp:8080"
+//!
+//! [[plugins]]
+//! name = "Post to Slack"
+//! command = "~/bin/slack-post"
+//!
+//! [[rules]]
+//! when = { app = ["code"] }
+//! then = { preset = "code-reviewer" }
+//!
+//! [upload]
+//! destination = "imgur"
+//!
+//! [upload.imgur]
+//! client_id = "1a2b3c4d5e6f7a8"
+//!
+//! [usage]
+//! monthly_budget = 20.0
+//! ```
 
+use crate::archive::Archive;
+use crate::capture::DaemonMonitor;
+use crate::color::ProfileSource;
+use crate::diagnostics::{self, Diagnostic};
 use crate::error::{AppError, Result};
+use crate::history::Retention;
+use crate::hotkey::{Hotkey, HotkeyAction};
+use crate::image_processing::{ImageEncoding, UpscaleFilter};
+use crate::paths;
+use crate::plugins::Plugin;
+use crate::presets::SystemPromptPreset;
+use crate::profiles::{ProfileOpenAi, ProfileVertex, Profiles};
+use crate::rules::Rule;
+use crate::ui::{Settings, DEFAULT_HOTKEY};
+use crate::upload::{Destination, ImgurOptions, S3Options, UploadConfig, WebhookOptions};
+use crate::usage::Budget;
+use crate::watch::ConfigUpdates;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::env;
+use std::fmt;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// Application configuration containing API keys and model settings.
 ///
@@ -24,15 +106,512 @@ pub struct Config {
     pub gemini_api_key: String,
     /// Model name to use (e.g., "gemini-flash-latest").
     pub model_name: String,
+    /// Vertex AI settings; when set, requests go to Vertex instead of the
+    /// public Generative Language API and the API key is not used.
+    pub vertex: Option<VertexConfig>,
+    /// OpenAI-compatible gateway; when set, requests are sent there with
+    /// `model_name` as the model string instead of to the Gemini API.
+    pub openai: Option<OpenAiConfig>,
+    /// Network timeouts applied to API requests.
+    pub timeouts: TimeoutConfig,
+    /// Proxy and TLS settings for corporate networks.
+    pub network: NetworkConfig,
+    /// System prompt preset overriding the one saved in UI settings.
+    pub preset: Option<SystemPromptPreset>,
+    /// Custom system prompt overriding the one saved in UI settings.
+    pub system_prompt: Option<String>,
+    /// Language answers are written in (code or name, e.g. "de" or "German"),
+    /// overriding the one saved in UI settings.
+    pub response_language: Option<String>,
+    /// How the selection overlay covers the screen.
+    pub overlay_mode: OverlayMode,
+    /// Global hotkey the daemon opens the overlay with.
+    pub hotkey: Hotkey,
+    /// More daemon hotkeys and their actions, from the `[hotkeys]` table.
+    pub hotkeys: Vec<(Hotkey, HotkeyAction)>,
+    /// What the daemon captures when a hotkey is pressed.
+    pub daemon_monitor: DaemonMonitor,
+    /// Ignore the daemon's hotkeys while the focused window is fullscreen.
+    pub pause_in_fullscreen: bool,
+    /// How images are encoded for upload.
+    pub image_encoding: ImageEncoding,
+    /// Most pixels an uploaded image may have; larger ones are downscaled
+    /// before encoding. `None` (or `0` in the config) leaves them as they are.
+    pub max_image_pixels: Option<u64>,
+    /// Shortest edge an uploaded image should have; smaller ones are
+    /// enlarged before encoding. `None` (or `0` in the config) leaves them
+    /// as they are.
+    pub upscale_min_edge: Option<u32>,
+    /// How images below [`Self::upscale_min_edge`] are enlarged.
+    pub upscale_filter: UpscaleFilter,
+    /// Where the monitors' color profiles come from, to convert captures
+    /// to sRGB.
+    pub color_profile: ProfileSource,
+    /// Copy each finished answer to the clipboard.
+    pub copy_answers: bool,
+    /// Where each answered crop and answer are saved, if anywhere.
+    pub archive: Archive,
+    /// How long and how many entries the history keeps.
+    pub history: Retention,
+    /// Programs the answer can be handed to, from the `[[plugins]]` tables.
+    pub plugins: Vec<Plugin>,
+    /// Rules the daemon adjusts captures with, from the `[[rules]]` tables.
+    pub rules: Vec<Rule>,
+    /// Where shared selections are uploaded, from the `[upload]` table.
+    pub upload: Option<UploadConfig>,
+    /// Monthly budget for the estimated spend, from the `[usage]` table.
+    pub budget: Budget,
+    /// Name of the profile the configuration was built with, if any.
+    pub profile: Option<String>,
+    /// Layer each value came from, by key
+    sources: BTreeMap<&'static str, ConfigSource>,
+    /// Builder values the configuration is rebuilt from on reload
+    overrides: ConfigBuilder,
+}
+
+/// Where a configuration value came from, from lowest to highest precedence.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// The built-in default.
+    Default,
+    /// The config file.
+    File,
+    /// The overlay's saved settings (`settings.json` or the keyring).
+    Settings,
+    /// An environment variable (possibly from a `.env` file).
+    Env(&'static str),
+    /// The named profile.
+    Profile(String),
+    /// Set on the [`ConfigBuilder`].
+    Explicit,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "default"),
+            ConfigSource::File => write!(f, "config file"),
+            ConfigSource::Settings => write!(f, "saved settings"),
+            ConfigSource::Env(name) => write!(f, "environment ({})", name),
+            ConfigSource::Profile(name) => write!(f, "profile '{}'", name),
+            ConfigSource::Explicit => write!(f, "explicit"),
+        }
+    }
+}
+
+/// How the selection overlay window covers the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayMode {
+    /// A fullscreen window, replaced by a borderless one if the window
+    /// manager doesn't place it exactly over the captured monitor.
+    #[default]
+    Fullscreen,
+    /// A borderless window sized to the captured monitor from the start,
+    /// for tiling window managers that mishandle fullscreen requests.
+    Borderless,
+}
+
+impl OverlayMode {
+    /// Reads `AI_SHOT_OVERLAY` (`fullscreen` or `borderless`).
+    fn from_env() -> Option<Self> {
+        match env::var("AI_SHOT_OVERLAY").ok()?.trim().to_ascii_lowercase().as_str() {
+            "borderless" => Some(OverlayMode::Borderless),
+            "fullscreen" => Some(OverlayMode::Fullscreen),
+            _ => None,
+        }
+    }
+}
+
+/// Proxy and certificate settings for the HTTP client.
+///
+/// When no proxy is configured, the standard `HTTPS_PROXY`/`NO_PROXY`
+/// environment variables are still honored by the HTTP client.
+#[derive(Clone, Debug, Default)]
+pub struct NetworkConfig {
+    /// Proxy URL for all requests (e.g., "http://proxy.corp:8080").
+    pub proxy: Option<String>,
+    /// Comma-separated hosts that bypass the proxy (e.g., "localhost,.corp").
+    pub no_proxy: Option<String>,
+    /// PEM file with additional root certificates to trust.
+    pub ca_bundle: Option<PathBuf>,
+}
+
+impl NetworkConfig {
+    /// Reads `AI_SHOT_PROXY`, `AI_SHOT_NO_PROXY`, and `AI_SHOT_CA_BUNDLE`,
+    /// falling back to the config file's `[network]` table.
+    fn layered(layers: &mut Layers, file: FileNetwork) -> Self {
+        let non_empty = |name: &str| env::var(name).ok().filter(|v| !v.trim().is_empty());
+        let proxy = [
+            (non_empty("AI_SHOT_PROXY"), ConfigSource::Env("AI_SHOT_PROXY")),
+            (file.proxy, ConfigSource::File),
+        ];
+        let no_proxy = [
+            (non_empty("AI_SHOT_NO_PROXY"), ConfigSource::Env("AI_SHOT_NO_PROXY")),
+            (file.no_proxy, ConfigSource::File),
+        ];
+        let ca_bundle = [
+            (
+                non_empty("AI_SHOT_CA_BUNDLE").map(PathBuf::from),
+                ConfigSource::Env("AI_SHOT_CA_BUNDLE"),
+            ),
+            (file.ca_bundle, ConfigSource::File),
+        ];
+        Self {
+            proxy: layers.pick("network.proxy", proxy),
+            no_proxy: layers.pick("network.no_proxy", no_proxy),
+            ca_bundle: layers.pick("network.ca_bundle", ca_bundle),
+        }
+    }
+}
+
+/// Network timeout and keepalive settings for API requests.
+///
+/// Long thinking-mode responses can legitimately take minutes, so the read
+/// timeout bounds the gap *between* streamed chunks while `deadline` bounds
+/// the whole request.
+#[derive(Clone, Debug)]
+pub struct TimeoutConfig {
+    /// Maximum time to establish a connection.
+    pub connect: Duration,
+    /// Maximum time to wait for data on an open connection.
+    pub read: Duration,
+    /// Overall deadline for a request, including streaming (`None` for no limit).
+    pub deadline: Option<Duration>,
+    /// TCP keepalive interval (`None` to disable).
+    pub keepalive: Option<Duration>,
+}
+
+impl TimeoutConfig {
+    /// Reads overrides from `AI_SHOT_CONNECT_TIMEOUT`, `AI_SHOT_READ_TIMEOUT`,
+    /// `AI_SHOT_REQUEST_DEADLINE`, and `AI_SHOT_KEEPALIVE`, falling back to
+    /// the config file's `[timeouts]` table (in seconds; `0` disables the
+    /// deadline or keepalive).
+    fn layered(layers: &mut Layers, file: FileTimeouts) -> Self {
+        let mut secs = |key: &'static str, name: &'static str, file_value: Option<u64>| {
+            let value = env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+            layers
+                .pick(key, [(value, ConfigSource::Env(name)), (file_value, ConfigSource::File)])
+                .map(Duration::from_secs)
+        };
+        let optional = |value: Option<Duration>, default: Option<Duration>| match value {
+            Some(d) if d.is_zero() => None,
+            Some(d) => Some(d),
+            None => default,
+        };
+
+        let defaults = Self::default();
+        Self {
+            connect: secs("timeouts.connect", "AI_SHOT_CONNECT_TIMEOUT", file.connect)
+                .unwrap_or(defaults.connect),
+            read: secs("timeouts.read", "AI_SHOT_READ_TIMEOUT", file.read).unwrap_or(defaults.read),
+            deadline: optional(
+                secs("timeouts.deadline", "AI_SHOT_REQUEST_DEADLINE", file.deadline),
+                defaults.deadline,
+            ),
+            keepalive: optional(
+                secs("timeouts.keepalive", "AI_SHOT_KEEPALIVE", file.keepalive),
+                defaults.keepalive,
+            ),
+        }
+    }
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            connect: Duration::from_secs(10),
+            read: Duration::from_secs(120),
+            deadline: Some(Duration::from_secs(600)),
+            keepalive: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+/// Google Cloud project settings for the Vertex AI endpoint.
+#[derive(Clone, Debug)]
+pub struct VertexConfig {
+    /// Google Cloud project ID.
+    pub project: String,
+    /// Region of the endpoint (e.g., "us-central1"), or "global".
+    pub location: String,
+    /// Service-account key file; Application Default Credentials are used when `None`.
+    pub credentials: Option<PathBuf>,
+}
+
+/// Endpoint settings for an OpenAI-compatible chat-completions gateway.
+#[derive(Clone, Debug)]
+pub struct OpenAiConfig {
+    /// Base URL of the API, up to and including the version
+    /// (e.g., "http://localhost:4000/v1" or "https://openrouter.ai/api/v1").
+    pub base_url: String,
+    /// Bearer token for the gateway (may be empty for local proxies).
+    pub api_key: String,
+}
+
+impl OpenAiConfig {
+    /// Reads `AI_SHOT_OPENAI_BASE_URL` and `AI_SHOT_OPENAI_API_KEY`
+    /// (falling back to `OPENAI_API_KEY`).
+    fn from_env() -> Option<Self> {
+        let base_url = env::var("AI_SHOT_OPENAI_BASE_URL")
+            .ok()
+            .filter(|v| !v.trim().is_empty())?;
+        let api_key = env::var("AI_SHOT_OPENAI_API_KEY")
+            .or_else(|_| env::var("OPENAI_API_KEY"))
+            .unwrap_or_default();
+        Some(Self { base_url, api_key })
+    }
+}
+
+/// Parses the `[hotkeys]` table, rejecting hotkeys bound twice.
+fn parse_hotkeys(table: &BTreeMap<String, String>) -> Result<Vec<(Hotkey, HotkeyAction)>> {
+    let mut hotkeys: Vec<(Hotkey, HotkeyAction)> = Vec::with_capacity(table.len());
+    for (hotkey, action) in table {
+        let hotkey: Hotkey = hotkey.parse()?;
+        if hotkeys.iter().any(|(bound, _)| *bound == hotkey) {
+            return Err(AppError::config(format!(
+                "Hotkey {} is bound more than once in [hotkeys]",
+                hotkey
+            )));
+        }
+        hotkeys.push((hotkey, action.parse()?));
+    }
+    Ok(hotkeys)
+}
+
+/// Contents of `config.toml`; every key is optional.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    api_key: Option<String>,
+    model: Option<String>,
+    vertex: Option<ProfileVertex>,
+    openai: Option<ProfileOpenAi>,
+    preset: Option<SystemPromptPreset>,
+    system_prompt: Option<String>,
+    response_language: Option<String>,
+    overlay_mode: Option<OverlayMode>,
+    hotkey: Option<String>,
+    hotkeys: BTreeMap<String, String>,
+    daemon_monitor: Option<String>,
+    pause_in_fullscreen: Option<bool>,
+    image_encoding: Option<String>,
+    max_image_pixels: Option<u64>,
+    upscale_min_edge: Option<u32>,
+    upscale_filter: Option<String>,
+    color_profile: Option<String>,
+    timeouts: FileTimeouts,
+    network: FileNetwork,
+    history: FileHistory,
+    plugins: Vec<Plugin>,
+    rules: Vec<Rule>,
+    upload: FileUpload,
+    usage: FileUsage,
+}
+
+/// The `[timeouts]` table, in seconds.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileTimeouts {
+    connect: Option<u64>,
+    read: Option<u64>,
+    deadline: Option<u64>,
+    keepalive: Option<u64>,
+}
+
+/// The `[history]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileHistory {
+    max_age_days: Option<u64>,
+    max_entries: Option<u64>,
+}
+
+/// The `[network]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileNetwork {
+    proxy: Option<String>,
+    no_proxy: Option<String>,
+    ca_bundle: Option<PathBuf>,
+}
+
+/// The `[usage]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileUsage {
+    monthly_budget: Option<f64>,
+    hard_limit: Option<bool>,
+}
+
+/// The `[upload]` table.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileUpload {
+    destination: Option<String>,
+    include_answer: Option<bool>,
+    imgur: Option<ImgurOptions>,
+    s3: Option<S3Options>,
+    webhook: Option<WebhookOptions>,
+}
+
+impl FileUpload {
+    /// Returns the destination `destination` names, with its table.
+    fn into_config(self) -> Result<Option<UploadConfig>> {
+        let Some(name) = self.destination else {
+            return Ok(None);
+        };
+        let missing = |table: &str| {
+            AppError::config(format!(
+                "upload.destination = \"{}\" needs an [upload.{}] table",
+                name, table
+            ))
+        };
+        let destination = match name.as_str() {
+            "imgur" => Destination::Imgur(self.imgur.ok_or_else(|| missing("imgur"))?),
+            "s3" => Destination::S3(self.s3.ok_or_else(|| missing("s3"))?),
+            "webhook" => Destination::Webhook(self.webhook.ok_or_else(|| missing("webhook"))?),
+            other => {
+                return Err(AppError::config(format!(
+                    "Unknown upload destination '{}' (expected imgur, s3 or webhook)",
+                    other
+                )));
+            }
+        };
+        Ok(Some(UploadConfig {
+            destination,
+            include_answer: self.include_answer.unwrap_or(false),
+        }))
+    }
+}
+
+impl ConfigFile {
+    /// Reads the config file; a missing file sets nothing.
+    fn load() -> Result<Self> {
+        let Some(path) = Config::file_path() else {
+            return Ok(Self::default());
+        };
+        match fs::read_to_string(&path) {
+            Ok(text) => toml::from_str(&text)
+                .map_err(|e| AppError::config(format!("Invalid {}: {}", path.display(), e))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Takes each value from the highest layer that sets it, and remembers
+/// which layer that was.
+#[derive(Default)]
+struct Layers {
+    sources: BTreeMap<&'static str, ConfigSource>,
+}
+
+impl Layers {
+    /// Returns the first value that is set, from highest to lowest layer.
+    fn pick<T>(
+        &mut self,
+        key: &'static str,
+        candidates: impl IntoIterator<Item = (Option<T>, ConfigSource)>,
+    ) -> Option<T> {
+        for (value, source) in candidates {
+            if value.is_some() {
+                self.sources.insert(key, source);
+                return value;
+            }
+        }
+        None
+    }
+
+    /// Records the source of every key in `keys`.
+    fn set(&mut self, keys: &[&'static str], source: ConfigSource) {
+        for key in keys {
+            self.sources.insert(*key, source.clone());
+        }
+    }
+}
+
+/// Reads the history retention from `AI_SHOT_HISTORY_MAX_AGE_DAYS` and
+/// `AI_SHOT_HISTORY_MAX_ENTRIES`, falling back to the config file's
+/// `[history]` table (`0` keeps everything).
+fn layered_retention(layers: &mut Layers, file: FileHistory) -> Retention {
+    let mut number = |key: &'static str, name: &'static str, file_value: Option<u64>| {
+        let value = env::var(name).ok().and_then(|v| v.trim().parse::<u64>().ok());
+        layers
+            .pick(key, [(value, ConfigSource::Env(name)), (file_value, ConfigSource::File)])
+            .filter(|&number| number > 0)
+    };
+    let max_age_days =
+        number("history.max_age_days", "AI_SHOT_HISTORY_MAX_AGE_DAYS", file.max_age_days);
+    let max_entries =
+        number("history.max_entries", "AI_SHOT_HISTORY_MAX_ENTRIES", file.max_entries);
+    Retention {
+        max_age: max_age_days.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        max_entries: max_entries.map(|entries| entries as usize),
+    }
+}
+
+/// Reads an environment variable as a layer candidate.
+fn env_var(name: &'static str) -> (Option<String>, ConfigSource) {
+    (env::var(name).ok(), ConfigSource::Env(name))
+}
+
+/// Values of the overlay's saved settings; empty ones are left unset.
+#[derive(Clone, Debug, Default)]
+struct SettingsLayer {
+    api_key: Option<String>,
+    model_name: Option<String>,
+    preset: Option<SystemPromptPreset>,
+    system_prompt: Option<String>,
+    response_language: Option<String>,
+    hotkey: Option<String>,
+    image_encoding: Option<String>,
+}
+
+impl SettingsLayer {
+    fn from_settings(settings: &Settings) -> Self {
+        let non_empty = |value: &str| Some(value.to_string()).filter(|v| !v.trim().is_empty());
+        Self {
+            api_key: non_empty(&settings.api_key),
+            model_name: non_empty(&settings.model),
+            preset: settings.preset,
+            system_prompt: non_empty(&settings.system_prompt),
+            response_language: non_empty(&settings.response_language),
+            // Left at the default, it shouldn't hide a hotkey from config.toml
+            hotkey: non_empty(&settings.hotkey).filter(|hotkey| hotkey != DEFAULT_HOTKEY),
+            image_encoding: non_empty(&settings.image_encoding),
+        }
+    }
 }
 
 /// Builder for [`Config`] with sensible defaults.
 ///
 /// Allows overriding specific values while loading others from the environment.
-#[derive(Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ConfigBuilder {
     api_key: Option<String>,
     model_name: Option<String>,
+    vertex: Option<VertexConfig>,
+    openai: Option<OpenAiConfig>,
+    timeouts: Option<TimeoutConfig>,
+    network: Option<NetworkConfig>,
+    history: Option<Retention>,
+    preset: Option<SystemPromptPreset>,
+    system_prompt: Option<String>,
+    response_language: Option<String>,
+    overlay_mode: Option<OverlayMode>,
+    hotkey: Option<Hotkey>,
+    daemon_monitor: Option<DaemonMonitor>,
+    pause_in_fullscreen: Option<bool>,
+    image_encoding: Option<ImageEncoding>,
+    max_image_pixels: Option<u64>,
+    upscale_min_edge: Option<u32>,
+    upscale_filter: Option<UpscaleFilter>,
+    color_profile: Option<ProfileSource>,
+    copy_answers: bool,
+    archive: Archive,
+    profile: Option<String>,
+    settings: SettingsLayer,
 }
 
 impl ConfigBuilder {
@@ -53,33 +632,523 @@ impl ConfigBuilder {
         self
     }
 
+    /// Routes requests through Vertex AI for the given project and location.
+    ///
+    /// Overrides the `GOOGLE_GENAI_USE_VERTEXAI` environment configuration.
+    pub fn with_vertex(mut self, project: impl Into<String>, location: impl Into<String>) -> Self {
+        self.vertex = Some(VertexConfig {
+            project: project.into(),
+            location: location.into(),
+            credentials: None,
+        });
+        self
+    }
+
+    /// Sends requests to an OpenAI-compatible gateway at `base_url`.
+    ///
+    /// Overrides `AI_SHOT_OPENAI_BASE_URL` and `AI_SHOT_OPENAI_API_KEY`.
+    pub fn with_openai(mut self, base_url: impl Into<String>, api_key: impl Into<String>) -> Self {
+        self.openai = Some(OpenAiConfig {
+            base_url: base_url.into(),
+            api_key: api_key.into(),
+        });
+        self
+    }
+
+    /// Sets how long and how many history entries are kept, overriding
+    /// `AI_SHOT_HISTORY_MAX_AGE_DAYS` and `AI_SHOT_HISTORY_MAX_ENTRIES`.
+    pub fn with_history_retention(mut self, retention: Retention) -> Self {
+        self.history = Some(retention);
+        self
+    }
+
+    /// Sets network timeouts, overriding the environment variables.
+    pub fn with_timeouts(mut self, timeouts: TimeoutConfig) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Sets proxy and CA settings, overriding the environment variables.
+    pub fn with_network(mut self, network: NetworkConfig) -> Self {
+        self.network = Some(network);
+        self
+    }
+
+    /// Selects a built-in system prompt preset.
+    pub fn with_preset(mut self, preset: SystemPromptPreset) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    /// Sets a custom system prompt, combined with the preset if one is set.
+    pub fn with_system_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.system_prompt = Some(prompt.into());
+        self
+    }
+
+    /// Sets the language answers are written in, overriding `AI_SHOT_LANG`.
+    pub fn with_response_language(mut self, language: impl Into<String>) -> Self {
+        self.response_language = Some(language.into());
+        self
+    }
+
+    /// Sets how the overlay covers the screen, overriding `AI_SHOT_OVERLAY`.
+    pub fn with_overlay_mode(mut self, mode: OverlayMode) -> Self {
+        self.overlay_mode = Some(mode);
+        self
+    }
+
+    /// Sets the daemon's global hotkey, overriding `AI_SHOT_HOTKEY`.
+    pub fn with_hotkey(mut self, hotkey: Hotkey) -> Self {
+        self.hotkey = Some(hotkey);
+        self
+    }
+
+    /// Sets what the daemon captures, overriding `AI_SHOT_DAEMON_MONITOR`.
+    pub fn with_daemon_monitor(mut self, monitor: DaemonMonitor) -> Self {
+        self.daemon_monitor = Some(monitor);
+        self
+    }
+
+    /// Sets whether the daemon ignores its hotkeys while a fullscreen
+    /// window has the focus.
+    pub fn with_pause_in_fullscreen(mut self, pause: bool) -> Self {
+        self.pause_in_fullscreen = Some(pause);
+        self
+    }
+
+    /// Sets how images are encoded for upload, overriding
+    /// `AI_SHOT_IMAGE_ENCODING`.
+    pub fn with_image_encoding(mut self, encoding: ImageEncoding) -> Self {
+        self.image_encoding = Some(encoding);
+        self
+    }
+
+    /// Sets the most pixels an uploaded image may have (`0` for no limit),
+    /// overriding `AI_SHOT_MAX_IMAGE_PIXELS`.
+    pub fn with_max_image_pixels(mut self, max_pixels: u64) -> Self {
+        self.max_image_pixels = Some(max_pixels);
+        self
+    }
+
+    /// Sets the shortest edge uploaded images are enlarged to (`0` to leave
+    /// them as they are), overriding `AI_SHOT_UPSCALE_MIN_EDGE`.
+    pub fn with_upscale_min_edge(mut self, min_edge: u32) -> Self {
+        self.upscale_min_edge = Some(min_edge);
+        self
+    }
+
+    /// Sets how small images are enlarged, overriding
+    /// `AI_SHOT_UPSCALE_FILTER`.
+    pub fn with_upscale_filter(mut self, filter: UpscaleFilter) -> Self {
+        self.upscale_filter = Some(filter);
+        self
+    }
+
+    /// Sets where the monitors' color profiles come from, overriding
+    /// `AI_SHOT_COLOR_PROFILE`.
+    pub fn with_color_profile(mut self, source: ProfileSource) -> Self {
+        self.color_profile = Some(source);
+        self
+    }
+
+    /// Copies each finished answer to the clipboard.
+    pub fn with_copy_answers(mut self, copy: bool) -> Self {
+        self.copy_answers = copy;
+        self
+    }
+
+    /// Saves each answered crop and answer to templated paths.
+    pub fn with_archive(mut self, archive: Archive) -> Self {
+        self.archive = archive;
+        self
+    }
+
+    /// Selects a profile from the profiles file, overriding `AI_SHOT_PROFILE`
+    /// and the file's default.
+    ///
+    /// The profile's values take precedence over environment variables but
+    /// not over values set on this builder.
+    pub fn with_profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Uses the overlay's saved settings as a layer between the config file
+    /// and the environment (see the module docs), along with the profile
+    /// picked in the settings window, if any.
+    pub fn with_settings(mut self, settings: &Settings) -> Self {
+        self.settings = SettingsLayer::from_settings(settings);
+        if let Some((name, _)) = &settings.profile {
+            self.profile = Some(name.clone());
+        }
+        self
+    }
+
     /// Builds the configuration.
     ///
-    /// Values not explicitly set are loaded from environment variables.
-    /// The API key is required and will return an error if not found.
+    /// Values not explicitly set are taken from the active profile, then
+    /// environment variables, then the saved settings, then the config file
+    /// (see the module docs).
     ///
     /// # Errors
-    /// Returns [`AppError`] if configuration validation fails.
+    /// Returns [`AppError`] if configuration validation fails, or the
+    /// config or profiles file is invalid or lacks the selected profile.
     /// Note: `GEMINI_API_KEY` is no longer strictly required at build time
     /// and can be provided later via UI settings.
     pub fn build(self) -> Result<Config> {
-        // Try explicit value first, then environment variable, then default to empty
-        let api_key = self
-            .api_key
-            .or_else(|| env::var("GEMINI_API_KEY").ok())
+        use ConfigSource::{Explicit, File, Settings as Saved};
+
+        let overrides = self.clone();
+        let file = ConfigFile::load()?;
+        let (profile_name, profile) = match Profiles::load()?.select(self.profile.as_deref())? {
+            Some((name, profile)) => (Some(name), profile),
+            None => (None, Default::default()),
+        };
+        let from_profile = || ConfigSource::Profile(profile_name.clone().unwrap_or_default());
+        let mut layers = Layers::default();
+
+        // Empty if not set anywhere; it can be provided later via UI settings
+        let api_key = layers
+            .pick(
+                "api_key",
+                [
+                    (self.api_key, Explicit),
+                    (profile.api_key, from_profile()),
+                    env_var("GEMINI_API_KEY"),
+                    (self.settings.api_key, Saved),
+                    (file.api_key, File),
+                ],
+            )
             .unwrap_or_default();
 
         // Model has a sensible default
-        let model_name = self
-            .model_name
-            .or_else(|| env::var("GEMINI_MODEL").ok())
+        let model_name = layers
+            .pick(
+                "model",
+                [
+                    (self.model_name, Explicit),
+                    (profile.model, from_profile()),
+                    env_var("GEMINI_MODEL"),
+                    (self.settings.model_name, Saved),
+                    (file.model, File),
+                ],
+            )
             .unwrap_or_else(|| "gemini-flash-latest".to_string());
 
+        // Vertex AI is opt-in, using the same variables as Google's SDKs
+        let env_vertex = if self.vertex.is_none() && profile.vertex.is_none() {
+            Self::vertex_from_env()?
+        } else {
+            None
+        };
+        let vertex = layers.pick(
+            "vertex",
+            [
+                (self.vertex, Explicit),
+                (profile.vertex.map(VertexConfig::from), from_profile()),
+                (env_vertex, ConfigSource::Env("GOOGLE_GENAI_USE_VERTEXAI")),
+                (file.vertex.map(VertexConfig::from), File),
+            ],
+        );
+
+        let openai = layers.pick(
+            "openai",
+            [
+                (self.openai, Explicit),
+                (profile.openai.map(OpenAiConfig::from), from_profile()),
+                (OpenAiConfig::from_env(), ConfigSource::Env("AI_SHOT_OPENAI_BASE_URL")),
+                (file.openai.map(OpenAiConfig::from), File),
+            ],
+        );
+
+        let timeouts = match self.timeouts {
+            Some(timeouts) => {
+                layers.set(
+                    &["timeouts.connect", "timeouts.read", "timeouts.deadline", "timeouts.keepalive"],
+                    Explicit,
+                );
+                timeouts
+            }
+            None => TimeoutConfig::layered(&mut layers, file.timeouts),
+        };
+        let network = match self.network {
+            Some(network) => {
+                layers.set(&["network.proxy", "network.no_proxy", "network.ca_bundle"], Explicit);
+                network
+            }
+            None => NetworkConfig::layered(&mut layers, file.network),
+        };
+        let history = match self.history {
+            Some(retention) => {
+                layers.set(&["history.max_age_days", "history.max_entries"], Explicit);
+                retention
+            }
+            None => layered_retention(&mut layers, file.history),
+        };
+
+        let non_empty = |lang: Option<String>| lang.filter(|lang| !lang.trim().is_empty());
+        let response_language = layers.pick(
+            "response_language",
+            [
+                (non_empty(self.response_language), Explicit),
+                (non_empty(profile.response_language), from_profile()),
+                (non_empty(env::var("AI_SHOT_LANG").ok()), ConfigSource::Env("AI_SHOT_LANG")),
+                (self.settings.response_language, Saved),
+                (non_empty(file.response_language), File),
+            ],
+        );
+
+        let preset = layers.pick(
+            "preset",
+            [
+                (self.preset, Explicit),
+                (profile.preset, from_profile()),
+                (self.settings.preset, Saved),
+                (file.preset, File),
+            ],
+        );
+        let system_prompt = layers.pick(
+            "system_prompt",
+            [
+                (self.system_prompt, Explicit),
+                (profile.system_prompt, from_profile()),
+                (self.settings.system_prompt, Saved),
+                (file.system_prompt, File),
+            ],
+        );
+        let overlay_mode = layers
+            .pick(
+                "overlay_mode",
+                [
+                    (self.overlay_mode, Explicit),
+                    (OverlayMode::from_env(), ConfigSource::Env("AI_SHOT_OVERLAY")),
+                    (file.overlay_mode, File),
+                ],
+            )
+            .unwrap_or_default();
+        let hotkey = match self.hotkey {
+            Some(hotkey) => {
+                layers.set(&["hotkey"], Explicit);
+                hotkey
+            }
+            None => layers
+                .pick(
+                    "hotkey",
+                    [
+                        env_var("AI_SHOT_HOTKEY"),
+                        (self.settings.hotkey, Saved),
+                        (file.hotkey, File),
+                    ],
+                )
+                .map(|hotkey| hotkey.parse())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        let hotkeys = parse_hotkeys(&file.hotkeys)?;
+        if !hotkeys.is_empty() {
+            layers.set(&["hotkeys"], File);
+        }
+        let plugins = file.plugins;
+        if !plugins.is_empty() {
+            layers.set(&["plugins"], File);
+        }
+        let rules = file.rules;
+        for rule in &rules {
+            rule.validate()?;
+        }
+        if !rules.is_empty() {
+            layers.set(&["rules"], File);
+        }
+        let upload = file.upload.into_config()?;
+        if upload.is_some() {
+            layers.set(&["upload"], File);
+        }
+        let budget = match file.usage.monthly_budget {
+            Some(limit) if !(limit.is_finite() && limit > 0.0) => {
+                return Err(AppError::config(format!(
+                    "usage.monthly_budget must be a positive amount, not {}",
+                    limit
+                )));
+            }
+            monthly => Budget {
+                monthly,
+                hard_limit: file.usage.hard_limit.unwrap_or(false),
+            },
+        };
+        if budget.monthly.is_some() {
+            layers.set(&["usage.monthly_budget"], File);
+        }
+        if file.usage.hard_limit.is_some() {
+            layers.set(&["usage.hard_limit"], File);
+        }
+        let daemon_monitor = match self.daemon_monitor {
+            Some(monitor) => {
+                layers.set(&["daemon_monitor"], Explicit);
+                monitor
+            }
+            None => layers
+                .pick(
+                    "daemon_monitor",
+                    [env_var("AI_SHOT_DAEMON_MONITOR"), (file.daemon_monitor, File)],
+                )
+                .map(|monitor| monitor.parse())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        // Games in exclusive fullscreen shouldn't lose the focus to the overlay
+        let pause_in_fullscreen = layers
+            .pick(
+                "pause_in_fullscreen",
+                [(self.pause_in_fullscreen, Explicit), (file.pause_in_fullscreen, File)],
+            )
+            .unwrap_or(true);
+        let image_encoding = match self.image_encoding {
+            Some(encoding) => {
+                layers.set(&["image_encoding"], Explicit);
+                encoding
+            }
+            None => layers
+                .pick(
+                    "image_encoding",
+                    [
+                        env_var("AI_SHOT_IMAGE_ENCODING"),
+                        (self.settings.image_encoding, Saved),
+                        (file.image_encoding, File),
+                    ],
+                )
+                .map(|encoding| encoding.parse())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        let env_pixels = env::var("AI_SHOT_MAX_IMAGE_PIXELS")
+            .ok()
+            .map(|value| {
+                value.trim().parse::<u64>().map_err(|_| {
+                    AppError::config(format!(
+                        "Invalid AI_SHOT_MAX_IMAGE_PIXELS '{}', expected a number of pixels",
+                        value
+                    ))
+                })
+            })
+            .transpose()?;
+        let max_image_pixels = layers
+            .pick(
+                "max_image_pixels",
+                [
+                    (self.max_image_pixels, Explicit),
+                    (env_pixels, ConfigSource::Env("AI_SHOT_MAX_IMAGE_PIXELS")),
+                    (file.max_image_pixels, File),
+                ],
+            )
+            .filter(|&max_pixels| max_pixels > 0);
+        let env_min_edge = env::var("AI_SHOT_UPSCALE_MIN_EDGE")
+            .ok()
+            .map(|value| {
+                value.trim().parse::<u32>().map_err(|_| {
+                    AppError::config(format!(
+                        "Invalid AI_SHOT_UPSCALE_MIN_EDGE '{}', expected a number of pixels",
+                        value
+                    ))
+                })
+            })
+            .transpose()?;
+        let upscale_min_edge = layers
+            .pick(
+                "upscale_min_edge",
+                [
+                    (self.upscale_min_edge, Explicit),
+                    (env_min_edge, ConfigSource::Env("AI_SHOT_UPSCALE_MIN_EDGE")),
+                    (file.upscale_min_edge, File),
+                ],
+            )
+            .filter(|&min_edge| min_edge > 0);
+        let upscale_filter = match self.upscale_filter {
+            Some(filter) => {
+                layers.set(&["upscale_filter"], Explicit);
+                filter
+            }
+            None => layers
+                .pick(
+                    "upscale_filter",
+                    [env_var("AI_SHOT_UPSCALE_FILTER"), (file.upscale_filter, File)],
+                )
+                .map(|filter| filter.parse())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+        let color_profile = match self.color_profile {
+            Some(source) => {
+                layers.set(&["color_profile"], Explicit);
+                source
+            }
+            None => layers
+                .pick(
+                    "color_profile",
+                    [env_var("AI_SHOT_COLOR_PROFILE"), (file.color_profile, File)],
+                )
+                .map(|source| source.parse())
+                .transpose()?
+                .unwrap_or_default(),
+        };
+
         Ok(Config {
             gemini_api_key: api_key,
             model_name,
+            vertex,
+            openai,
+            timeouts,
+            network,
+            preset,
+            system_prompt,
+            response_language,
+            overlay_mode,
+            hotkey,
+            hotkeys,
+            daemon_monitor,
+            pause_in_fullscreen,
+            image_encoding,
+            max_image_pixels,
+            upscale_min_edge,
+            upscale_filter,
+            color_profile,
+            copy_answers: self.copy_answers,
+            archive: self.archive,
+            history,
+            plugins,
+            rules,
+            upload,
+            budget,
+            profile: profile_name,
+            sources: layers.sources,
+            overrides,
         })
     }
+
+    /// Reads Vertex AI settings from `GOOGLE_GENAI_USE_VERTEXAI`,
+    /// `GOOGLE_CLOUD_PROJECT`, `GOOGLE_CLOUD_LOCATION`, and
+    /// `GOOGLE_APPLICATION_CREDENTIALS`.
+    fn vertex_from_env() -> Result<Option<VertexConfig>> {
+        let enabled = env::var("GOOGLE_GENAI_USE_VERTEXAI")
+            .map(|v| matches!(v.to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+            .unwrap_or(false);
+        if !enabled {
+            return Ok(None);
+        }
+
+        let project = env::var("GOOGLE_CLOUD_PROJECT").map_err(|_| {
+            AppError::MissingEnvVar("GOOGLE_CLOUD_PROJECT (required for Vertex AI)".to_string())
+        })?;
+        let location =
+            env::var("GOOGLE_CLOUD_LOCATION").unwrap_or_else(|_| "us-central1".to_string());
+        let credentials = env::var_os("GOOGLE_APPLICATION_CREDENTIALS").map(PathBuf::from);
+
+        Ok(Some(VertexConfig {
+            project,
+            location,
+            credentials,
+        }))
+    }
 }
 
 impl Config {
@@ -106,13 +1175,114 @@ impl Config {
         Self::builder().build()
     }
 
+    /// Returns the path of the config file, if a config directory exists.
+    pub fn file_path() -> Option<PathBuf> {
+        paths::config_file()
+    }
+
+    /// Returns where the value of `key` came from.
+    ///
+    /// Keys are the names used in the config file, with tables joined by a
+    /// dot (e.g. `model`, `vertex`, `timeouts.read`). Unknown keys and values
+    /// left at their default report [`ConfigSource::Default`].
+    pub fn source(&self, key: &str) -> ConfigSource {
+        self.sources.get(key).cloned().unwrap_or(ConfigSource::Default)
+    }
+
+    /// Returns the keys of all values not left at their default, with their
+    /// sources.
+    pub fn sources(&self) -> impl Iterator<Item = (&'static str, &ConfigSource)> {
+        self.sources.iter().map(|(key, source)| (*key, source))
+    }
+
+    /// Returns the action bound to `pressed`, if any.
+    ///
+    /// [`Config::hotkeys`] take precedence over [`Config::hotkey`], which
+    /// selects a region.
+    pub fn hotkey_action(&self, pressed: Hotkey) -> Option<HotkeyAction> {
+        self.hotkey_bindings()
+            .into_iter()
+            .find(|(hotkey, _)| *hotkey == pressed)
+            .map(|(_, action)| action)
+    }
+
+    /// Returns every daemon hotkey with its action, [`Config::hotkey`] first
+    /// unless the `[hotkeys]` table rebinds it.
+    pub fn hotkey_bindings(&self) -> Vec<(Hotkey, HotkeyAction)> {
+        let mut bindings = Vec::with_capacity(self.hotkeys.len() + 1);
+        if !self.hotkeys.iter().any(|(hotkey, _)| *hotkey == self.hotkey) {
+            bindings.push((self.hotkey, HotkeyAction::Select));
+        }
+        bindings.extend(self.hotkeys.iter().copied());
+        bindings
+    }
+
+    /// Returns a builder for this configuration with the overlay's saved
+    /// `settings` as a layer (see [`ConfigBuilder::with_settings`]).
+    ///
+    /// Values set on the original builder, such as command-line flags, keep
+    /// precedence over the settings.
+    pub fn with_settings(&self, settings: &Settings) -> ConfigBuilder {
+        self.overrides.clone().with_settings(settings)
+    }
+
+    /// Checks the configuration for problems that would make requests fail,
+    /// such as a missing API key or an unreachable proxy.
+    ///
+    /// Returns an empty list if none were found, otherwise errors before
+    /// warnings. Reaching the proxy may take a few seconds; see
+    /// [`crate::diagnostics`].
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        diagnostics::validate(self)
+    }
+
+    /// Watches the config, profiles and settings files and yields this
+    /// configuration rebuilt after each change.
+    ///
+    /// Values set on the builder stay in place; everything else is read
+    /// again from the files and the environment. See [`crate::watch`].
+    ///
+    /// # Errors
+    /// Returns an error if the watcher thread cannot be started.
+    pub fn subscribe(&self) -> Result<ConfigUpdates> {
+        ConfigUpdates::start(self.overrides.clone())
+    }
+
     /// Creates a config with a specific API key and default model.
     ///
     /// Useful for testing or programmatic initialization.
     pub fn with_key(api_key: impl Into<String>) -> Self {
+        let api_key = api_key.into();
         Self {
-            gemini_api_key: api_key.into(),
+            gemini_api_key: api_key.clone(),
             model_name: "gemini-flash-latest".to_string(),
+            vertex: None,
+            openai: None,
+            timeouts: TimeoutConfig::default(),
+            network: NetworkConfig::default(),
+            preset: None,
+            system_prompt: None,
+            response_language: None,
+            overlay_mode: OverlayMode::default(),
+            hotkey: Hotkey::default(),
+            hotkeys: Vec::new(),
+            daemon_monitor: DaemonMonitor::default(),
+            pause_in_fullscreen: true,
+            image_encoding: ImageEncoding::default(),
+            max_image_pixels: None,
+            upscale_min_edge: None,
+            upscale_filter: UpscaleFilter::default(),
+            color_profile: ProfileSource::default(),
+            copy_answers: false,
+            archive: Archive::default(),
+            history: Retention::default(),
+            plugins: Vec::new(),
+            rules: Vec::new(),
+            upload: None,
+            budget: Budget::default(),
+            profile: None,
+            sources: BTreeMap::from([("api_key", ConfigSource::Explicit)]),
+            overrides: Config::builder().with_api_key(api_key),
         }
     }
 }
\ No newline at end of file
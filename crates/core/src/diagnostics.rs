@@ -0,0 +1,235 @@
+//! Checking a configuration before it is used.
+//!
+//! [`Config::validate`] looks for problems that would otherwise only show up
+//! as a failed request: a missing API key, a model name no backend knows, a
+//! proxy that can't be reached, a CA bundle that isn't PEM or a color profile
+//! that can't be used. Each problem is a [`Diagnostic`] naming the config key
+//! it concerns and, where possible, how to fix it.
+//!
+//! Checking the proxy opens a TCP connection, so validation can take up to a
+//! few seconds and should not run on a UI thread.
+//!
+//! # Example
+//!
+//! ```ignore
+//! for diagnostic in config.validate() {
+//!     eprintln!("{}", diagnostic);
+//! }
+//! ```
+
+use crate::color::{DisplayProfile, ProfileSource};
+use crate::config::{Config, NetworkConfig};
+use crate::error::AppError;
+use crate::models::{self, ModelCapabilities};
+use crate::ui::AVAILABLE_MODELS;
+use reqwest::Url;
+use std::fmt;
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Longest time spent trying to reach the proxy.
+const PROXY_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How serious a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Requests may still work, but probably not as intended.
+    Warning,
+    /// Requests will fail until this is fixed.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A problem found in a configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// Config key the problem concerns (as in [`Config::source`]).
+    pub key: &'static str,
+    /// What is wrong.
+    pub message: String,
+    /// How to fix it, if known.
+    pub hint: Option<String>,
+}
+
+impl Diagnostic {
+    pub(crate) fn error(key: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            key,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    pub(crate) fn warning(key: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(key, message)
+        }
+    }
+
+    pub(crate) fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.key, self.message)?;
+        if let Some(hint) = &self.hint {
+            write!(f, " ({})", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs every check on `config`, errors first.
+pub(crate) fn validate(config: &Config) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    check_credentials(config, &mut diagnostics);
+    check_model(config, &mut diagnostics);
+    check_network(&config.network, config.timeouts.connect, &mut diagnostics);
+    check_color_profile(&config.color_profile, &mut diagnostics);
+    diagnostics.sort_by_key(|diagnostic| std::cmp::Reverse(diagnostic.severity));
+    diagnostics
+}
+
+/// Checks that the selected backend has what it needs to authenticate.
+fn check_credentials(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(vertex) = &config.vertex {
+        if vertex.project.trim().is_empty() {
+            diagnostics.push(
+                Diagnostic::error("vertex", "No Google Cloud project is set")
+                    .with_hint("set GOOGLE_CLOUD_PROJECT or `project` in the profile"),
+            );
+        }
+        if let Some(path) = vertex.credentials.as_ref().filter(|path| !path.is_file()) {
+            diagnostics.push(
+                Diagnostic::error(
+                    "vertex",
+                    format!("Credentials file {} does not exist", path.display()),
+                )
+                .with_hint("check GOOGLE_APPLICATION_CREDENTIALS"),
+            );
+        }
+    } else if let Some(openai) = &config.openai {
+        match Url::parse(&openai.base_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => diagnostics.push(Diagnostic::error(
+                "openai",
+                format!("Unsupported scheme '{}' in {}", url.scheme(), openai.base_url),
+            )),
+            Err(e) => diagnostics.push(
+                Diagnostic::error("openai", format!("Invalid base URL {}: {}", openai.base_url, e))
+                    .with_hint("include the version, e.g. http://localhost:4000/v1"),
+            ),
+        }
+    } else if config.gemini_api_key.trim().is_empty() {
+        diagnostics.push(
+            Diagnostic::error("api_key", "No Gemini API key is set").with_hint(
+                "set GEMINI_API_KEY, run `ai-shot config set api_key <key>` \
+                 or enter it in Settings > API keys",
+            ),
+        );
+    }
+}
+
+/// Checks that the model name is one the backend is likely to know.
+fn check_model(config: &Config, diagnostics: &mut Vec<Diagnostic>) {
+    let model = config.model_name.trim();
+    if model.is_empty() {
+        diagnostics.push(Diagnostic::error("model", "No model is set"));
+        return;
+    }
+    // Gateways route arbitrary model names, so only Gemini names are checked
+    if config.openai.is_some()
+        || AVAILABLE_MODELS.contains(&model)
+        || ModelCapabilities::of(model).is_some()
+    {
+        return;
+    }
+
+    let diagnostic = Diagnostic::warning("model", format!("Unknown model '{}'", model));
+    diagnostics.push(match models::closest_known(model) {
+        Some(known) => diagnostic.with_hint(format!("did you mean '{}'?", known)),
+        None => diagnostic.with_hint("run `ai-shot models` to list the available models"),
+    });
+}
+
+/// Checks that the proxy answers and the CA bundle holds certificates.
+fn check_network(network: &NetworkConfig, connect: Duration, diagnostics: &mut Vec<Diagnostic>) {
+    if let Some(proxy) = &network.proxy
+        && let Err(message) = reach_proxy(proxy, connect.min(PROXY_CHECK_TIMEOUT))
+    {
+        diagnostics.push(
+            Diagnostic::error("network.proxy", message)
+                .with_hint("check AI_SHOT_PROXY or `proxy` in config.toml"),
+        );
+    }
+
+    if let Some(path) = &network.ca_bundle {
+        match fs::read_to_string(path) {
+            Ok(pem) if pem.contains("-----BEGIN CERTIFICATE-----") => {}
+            Ok(_) => diagnostics.push(Diagnostic::error(
+                "network.ca_bundle",
+                format!("{} contains no PEM certificates", path.display()),
+            )),
+            Err(e) => diagnostics.push(Diagnostic::error(
+                "network.ca_bundle",
+                format!("Cannot read {}: {}", path.display(), e),
+            )),
+        }
+    }
+}
+
+/// Checks that a configured color profile file can be used.
+fn check_color_profile(source: &ProfileSource, diagnostics: &mut Vec<Diagnostic>) {
+    if let ProfileSource::File(path) = source
+        && let Err(e) = DisplayProfile::load(path)
+    {
+        let message = match e {
+            AppError::Config(message) => message,
+            e => e.to_string(),
+        };
+        diagnostics.push(
+            Diagnostic::error("color_profile", message)
+                .with_hint("use an RGB display profile, or 'auto' or 'off'"),
+        );
+    }
+}
+
+/// Opens a TCP connection to the proxy's host and port.
+fn reach_proxy(proxy: &str, timeout: Duration) -> Result<(), String> {
+    let url = Url::parse(proxy).map_err(|e| format!("Invalid proxy URL {}: {}", proxy, e))?;
+    let host = url.host_str().ok_or_else(|| format!("Proxy URL {} has no host", proxy))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| format!("Proxy URL {} has no port", proxy))?;
+
+    let addresses = (host, port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve proxy host {}: {}", host, e))?;
+    let mut last_error = None;
+    for address in addresses {
+        match TcpStream::connect_timeout(&address, timeout) {
+            Ok(_) => return Ok(()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(match last_error {
+        Some(e) => format!("Proxy {} is unreachable: {}", proxy, e),
+        None => format!("Proxy host {} has no addresses", host),
+    })
+}
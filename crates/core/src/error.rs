@@ -31,6 +31,14 @@ pub enum AppError {
     #[error("Image processing failed: {0}")]
     ImageProcessing(String),
 
+    /// Local OCR failed or is unavailable.
+    #[error("OCR failed: {0}")]
+    Ocr(String),
+
+    /// Text-to-speech output failed or is unavailable.
+    #[error("Speech output failed: {0}")]
+    Speech(String),
+
     /// The selection area is empty or has zero dimensions.
     #[error("Selection area is empty or invalid")]
     EmptySelection,
@@ -39,10 +47,42 @@ pub enum AppError {
     #[error("Gemini API error: {0}")]
     GeminiApi(String),
 
+    /// A request exceeded its connect, read, or overall deadline.
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+
+    /// Error from an OpenAI-compatible gateway.
+    #[error("OpenAI-compatible API error: {0}")]
+    OpenAi(String),
+
     /// Rate limited by the Gemini API.
     #[error("Rate limited by Gemini API, please retry later")]
     RateLimited,
 
+    /// Too many tasks are queued for a provider; the caller should back off.
+    #[error("Task queue for {0} is full, please retry later")]
+    QueueFull(String),
+
+    /// A local tool invoked by the model failed.
+    #[error("Tool error: {0}")]
+    Tool(String),
+
+    /// A plugin could not be run or failed.
+    #[error("Plugin error: {0}")]
+    Plugin(String),
+
+    /// The monthly budget is spent and its limit is hard.
+    #[error("Budget exceeded: {0}")]
+    Budget(String),
+
+    /// Sharing a selection through a link failed.
+    #[error("Upload error: {0}")]
+    Upload(String),
+
+    /// The OS keyring is unavailable or refused access.
+    #[error("Keyring error: {0}")]
+    Secrets(String),
+
     /// UI-related errors (rendering, window management).
     #[error("UI error: {0}")]
     Ui(String),
@@ -76,11 +116,51 @@ impl AppError {
         Self::ImageProcessing(msg.into())
     }
 
+    /// Creates an OCR error with the given message.
+    pub fn ocr(msg: impl Into<String>) -> Self {
+        Self::Ocr(msg.into())
+    }
+
+    /// Creates a speech output error with the given message.
+    pub fn speech(msg: impl Into<String>) -> Self {
+        Self::Speech(msg.into())
+    }
+
     /// Creates a Gemini API error with the given message.
     pub fn gemini(msg: impl Into<String>) -> Self {
         Self::GeminiApi(msg.into())
     }
 
+    /// Creates an OpenAI-compatible gateway error with the given message.
+    pub fn openai(msg: impl Into<String>) -> Self {
+        Self::OpenAi(msg.into())
+    }
+
+    /// Creates a tool error with the given message.
+    pub fn tool(msg: impl Into<String>) -> Self {
+        Self::Tool(msg.into())
+    }
+
+    /// Creates a plugin error with the given message.
+    pub fn plugin(msg: impl Into<String>) -> Self {
+        Self::Plugin(msg.into())
+    }
+
+    /// Creates a budget error with the given message.
+    pub fn budget(msg: impl Into<String>) -> Self {
+        Self::Budget(msg.into())
+    }
+
+    /// Creates an upload error with the given message.
+    pub fn upload(msg: impl Into<String>) -> Self {
+        Self::Upload(msg.into())
+    }
+
+    /// Creates a keyring error with the given message.
+    pub fn secrets(msg: impl Into<String>) -> Self {
+        Self::Secrets(msg.into())
+    }
+
     /// Creates a UI error with the given message.
     pub fn ui(msg: impl Into<String>) -> Self {
         Self::Ui(msg.into())
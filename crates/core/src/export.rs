@@ -0,0 +1,226 @@
+//! Conversation export to Markdown and HTML.
+//!
+//! Exports capture the prompt, a thumbnail of the selection, the model's
+//! thinking output, and the answer in a file that can be shared or archived.
+//!
+//! - **Markdown** writes the thumbnail as a sibling PNG file and links it.
+//! - **HTML** produces a single standalone file with the thumbnail embedded
+//!   as a data URI and the answer rendered from Markdown. HTML the model
+//!   wrote into the answer is escaped rather than passed through, links
+//!   other than `http`, `https` and `mailto` lead nowhere, and images in the
+//!   answer are replaced by their alt text so opening the file loads nothing
+//!   remote.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::export::{Conversation, ExportFormat};
+//!
+//! let conversation = Conversation {
+//!     prompt: "Explain this code",
+//!     model: "gemini-flash-latest",
+//!     answer: &answer,
+//!     thoughts: "",
+//!     image: Some(&cropped),
+//! };
+//! conversation.export("explanation.html".as_ref(), ExportFormat::Html)?;
+//! ```
+
+use crate::error::{AppError, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use image::{DynamicImage, ImageFormat};
+use pulldown_cmark::{CowStr, Event, Tag, TagEnd};
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+
+/// Longest edge of the exported selection thumbnail, in pixels.
+const EXPORT_THUMBNAIL_SIZE: u32 = 480;
+
+/// Output format for an exported conversation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Markdown with a sibling PNG thumbnail.
+    Markdown,
+    /// Standalone HTML with an embedded thumbnail.
+    Html,
+}
+
+impl ExportFormat {
+    /// File extension for this format (without the dot).
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+
+    /// Infers the format from a file extension, defaulting to Markdown.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm") => {
+                Self::Html
+            }
+            _ => Self::Markdown,
+        }
+    }
+}
+
+/// A single prompt/answer exchange to export.
+pub struct Conversation<'a> {
+    /// The user's prompt.
+    pub prompt: &'a str,
+    /// Model that produced the answer.
+    pub model: &'a str,
+    /// The model's answer (Markdown).
+    pub answer: &'a str,
+    /// Thinking output, empty if thinking mode was off.
+    pub thoughts: &'a str,
+    /// The selected region sent with the prompt.
+    pub image: Option<&'a DynamicImage>,
+}
+
+impl Conversation<'_> {
+    /// Writes the conversation to `path` in the given format.
+    ///
+    /// # Errors
+    /// Returns an error if the thumbnail cannot be encoded or a file cannot
+    /// be written.
+    pub fn export(&self, path: &Path, format: ExportFormat) -> Result<()> {
+        let content = match format {
+            ExportFormat::Markdown => self.to_markdown(path)?,
+            ExportFormat::Html => self.to_html()?,
+        };
+        fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Renders Markdown, writing the thumbnail next to `path`.
+    fn to_markdown(&self, path: &Path) -> Result<String> {
+        let mut out = format!("# {}\n\n*Model: {}*\n\n", self.prompt, self.model);
+
+        if let Some(image) = self.image {
+            let thumb_path = path.with_extension("png");
+            image
+                .thumbnail(EXPORT_THUMBNAIL_SIZE, EXPORT_THUMBNAIL_SIZE)
+                .save(&thumb_path)
+                .map_err(|e| AppError::image(format!("Failed to save thumbnail: {}", e)))?;
+            if let Some(name) = thumb_path.file_name().and_then(|n| n.to_str()) {
+                out.push_str(&format!("![Selection]({})\n\n", name));
+            }
+        }
+
+        if !self.thoughts.trim().is_empty() {
+            out.push_str("<details>\n<summary>Thinking process</summary>\n\n");
+            out.push_str(self.thoughts.trim());
+            out.push_str("\n\n</details>\n\n");
+        }
+
+        out.push_str("## Answer\n\n");
+        out.push_str(self.answer.trim());
+        out.push('\n');
+        Ok(out)
+    }
+
+    /// Renders a standalone HTML document.
+    fn to_html(&self) -> Result<String> {
+        let mut body = format!(
+            "<h1>{}</h1>\n<p class=\"meta\">Model: {}</p>\n",
+            escape_html(self.prompt),
+            escape_html(self.model)
+        );
+
+        if let Some(image) = self.image {
+            let mut png = Vec::new();
+            image
+                .thumbnail(EXPORT_THUMBNAIL_SIZE, EXPORT_THUMBNAIL_SIZE)
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(|e| AppError::image(format!("Failed to encode thumbnail: {}", e)))?;
+            body.push_str(&format!(
+                "<img alt=\"Selection\" src=\"data:image/png;base64,{}\">\n",
+                BASE64.encode(png)
+            ));
+        }
+
+        if !self.thoughts.trim().is_empty() {
+            body.push_str(&format!(
+                "<details><summary>Thinking process</summary><pre>{}</pre></details>\n",
+                escape_html(self.thoughts.trim())
+            ));
+        }
+
+        body.push_str("<h2>Answer</h2>\n");
+        // Raw HTML in the answer is shown as text, so nothing the model wrote
+        // runs when the export is opened
+        let answer = pulldown_cmark::Parser::new_ext(self.answer, pulldown_cmark::Options::all())
+            .filter_map(|event| match event {
+                Event::Html(html) | Event::InlineHtml(html) => Some(Event::Text(html)),
+                Event::Start(Tag::Link {
+                    link_type,
+                    dest_url,
+                    title,
+                    id,
+                }) if !is_safe_link(&dest_url) => Some(Event::Start(Tag::Link {
+                    link_type,
+                    dest_url: CowStr::from("#"),
+                    title,
+                    id,
+                })),
+                // Only the alt text is kept, which the parser emits between these
+                Event::Start(Tag::Image { .. }) | Event::End(TagEnd::Image) => None,
+                event => Some(event),
+            });
+        pulldown_cmark::html::push_html(&mut body, answer);
+
+        Ok(format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+             <style>\n{}\n</style>\n</head>\n<body>\n{}</body>\n</html>\n",
+            escape_html(self.prompt),
+            HTML_STYLE,
+            body
+        ))
+    }
+}
+
+/// Minimal readable styling for exported HTML.
+const HTML_STYLE: &str = "body { font-family: system-ui, sans-serif; max-width: 860px; margin: 2em auto; \
+padding: 0 1em; line-height: 1.5; }\n\
+img { max-width: 100%; border: 1px solid #ccc; }\n\
+pre { background: #f4f4f4; padding: 0.8em; overflow-x: auto; }\n\
+code { font-family: ui-monospace, monospace; }\n\
+.meta { color: #666; }";
+
+/// Returns whether a link target from the answer may be kept: relative
+/// targets and `http`, `https` and `mailto` URLs.
+fn is_safe_link(url: &str) -> bool {
+    // Browsers ignore whitespace and control characters inside a scheme
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_ascii_control())
+        .collect();
+    match url.find([':', '/', '?', '#']) {
+        Some(end) if url[end..].starts_with(':') => {
+            let scheme = &url[..end];
+            ["http", "https", "mailto"]
+                .iter()
+                .any(|safe| scheme.eq_ignore_ascii_case(safe))
+        }
+        _ => true,
+    }
+}
+
+/// Escapes text for inclusion in HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -7,9 +7,13 @@
 //!
 //! - Image analysis with text prompts
 //! - Streaming responses for real-time display
+//! - Multi-turn follow-up questions about the same image
+//! - Image editing with image-capable models
 //! - System prompt support
 //! - "Thinking" mode for Gemini 2.0+ models
 //! - Google Search grounding
+//! - Local function calling via [`tools`]
+//! - Vertex AI endpoints with service-account / ADC authentication
 //!
 //! # Example
 //!
@@ -20,11 +24,11 @@
 //! let client = GeminiClient::new(&config)?;
 //!
 //! // Simple analysis
-//! let response = client.analyze_image(base64_image, "What is this?").await?;
+//! let response = client.analyze_image(image, "What is this?").await?;
 //!
 //! // Streaming analysis
 //! let mut stream = client.analyze_image_stream(
-//!     base64_image,
+//!     image,
 //!     "Explain this code".to_string(),
 //!     String::new(),  // system prompt
 //!     false,          // thinking
@@ -38,9 +42,22 @@
 //! }
 //! ```
 
+pub mod tools;
+mod vertex;
+
 use crate::config::Config;
 use crate::error::{AppError, Result};
-use gemini_rust::{Blob, Content, Gemini, Message, Part, Role};
+use crate::image_processing::EncodedImage;
+use crate::models::{ModelCapabilities, ModelInfo};
+use gemini_rust::{Blob, Content, Gemini, GeminiBuilder, Message, Part, Role};
+use std::time::Duration;
+use tools::{function_response_part, ToolCall, ToolRegistry};
+
+/// Maximum number of model turns in a single tool-use session.
+const MAX_TOOL_ROUNDS: usize = 5;
+
+/// Model used for image generation and editing.
+pub const IMAGE_EDIT_MODEL: &str = "gemini-2.0-flash-preview-image-generation";
 
 /// Client for interacting with Google's Gemini AI API.
 ///
@@ -61,8 +78,22 @@ use gemini_rust::{Blob, Content, Gemini, Message, Part, Role};
 /// ```
 pub struct GeminiClient {
     client: Gemini,
+    /// Raw HTTP client for endpoints not covered by `gemini-rust`.
+    http: reqwest::Client,
+    /// Full model URL (e.g., ".../v1beta/models/gemini-flash-latest").
+    model_url: String,
+    /// API key for the public API (empty for Vertex AI).
+    api_key: String,
+    /// Model name, recorded in debug logs.
+    model: String,
+    /// Overall deadline applied to each request, including streaming.
+    deadline: Option<Duration>,
 }
 
+/// Pinned stream of response events returned by streaming requests.
+pub type GeminiEventStream =
+    std::pin::Pin<Box<dyn futures::Stream<Item = Result<Vec<GeminiStreamEvent>>> + Send>>;
+
 /// Events emitted during streaming responses.
 ///
 /// The stream alternates between regular text and "thinking" content
@@ -73,12 +104,47 @@ pub enum GeminiStreamEvent {
     Text(String),
     /// Thinking/reasoning content (when thinking mode is enabled).
     Thought(String),
+    /// A local tool was invoked on behalf of the model.
+    ToolCall {
+        /// Name of the invoked tool.
+        name: String,
+        /// JSON response that was returned to the model.
+        output: String,
+    },
+    /// Why the model stopped generating (e.g. `STOP`, `MAX_TOKENS`).
+    Finish(String),
+    /// Token usage reported so far (cumulative for the request).
+    Usage {
+        /// Input (prompt and image) tokens.
+        input_tokens: u32,
+        /// Output tokens, including thinking, generated so far.
+        output_tokens: u32,
+    },
+}
+
+/// Result of an image editing request.
+#[derive(Debug, Clone, Default)]
+pub struct ImageEdit {
+    /// The generated image, if the model returned one.
+    pub image: Option<image::DynamicImage>,
+    /// Any text the model returned alongside the image.
+    pub text: String,
+}
+
+/// A completed prompt/answer exchange in a multi-turn conversation.
+#[derive(Debug, Clone, Default)]
+pub struct ChatTurn {
+    /// The user's prompt.
+    pub prompt: String,
+    /// The model's answer.
+    pub answer: String,
 }
 
 impl GeminiClient {
     /// Creates a new Gemini client with the provided configuration.
     ///
-    /// Initializes the HTTP client and validates the model URL.
+    /// Initializes the HTTP client (timeouts, proxy, extra root certificates)
+    /// and validates the model URL.
     ///
     /// # Arguments
     /// * `config` - Configuration containing API key and model name
@@ -87,8 +153,16 @@ impl GeminiClient {
     ///
     /// Returns [`AppError::Config`] if:
     /// - The base URL is invalid
+    /// - The proxy URL or CA bundle is invalid
     /// - Client initialization fails
+    /// - The configuration targets Vertex AI, which needs [`Self::connect`]
     pub fn new(config: &Config) -> Result<Self> {
+        if config.vertex.is_some() {
+            return Err(AppError::config(
+                "Vertex AI requires an access token; use GeminiClient::connect",
+            ));
+        }
+
         // Initialize the client with the API key and model
         let base_url = url::Url::parse("https://generativelanguage.googleapis.com/v1beta/")
             .map_err(|e| AppError::config(format!("Invalid base URL: {}", e)))?;
@@ -104,19 +178,86 @@ impl GeminiClient {
             model_name
         );
 
-        let client = Gemini::with_model_and_base_url(&config.gemini_api_key, model_url, base_url)
+        let client = GeminiBuilder::new(config.gemini_api_key.clone())
+            .with_model(model_url.clone())
+            .with_base_url(base_url)
+            .with_http_client(Self::http_client(config)?)
+            .build()
             .map_err(|e| AppError::config(format!("Failed to create Gemini client: {}", e)))?;
+        let http = Self::http_client(config)?
+            .build()
+            .map_err(|e| AppError::config(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            http,
+            model_url,
+            api_key: config.gemini_api_key.clone(),
+            model: config.model_name.clone(),
+            deadline: config.timeouts.deadline,
+        })
+    }
+
+    /// Creates a client, resolving credentials that require network access.
+    ///
+    /// For the public API this is equivalent to [`Self::new`]. When
+    /// [`Config::vertex`] is set, an OAuth access token is obtained from the
+    /// configured service account or Application Default Credentials and
+    /// requests are sent to the regional Vertex AI endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Config`] if credentials cannot be resolved or the
+    /// client cannot be built.
+    pub async fn connect(config: &Config) -> Result<Self> {
+        let Some(vertex_config) = &config.vertex else {
+            return Self::new(config);
+        };
+
+        let token = vertex::access_token(vertex_config).await?;
+
+        let base = vertex::base_url(vertex_config);
+        let base_url = url::Url::parse(&base)
+            .map_err(|e| AppError::config(format!("Invalid Vertex AI URL: {}", e)))?;
+        let model = config
+            .model_name
+            .trim_start_matches("models/")
+            .trim_start_matches("publishers/google/models/");
+        let model_url = format!("{}publishers/google/models/{}", base, model);
+
+        let mut headers = reqwest::header::HeaderMap::new();
+        let bearer = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", token))
+            .map_err(|e| AppError::config(format!("Invalid access token: {}", e)))?;
+        headers.insert(reqwest::header::AUTHORIZATION, bearer);
+
+        let client = GeminiBuilder::new(String::new())
+            .with_model(model_url.clone())
+            .with_base_url(base_url)
+            .with_http_client(Self::http_client(config)?.default_headers(headers.clone()))
+            .build()
+            .map_err(|e| AppError::config(format!("Failed to create Vertex AI client: {}", e)))?;
+        let http = Self::http_client(config)?
+            .default_headers(headers)
+            .build()
+            .map_err(|e| AppError::config(format!("Failed to create HTTP client: {}", e)))?;
 
-        Ok(Self { client })
+        Ok(Self {
+            client,
+            http,
+            model_url,
+            api_key: String::new(),
+            model: config.model_name.clone(),
+            deadline: config.timeouts.deadline,
+        })
     }
-    
+
     /// Sends an image and a text prompt to the Gemini API.
     ///
     /// This is a simple one-shot request that waits for the complete response.
     /// For streaming responses, use [`Self::analyze_image_stream`].
     ///
     /// # Arguments
-    /// * `base64_image` - Base64-encoded JPEG image data
+    /// * `image` - The encoded image
     /// * `prompt` - Text prompt describing what to analyze
     ///
     /// # Errors
@@ -124,24 +265,23 @@ impl GeminiClient {
     /// Returns [`AppError::GeminiApi`] if:
     /// - The API request fails
     /// - No text response is received
-    pub async fn analyze_image(&self, base64_image: String, prompt: String) -> Result<String> {
-        let message = self.build_image_message(base64_image, prompt);
+    pub async fn analyze_image(&self, image: EncodedImage, prompt: String) -> Result<String> {
+        let image = image_part(image);
+        let messages = conversation_messages(vec![image], &[], prompt);
 
+        let request = self.client.generate_content().with_messages(messages);
         let response = self
-            .client
-            .generate_content()
-            .with_messages(vec![message])
-            .execute()
-            .await
-            .map_err(|e| AppError::gemini(format!("API request failed: {:?}", e)))?;
+            .with_deadline(request.execute())
+            .await?
+            .map_err(|e| api_error("API request failed", e))?;
 
         // Extract text from response
-        if let Some(candidate) = response.candidates.first() {
-            if let Some(parts) = &candidate.content.parts {
-                for part in parts {
-                    if let Part::Text { text, .. } = part {
-                        return Ok(text.clone());
-                    }
+        if let Some(candidate) = response.candidates.first()
+            && let Some(parts) = &candidate.content.parts
+        {
+            for part in parts {
+                if let Part::Text { text, .. } = part {
+                    return Ok(text.clone());
                 }
             }
         }
@@ -154,7 +294,7 @@ impl GeminiClient {
     /// enabling real-time display of the response.
     ///
     /// # Arguments
-    /// * `base64_image` - Base64-encoded JPEG image data
+    /// * `image` - The encoded image
     /// * `prompt` - Text prompt describing what to analyze
     /// * `system_prompt` - Optional system instructions (empty string to skip)
     /// * `thinking_enabled` - Enable "thinking" mode (Gemini 2.0+ only)
@@ -170,46 +310,146 @@ impl GeminiClient {
     /// Returns [`AppError::GeminiApi`] if the stream cannot be established.
     pub async fn analyze_image_stream(
         &self,
-        base64_image: String,
+        image: EncodedImage,
         prompt: String,
         system_prompt: String,
         thinking_enabled: bool,
         google_search: bool,
-    ) -> Result<std::pin::Pin<Box<dyn futures::Stream<Item = Result<Vec<GeminiStreamEvent>>> + Send>>>
-    {
-        use futures::TryStreamExt;
-        
-        // Construct image data blob
-        let blob = Blob {
-            mime_type: "image/jpeg".to_string(),
-            data: base64_image
-        };
+    ) -> Result<GeminiEventStream> {
+        self.continue_image_stream(
+            image,
+            &[],
+            prompt,
+            system_prompt,
+            thinking_enabled,
+            google_search,
+        )
+        .await
+    }
 
-        // Construct parts
-        let image_part = Part::InlineData {
-            inline_data: blob,
-        };
+    /// Sends a follow-up question about an image with streaming response.
+    ///
+    /// The image is attached to the first user turn and the earlier
+    /// exchanges in `history` are replayed, so the model answers `prompt`
+    /// in the context of the whole conversation.
+    ///
+    /// # Arguments
+    /// * `image` - The encoded image
+    /// * `history` - Completed exchanges, oldest first
+    /// * `prompt` - The new question
+    /// * `system_prompt` - Optional system instructions (empty string to skip)
+    /// * `thinking_enabled` - Enable "thinking" mode (Gemini 2.0+ only)
+    /// * `google_search` - Enable Google Search grounding
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if the stream cannot be established.
+    pub async fn continue_image_stream(
+        &self,
+        image: EncodedImage,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+        thinking_enabled: bool,
+        google_search: bool,
+    ) -> Result<GeminiEventStream> {
+        self.continue_images_stream(
+            vec![image],
+            history,
+            prompt,
+            system_prompt,
+            thinking_enabled,
+            google_search,
+        )
+        .await
+    }
 
-        let text_part = Part::Text {
-            text: prompt,
-            thought: None,
-            thought_signature: None,
-        };
+    /// Asks about several images at once, continuing the conversation in
+    /// `history`.
+    ///
+    /// The images are attached to the first user turn in order, so prompts
+    /// can refer to them as "the first image", "the second image", and so on.
+    ///
+    /// # Arguments
+    /// * `images` - The encoded images, in selection order
+    /// * `history` - Completed exchanges, oldest first (empty for a new chat)
+    /// * `prompt` - The question
+    /// * `system_prompt` - Optional system instructions (empty string to skip)
+    /// * `thinking_enabled` - Enable "thinking" mode (Gemini 2.0+ only)
+    /// * `google_search` - Enable Google Search grounding
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if the stream cannot be established.
+    pub async fn continue_images_stream(
+        &self,
+        images: Vec<EncodedImage>,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+        thinking_enabled: bool,
+        google_search: bool,
+    ) -> Result<GeminiEventStream> {
+        let context = images.into_iter().map(image_part).collect();
+        let messages = conversation_messages(context, history, prompt);
 
-        // Create the content payload
-        let content = Content {
-            role: Some(Role::User),
-            parts: Some(vec![text_part, image_part]),
-        };
+        self.stream_messages(messages, system_prompt, thinking_enabled, google_search)
+            .await
+    }
 
-        // Create the message payload
-        let message = Message {
-            role: Role::User,
-            content,
-        };
+    /// Sends a text-only prompt to the Gemini API with streaming response.
+    ///
+    /// Used when the image content has already been converted to text locally
+    /// (e.g., via OCR), which is cheaper and keeps the image private.
+    ///
+    /// # Arguments
+    /// * `text` - Text extracted from the image
+    /// * `history` - Completed exchanges, oldest first (empty for a new chat)
+    /// * `prompt` - Instruction describing what to do with the text
+    /// * `system_prompt` - Optional system instructions (empty string to skip)
+    /// * `thinking_enabled` - Enable "thinking" mode (Gemini 2.0+ only)
+    /// * `google_search` - Enable Google Search grounding
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if the stream cannot be established.
+    pub async fn analyze_text_stream(
+        &self,
+        text: String,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+        thinking_enabled: bool,
+        google_search: bool,
+    ) -> Result<GeminiEventStream> {
+        let context = text_part(format!("Text extracted from a screenshot:\n\n{}", text));
+        let messages = conversation_messages(vec![context], history, prompt);
+
+        self.stream_messages(messages, system_prompt, thinking_enabled, google_search)
+            .await
+    }
+
+    /// Streams the model's reply to a conversation.
+    async fn stream_messages(
+        &self,
+        messages: Vec<Message>,
+        system_prompt: String,
+        thinking_enabled: bool,
+        google_search: bool,
+    ) -> Result<GeminiEventStream> {
+        use futures::TryStreamExt;
+
+        // Leave out options the model would reject instead of failing the request
+        for warning in ModelCapabilities::warnings(&self.model, thinking_enabled, google_search) {
+            tracing::warn!("{}", warning);
+        }
+        let (thinking_enabled, google_search) =
+            ModelCapabilities::supported_options(&self.model, thinking_enabled, google_search);
+
+        log_request(&self.model, &messages, &system_prompt, thinking_enabled, google_search);
 
         // Prepare request builder
-        let mut request = self.client.generate_content().with_messages(vec![message]);
+        let mut request = self.client.generate_content().with_messages(messages);
 
         if !system_prompt.trim().is_empty() {
             request = request.with_system_prompt(&system_prompt);
@@ -224,18 +464,25 @@ impl GeminiClient {
         }
 
         // Execute stream
-        let stream = request
-            .execute_stream()
-            .await
-            .map_err(|e| AppError::gemini(format!("API request failed: {:?}", e)))?;
+        let started = tokio::time::Instant::now();
+        let stream = self
+            .with_deadline(request.execute_stream())
+            .await?
+            .map_err(|e| api_error("API request failed", e))?;
 
         // Convert the Gemini stream into a Stream of Vec<GeminiStreamEvent>
         let mapped_stream = stream
-            .map_err(|e| AppError::gemini(format!("Stream error: {:?}", e)))
-            .try_filter_map(|response| async move {
+            .map_err(|e| api_error("Stream error", e))
+            .try_filter_map(move |response| async move {
                 let mut events = Vec::new();
 
                 if let Some(candidate) = response.candidates.first() {
+                    tracing::debug!(
+                        elapsed_ms = started.elapsed().as_millis() as u64,
+                        finish_reason = ?candidate.finish_reason,
+                        "gemini chunk received"
+                    );
+
                     if let Some(parts) = &candidate.content.parts {
                         for part in parts {
                             if let Part::Text { text, thought, .. } = part {
@@ -250,6 +497,14 @@ impl GeminiClient {
                             }
                         }
                     }
+
+                    events.extend(candidate.finish_reason.as_ref().and_then(finish_event));
+                }
+
+                events.extend(response.usage_metadata.as_ref().and_then(usage_event));
+
+                for event in &events {
+                    tracing::trace!(?event, "gemini stream event");
                 }
 
                 if events.is_empty() {
@@ -259,33 +514,592 @@ impl GeminiClient {
                 }
             });
 
-        Ok(Box::pin(mapped_stream))
+        let mapped_stream: GeminiEventStream = Box::pin(mapped_stream);
+        Ok(match self.deadline {
+            Some(deadline) => with_stream_deadline(mapped_stream, started + deadline, deadline),
+            None => mapped_stream,
+        })
+    }
+
+    /// Sends an image and prompt with local tools available to the model.
+    ///
+    /// Runs a multi-turn session: text and thoughts are streamed to `on_event`
+    /// as they arrive, function calls requested by the model are executed via
+    /// `tools`, and their results are sent back until the model produces a
+    /// final answer without further calls.
+    ///
+    /// # Arguments
+    /// * `image` - The encoded image
+    /// * `history` - Completed exchanges, oldest first (empty for a new chat)
+    /// * `prompt` - Text prompt describing what to analyze
+    /// * `system_prompt` - Optional system instructions (empty string to skip)
+    /// * `tools` - Tools the model may call
+    /// * `on_event` - Callback receiving streamed events, including tool calls
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if a request fails or the model keeps
+    /// calling tools beyond the round limit.
+    pub async fn analyze_image_with_tools<F>(
+        &self,
+        image: EncodedImage,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+        tools: &ToolRegistry,
+        on_event: F,
+    ) -> Result<()>
+    where
+        F: FnMut(GeminiStreamEvent),
+    {
+        self.analyze_images_with_tools(
+            vec![image],
+            history,
+            prompt,
+            system_prompt,
+            tools,
+            on_event,
+        )
+        .await
+    }
+
+    /// Sends several images and a prompt with local tools available to the
+    /// model.
+    ///
+    /// Behaves like [`Self::analyze_image_with_tools`], attaching the images
+    /// to the first user turn in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if a request fails or the model keeps
+    /// calling tools beyond the round limit, and [`AppError::Timeout`] if the
+    /// whole session does not finish within the deadline.
+    pub async fn analyze_images_with_tools<F>(
+        &self,
+        images: Vec<EncodedImage>,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+        tools: &ToolRegistry,
+        on_event: F,
+    ) -> Result<()>
+    where
+        F: FnMut(GeminiStreamEvent),
+    {
+        let context = images.into_iter().map(image_part).collect();
+        let messages = conversation_messages(context, history, prompt);
+        let session = self.tool_session(messages, system_prompt, tools, on_event);
+
+        // The deadline covers every round, as it covers a whole streamed reply
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, session).await.map_err(|_| {
+                AppError::Timeout(format!(
+                    "response not finished within {}s",
+                    deadline.as_secs()
+                ))
+            })?,
+            None => session.await,
+        }
+    }
+
+    /// Runs the rounds of a tool session until the model stops calling tools.
+    ///
+    /// Usage is reported as the total over all rounds so far, since every
+    /// round is billed.
+    async fn tool_session<F>(
+        &self,
+        mut messages: Vec<Message>,
+        system_prompt: String,
+        tools: &ToolRegistry,
+        mut on_event: F,
+    ) -> Result<()>
+    where
+        F: FnMut(GeminiStreamEvent),
+    {
+        use futures::TryStreamExt;
+
+        let tool = tools.to_gemini_tool()?;
+        let (mut spent_input, mut spent_output) = (0, 0);
+
+        for _ in 0..MAX_TOOL_ROUNDS {
+            let mut request = self
+                .client
+                .generate_content()
+                .with_messages(messages.clone())
+                .with_tool(tool.clone());
+
+            if !system_prompt.trim().is_empty() {
+                request = request.with_system_prompt(&system_prompt);
+            }
+
+            let stream = request
+                .execute_stream()
+                .await
+                .map_err(|e| api_error("API request failed", e))?;
+            let mut stream = std::pin::pin!(stream);
+
+            // Collect the model turn so it can be replayed with the tool results
+            let mut model_parts = Vec::new();
+            let mut calls = Vec::new();
+            let (mut round_input, mut round_output) = (0, 0);
+
+            while let Some(response) =
+                stream.try_next().await.map_err(|e| api_error("Stream error", e))?
+            {
+                if let Some(candidate) = response.candidates.first() {
+                    for part in candidate.content.parts.iter().flatten() {
+                        if let Part::Text { text, thought, .. } = part {
+                            if thought.unwrap_or(false) {
+                                on_event(GeminiStreamEvent::Thought(text.clone()));
+                            } else {
+                                on_event(GeminiStreamEvent::Text(text.clone()));
+                            }
+                        } else if let Some(call) = ToolCall::from_part(part) {
+                            calls.push(call);
+                        }
+                        model_parts.push(part.clone());
+                    }
+
+                    if let Some(finish) = candidate.finish_reason.as_ref().and_then(finish_event) {
+                        on_event(finish);
+                    }
+                }
+
+                // Each chunk reports the round's usage so far
+                if let Some(GeminiStreamEvent::Usage {
+                    input_tokens,
+                    output_tokens,
+                }) = response.usage_metadata.as_ref().and_then(usage_event)
+                {
+                    (round_input, round_output) = (input_tokens, output_tokens);
+                    on_event(GeminiStreamEvent::Usage {
+                        input_tokens: spent_input + input_tokens,
+                        output_tokens: spent_output + output_tokens,
+                    });
+                }
+            }
+
+            spent_input += round_input;
+            spent_output += round_output;
+
+            if calls.is_empty() {
+                return Ok(());
+            }
+
+            messages.push(Message {
+                role: Role::Model,
+                content: Content {
+                    role: Some(Role::Model),
+                    parts: Some(model_parts),
+                },
+            });
+
+            let mut response_parts = Vec::with_capacity(calls.len());
+            for call in calls {
+                let output = tools.dispatch(&call);
+                on_event(GeminiStreamEvent::ToolCall {
+                    name: call.name.clone(),
+                    output: output.to_string(),
+                });
+                response_parts.push(function_response_part(&call.name, output)?);
+            }
+
+            messages.push(Message {
+                role: Role::User,
+                content: Content {
+                    role: Some(Role::User),
+                    parts: Some(response_parts),
+                },
+            });
+        }
+
+        Err(AppError::gemini(format!(
+            "Model exceeded the limit of {} tool rounds",
+            MAX_TOOL_ROUNDS
+        )))
+    }
+
+    /// Counts the input tokens an image and prompt would use.
+    ///
+    /// Calls the `countTokens` endpoint, which is free and fast, so the size
+    /// of a request can be checked before uploading it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if the request fails or the response
+    /// has no token count.
+    pub async fn count_tokens(&self, image: EncodedImage, prompt: String) -> Result<u32> {
+        let image = image_part(image);
+        let messages = conversation_messages(vec![image], &[], prompt);
+        let contents: Vec<&Content> = messages.iter().map(|message| &message.content).collect();
+
+        let mut request = self
+            .http
+            .post(format!("{}:countTokens", self.model_url))
+            .json(&serde_json::json!({ "contents": contents }));
+        if !self.api_key.is_empty() {
+            request = request.header("x-goog-api-key", &self.api_key);
+        }
+
+        let response = self
+            .with_deadline(request.send())
+            .await?
+            .map_err(|e| api_error("Token count failed", e))?;
+        if !response.status().is_success() {
+            return Err(AppError::gemini(format!("Token count failed: {}", response.status())));
+        }
+
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| api_error("Token count failed", e))?;
+        body["totalTokens"]
+            .as_u64()
+            .map(|tokens| tokens as u32)
+            .ok_or_else(|| AppError::gemini("Token count missing from response"))
+    }
+
+    /// Checks that the credentials can access the configured model.
+    ///
+    /// Fetches the model's metadata, which costs no tokens, so it can back a
+    /// "Test key" action.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] with the HTTP status and the API's
+    /// error message if access is denied or the model does not exist.
+    pub async fn check_access(&self) -> Result<()> {
+        let mut request = self.http.get(&self.model_url);
+        if !self.api_key.is_empty() {
+            request = request.header("x-goog-api-key", &self.api_key);
+        }
+
+        let response = self
+            .with_deadline(request.send())
+            .await?
+            .map_err(|e| api_error("Access check failed", e))?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+
+        let body: serde_json::Value = response.json().await.unwrap_or_default();
+        let message = body["error"]["message"].as_str().unwrap_or("no details");
+        Err(AppError::gemini(format!("{}: {}", status, message)))
+    }
+
+    /// Lists the models that can generate content.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if the request fails.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        // The model URL ends in ".../models/<name>"
+        let list_url = self
+            .model_url
+            .rsplit_once('/')
+            .map_or(self.model_url.as_str(), |(base, _)| base);
+
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self.http.get(list_url).query(&[("pageSize", "1000")]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            if !self.api_key.is_empty() {
+                request = request.header("x-goog-api-key", &self.api_key);
+            }
+
+            let response = self
+                .with_deadline(request.send())
+                .await?
+                .map_err(|e| api_error("Listing models failed", e))?;
+            let status = response.status();
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            if !status.is_success() {
+                let message = body["error"]["message"].as_str().unwrap_or("no details");
+                return Err(AppError::gemini(format!("{}: {}", status, message)));
+            }
+
+            let entries = body["models"].as_array().into_iter().flatten();
+            models.extend(entries.filter_map(ModelInfo::from_gemini));
+            page_token = body["nextPageToken"]
+                .as_str()
+                .filter(|token| !token.is_empty())
+                .map(str::to_string);
+            if page_token.is_none() {
+                return Ok(models);
+            }
+        }
+    }
+
+    /// Sends an image with editing instructions to an image-capable model.
+    ///
+    /// The client must be configured with a model that supports image
+    /// output, such as [`IMAGE_EDIT_MODEL`].
+    ///
+    /// # Arguments
+    /// * `image` - The encoded image
+    /// * `prompt` - Editing instructions (e.g., "remove the background")
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::GeminiApi`] if the request fails, and
+    /// [`AppError::ImageProcessing`] if the returned image cannot be decoded.
+    pub async fn edit_image(&self, image: EncodedImage, prompt: String) -> Result<ImageEdit> {
+        use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+        let generation_config = serde_json::from_value(serde_json::json!({
+            "responseModalities": ["TEXT", "IMAGE"]
+        }))?;
+
+        let image = image_part(image);
+        let request = self
+            .client
+            .generate_content()
+            .with_messages(conversation_messages(vec![image], &[], prompt))
+            .with_generation_config(generation_config);
+        let response = self
+            .with_deadline(request.execute())
+            .await?
+            .map_err(|e| api_error("API request failed", e))?;
+
+        let mut edit = ImageEdit::default();
+        let parts = response
+            .candidates
+            .first()
+            .and_then(|candidate| candidate.content.parts.as_ref());
+
+        for part in parts.into_iter().flatten() {
+            match part {
+                Part::Text { text, thought, .. } if !thought.unwrap_or(false) => {
+                    edit.text.push_str(text);
+                }
+                Part::InlineData { inline_data } if edit.image.is_none() => {
+                    let bytes = BASE64.decode(&inline_data.data).map_err(|e| {
+                        AppError::image(format!("Invalid image data from Gemini: {}", e))
+                    })?;
+                    let image = image::load_from_memory(&bytes).map_err(|e| {
+                        AppError::image(format!("Failed to decode generated image: {}", e))
+                    })?;
+                    edit.image = Some(image);
+                }
+                _ => {}
+            }
+        }
+
+        if edit.image.is_none() && edit.text.is_empty() {
+            return Err(AppError::gemini("No image or text received from Gemini"));
+        }
+
+        Ok(edit)
     }
 
     // ── Private Helper Methods ───────────────────────────────────────────────
 
-    /// Builds a message containing an image and text prompt.
-    fn build_image_message(&self, base64_image: String, prompt: String) -> Message {
-        let blob = Blob {
-            mime_type: "image/jpeg".to_string(),
-            data: base64_image,
-        };
+    /// Builds the HTTP client with the configured timeouts, proxy, and CAs.
+    pub(crate) fn http_client(config: &Config) -> Result<reqwest::ClientBuilder> {
+        let timeouts = &config.timeouts;
+        let network = &config.network;
 
-        let image_part = Part::InlineData { inline_data: blob };
-        let text_part = Part::Text {
-            text: prompt,
-            thought: None,
-            thought_signature: None,
-        };
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(timeouts.connect)
+            .read_timeout(timeouts.read)
+            .tcp_keepalive(timeouts.keepalive)
+            .pool_idle_timeout(timeouts.keepalive);
+
+        if let Some(proxy_url) = &network.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| AppError::config(format!("Invalid proxy URL '{}': {}", proxy_url, e)))?
+                .no_proxy(network.no_proxy.as_deref().and_then(reqwest::NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &network.ca_bundle {
+            let pem = std::fs::read(path).map_err(|e| {
+                AppError::config(format!("Failed to read CA bundle {}: {}", path.display(), e))
+            })?;
+            let certificates = reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| {
+                AppError::config(format!("Invalid CA bundle {}: {}", path.display(), e))
+            })?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        Ok(builder)
+    }
+
+    /// Runs a future under the client's overall deadline.
+    async fn with_deadline<F: std::future::Future>(&self, future: F) -> Result<F::Output> {
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, future).await.map_err(|_| {
+                AppError::Timeout(format!("no response within {}s", deadline.as_secs()))
+            }),
+            None => Ok(future.await),
+        }
+    }
+
+}
+
+/// Builds the message list for a conversation about captured content.
+///
+/// `context` (the images, or text extracted from them) is attached to the
+/// first user turn; later turns carry only text.
+fn conversation_messages(context: Vec<Part>, history: &[ChatTurn], prompt: String) -> Vec<Message> {
+    let mut context = Some(context);
+    let mut messages = Vec::with_capacity(history.len() * 2 + 1);
 
-        let content = Content {
+    for turn in history {
+        messages.push(user_message(turn.prompt.clone(), context.take().unwrap_or_default()));
+        messages.push(Message {
+            role: Role::Model,
+            content: Content {
+                role: Some(Role::Model),
+                parts: Some(vec![text_part(turn.answer.clone())]),
+            },
+        });
+    }
+    messages.push(user_message(prompt, context.take().unwrap_or_default()));
+
+    messages
+}
+
+/// Builds a user message from a prompt and attached content.
+fn user_message(prompt: String, context: Vec<Part>) -> Message {
+    let mut parts = vec![text_part(prompt)];
+    parts.extend(context);
+
+    Message {
+        role: Role::User,
+        content: Content {
             role: Some(Role::User),
-            parts: Some(vec![text_part, image_part]),
-        };
+            parts: Some(parts),
+        },
+    }
+}
+
+/// Builds a plain text part.
+fn text_part(text: String) -> Part {
+    Part::Text {
+        text,
+        thought: None,
+        thought_signature: None,
+    }
+}
+
+/// Builds an inline image part.
+fn image_part(image: EncodedImage) -> Part {
+    Part::InlineData {
+        inline_data: Blob {
+            mime_type: image.mime_type.to_string(),
+            data: image.data,
+        },
+    }
+}
+
+/// Builds a usage event from a response's usage metadata.
+///
+/// Read through the serialized (`camelCase`) form so it doesn't depend on
+/// the exact integer types of the client's metadata struct.
+fn usage_event<T: serde::Serialize>(metadata: &T) -> Option<GeminiStreamEvent> {
+    let metadata = serde_json::to_value(metadata).ok()?;
+    let count = |key: &str| metadata[key].as_u64().unwrap_or(0) as u32;
+
+    let input_tokens = count("promptTokenCount");
+    let output_tokens = count("candidatesTokenCount") + count("thoughtsTokenCount");
+    (input_tokens > 0 || output_tokens > 0).then_some(GeminiStreamEvent::Usage {
+        input_tokens,
+        output_tokens,
+    })
+}
 
-        Message {
-            role: Role::User,
-            content,
+/// Builds a finish event from a candidate's finish reason.
+///
+/// Uses the serialized (`SCREAMING_SNAKE_CASE`) name the API sent.
+fn finish_event<T: serde::Serialize>(reason: &T) -> Option<GeminiStreamEvent> {
+    let reason = serde_json::to_value(reason).ok()?;
+    Some(GeminiStreamEvent::Finish(reason.as_str()?.to_string()))
+}
+
+/// Converts a client error into an [`AppError`], detecting HTTP timeouts.
+fn api_error<E>(context: &str, error: E) -> AppError
+where
+    E: std::error::Error + 'static,
+{
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&error);
+    while let Some(err) = source {
+        if let Some(http) = err.downcast_ref::<reqwest::Error>()
+            && http.is_timeout()
+        {
+            return AppError::Timeout(format!("{}: {}", context, http));
         }
+        source = err.source();
     }
-}
\ No newline at end of file
+
+    tracing::debug!(context, error = ?error, "gemini request failed");
+    AppError::gemini(format!("{}: {:?}", context, error))
+}
+
+/// Records sanitized request metadata: sizes and flags, never keys or images.
+fn log_request(
+    model: &str,
+    messages: &[Message],
+    system_prompt: &str,
+    thinking_enabled: bool,
+    google_search: bool,
+) {
+    if !tracing::enabled!(tracing::Level::DEBUG) {
+        return;
+    }
+
+    let parts = messages
+        .iter()
+        .filter_map(|message| message.content.parts.as_ref())
+        .flatten();
+    let (mut text_chars, mut image_bytes) = (0, 0);
+    for part in parts {
+        match part {
+            Part::Text { text, .. } => text_chars += text.len(),
+            Part::InlineData { inline_data } => image_bytes += inline_data.data.len() * 3 / 4,
+            _ => {}
+        }
+    }
+
+    tracing::debug!(
+        model,
+        messages = messages.len(),
+        text_chars,
+        image_bytes,
+        system_prompt_chars = system_prompt.len(),
+        thinking_enabled,
+        google_search,
+        "gemini request"
+    );
+}
+
+/// Ends a stream with [`AppError::Timeout`] once `deadline` passes.
+pub(crate) fn with_stream_deadline(
+    stream: GeminiEventStream,
+    deadline: tokio::time::Instant,
+    limit: Duration,
+) -> GeminiEventStream {
+    use futures::StreamExt;
+
+    let state = Some((stream, deadline));
+    Box::pin(futures::stream::unfold(state, move |state| async move {
+        let (mut stream, deadline) = state?;
+        match tokio::time::timeout_at(deadline, stream.next()).await {
+            Ok(Some(item)) => Some((item, Some((stream, deadline)))),
+            Ok(None) => None,
+            Err(_) => Some((
+                Err(AppError::Timeout(format!(
+                    "response not finished within {}s",
+                    limit.as_secs()
+                ))),
+                None,
+            )),
+        }
+    }))
+}
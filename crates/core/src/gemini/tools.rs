@@ -0,0 +1,334 @@
+//! Local function-calling (tool use) support.
+//!
+//! Tools are local functions that Gemini can ask the application to run, such
+//! as opening a URL found in a screenshot or copying an extracted value to the
+//! clipboard. Their declarations are sent with the request; when the model
+//! responds with a function call, the matching tool is executed and its output
+//! is fed back to the model in a follow-up turn.
+//!
+//! # Safety
+//!
+//! Tools with side effects beyond the clipboard or browser (e.g. running shell
+//! commands) report [`LocalTool::requires_confirmation`]. The registry only
+//! executes them when a confirmation callback approves the call; without a
+//! callback they are always denied.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::gemini::tools::ToolRegistry;
+//!
+//! let tools = ToolRegistry::builtin().with_confirmation(|call| {
+//!     println!("Allow {}? {}", call.name, call.args);
+//!     true
+//! });
+//!
+//! client
+//!     .analyze_image_with_tools(image, &[], prompt, String::new(), &tools, |event| {
+//!         println!("{:?}", event);
+//!     })
+//!     .await?;
+//! ```
+
+use crate::error::{AppError, Result};
+use gemini_rust::{Part, Tool};
+use serde_json::{json, Value};
+use std::process::Command;
+
+/// Maximum number of bytes of command output returned to the model.
+const MAX_OUTPUT_BYTES: usize = 8 * 1024;
+
+/// A function call requested by the model.
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    /// Name of the tool to invoke.
+    pub name: String,
+    /// Arguments as a JSON object.
+    pub args: Value,
+}
+
+impl ToolCall {
+    /// Extracts a function call from a response part, if it contains one.
+    ///
+    /// The part is inspected through its REST representation
+    /// (`{"functionCall": {"name", "args"}}`).
+    pub(crate) fn from_part(part: &Part) -> Option<Self> {
+        let value = serde_json::to_value(part).ok()?;
+        let call = value.get("functionCall")?;
+        Some(Self {
+            name: call.get("name")?.as_str()?.to_string(),
+            args: call.get("args").cloned().unwrap_or_else(|| json!({})),
+        })
+    }
+
+    /// Returns a string argument by key.
+    fn str_arg(&self, key: &str) -> Result<&str> {
+        self.args
+            .get(key)
+            .and_then(Value::as_str)
+            .ok_or_else(|| AppError::tool(format!("{}: missing argument '{}'", self.name, key)))
+    }
+}
+
+/// A local function that can be invoked by the model.
+pub trait LocalTool: Send + Sync {
+    /// Unique function name exposed to the model.
+    fn name(&self) -> &str;
+
+    /// Human-readable description used by the model to decide when to call it.
+    fn description(&self) -> &str;
+
+    /// JSON schema of the function parameters.
+    fn parameters(&self) -> Value;
+
+    /// Whether the user must approve each call before it runs.
+    fn requires_confirmation(&self) -> bool {
+        false
+    }
+
+    /// Executes the tool and returns a JSON result for the model.
+    fn execute(&self, call: &ToolCall) -> Result<Value>;
+}
+
+/// Callback deciding whether a confirmation-gated call may run.
+type ConfirmFn = Box<dyn Fn(&ToolCall) -> bool + Send + Sync>;
+
+/// A set of tools available to the model for a request.
+pub struct ToolRegistry {
+    tools: Vec<Box<dyn LocalTool>>,
+    confirm: Option<ConfirmFn>,
+}
+
+impl ToolRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            tools: Vec::new(),
+            confirm: None,
+        }
+    }
+
+    /// Creates a registry with the built-in tools
+    /// (`open_url`, `copy_to_clipboard`, `run_shell_command`).
+    pub fn builtin() -> Self {
+        Self::new()
+            .with_tool(OpenUrlTool)
+            .with_tool(CopyToClipboardTool)
+            .with_tool(RunShellCommandTool)
+    }
+
+    /// Registers an additional tool.
+    pub fn with_tool(mut self, tool: impl LocalTool + 'static) -> Self {
+        self.tools.push(Box::new(tool));
+        self
+    }
+
+    /// Sets the callback used to approve confirmation-gated tools.
+    pub fn with_confirmation(mut self, confirm: impl Fn(&ToolCall) -> bool + Send + Sync + 'static) -> Self {
+        self.confirm = Some(Box::new(confirm));
+        self
+    }
+
+    /// Returns whether no tools are registered.
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Builds the Gemini tool declaration for all registered functions.
+    ///
+    /// # Errors
+    /// Returns [`AppError::Tool`] if a declaration cannot be represented.
+    pub(crate) fn to_gemini_tool(&self) -> Result<Tool> {
+        let declarations: Vec<Value> = self
+            .tools
+            .iter()
+            .map(|tool| {
+                json!({
+                    "name": tool.name(),
+                    "description": tool.description(),
+                    "parameters": tool.parameters(),
+                })
+            })
+            .collect();
+
+        serde_json::from_value(json!({ "functionDeclarations": declarations }))
+            .map_err(|e| AppError::tool(format!("Invalid tool declaration: {}", e)))
+    }
+
+    /// Executes a call and returns the JSON response sent back to the model.
+    ///
+    /// Failures are reported to the model as `{"error": ...}` rather than
+    /// aborting the conversation, so it can recover or explain the problem.
+    pub fn dispatch(&self, call: &ToolCall) -> Value {
+        let Some(tool) = self.tools.iter().find(|t| t.name() == call.name) else {
+            return json!({ "error": format!("Unknown tool: {}", call.name) });
+        };
+
+        if tool.requires_confirmation() {
+            let approved = self.confirm.as_ref().is_some_and(|confirm| confirm(call));
+            if !approved {
+                return json!({ "error": "The user declined to run this action." });
+            }
+        }
+
+        match tool.execute(call) {
+            Ok(result) => json!({ "result": result }),
+            Err(e) => json!({ "error": e.to_string() }),
+        }
+    }
+}
+
+impl Default for ToolRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Builds the response part carrying a tool result back to the model.
+pub(crate) fn function_response_part(name: &str, response: Value) -> Result<Part> {
+    serde_json::from_value(json!({
+        "functionResponse": { "name": name, "response": response }
+    }))
+    .map_err(|e| AppError::tool(format!("Invalid tool response: {}", e)))
+}
+
+/// Opens a URL in the user's default browser.
+///
+/// Only `http` and `https` URLs are accepted.
+pub fn open_url(url: &str) -> Result<()> {
+    let parsed = url::Url::parse(url).map_err(|e| AppError::tool(format!("Invalid URL: {}", e)))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err(AppError::tool(format!("Refusing to open non-web URL: {}", url)));
+    }
+
+    #[cfg(target_os = "windows")]
+    let mut command = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", "start", ""]);
+        c
+    };
+    #[cfg(target_os = "macos")]
+    let mut command = Command::new("open");
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let mut command = Command::new("xdg-open");
+
+    command.arg(parsed.as_str()).spawn()?;
+    Ok(())
+}
+
+/// Opens a web URL in the default browser.
+struct OpenUrlTool;
+
+impl LocalTool for OpenUrlTool {
+    fn name(&self) -> &str {
+        "open_url"
+    }
+
+    fn description(&self) -> &str {
+        "Open a web page in the user's default browser."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "url": { "type": "string", "description": "Absolute http(s) URL to open" }
+            },
+            "required": ["url"]
+        })
+    }
+
+    fn execute(&self, call: &ToolCall) -> Result<Value> {
+        let url = call.str_arg("url")?;
+        open_url(url)?;
+        Ok(json!(format!("Opened {}", url)))
+    }
+}
+
+/// Places text on the system clipboard.
+struct CopyToClipboardTool;
+
+impl LocalTool for CopyToClipboardTool {
+    fn name(&self) -> &str {
+        "copy_to_clipboard"
+    }
+
+    fn description(&self) -> &str {
+        "Copy text to the user's clipboard."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "text": { "type": "string", "description": "Text to copy" }
+            },
+            "required": ["text"]
+        })
+    }
+
+    fn execute(&self, call: &ToolCall) -> Result<Value> {
+        let text = call.str_arg("text")?;
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| AppError::tool(format!("Clipboard unavailable: {}", e)))?;
+        Ok(json!(format!("Copied {} characters", text.chars().count())))
+    }
+}
+
+/// Runs a shell command after explicit user confirmation.
+struct RunShellCommandTool;
+
+impl LocalTool for RunShellCommandTool {
+    fn name(&self) -> &str {
+        "run_shell_command"
+    }
+
+    fn description(&self) -> &str {
+        "Run a shell command on the user's machine and return its output. \
+         The user is asked to confirm every command."
+    }
+
+    fn parameters(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "command": { "type": "string", "description": "Command line to execute" }
+            },
+            "required": ["command"]
+        })
+    }
+
+    fn requires_confirmation(&self) -> bool {
+        true
+    }
+
+    fn execute(&self, call: &ToolCall) -> Result<Value> {
+        let command_line = call.str_arg("command")?;
+
+        #[cfg(target_os = "windows")]
+        let output = Command::new("cmd").args(["/C", command_line]).output()?;
+        #[cfg(not(target_os = "windows"))]
+        let output = Command::new("sh").args(["-c", command_line]).output()?;
+
+        Ok(json!({
+            "exit_code": output.status.code(),
+            "stdout": truncate_output(&output.stdout),
+            "stderr": truncate_output(&output.stderr),
+        }))
+    }
+}
+
+/// Decodes command output lossily and caps it at [`MAX_OUTPUT_BYTES`].
+fn truncate_output(bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    if text.len() <= MAX_OUTPUT_BYTES {
+        return text.into_owned();
+    }
+
+    let mut end = MAX_OUTPUT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}\n[output truncated]", &text[..end])
+}
@@ -0,0 +1,67 @@
+//! Vertex AI endpoint and authentication support.
+//!
+//! Organizations that only allow Vertex AI cannot use Generative Language API
+//! keys. Vertex requests go to a regional `aiplatform.googleapis.com` endpoint
+//! scoped to a Google Cloud project and authenticate with an OAuth access
+//! token instead of a key.
+//!
+//! Tokens are obtained from an explicit service-account key file when one is
+//! configured, otherwise through Application Default Credentials
+//! (`GOOGLE_APPLICATION_CREDENTIALS`, `gcloud auth application-default login`,
+//! or the metadata server when running on Google Cloud).
+
+use crate::config::VertexConfig;
+use crate::error::{AppError, Result};
+use gcp_auth::TokenProvider;
+
+/// OAuth scope required for Vertex AI requests.
+const CLOUD_PLATFORM_SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+
+/// Returns the base URL of the Vertex AI endpoint for a project and location.
+pub(crate) fn base_url(vertex: &VertexConfig) -> String {
+    let host = if vertex.location == "global" {
+        "aiplatform.googleapis.com".to_string()
+    } else {
+        format!("{}-aiplatform.googleapis.com", vertex.location)
+    };
+
+    format!(
+        "https://{}/v1/projects/{}/locations/{}/",
+        host, vertex.project, vertex.location
+    )
+}
+
+/// Fetches an access token for Vertex AI.
+///
+/// # Errors
+/// Returns [`AppError::Config`] if no usable credentials are found or the
+/// token exchange fails.
+pub(crate) async fn access_token(vertex: &VertexConfig) -> Result<String> {
+    let scopes = &[CLOUD_PLATFORM_SCOPE];
+
+    let token = match &vertex.credentials {
+        Some(path) => {
+            let account = gcp_auth::CustomServiceAccount::from_file(path).map_err(|e| {
+                AppError::config(format!(
+                    "Failed to load service account {}: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
+            account.token(scopes).await
+        }
+        None => {
+            let provider = gcp_auth::provider().await.map_err(|e| {
+                AppError::config(format!(
+                    "No Google Cloud credentials found (run `gcloud auth application-default login` \
+                     or set GOOGLE_APPLICATION_CREDENTIALS): {}",
+                    e
+                ))
+            })?;
+            provider.token(scopes).await
+        }
+    }
+    .map_err(|e| AppError::config(format!("Failed to obtain Vertex AI access token: {}", e)))?;
+
+    Ok(token.as_str().to_string())
+}
@@ -0,0 +1,371 @@
+//! History of past analyses.
+//!
+//! Every answered selection is appended as one JSON line to
+//! `history/history.jsonl` in the user's data directory, newest last. The
+//! cropped image is kept next to it in an [`ImageStore`], so browsing the
+//! history only decodes small thumbnails.
+//!
+//! Entries are found with [`HistoryStore::query`] and removed with
+//! [`HistoryStore::delete`] and [`HistoryStore::purge_older_than`]. A
+//! [`Retention`] policy (the `[history]` table of `config.toml`) limits how
+//! old and how many entries are kept; removing entries rewrites the log.
+//! Appends and rewrites hold a lock on `history/history.lock`, so the
+//! daemon, overlays and CLI commands recording at the same time don't lose
+//! each other's entries.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::history::{HistoryEntry, HistoryQuery, HistoryStore};
+//!
+//! let mut store = HistoryStore::open_default().expect("no data directory");
+//! store.record(&HistoryEntry::new("What is this?", "gemini-2.5-pro", answer), &crop)?;
+//! store.apply_retention(&config.history)?;
+//!
+//! let query = HistoryQuery {
+//!     text: Some("invoice".into()),
+//!     ..HistoryQuery::default()
+//! };
+//! for entry in store.query(&query)? {
+//!     println!("{}: {}", entry.id, entry.prompt);
+//! }
+//! ```
+
+use crate::error::Result;
+use crate::paths;
+use crate::thumbnails::ImageStore;
+use chrono::{Local, TimeZone};
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Byte budget for full images decoded while browsing the history.
+const IMAGE_CACHE_BYTES: usize = 128 * 1024 * 1024;
+
+/// One answered question about a selection.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Unique id, also naming the stored images.
+    pub id: String,
+    /// Unix timestamp (seconds) of the answer.
+    pub created_at: u64,
+    /// The question that was asked.
+    pub prompt: String,
+    /// Model that answered.
+    pub model: String,
+    /// The answer text.
+    pub answer: String,
+    /// Thinking content, if the model produced any.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub thoughts: String,
+    /// Tokens used, if the API reported them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// Tokens used by one request.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    /// Input (prompt and image) tokens.
+    pub input_tokens: u32,
+    /// Output tokens, including thinking.
+    pub output_tokens: u32,
+}
+
+/// Entries created by this process so far, keeping their ids apart.
+static CREATED: AtomicU64 = AtomicU64::new(0);
+
+impl HistoryEntry {
+    /// Creates an entry stamped with the current time.
+    ///
+    /// The id is the time in milliseconds followed by the process id and a
+    /// counter, so entries created at once by several overlays, the daemon
+    /// or a batch never share one.
+    pub fn new(prompt: impl Into<String>, model: impl Into<String>, answer: impl Into<String>) -> Self {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let count = CREATED.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id: format!("{}-{}-{}", now.as_millis(), std::process::id(), count),
+            created_at: now.as_secs(),
+            prompt: prompt.into(),
+            model: model.into(),
+            answer: answer.into(),
+            thoughts: String::new(),
+            usage: None,
+        }
+    }
+
+    /// Returns when the entry was answered, in local time as
+    /// `YYYY-MM-DD HH:MM`.
+    pub fn time(&self) -> String {
+        i64::try_from(self.created_at)
+            .ok()
+            .and_then(|secs| Local.timestamp_opt(secs, 0).single())
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    /// Sets the thinking content.
+    pub fn with_thoughts(mut self, thoughts: impl Into<String>) -> Self {
+        self.thoughts = thoughts.into();
+        self
+    }
+
+    /// Sets the tokens used; zeros mean the API reported none.
+    pub fn with_usage(mut self, input_tokens: u32, output_tokens: u32) -> Self {
+        self.usage = (input_tokens > 0 || output_tokens > 0).then_some(Usage {
+            input_tokens,
+            output_tokens,
+        });
+        self
+    }
+}
+
+/// Which entries [`HistoryStore::query`] returns; unset fields match every
+/// entry.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HistoryQuery {
+    /// Words that must all appear in the prompt, answer or model, ignoring
+    /// case.
+    pub text: Option<String>,
+    /// Part of the model name, ignoring case.
+    pub model: Option<String>,
+    /// Earliest Unix timestamp (seconds), inclusive.
+    pub since: Option<u64>,
+    /// Latest Unix timestamp (seconds), exclusive.
+    pub until: Option<u64>,
+    /// Most entries to return.
+    pub limit: Option<usize>,
+}
+
+impl HistoryQuery {
+    /// Returns whether `entry` matches every condition but the limit.
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let contains = |haystack: &str, needle: &str| {
+            haystack.to_lowercase().contains(&needle.to_lowercase())
+        };
+        let text = self.text.as_deref().unwrap_or_default();
+        let text_matches = text.split_whitespace().all(|word| {
+            contains(&entry.prompt, word)
+                || contains(&entry.answer, word)
+                || contains(&entry.model, word)
+        });
+        text_matches
+            && self.model.as_deref().is_none_or(|model| contains(&entry.model, model))
+            && self.since.is_none_or(|since| entry.created_at >= since)
+            && self.until.is_none_or(|until| entry.created_at < until)
+    }
+}
+
+/// How long and how many entries the history keeps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Entries older than this are removed (`None` keeps them).
+    pub max_age: Option<Duration>,
+    /// Only this many of the newest entries are kept (`None` for all).
+    pub max_entries: Option<usize>,
+}
+
+/// File-backed history of analyses with their cropped images.
+pub struct HistoryStore {
+    /// Path of the JSONL log.
+    path: PathBuf,
+    images: ImageStore,
+}
+
+impl HistoryStore {
+    /// Opens the store in the default data directory.
+    ///
+    /// Returns `None` if the platform data directory cannot be determined.
+    pub fn open_default() -> Option<Self> {
+        paths::history_dir().map(Self::at)
+    }
+
+    /// Opens a store rooted at a specific directory.
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        Self {
+            path: dir.join("history.jsonl"),
+            images: ImageStore::new(dir.join("images"), IMAGE_CACHE_BYTES),
+        }
+    }
+
+    /// Appends an entry and stores its cropped image.
+    ///
+    /// # Errors
+    /// Returns an error if the image or the log cannot be written.
+    pub fn record(&mut self, entry: &HistoryEntry, crop: &DynamicImage) -> Result<()> {
+        self.images.insert(&entry.id, crop)?;
+
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let _lock = self.lock()?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns all entries, newest first.
+    ///
+    /// Lines that cannot be parsed (e.g. cut short by a crash) are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the log exists but cannot be read.
+    pub fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    /// Returns the entries `query` matches, newest first.
+    ///
+    /// # Errors
+    /// Returns an error if the log exists but cannot be read.
+    pub fn query(&self, query: &HistoryQuery) -> Result<Vec<HistoryEntry>> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| query.matches(entry))
+            .take(query.limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Removes an entry and its images.
+    ///
+    /// Returns whether the entry existed.
+    ///
+    /// # Errors
+    /// Returns an error if the log cannot be rewritten.
+    pub fn delete(&mut self, id: &str) -> Result<bool> {
+        Ok(self.remove_where(|entry| entry.id == id)? > 0)
+    }
+
+    /// Removes entries answered more than `age` ago, with their images.
+    ///
+    /// Returns the number of entries removed.
+    ///
+    /// # Errors
+    /// Returns an error if the log cannot be rewritten.
+    pub fn purge_older_than(&mut self, age: Duration) -> Result<usize> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let cutoff = now.saturating_sub(age).as_secs();
+        self.remove_where(|entry| entry.created_at < cutoff)
+    }
+
+    /// Removes the entries `retention` doesn't keep, with their images.
+    ///
+    /// Returns the number of entries removed.
+    ///
+    /// # Errors
+    /// Returns an error if the log cannot be read or rewritten.
+    pub fn apply_retention(&mut self, retention: &Retention) -> Result<usize> {
+        let mut removed = match retention.max_age {
+            Some(age) => self.purge_older_than(age)?,
+            None => 0,
+        };
+        if let Some(max_entries) = retention.max_entries {
+            let excess: HashSet<String> =
+                self.entries()?.into_iter().skip(max_entries).map(|entry| entry.id).collect();
+            if !excess.is_empty() {
+                removed += self.remove_where(|entry| excess.contains(&entry.id))?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Returns the path of an entry's full cropped image.
+    pub fn image_path(&self, id: &str) -> PathBuf {
+        self.images.image_path(id)
+    }
+
+    /// Returns the thumbnail of an entry's image.
+    ///
+    /// # Errors
+    /// Returns an error if the entry has no readable image.
+    pub fn thumbnail(&self, id: &str) -> Result<DynamicImage> {
+        self.images.thumbnail(id)
+    }
+
+    /// Returns the full cropped image of an entry.
+    ///
+    /// # Errors
+    /// Returns an error if the entry has no readable image.
+    pub fn image(&mut self, id: &str) -> Result<Arc<DynamicImage>> {
+        self.images.full_image(id)
+    }
+
+    /// Rewrites the log without the entries `remove` picks and deletes
+    /// their images; returns how many were removed.
+    ///
+    /// The new log is written next to the old one and moved over it, so a
+    /// crash leaves one of them intact. Unreadable lines are dropped. The
+    /// log stays locked from reading to moving, so no entry is appended in
+    /// between and lost.
+    fn remove_where(&mut self, remove: impl Fn(&HistoryEntry) -> bool) -> Result<usize> {
+        let _lock = self.lock()?;
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Kept lines are copied as they are, so fields of newer versions survive
+        let mut kept = String::with_capacity(content.len());
+        let mut removed = Vec::new();
+        for line in content.lines() {
+            match serde_json::from_str::<HistoryEntry>(line) {
+                Ok(entry) if remove(&entry) => removed.push(entry.id),
+                Ok(_) => {
+                    kept.push_str(line);
+                    kept.push('\n');
+                }
+                Err(_) => {}
+            }
+        }
+        if removed.is_empty() {
+            return Ok(0);
+        }
+
+        let temporary = self.path.with_extension("jsonl.tmp");
+        fs::write(&temporary, kept)?;
+        fs::rename(&temporary, &self.path)?;
+        for id in &removed {
+            self.images.remove(id);
+        }
+        Ok(removed.len())
+    }
+
+    /// Locks the log against other processes until the returned file is
+    /// dropped.
+    ///
+    /// The lock is on a separate file, since rewrites replace the log.
+    fn lock(&self) -> Result<File> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.path.with_extension("lock"))?;
+        file.lock()?;
+        Ok(file)
+    }
+}
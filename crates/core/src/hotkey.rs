@@ -0,0 +1,318 @@
+//! Global hotkeys of the daemon mode.
+//!
+//! Hotkeys are written as modifiers and one key joined by `+`, in any case
+//! and order: `ctrl+alt+x`, `Ctrl+Shift+S`, `super+prtsc`, `f9`. They are
+//! set with `hotkey` in `config.toml` or the settings, or `AI_SHOT_HOTKEY`.
+//!
+//! Modifiers are `ctrl` (`control`), `alt` (`option`), `shift` and `super`
+//! (`win`, `cmd`, `meta`). Keys are letters, digits, `f1`–`f24`, `prtsc`
+//! (`print`, `printscreen`), `space`, `enter`, `tab`, `esc`, `insert`,
+//! `delete`, `home`, `end`, `pageup`, `pagedown` and the arrows `up`,
+//! `down`, `left`, `right`.
+//!
+//! Besides `hotkey`, which opens the overlay, the `[hotkeys]` table of
+//! `config.toml` binds more hotkeys to [`HotkeyAction`]s:
+//!
+//! ```toml
+//! [hotkeys]
+//! "ctrl+alt+w" = "active-window"
+//! "ctrl+alt+o" = "ocr"
+//! "ctrl+alt+r" = "repeat-prompt"
+//! "ctrl+alt+p" = "toggle-pause"
+//! "ctrl+alt+h" = "hold-select"
+//! ```
+//!
+//! `hold-select` is the fastest way to ask the same thing repeatedly: hold
+//! the keys, move the pointer from one corner of the area to the other and
+//! let go. It needs key releases and the pointer position, so it is not
+//! available through the Wayland portal or `ai-shot trigger`.
+//!
+//! [`Hotkey::conflicts`] reports combinations the operating system or
+//! desktop usually claims for itself, which the daemon would never see or
+//! would steal from other programs.
+
+use crate::error::AppError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A key other than a modifier.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// A letter (uppercase) or digit.
+    Char(char),
+    /// A function key, `F1` to `F24`.
+    F(u8),
+    PrintScreen,
+    Space,
+    Enter,
+    Tab,
+    Escape,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// Named keys with their spellings; the first one is used for display.
+const NAMED_KEYS: &[(Key, &[&str])] = &[
+    (Key::PrintScreen, &["prtsc", "print", "printscreen", "prtscr"]),
+    (Key::Space, &["space"]),
+    (Key::Enter, &["enter", "return"]),
+    (Key::Tab, &["tab"]),
+    (Key::Escape, &["esc", "escape"]),
+    (Key::Insert, &["insert", "ins"]),
+    (Key::Delete, &["delete", "del"]),
+    (Key::Home, &["home"]),
+    (Key::End, &["end"]),
+    (Key::PageUp, &["pageup", "pgup"]),
+    (Key::PageDown, &["pagedown", "pgdn"]),
+    (Key::Up, &["up"]),
+    (Key::Down, &["down"]),
+    (Key::Left, &["left"]),
+    (Key::Right, &["right"]),
+];
+
+impl Key {
+    fn parse(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return c.is_ascii_alphanumeric().then(|| Key::Char(c.to_ascii_uppercase()));
+        }
+        if let Some(number) = name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+            return (1..=24).contains(&number).then_some(Key::F(number));
+        }
+        NAMED_KEYS
+            .iter()
+            .find(|(_, names)| names.contains(&name))
+            .map(|(key, _)| *key)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{}", c.to_ascii_lowercase()),
+            Key::F(number) => write!(f, "f{}", number),
+            key => {
+                let name = NAMED_KEYS
+                    .iter()
+                    .find(|(named, _)| named == key)
+                    .map_or("?", |(_, names)| names[0]);
+                f.write_str(name)
+            }
+        }
+    }
+}
+
+/// Modifier keys held together with the key.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    /// The Control key.
+    pub ctrl: bool,
+    /// The Alt or Option key.
+    pub alt: bool,
+    /// The Shift key.
+    pub shift: bool,
+    /// The Windows, Command or Super key.
+    pub super_key: bool,
+}
+
+impl Modifiers {
+    /// Returns whether no modifier is held.
+    pub fn is_empty(self) -> bool {
+        self == Self::default()
+    }
+}
+
+/// A key combination, e.g. `ctrl+alt+x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Hotkey {
+    /// Modifiers that must be held; no others may be.
+    pub modifiers: Modifiers,
+    /// The key that triggers the hotkey.
+    pub key: Key,
+}
+
+/// Shortcuts taken by common desktops: (combination, what it does).
+const SYSTEM_SHORTCUTS: &[(&str, &str)] = &[
+    ("ctrl+alt+delete", "the system security screen"),
+    ("alt+tab", "switching windows"),
+    ("alt+f4", "closing windows"),
+    ("super+l", "locking the screen on Windows and GNOME"),
+    ("ctrl+alt+l", "locking the screen on KDE and some Linux desktops"),
+    ("ctrl+alt+t", "opening a terminal on Ubuntu"),
+    ("prtsc", "the desktop's own screenshot tool"),
+    ("super+shift+s", "the Windows Snipping Tool"),
+    ("super+shift+3", "macOS screenshots"),
+    ("super+shift+4", "macOS screenshots"),
+    ("super+shift+5", "macOS screenshots"),
+    ("super+space", "Spotlight and input source switching"),
+    ("ctrl+c", "copying in every application"),
+    ("ctrl+v", "pasting in every application"),
+    ("ctrl+x", "cutting in every application"),
+    ("ctrl+z", "undo in every application"),
+];
+
+impl Hotkey {
+    /// Returns what the combination is usually taken by, if the operating
+    /// system, desktop or applications commonly use it.
+    ///
+    /// Only well-known shortcuts are detected; desktop-specific bindings
+    /// the user configured cannot be seen from here.
+    pub fn conflicts(&self) -> Option<&'static str> {
+        SYSTEM_SHORTCUTS
+            .iter()
+            .find(|(combination, _)| combination.parse::<Hotkey>().ok() == Some(*self))
+            .map(|(_, owner)| *owner)
+    }
+}
+
+impl Default for Hotkey {
+    /// `ctrl+alt+x`.
+    fn default() -> Self {
+        Self {
+            modifiers: Modifiers {
+                ctrl: true,
+                alt: true,
+                ..Modifiers::default()
+            },
+            key: Key::Char('X'),
+        }
+    }
+}
+
+impl FromStr for Hotkey {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| AppError::config(format!("Invalid hotkey '{}': {}", s, reason));
+
+        let mut modifiers = Modifiers::default();
+        let mut key = None;
+        for part in s.split('+').map(|part| part.trim().to_ascii_lowercase()) {
+            let modifier = match part.as_str() {
+                "ctrl" | "control" => &mut modifiers.ctrl,
+                "alt" | "option" => &mut modifiers.alt,
+                "shift" => &mut modifiers.shift,
+                "super" | "win" | "cmd" | "command" | "meta" => &mut modifiers.super_key,
+                "" => return Err(invalid("empty key name")),
+                name => {
+                    let parsed = Key::parse(name)
+                        .ok_or_else(|| invalid(&format!("unknown key '{}'", name)))?;
+                    if key.replace(parsed).is_some() {
+                        return Err(invalid("only one key besides modifiers is allowed"));
+                    }
+                    continue;
+                }
+            };
+            *modifier = true;
+        }
+
+        let key = key.ok_or_else(|| invalid("no key besides modifiers"))?;
+        // A bare letter would be swallowed while typing anywhere
+        if modifiers.is_empty()
+            && matches!(key, Key::Char(_) | Key::Space | Key::Enter | Key::Tab)
+        {
+            return Err(invalid("add a modifier such as ctrl or alt"));
+        }
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "ctrl"),
+            (self.modifiers.alt, "alt"),
+            (self.modifiers.shift, "shift"),
+            (self.modifiers.super_key, "super"),
+        ];
+        for (held, name) in modifiers {
+            if held {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// What the daemon does when a hotkey is pressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum HotkeyAction {
+    /// Capture the screen and open the overlay to select a region.
+    Select,
+    /// Ask the default prompt about the active window and copy the answer.
+    ActiveWindow,
+    /// Copy the text of the active window to the clipboard.
+    Ocr,
+    /// Ask the last prompt typed in the overlay about the active window and
+    /// copy the answer.
+    RepeatPrompt,
+    /// Pause the daemon's other hotkeys, or resume them ("do not disturb").
+    TogglePause,
+    /// Ask the default prompt about the area the pointer moves across while
+    /// the hotkey is held, without the overlay, and copy the answer.
+    HoldSelect,
+}
+
+/// Actions by name in `config.toml`, with what they do.
+const ACTIONS: &[(HotkeyAction, &str, &str)] = &[
+    (HotkeyAction::Select, "select", "select a region"),
+    (HotkeyAction::ActiveWindow, "active-window", "ask about the active window"),
+    (HotkeyAction::Ocr, "ocr", "copy the text of the active window"),
+    (HotkeyAction::RepeatPrompt, "repeat-prompt", "ask the last prompt about the active window"),
+    (HotkeyAction::TogglePause, "toggle-pause", "pause or resume the other hotkeys"),
+    (HotkeyAction::HoldSelect, "hold-select", "ask about the area the pointer crosses while held"),
+];
+
+impl HotkeyAction {
+    /// Returns every action.
+    pub fn all() -> impl Iterator<Item = HotkeyAction> {
+        ACTIONS.iter().map(|(action, _, _)| *action)
+    }
+
+    /// Describes the action for the daemon's list of hotkeys.
+    pub fn description(self) -> &'static str {
+        ACTIONS
+            .iter()
+            .find(|(action, _, _)| *action == self)
+            .map_or("", |(_, _, description)| description)
+    }
+}
+
+impl FromStr for HotkeyAction {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.trim().to_ascii_lowercase().replace('_', "-");
+        ACTIONS
+            .iter()
+            .find(|(_, action_name, _)| *action_name == name)
+            .map(|(action, _, _)| *action)
+            .ok_or_else(|| {
+                let names: Vec<&str> = ACTIONS.iter().map(|(_, name, _)| *name).collect();
+                AppError::config(format!(
+                    "Unknown hotkey action '{}' (expected one of: {})",
+                    s,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+impl fmt::Display for HotkeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = ACTIONS
+            .iter()
+            .find(|(action, _, _)| action == self)
+            .map_or("?", |(_, name, _)| name);
+        f.write_str(name)
+    }
+}
@@ -3,18 +3,326 @@
 //! This module handles cropping screen captures based on UI selections
 //! and encoding them for transmission to the Gemini API.
 //!
+//! # Encoding
+//!
+//! Uploads are JPEG by default, which keeps them small but smears small
+//! text. [`ImageEncoding`] selects PNG or lossless WebP instead, which keep
+//! text-heavy screenshots sharp at the cost of larger uploads.
+//!
+//! Encoded images hold nothing but pixels: no EXIF data, color profiles,
+//! text chunks or timestamps. Encoding is deterministic, so the same pixels
+//! always produce the same bytes.
+//!
+//! Screenshots of text or code can also be run through
+//! [`ImageProcessor::enhance_text`] per request ([`UploadOptions`]), which
+//! helps the model read small or low-contrast text.
+//!
 //! # Coordinate Mapping
 //!
 //! The UI displays images at logical pixel sizes (e.g., 1920x1080) while
 //! the actual captured image may be at a different resolution (e.g., 3840x2160).
 //! This module handles the coordinate transformation between UI space and
 //! image space.
+//!
+//! # Comparing
+//!
+//! [`ImageProcessor::diff`] compares two screenshots and highlights the
+//! pixels that changed, e.g. to ask the model what changed between them.
+//!
+//! # Redaction
+//!
+//! Sensitive parts of a selection can be masked with [`Redaction`]s. They are
+//! burned into the pixels by [`ImageProcessor::apply_redactions`] before any
+//! cropping, OCR, or encoding, so masked content never leaves the machine.
 
 use crate::error::{AppError, Result};
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
 use eframe::egui;
-use image::{DynamicImage, ImageFormat};
-use std::io::Cursor;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Encoded image size above which uploads are likely to be slow or rejected.
+pub const LARGE_PAYLOAD_BYTES: usize = 4 * 1024 * 1024;
+
+/// Longest edge, in pixels, that oversized selections are downscaled to.
+pub const DOWNSCALE_MAX_EDGE: u32 = 2048;
+
+/// Shortest edge, in pixels, that tiny selections are enlarged to unless
+/// configured otherwise.
+pub const DEFAULT_UPSCALE_MIN_EDGE: u32 = 256;
+
+/// JPEG quality used unless configured otherwise.
+pub const DEFAULT_JPEG_QUALITY: u8 = 75;
+
+/// How images are encoded for upload.
+///
+/// Written as `jpeg`, `jpeg:90` (with a quality from 1 to 100), `png` or
+/// `webp` in `config.toml`, the settings and `AI_SHOT_IMAGE_ENCODING`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageEncoding {
+    /// Lossy JPEG; the smallest uploads.
+    Jpeg {
+        /// Quality from 1 to 100.
+        quality: u8,
+    },
+    /// Lossless PNG.
+    Png,
+    /// Lossless WebP, usually smaller than PNG. The image crate doesn't
+    /// write lossy WebP.
+    WebP,
+}
+
+impl ImageEncoding {
+    /// Returns the MIME type the API is told the data has.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageEncoding::Jpeg { .. } => "image/jpeg",
+            ImageEncoding::Png => "image/png",
+            ImageEncoding::WebP => "image/webp",
+        }
+    }
+}
+
+impl Default for ImageEncoding {
+    /// JPEG at [`DEFAULT_JPEG_QUALITY`].
+    fn default() -> Self {
+        ImageEncoding::Jpeg {
+            quality: DEFAULT_JPEG_QUALITY,
+        }
+    }
+}
+
+impl FromStr for ImageEncoding {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = |reason: &str| {
+            AppError::config(format!("Invalid image encoding '{}': {}", s, reason))
+        };
+        let lowercase = s.trim().to_ascii_lowercase();
+        let (name, quality) = match lowercase.split_once(':') {
+            Some((name, quality)) => (name.trim(), Some(quality.trim())),
+            None => (lowercase.as_str(), None),
+        };
+
+        match (name, quality) {
+            ("jpeg" | "jpg", None) => Ok(ImageEncoding::default()),
+            ("jpeg" | "jpg", Some(quality)) => match quality.parse::<u8>() {
+                Ok(quality) if (1..=100).contains(&quality) => Ok(ImageEncoding::Jpeg { quality }),
+                _ => Err(invalid("the quality must be a number from 1 to 100")),
+            },
+            ("png" | "webp", Some(_)) => Err(invalid("only JPEG has a quality")),
+            ("png", None) => Ok(ImageEncoding::Png),
+            ("webp", None) => Ok(ImageEncoding::WebP),
+            _ => Err(invalid("expected jpeg, jpeg:<quality>, png or webp")),
+        }
+    }
+}
+
+impl fmt::Display for ImageEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageEncoding::Jpeg { quality } => write!(f, "jpeg:{}", quality),
+            ImageEncoding::Png => f.write_str("png"),
+            ImageEncoding::WebP => f.write_str("webp"),
+        }
+    }
+}
+
+/// How tiny selections are enlarged.
+///
+/// Written as `lanczos` or `nearest` in `config.toml` and
+/// `AI_SHOT_UPSCALE_FILTER`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UpscaleFilter {
+    /// Smooth resampling; reads best for photos and anti-aliased text.
+    #[default]
+    Lanczos,
+    /// Repeats each pixel; keeps pixel art and 1px lines crisp.
+    Nearest,
+}
+
+impl FromStr for UpscaleFilter {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "lanczos" => Ok(UpscaleFilter::Lanczos),
+            "nearest" => Ok(UpscaleFilter::Nearest),
+            _ => Err(AppError::config(format!(
+                "Invalid upscale filter '{}': expected lanczos or nearest",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for UpscaleFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UpscaleFilter::Lanczos => f.write_str("lanczos"),
+            UpscaleFilter::Nearest => f.write_str("nearest"),
+        }
+    }
+}
+
+/// Blur radius of the sharpening in [`ImageProcessor::enhance_text`].
+const TEXT_SHARPEN_SIGMA: f32 = 0.8;
+
+/// Brightness difference below which [`ImageProcessor::enhance_text`]
+/// leaves pixels unsharpened, so flat backgrounds don't turn grainy.
+const TEXT_SHARPEN_THRESHOLD: i32 = 4;
+
+/// An image encoded for upload, with what the API needs to know about it.
+///
+/// The MIME type travels with the data, so the request always declares the
+/// encoding the image actually has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedImage {
+    /// The encoded image as Base64.
+    pub data: String,
+    /// MIME type of the encoding, e.g. `image/jpeg`.
+    pub mime_type: &'static str,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+    /// Size of the encoded image in bytes, before Base64.
+    pub byte_len: usize,
+}
+
+/// How a selection is prepared for upload.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UploadOptions {
+    /// Longest edge in pixels; larger selections are downscaled.
+    pub max_edge: Option<u32>,
+    /// Most pixels; larger selections are downscaled.
+    pub max_pixels: Option<u64>,
+    /// Shortest edge in pixels; smaller selections are enlarged.
+    pub min_edge: Option<u32>,
+    /// How selections below `min_edge` are enlarged.
+    pub upscale_filter: UpscaleFilter,
+    /// Run [`ImageProcessor::enhance_text`] after downscaling.
+    pub enhance_text: bool,
+    /// How the image is encoded.
+    pub encoding: ImageEncoding,
+}
+
+/// Size of pixelation blocks, in UI pixels.
+///
+/// Large enough that typical UI text becomes unreadable.
+pub const PIXELATE_BLOCK: f32 = 12.0;
+
+/// How a redacted region is masked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedactionStyle {
+    /// Replace the region with coarse blocks of its average colors.
+    #[default]
+    Pixelate,
+    /// Paint the region solid black.
+    Fill,
+}
+
+/// A region to mask before the image is sent anywhere.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Redaction {
+    /// The masked region in UI coordinates.
+    pub rect: egui::Rect,
+    /// How the region is masked.
+    pub style: RedactionStyle,
+}
+
+/// Channel difference up to which pixels count as unchanged, so JPEG noise
+/// and font smoothing don't show up as changes.
+const DIFF_TOLERANCE: u8 = 16;
+
+/// Two images compared by [`ImageProcessor::diff`].
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// The second image faded to gray, with changed pixels in red.
+    pub image: DynamicImage,
+    /// Number of pixels that differ.
+    pub changed_pixels: u64,
+    /// The smallest region containing every changed pixel, if any changed.
+    pub changed_region: Option<PixelRegion>,
+}
+
+impl ImageDiff {
+    /// Returns the share of changed pixels, from 0 to 100.
+    pub fn changed_percent(&self) -> f64 {
+        let total = u64::from(self.image.width()) * u64::from(self.image.height());
+        if total == 0 {
+            return 0.0;
+        }
+        self.changed_pixels as f64 * 100.0 / total as f64
+    }
+}
+
+/// A rectangular region of an image in pixel coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PixelRegion {
+    /// Left edge in pixels.
+    pub x: u32,
+    /// Top edge in pixels.
+    pub y: u32,
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+impl PixelRegion {
+    /// Clips the region to an image of the given size.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::EmptySelection`] if no part of the region lies
+    /// inside the image.
+    pub fn clamp_to(self, image_width: u32, image_height: u32) -> Result<PixelRegion> {
+        let width = self.width.min(image_width.saturating_sub(self.x));
+        let height = self.height.min(image_height.saturating_sub(self.y));
+        if width == 0 || height == 0 {
+            return Err(AppError::EmptySelection);
+        }
+        Ok(PixelRegion { width, height, ..self })
+    }
+}
+
+/// Parses regions written as `X,Y,WxH` (e.g. `100,200,640x480`), or as
+/// `X,Y WxH` the way `slurp` and `ai-shot select` print them.
+impl FromStr for PixelRegion {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || AppError::config(format!("Invalid region '{}', expected X,Y,WxH", s));
+        let (x, rest) = s.trim().split_once(',').ok_or_else(invalid)?;
+        let (y, size) = rest.trim_start().split_once([',', ' ']).ok_or_else(invalid)?;
+        let (width, height) = size.split_once(['x', 'X']).ok_or_else(invalid)?;
+        let number = |value: &str| value.trim().parse::<u32>().map_err(|_| invalid());
+
+        let region = PixelRegion {
+            x: number(x)?,
+            y: number(y)?,
+            width: number(width)?,
+            height: number(height)?,
+        };
+        if region.width == 0 || region.height == 0 {
+            return Err(AppError::EmptySelection);
+        }
+        Ok(region)
+    }
+}
+
+impl fmt::Display for PixelRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}x{}", self.x, self.y, self.width, self.height)
+    }
+}
 
 /// Image processing utilities for the selection workflow.
 ///
@@ -37,7 +345,7 @@ impl ImageProcessor {
     ///
     /// # Returns
     ///
-    /// A Base64-encoded JPEG string ready for API transmission.
+    /// The selection as a JPEG, ready for API transmission.
     ///
     /// # Errors
     ///
@@ -47,7 +355,7 @@ impl ImageProcessor {
     /// # Example
     ///
     /// ```ignore
-    /// let base64 = ImageProcessor::process_selection(
+    /// let image = ImageProcessor::process_selection(
     ///     &screenshot,
     ///     selection_rect,
     ///     screen_size,
@@ -57,10 +365,219 @@ impl ImageProcessor {
         original: &DynamicImage,
         selection: egui::Rect,
         ui_size: egui::Vec2,
-    ) -> Result<String> {
+    ) -> Result<EncodedImage> {
+        Self::process_selection_with_options(original, selection, ui_size, UploadOptions::default())
+    }
+
+    /// Crops a selection like [`Self::process_selection`], prepares it
+    /// with [`Self::prepare_upload`] and encodes it as `options` say.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::EmptySelection`] if the selection has zero area.
+    /// Returns [`AppError::ImageProcessing`] if encoding fails.
+    pub fn process_selection_with_options(
+        original: &DynamicImage,
+        selection: egui::Rect,
+        ui_size: egui::Vec2,
+        options: UploadOptions,
+    ) -> Result<EncodedImage> {
+        let cropped = Self::crop_selection(original, selection, ui_size)?;
+        let prepared = Self::prepare_upload(cropped, options);
+        Self::encode_image(&prepared, options.encoding)
+    }
+
+    /// Scales an image to the limits in `options`, then enhances its text
+    /// if asked to.
+    pub fn prepare_upload(image: DynamicImage, options: UploadOptions) -> DynamicImage {
+        let mut image = match options.max_edge {
+            Some(max_edge) => Self::downscale_to_fit(image, max_edge),
+            None => image,
+        };
+        if let Some(smaller) =
+            options.max_pixels.and_then(|max| Self::downscale_to_pixels(&image, max))
+        {
+            image = smaller;
+        }
+        let upscaled = options.min_edge.and_then(|min_edge| {
+            Self::upscale_to_min_edge(&image, min_edge, options.upscale_filter)
+        });
+        if let Some(larger) = upscaled {
+            image = larger;
+        }
+        if options.enhance_text {
+            image = Self::enhance_text(&image);
+        }
+        image
+    }
+
+    /// Prepares a screenshot of text or code for the model to read:
+    /// converts it to grayscale, stretches the contrast between its darkest
+    /// and brightest tones and sharpens it lightly.
+    ///
+    /// Colors are lost, so this suits text rather than charts or photos.
+    pub fn enhance_text(image: &DynamicImage) -> DynamicImage {
+        let mut gray = image.to_luma8();
+
+        // The darkest and brightest 1% are ignored, so a few stray pixels
+        // don't keep the contrast from being stretched
+        let mut histogram = [0u64; 256];
+        for pixel in gray.pixels() {
+            histogram[pixel.0[0] as usize] += 1;
+        }
+        let cutoff = gray.width() as u64 * gray.height() as u64 / 100;
+        let tone_at = |tones: &mut dyn Iterator<Item = usize>| {
+            let mut seen = 0;
+            for tone in tones {
+                seen += histogram[tone];
+                if seen > cutoff {
+                    return tone as u8;
+                }
+            }
+            0
+        };
+        let darkest = tone_at(&mut (0..256));
+        let brightest = tone_at(&mut (0..256).rev());
+
+        if brightest > darkest {
+            let range = (brightest - darkest) as f32;
+            for pixel in gray.pixels_mut() {
+                let tone = pixel.0[0].clamp(darkest, brightest) - darkest;
+                pixel.0[0] = (tone as f32 * 255.0 / range).round() as u8;
+            }
+        }
+
+        let sharpened =
+            image::imageops::unsharpen(&gray, TEXT_SHARPEN_SIGMA, TEXT_SHARPEN_THRESHOLD);
+        DynamicImage::ImageLuma8(sharpened)
+    }
+
+    /// Shrinks an image so its longest edge is at most `max_edge` pixels,
+    /// preserving the aspect ratio. Smaller images are returned unchanged.
+    pub fn downscale_to_fit(image: DynamicImage, max_edge: u32) -> DynamicImage {
+        if image.width() <= max_edge && image.height() <= max_edge {
+            return image;
+        }
+        image.resize(max_edge, max_edge, image::imageops::FilterType::Lanczos3)
+    }
+
+    /// Shrinks an image to at most `max_pixels` pixels, preserving the
+    /// aspect ratio, with a Lanczos filter that keeps text legible.
+    ///
+    /// Returns `None` if the image is small enough already.
+    pub fn downscale_to_pixels(image: &DynamicImage, max_pixels: u64) -> Option<DynamicImage> {
+        let pixels = image.width() as u64 * image.height() as u64;
+        if pixels <= max_pixels {
+            return None;
+        }
+        let scale = (max_pixels as f64 / pixels as f64).sqrt();
+        let width = ((image.width() as f64 * scale) as u32).max(1);
+        let height = ((image.height() as f64 * scale) as u32).max(1);
+        Some(image.resize_exact(width, height, image::imageops::FilterType::Lanczos3))
+    }
+
+    /// Enlarges an image by a whole factor until its shortest edge is at
+    /// least `min_edge`, or returns `None` if it already is.
+    ///
+    /// Whole factors keep pixel edges sharp with [`UpscaleFilter::Nearest`].
+    /// The longest edge stays within [`DOWNSCALE_MAX_EDGE`], so thin strips
+    /// are enlarged less.
+    pub fn upscale_to_min_edge(
+        image: &DynamicImage,
+        min_edge: u32,
+        filter: UpscaleFilter,
+    ) -> Option<DynamicImage> {
+        let (shortest, longest) = (
+            image.width().min(image.height()),
+            image.width().max(image.height()),
+        );
+        if shortest == 0 || shortest >= min_edge {
+            return None;
+        }
+        let factor = min_edge.div_ceil(shortest).min(DOWNSCALE_MAX_EDGE / longest.max(1));
+        if factor < 2 {
+            return None;
+        }
+        let filter = match filter {
+            UpscaleFilter::Lanczos => image::imageops::FilterType::Lanczos3,
+            UpscaleFilter::Nearest => image::imageops::FilterType::Nearest,
+        };
+        Some(image.resize_exact(image.width() * factor, image.height() * factor, filter))
+    }
+
+    /// Crops an image based on UI selection coordinates without encoding it.
+    ///
+    /// Performs the same UI-to-image coordinate mapping as
+    /// [`Self::process_selection`], returning the raw cropped pixels.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::EmptySelection`] if the selection has zero area.
+    pub fn crop_selection(
+        original: &DynamicImage,
+        selection: egui::Rect,
+        ui_size: egui::Vec2,
+    ) -> Result<DynamicImage> {
+        let region = Self::map_selection(selection, ui_size, original.width(), original.height())?;
+
+        // Crop the image (immutable operation, returns new image)
+        Ok(original.crop_imm(region.x, region.y, region.width, region.height))
+    }
+
+    /// Crops a region given in image pixels, clipped to the image bounds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::EmptySelection`] if the region lies outside the image.
+    pub fn crop_region(original: &DynamicImage, region: PixelRegion) -> Result<DynamicImage> {
+        let region = region.clamp_to(original.width(), original.height())?;
+        Ok(original.crop_imm(region.x, region.y, region.width, region.height))
+    }
+
+    /// Reads the image currently on the clipboard, e.g. a screenshot copied
+    /// by another tool.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if the clipboard is unavailable
+    /// or holds no image.
+    pub fn from_clipboard() -> Result<DynamicImage> {
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| AppError::image(format!("Clipboard unavailable: {}", e)))?;
+        let data = clipboard.get_image().map_err(|e| match e {
+            arboard::Error::ContentNotAvailable => AppError::image("The clipboard holds no image"),
+            e => AppError::image(format!("Failed to read the clipboard image: {}", e)),
+        })?;
+        RgbaImage::from_raw(data.width as u32, data.height as u32, data.bytes.into_owned())
+            .map(DynamicImage::ImageRgba8)
+            .ok_or_else(|| AppError::image("The clipboard image has an invalid size"))
+    }
+
+    /// Maps a selection in UI coordinates to a pixel region of the image.
+    ///
+    /// Scales by the ratio between image and UI size (HiDPI) and clamps the
+    /// result to the image bounds.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::EmptySelection`] if the mapped region has zero area
+    /// or the UI size is degenerate.
+    pub fn map_selection(
+        selection: egui::Rect,
+        ui_size: egui::Vec2,
+        image_width: u32,
+        image_height: u32,
+    ) -> Result<PixelRegion> {
+        if !(ui_size.x > 0.0 && ui_size.y > 0.0) {
+            return Err(AppError::EmptySelection);
+        }
+
+        // Clip to the visible area so off-screen parts don't shift the crop
+        let selection = selection.intersect(egui::Rect::from_min_size(egui::Pos2::ZERO, ui_size));
+
         // Calculate scaling factors between UI and image coordinates
-        let scale_x = original.width() as f32 / ui_size.x;
-        let scale_y = original.height() as f32 / ui_size.y;
+        let scale_x = image_width as f32 / ui_size.x;
+        let scale_y = image_height as f32 / ui_size.y;
 
         // Transform UI coordinates to image coordinates
         let x = (selection.min.x * scale_x).max(0.0) as u32;
@@ -71,11 +588,11 @@ impl ImageProcessor {
         let mut height = (selection.height() * scale_y) as u32;
 
         // Clamp to image bounds to prevent out-of-bounds errors
-        if x + width > original.width() {
-            width = original.width().saturating_sub(x);
+        if x.saturating_add(width) > image_width {
+            width = image_width.saturating_sub(x);
         }
-        if y + height > original.height() {
-            height = original.height().saturating_sub(y);
+        if y.saturating_add(height) > image_height {
+            height = image_height.saturating_sub(y);
         }
 
         // Validate selection has non-zero area
@@ -83,28 +600,253 @@ impl ImageProcessor {
             return Err(AppError::EmptySelection);
         }
 
-        // Crop the image (immutable operation, returns new image)
-        let cropped = original.crop_imm(x, y, width, height);
-
-        // Encode as JPEG
-        let base64_string = Self::encode_to_base64_jpeg(&cropped)?;
-
-        Ok(base64_string)
+        Ok(PixelRegion {
+            x,
+            y,
+            width,
+            height,
+        })
     }
 
     /// Encodes a DynamicImage to a Base64 JPEG string.
     ///
     /// Uses a reasonable JPEG quality setting for a balance between
     /// file size and image quality.
-    fn encode_to_base64_jpeg(image: &DynamicImage) -> Result<String> {
+    pub fn encode_to_base64_jpeg(image: &DynamicImage) -> Result<String> {
+        Self::encode_to_base64(image, ImageEncoding::default())
+    }
+
+    /// Encodes a DynamicImage to a Base64 string in the given encoding.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if encoding fails.
+    pub fn encode_to_base64(image: &DynamicImage, encoding: ImageEncoding) -> Result<String> {
+        Self::encode(image, encoding).map(|bytes| BASE64.encode(bytes))
+    }
+
+    /// Encodes a DynamicImage for upload, keeping its MIME type and size
+    /// with the data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if encoding fails.
+    pub fn encode_image(image: &DynamicImage, encoding: ImageEncoding) -> Result<EncodedImage> {
+        let bytes = Self::encode(image, encoding)?;
+        Ok(EncodedImage {
+            data: BASE64.encode(&bytes),
+            mime_type: encoding.mime_type(),
+            width: image.width(),
+            height: image.height(),
+            byte_len: bytes.len(),
+        })
+    }
+
+    /// Encodes a DynamicImage in the given encoding.
+    ///
+    /// Only the pixels are written, converted to 8 bits per channel; no
+    /// metadata is added, so nothing about the machine ends up in uploads.
+    /// The bytes depend on nothing but the pixels and `encoding`, which keeps
+    /// them stable for use as cache keys.
+    ///
+    /// Alpha is dropped for JPEG, which has no alpha channel, and for the
+    /// other encodings when every pixel is opaque.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if encoding fails.
+    pub fn encode(image: &DynamicImage, encoding: ImageEncoding) -> Result<Vec<u8>> {
+        let image = &encodable_pixels(image, encoding);
         let mut buffer: Vec<u8> = Vec::new();
-        let mut cursor = Cursor::new(&mut buffer);
+        let encoded = match encoding {
+            ImageEncoding::Jpeg { quality } => {
+                image.write_with_encoder(JpegEncoder::new_with_quality(&mut buffer, quality))
+            }
+            ImageEncoding::Png => image.write_with_encoder(PngEncoder::new(&mut buffer)),
+            ImageEncoding::WebP => image.write_with_encoder(WebPEncoder::new_lossless(&mut buffer)),
+        };
+        encoded.map_err(|e| AppError::image(format!("Failed to encode image: {}", e)))?;
 
-        image
-            .write_to(&mut cursor, ImageFormat::Jpeg)
-            .map_err(|e| AppError::image(format!("Failed to encode image: {}", e)))?;
+        Ok(buffer)
+    }
+
+    /// Writes an image to `path` in the given format.
+    ///
+    /// JPEG has no alpha channel, so images are flattened to RGB first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the format is not supported or writing fails.
+    pub fn save(image: &DynamicImage, path: &Path, format: ImageFormat) -> Result<()> {
+        let result = if format == ImageFormat::Jpeg {
+            DynamicImage::ImageRgb8(image.to_rgb8()).save_with_format(path, format)
+        } else {
+            image.save_with_format(path, format)
+        };
+        result.map_err(|e| AppError::image(format!("Failed to save {}: {}", path.display(), e)))
+    }
 
-        Ok(BASE64.encode(buffer))
+    /// Converts an image to an egui [`egui::ColorImage`] for texture upload.
+    ///
+    /// This is the most expensive step of opening the overlay on large
+    /// screens, so callers should do it once before the UI loop starts.
+    pub fn to_color_image(image: &DynamicImage) -> egui::ColorImage {
+        let image_buffer = image.to_rgba8();
+        let size = [image.width() as usize, image.height() as usize];
+        let pixels = image_buffer.as_flat_samples();
+        egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice())
+    }
+
+    /// Masks redacted regions of a full screenshot in place.
+    ///
+    /// Redactions are given in UI coordinates and mapped onto the image the
+    /// same way as selections. Regions that fall outside the image are
+    /// ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - The full captured screenshot
+    /// * `redactions` - Regions to mask, in UI coordinates
+    /// * `ui_size` - The size of the UI display area
+    pub fn apply_redactions(image: &mut DynamicImage, redactions: &[Redaction], ui_size: egui::Vec2) {
+        if redactions.is_empty() {
+            return;
+        }
+
+        let width = image.width();
+        let height = image.height();
+        if image.as_mut_rgba8().is_none() {
+            *image = DynamicImage::ImageRgba8(image.to_rgba8());
+        }
+        let Some(pixels) = image.as_mut_rgba8() else {
+            return;
+        };
+
+        // Keep blocks the same visual size on HiDPI captures
+        let block = (PIXELATE_BLOCK * width as f32 / ui_size.x.max(1.0)).round().max(1.0) as u32;
+
+        for redaction in redactions {
+            let Ok(region) = Self::map_selection(redaction.rect, ui_size, width, height) else {
+                continue;
+            };
+            match redaction.style {
+                RedactionStyle::Pixelate => pixelate(pixels, region, block),
+                RedactionStyle::Fill => fill(pixels, region, Rgba([0, 0, 0, 255])),
+            }
+        }
+    }
+
+    /// Masks regions given in image pixels in place, e.g. text found by
+    /// [`crate::privacy`].
+    ///
+    /// Pixelation blocks are as tall as each region, so a line of text turns
+    /// into a single row of blocks. Parts outside the image are ignored.
+    pub fn mask_regions(image: &mut DynamicImage, regions: &[PixelRegion], style: RedactionStyle) {
+        if regions.is_empty() {
+            return;
+        }
+        if image.as_mut_rgba8().is_none() {
+            *image = DynamicImage::ImageRgba8(image.to_rgba8());
+        }
+        let Some(pixels) = image.as_mut_rgba8() else {
+            return;
+        };
+        let (width, height) = pixels.dimensions();
+
+        for region in regions {
+            let Ok(region) = region.clamp_to(width, height) else {
+                continue;
+            };
+            match style {
+                RedactionStyle::Pixelate => pixelate(pixels, region, region.height),
+                RedactionStyle::Fill => fill(pixels, region, Rgba([0, 0, 0, 255])),
+            }
+        }
+    }
+
+    /// Compares two images pixel by pixel.
+    ///
+    /// Images of different sizes are compared on a canvas as large as both;
+    /// pixels only one of them covers count as changed. Small differences
+    /// (up to [`DIFF_TOLERANCE`] per channel) are ignored.
+    pub fn diff(a: &DynamicImage, b: &DynamicImage) -> ImageDiff {
+        let (before, after) = (a.to_rgba8(), b.to_rgba8());
+        let width = before.width().max(after.width());
+        let height = before.height().max(after.height());
+
+        let mut highlighted = RgbaImage::new(width, height);
+        let mut changed_pixels = 0;
+        // Left, top, right and bottom of the changes, inclusive
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for (x, y, pixel) in highlighted.enumerate_pixels_mut() {
+            let old = before.get_pixel_checked(x, y);
+            let new = after.get_pixel_checked(x, y);
+            let changed = match (old, new) {
+                (Some(old), Some(new)) => {
+                    old.0.iter().zip(new.0).any(|(o, n)| o.abs_diff(n) > DIFF_TOLERANCE)
+                }
+                _ => true,
+            };
+            let luma = new.or(old).map_or(0, |&Rgba([r, g, b, _])| {
+                ((u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114) / 1000) as u8
+            });
+
+            *pixel = if changed {
+                changed_pixels += 1;
+                bounds = Some(match bounds {
+                    Some((left, top, right, bottom)) => {
+                        (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                    }
+                    None => (x, y, x, y),
+                });
+                Rgba([255, luma / 3, luma / 3, 255])
+            } else {
+                // Faded, so the red stands out while the layout stays visible
+                let gray = 160 + luma / 3;
+                Rgba([gray, gray, gray, 255])
+            };
+        }
+
+        ImageDiff {
+            image: DynamicImage::ImageRgba8(highlighted),
+            changed_pixels,
+            changed_region: bounds.map(|(left, top, right, bottom)| PixelRegion {
+                x: left,
+                y: top,
+                width: right - left + 1,
+                height: bottom - top + 1,
+            }),
+        }
+    }
+
+    /// Pads an image with black bars to the given aspect ratio (width divided
+    /// by height), keeping it centered.
+    ///
+    /// Used to show images of any shape on the full-screen overlay without
+    /// stretching them.
+    pub fn letterbox(image: &DynamicImage, aspect: f32) -> DynamicImage {
+        let (width, height) = (image.width(), image.height());
+        if width == 0 || height == 0 || aspect.is_nan() || aspect <= 0.0 {
+            return image.clone();
+        }
+
+        let (canvas_width, canvas_height) = if (width as f32 / height as f32) < aspect {
+            ((height as f32 * aspect).round() as u32, height)
+        } else {
+            (width, (width as f32 / aspect).round() as u32)
+        };
+        if (canvas_width, canvas_height) == (width, height) {
+            return image.clone();
+        }
+
+        let mut canvas = RgbaImage::from_pixel(canvas_width, canvas_height, Rgba([0, 0, 0, 255]));
+        image::imageops::overlay(
+            &mut canvas,
+            &image.to_rgba8(),
+            ((canvas_width - width) / 2) as i64,
+            ((canvas_height - height) / 2) as i64,
+        );
+        DynamicImage::ImageRgba8(canvas)
     }
 
     /// Calculates the aspect ratio of an image.
@@ -115,4 +857,56 @@ impl ImageProcessor {
     pub fn aspect_ratio(image: &DynamicImage) -> f32 {
         image.width() as f32 / image.height() as f32
     }
-}
\ No newline at end of file
+}
+
+/// Converts an image to the 8-bit RGB or RGBA pixels `encoding` stores.
+fn encodable_pixels(image: &DynamicImage, encoding: ImageEncoding) -> DynamicImage {
+    if matches!(encoding, ImageEncoding::Jpeg { .. }) || !image.color().has_alpha() {
+        return DynamicImage::ImageRgb8(image.to_rgb8());
+    }
+    let rgba = image.to_rgba8();
+    if rgba.pixels().all(|pixel| pixel.0[3] == u8::MAX) {
+        DynamicImage::ImageRgba8(rgba).to_rgb8().into()
+    } else {
+        DynamicImage::ImageRgba8(rgba)
+    }
+}
+
+/// Replaces each `block`-sized cell of a region with its average color.
+fn pixelate(pixels: &mut RgbaImage, region: PixelRegion, block: u32) {
+    let region_right = region.x + region.width;
+    let region_bottom = region.y + region.height;
+
+    for cell_y in (region.y..region_bottom).step_by(block as usize) {
+        for cell_x in (region.x..region_right).step_by(block as usize) {
+            let cell = PixelRegion {
+                x: cell_x,
+                y: cell_y,
+                width: block.min(region_right - cell_x),
+                height: block.min(region_bottom - cell_y),
+            };
+
+            let mut sum = [0u64; 4];
+            for y in cell.y..cell.y + cell.height {
+                for x in cell.x..cell.x + cell.width {
+                    for (total, channel) in sum.iter_mut().zip(pixels.get_pixel(x, y).0) {
+                        *total += channel as u64;
+                    }
+                }
+            }
+
+            let count = (cell.width * cell.height) as u64;
+            let average = Rgba(sum.map(|total| (total / count) as u8));
+            fill(pixels, cell, average);
+        }
+    }
+}
+
+/// Paints a region with a solid color.
+fn fill(pixels: &mut RgbaImage, region: PixelRegion, color: Rgba<u8>) {
+    for y in region.y..region.y + region.height {
+        for x in region.x..region.x + region.width {
+            pixels.put_pixel(x, y, color);
+        }
+    }
+}
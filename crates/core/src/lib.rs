@@ -10,6 +10,7 @@
 //!
 //! - **Screen Capture**: Multi-monitor support via the [`capture`] module
 //! - **Image Processing**: Region cropping and base64 encoding via [`image_processing`]
+//! - **Content Detection**: Heuristic classification of selections via [`classifier`]
 //! - **AI Integration**: Gemini API streaming responses via [`gemini`]
 //! - **User Interface**: Interactive selection overlay via [`ui`]
 //!
@@ -34,25 +35,89 @@
 //!
 //! # Module Structure
 //!
+//! - [`analysis`]: Headless analysis of images without the overlay
+//! - [`archive`]: Saving crops and answers to templated file names
 //! - [`capture`]: Screen capture functionality
+//! - [`checkpoint`]: Crash-safe checkpointing of streaming responses
+//! - [`classifier`]: Content classification for smart default prompts
+//! - [`color`]: Converting captures from the monitor's color profile to sRGB
 //! - [`config`]: Configuration loading and management
+//! - [`diagnostics`]: Checking a configuration for problems before it is used
 //! - [`error`]: Error types and result aliases
+//! - [`export`]: Conversation export to Markdown/HTML
 //! - [`gemini`]: Gemini AI client with streaming support
+//! - [`history`]: History of past analyses with their images
+//! - [`hotkey`]: Global hotkeys of the daemon mode
 //! - [`image_processing`]: Image manipulation utilities
+//! - [`logging`]: Opt-in debug log of API traffic (`AI_SHOT_LOG`)
+//! - [`models`]: Model listing and name resolution
+//! - [`montage`]: Combining several regions into one labelled image
+//! - [`ocr`]: Local text extraction
+//! - [`openai`]: Client for OpenAI-compatible gateways (LiteLLM, OpenRouter)
+//! - [`paths`]: Locations of config, data and cache files (`--data-dir`)
+//! - [`plugins`]: Post-processing actions run by external programs
+//! - [`presets`]: Built-in system prompt presets
+//! - [`privacy`]: Finding and masking sensitive text before upload
+//! - [`profiles`]: Named configuration profiles
+//! - [`rules`]: Rules adjusting the daemon's captures to the focused app
+//! - [`scheduler`]: Shared concurrency limits and backpressure for API work
+//! - [`secrets`]: API key storage in the OS keyring
+//! - [`speech`]: Text-to-speech output of responses
+//! - [`stamp`]: Stamping saved screenshots with the time, host and a note
+//! - [`thumbnails`]: Thumbnail storage with lazy full-image decoding
 //! - [`ui`]: User interface components
+//! - [`upload`]: Sharing selections through a link (Imgur, S3, webhooks)
+//! - [`usage`]: Usage statistics, estimated spend and the monthly budget
+//! - [`watch`]: Reloading the configuration when its files change
 
+pub mod analysis;
+pub mod archive;
 pub mod capture;
+pub mod checkpoint;
+pub mod classifier;
+pub mod color;
 pub mod config;
+pub mod diagnostics;
 pub mod error;
+pub mod export;
+mod font;
 pub mod gemini;
+pub mod history;
+pub mod hotkey;
 pub mod image_processing;
+pub mod logging;
+pub mod models;
+pub mod montage;
+pub mod ocr;
+pub mod openai;
+pub mod paths;
+pub mod plugins;
+pub mod presets;
+pub mod privacy;
+pub mod profiles;
+pub mod rules;
+pub mod scheduler;
+pub mod secrets;
+pub mod speech;
+pub mod stamp;
+pub mod thumbnails;
 pub mod ui;
+pub mod upload;
+pub mod usage;
+pub mod watch;
 
 // Re-export primary types for convenience
-pub use capture::ScreenCapturer;
-pub use config::Config;
+pub use analysis::Analysis;
+pub use capture::{DaemonMonitor, MonitorSelector, ScreenCapturer};
+pub use classifier::ContentKind;
+pub use config::{Config, ConfigSource, OverlayMode};
 pub use error::{AppError, Result};
 pub use gemini::GeminiClient;
+pub use image_processing::{
+    EncodedImage, ImageDiff, ImageEncoding, ImageProcessor, PixelRegion, UploadOptions,
+    UpscaleFilter,
+};
+pub use presets::SystemPromptPreset;
 
 use image::DynamicImage;
 
@@ -85,9 +150,10 @@ impl AiShot {
     ///
     /// Returns an error if:
     /// - Screen capture initialization fails (e.g., no display available)
+    /// - The configured color profile cannot be read
     pub fn new() -> Result<Self> {
         let config = Config::load()?;
-        let capturer = ScreenCapturer::new()?;
+        let capturer = ScreenCapturer::new()?.with_color_profile(&config.color_profile)?;
         Ok(Self { config, capturer })
     }
 
@@ -101,9 +167,10 @@ impl AiShot {
     ///
     /// # Errors
     ///
-    /// Returns an error if screen capture initialization fails.
+    /// Returns an error if screen capture initialization fails or the
+    /// configured color profile cannot be read.
     pub fn with_config(config: Config) -> Result<Self> {
-        let capturer = ScreenCapturer::new()?;
+        let capturer = ScreenCapturer::new()?.with_color_profile(&config.color_profile)?;
         Ok(Self { config, capturer })
     }
 
@@ -120,12 +187,25 @@ impl AiShot {
         self.capturer.screen_count()
     }
 
+    /// Resolves a monitor given by index, `cursor`, or name to its index.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no monitor matches (see
+    /// [`ScreenCapturer::resolve_monitor`]).
+    pub fn resolve_monitor(&self, selector: &MonitorSelector) -> Result<usize> {
+        self.capturer.resolve_monitor(selector)
+    }
+
     /// Captures a specific monitor and launches the interactive UI.
     ///
     /// This is the main entry point for the visual selection workflow.
     /// It captures the specified monitor, displays a fullscreen overlay,
     /// and allows the user to select a region and query Gemini AI.
     ///
+    /// Returns the last selection sent, or `None` if the user closed the
+    /// overlay without asking anything.
+    ///
     /// # Arguments
     /// * `monitor_index` - Zero-based index of the monitor to capture
     ///
@@ -135,19 +215,75 @@ impl AiShot {
     /// - The monitor index is out of bounds
     /// - Screen capture fails
     /// - UI initialization fails
-    pub fn run_interactive(&self, monitor_index: usize) -> Result<()> {
+    pub fn run_interactive(&self, monitor_index: usize) -> Result<Option<ui::Selection>> {
         let screenshot = self.capturer.capture_screen_by_index(monitor_index)?;
-        ui::run_selection_ui(screenshot, self.config.clone())?;
-        Ok(())
+        let screen = self.capturer.screen_geometry(monitor_index).ok();
+        let windows = self.capturer.windows_on_screen(monitor_index).unwrap_or_default();
+        let mut config = self.config.clone();
+        config.archive = config.archive.for_monitor(&monitor_index.to_string());
+        ui::run_selection_ui_on_screen(screenshot, screen, windows, config)
     }
 
     /// Launches the interactive UI with a pre-captured image.
     ///
     /// This is useful when the image has already been captured (e.g., by a daemon)
-    /// or loaded from disk.
-    pub fn run_interactive_with_image(&self, image: DynamicImage) -> Result<()> {
-        ui::run_selection_ui(image, self.config.clone())?;
-        Ok(())
+    /// or loaded from disk. Window snapping uses the windows currently on the
+    /// primary monitor. Returns like [`Self::run_interactive`].
+    pub fn run_interactive_with_image(&self, image: DynamicImage) -> Result<Option<ui::Selection>> {
+        self.run_interactive_with_image_on(image, Some(0))
+    }
+
+    /// Launches the interactive UI with a pre-captured image of a monitor,
+    /// or of the whole desktop if `monitor_index` is `None` (see
+    /// [`Self::capture_desktop`]).
+    ///
+    /// The overlay is placed over the captured area; a desktop capture
+    /// always uses a borderless window spanning every monitor, without
+    /// window snapping. Returns like [`Self::run_interactive`].
+    pub fn run_interactive_with_image_on(
+        &self,
+        image: DynamicImage,
+        monitor_index: Option<usize>,
+    ) -> Result<Option<ui::Selection>> {
+        let mut config = self.config.clone();
+        let Some(index) = monitor_index else {
+            config.archive = config.archive.for_monitor("all");
+            config.overlay_mode = OverlayMode::Borderless;
+            let desktop = self.capturer.desktop_geometry();
+            return ui::run_selection_ui_on_screen(image, Some(desktop), Vec::new(), config);
+        };
+        let screen = self.capturer.screen_geometry(index).ok();
+        let windows = self.capturer.windows_on_screen(index).unwrap_or_default();
+        config.archive = config.archive.for_monitor(&index.to_string());
+        ui::run_selection_ui_on_screen(image, screen, windows, config)
+    }
+
+    /// Returns the desktop position, logical size and scale of a monitor.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the monitor index is out of bounds.
+    pub fn screen_geometry(&self, monitor_index: usize) -> Result<capture::ScreenGeometry> {
+        self.capturer.screen_geometry(monitor_index)
+    }
+
+    /// Captures a monitor and lets the user pick a region of it.
+    ///
+    /// Nothing is sent anywhere; the overlay closes once a region is
+    /// selected. Returns the region in the monitor's pixels, or `None` if the
+    /// user closed the overlay without selecting.
+    ///
+    /// # Arguments
+    /// * `monitor_index` - Zero-based index of the monitor to capture
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if capture or the UI fails.
+    pub fn pick_region(&self, monitor_index: usize) -> Result<Option<PixelRegion>> {
+        let screenshot = self.capturer.capture_screen_by_index(monitor_index)?;
+        let screen = self.capturer.screen_geometry(monitor_index).ok();
+        let windows = self.capturer.windows_on_screen(monitor_index).unwrap_or_default();
+        ui::pick_region(screenshot, screen, windows, self.config.clone())
     }
 
     /// Captures a screenshot from a specific monitor without UI.
@@ -161,6 +297,110 @@ impl AiShot {
         self.capturer.capture_screen_by_index(monitor_index)
     }
 
+    /// Returns the mouse pointer's position on the desktop, if available.
+    pub fn pointer_position(&self) -> Option<(i32, i32)> {
+        self.capturer.pointer_position()
+    }
+
+    /// Captures an area of the desktop in the coordinates of
+    /// [`Self::pointer_position`], cut off at the edges of the monitor
+    /// containing its top-left corner.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no monitor contains the corner or capture fails.
+    pub fn capture_desktop_area(
+        &self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Result<DynamicImage> {
+        self.capturer.capture_desktop_area(x, y, width, height)
+    }
+
+    /// Captures every monitor into one image laid out like the desktop.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if capturing any monitor fails.
+    pub fn capture_desktop(&self) -> Result<DynamicImage> {
+        self.capturer.capture_desktop()
+    }
+
+    /// Captures the window that has the keyboard focus, without UI.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no window has the focus or capture fails.
+    pub fn capture_active_window(&self) -> Result<DynamicImage> {
+        self.capturer.capture_active_window()
+    }
+
+    /// Returns the title of the focused window if it is fullscreen, e.g. a
+    /// game the daemon shouldn't interrupt.
+    pub fn fullscreen_window(&self) -> Option<String> {
+        self.capturer.fullscreen_window()
+    }
+
+    /// Describes where a capture for `action` is taken right now, to find
+    /// the [`rules::Rule`] that applies to it.
+    pub fn rule_context(&self, action: hotkey::HotkeyAction) -> rules::RuleContext {
+        let window = self.capturer.focused_window();
+        let monitor = self
+            .capturer
+            .resolve_monitor(&MonitorSelector::Cursor)
+            .ok()
+            .and_then(|index| self.capturer.monitors().into_iter().nth(index));
+        rules::RuleContext {
+            action,
+            app: window.as_ref().map(|window| window.app_name.clone()),
+            title: window.map(|window| window.title),
+            monitor,
+        }
+    }
+
+    /// Captures a monitor and asks about a region of it, without any UI.
+    ///
+    /// # Arguments
+    /// * `monitor_index` - Zero-based index of the monitor to capture
+    /// * `region` - Region in monitor pixels (the whole monitor if `None`)
+    /// * `prompt` - The question (a default for the detected content if `None`)
+    /// * `on_text` - Called with each piece of answer text as it arrives
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if capture fails, the region lies outside the
+    /// monitor, or the request fails.
+    pub async fn analyze_region(
+        &self,
+        monitor_index: usize,
+        region: Option<PixelRegion>,
+        prompt: Option<&str>,
+        on_text: impl FnMut(&str),
+    ) -> Result<Analysis> {
+        let screenshot = self.capturer.capture_screen_by_index(monitor_index)?;
+        analysis::analyze_region(&self.config, screenshot, region, prompt, on_text).await
+    }
+
+    /// Asks about the image on the clipboard, without any UI.
+    ///
+    /// # Arguments
+    /// * `prompt` - The question (a default for the detected content if `None`)
+    /// * `on_text` - Called with each piece of answer text as it arrives
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the clipboard holds no image or the request fails.
+    pub async fn analyze_clipboard(
+        &self,
+        prompt: Option<&str>,
+        on_text: impl FnMut(&str),
+    ) -> Result<Analysis> {
+        let image = ImageProcessor::from_clipboard()?;
+        analysis::analyze_region(&self.config, image, None, prompt, on_text).await
+    }
+
     /// Returns a reference to the current configuration.
     pub fn config(&self) -> &Config {
         &self.config
@@ -172,6 +412,25 @@ impl AiShot {
     pub fn config_mut(&mut self) -> &mut Config {
         &mut self.config
     }
+
+    /// Switches to the newest configuration from `updates`, if the files
+    /// changed since the last call. Returns whether the configuration changed.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let updates = app.config().subscribe()?;
+    /// // ... later, e.g. before each capture
+    /// app.refresh_config(&updates);
+    /// ```
+    pub fn refresh_config(&mut self, updates: &watch::ConfigUpdates) -> bool {
+        match updates.try_latest() {
+            Some(config) => {
+                self.config = config;
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 /// Initializes the library by loading environment variables.
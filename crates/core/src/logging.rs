@@ -0,0 +1,194 @@
+//! Opt-in debug logging of API traffic.
+//!
+//! Setting `AI_SHOT_LOG` (e.g., `AI_SHOT_LOG=debug`) writes a log of request
+//! metadata, streamed chunks, timings, and finish reasons to daily-rotated
+//! files in the data directory (e.g., `~/.local/share/ai-shot/logs` on Linux).
+//! The value accepts any `tracing` filter directive, such as
+//! `ai_shot_core=trace`.
+//!
+//! The CLI's `-v` flags additionally log to stderr
+//! ([`init_with_console`]).
+//!
+//! Long-running programs such as the daemon always log, to a file of their
+//! own per run ([`init_run_log`]), so a failure can be traced afterwards
+//! without having to reproduce it with `AI_SHOT_LOG` set.
+//!
+//! Logs are sanitized: API keys and image data are never recorded, and
+//! prompts and response text are only logged at `trace` level.
+//!
+//! # Example
+//!
+//! ```ignore
+//! // Keep the guard alive for the lifetime of the process so logs are flushed
+//! let _log_guard = ai_shot_core::logging::init();
+//! ```
+
+use crate::paths;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing::Level;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer, Registry};
+
+/// Number of daily log files kept before the oldest is deleted.
+const MAX_LOG_FILES: usize = 7;
+
+/// Number of per-run log files kept for each program.
+const MAX_RUN_LOGS: usize = 10;
+
+/// Returns the directory log files are written to.
+pub fn log_dir() -> Option<PathBuf> {
+    paths::log_dir()
+}
+
+/// Installs the file logger if `AI_SHOT_LOG` is set.
+///
+/// Returns a guard that flushes buffered records when dropped, or `None` if
+/// logging is disabled or the log file cannot be created. Failures are
+/// reported on stderr rather than aborting the application.
+pub fn init() -> Option<WorkerGuard> {
+    init_with_console(None)
+}
+
+/// Installs the file logger, plus a stderr logger at `console` level.
+///
+/// The stderr logger only shows ai-shot's own records (and warnings from
+/// dependencies), which is what the CLI's `-v` flags control.
+pub fn init_with_console(console: Option<Level>) -> Option<WorkerGuard> {
+    install(file_layer(), console)
+}
+
+/// Installs a log file for this run of `program` (e.g. `daemon`), plus a
+/// stderr logger at `console` level.
+///
+/// Unlike [`init`], this always logs, to `<program>-<date>-<time>.log` in
+/// the log directory at `info` level unless `AI_SHOT_LOG` sets another
+/// filter. Only the newest runs' files are kept.
+pub fn init_run_log(program: &str, console: Option<Level>) -> Option<WorkerGuard> {
+    install(run_log_layer(program), console)
+}
+
+/// Installs the given file layer and a stderr logger at `console` level.
+fn install<L>(file: Option<(L, WorkerGuard)>, console: Option<Level>) -> Option<WorkerGuard>
+where
+    L: Layer<Registry> + Send + Sync + 'static,
+{
+    let (file, guard) = match file {
+        Some((layer, guard)) => (Some(layer), Some(guard)),
+        None => (None, None),
+    };
+    let console = console.map(|level| {
+        let filter = EnvFilter::new(format!("warn,ai_shot_core={0},ai_shot_cli={0}", level));
+        fmt::layer()
+            .with_writer(io::stderr)
+            .with_target(false)
+            .with_filter(filter)
+    });
+    if file.is_none() && console.is_none() {
+        return None;
+    }
+
+    let installed = tracing_subscriber::registry().with(file).with(console).try_init();
+    if installed.is_err() {
+        // Another subscriber is already active; keep using it
+        return None;
+    }
+
+    if let Some(dir) = guard.as_ref().and(log_dir()) {
+        tracing::info!(dir = %dir.display(), "file logging enabled");
+    }
+    guard
+}
+
+/// Builds the log file layer if `AI_SHOT_LOG` is set.
+fn file_layer() -> Option<(impl Layer<Registry>, WorkerGuard)> {
+    let directive = env::var("AI_SHOT_LOG").ok().filter(|v| !v.trim().is_empty())?;
+    let dir = log_dir()?;
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix("ai-shot")
+        .filename_suffix("log")
+        .max_log_files(MAX_LOG_FILES)
+        .build(&dir);
+    let appender = match appender {
+        Ok(appender) => appender,
+        Err(e) => {
+            eprintln!("Warning: Failed to open log file in {}: {}", dir.display(), e);
+            return None;
+        }
+    };
+
+    Some(writer_layer(appender, &directive, "debug"))
+}
+
+/// Builds the layer of a per-run log file, see [`init_run_log`].
+fn run_log_layer(program: &str) -> Option<(impl Layer<Registry> + use<>, WorkerGuard)> {
+    let dir = log_dir()?;
+    prune_run_logs(&dir, program);
+
+    let started = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::NEVER)
+        .filename_prefix(format!("{}-{}", program, started))
+        .filename_suffix("log")
+        .build(&dir);
+    let appender = match appender {
+        Ok(appender) => appender,
+        Err(e) => {
+            eprintln!("Warning: Failed to open log file in {}: {}", dir.display(), e);
+            return None;
+        }
+    };
+
+    let directive = env::var("AI_SHOT_LOG").ok().filter(|v| !v.trim().is_empty());
+    Some(writer_layer(appender, directive.as_deref().unwrap_or("info"), "info"))
+}
+
+/// Deletes all but the newest `MAX_RUN_LOGS - 1` run logs of `program`,
+/// making room for this run's.
+fn prune_run_logs(dir: &Path, program: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let prefix = format!("{}-", program);
+    let mut logs: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(&prefix) && name.ends_with(".log"))
+        })
+        .collect();
+    // Timestamps in the names sort chronologically
+    logs.sort();
+    let excess = logs.len().saturating_sub(MAX_RUN_LOGS - 1);
+    for path in &logs[..excess] {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Formats records for a log file, filtered by `directive` (or `fallback`
+/// if it is invalid).
+fn writer_layer(
+    appender: RollingFileAppender,
+    directive: &str,
+    fallback: &str,
+) -> (impl Layer<Registry> + use<>, WorkerGuard) {
+    let filter = EnvFilter::try_new(directive).unwrap_or_else(|e| {
+        eprintln!("Warning: Invalid AI_SHOT_LOG value '{}': {}", directive, e);
+        EnvFilter::new(fallback)
+    });
+
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let layer = fmt::layer()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_filter(filter);
+    (layer, guard)
+}
@@ -0,0 +1,197 @@
+//! Listing the models a provider offers.
+//!
+//! [`list_models`] asks the configured backend (the Gemini API or an
+//! OpenAI-compatible gateway) which models it serves, with whatever
+//! capabilities it reports. [`closest_known`] resolves abbreviated model
+//! names such as `flash-lite` against the models the overlay offers.
+//!
+//! [`ModelCapabilities`] is a built-in table of what known models support,
+//! consulted before sending a request so that, say, enabling thinking on a
+//! model without it is reported as a warning and the option is left out,
+//! instead of failing with an API error.
+//!
+//! # Example
+//!
+//! ```ignore
+//! for model in ai_shot_core::models::list_models(&config).await? {
+//!     println!("{} (thinking: {:?})", model.name, model.thinking);
+//! }
+//! ```
+
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::gemini::GeminiClient;
+use crate::openai::OpenAiClient;
+use crate::ui::AVAILABLE_MODELS;
+use serde_json::Value;
+
+/// A model offered by the provider.
+///
+/// Capabilities are `None` when the provider does not report them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// Name to pass as the model (e.g. `gemini-flash-latest`).
+    pub name: String,
+    /// Human-readable name, if different from `name`.
+    pub display_name: Option<String>,
+    /// Whether the model accepts images.
+    pub vision: Option<bool>,
+    /// Whether the model supports thinking.
+    pub thinking: Option<bool>,
+    /// Maximum input tokens.
+    pub context_length: Option<u32>,
+}
+
+impl ModelInfo {
+    /// Reads a model from a Gemini API `models` entry.
+    ///
+    /// Returns `None` for models that cannot generate content (embeddings).
+    pub(crate) fn from_gemini(model: &Value) -> Option<Self> {
+        let methods = model["supportedGenerationMethods"].as_array()?;
+        if !methods.iter().any(|m| m.as_str() == Some("generateContent")) {
+            return None;
+        }
+
+        let name = model["name"].as_str()?.trim_start_matches("models/").to_string();
+        Some(Self {
+            // The API doesn't list input modalities, but every Gemini model is multimodal
+            vision: name.starts_with("gemini-").then_some(true),
+            display_name: model["displayName"].as_str().map(str::to_string),
+            thinking: Some(model["thinking"].as_bool().unwrap_or(false)),
+            context_length: model["inputTokenLimit"].as_u64().map(|limit| limit as u32),
+            name,
+        })
+    }
+
+    /// Reads a model from an OpenAI-compatible `/models` entry.
+    pub(crate) fn from_openai(model: &Value) -> Option<Self> {
+        Some(Self {
+            name: model["id"].as_str()?.to_string(),
+            // Reported by some gateways (e.g. OpenRouter)
+            context_length: model["context_length"].as_u64().map(|limit| limit as u32),
+            ..Self::default()
+        })
+    }
+}
+
+/// What a known model supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ModelCapabilities {
+    /// Whether the model supports thinking.
+    pub thinking: bool,
+    /// Whether the model supports Google Search grounding.
+    pub search_grounding: bool,
+    /// Longest image edge, in pixels, before the API scales images down.
+    pub max_image_edge: u32,
+    /// Maximum input tokens.
+    pub context_window: u32,
+}
+
+/// Capabilities by model name prefix; the first matching entry applies, so
+/// more specific prefixes come first.
+const CAPABILITIES: &[(&str, ModelCapabilities)] = &[
+    ("gemini-2.0-flash-preview-image", ModelCapabilities::new(false, false, 3072, 32_768)),
+    ("gemini-2.5-flash-image", ModelCapabilities::new(false, false, 3072, 32_768)),
+    ("gemini-2.0-flash-lite", ModelCapabilities::new(false, false, 3072, 1_048_576)),
+    ("gemini-2.0-flash", ModelCapabilities::new(false, true, 3072, 1_048_576)),
+    ("gemini-1.5-pro", ModelCapabilities::new(false, false, 3072, 2_097_152)),
+    ("gemini-1.5-flash", ModelCapabilities::new(false, false, 3072, 1_048_576)),
+    ("gemini-2.5-", ModelCapabilities::new(true, true, 3072, 1_048_576)),
+    ("gemini-3", ModelCapabilities::new(true, true, 3072, 1_048_576)),
+    ("gemini-flash", ModelCapabilities::new(true, true, 3072, 1_048_576)),
+    ("gemma-3", ModelCapabilities::new(false, false, 896, 131_072)),
+];
+
+impl ModelCapabilities {
+    const fn new(
+        thinking: bool,
+        search_grounding: bool,
+        max_image_edge: u32,
+        context_window: u32,
+    ) -> Self {
+        Self {
+            thinking,
+            search_grounding,
+            max_image_edge,
+            context_window,
+        }
+    }
+
+    /// Looks up a model, or returns `None` for models not in the table
+    /// (whose requests are sent unchanged).
+    ///
+    /// A `models/` prefix and surrounding whitespace are ignored.
+    pub fn of(model: &str) -> Option<Self> {
+        let model = model.trim().trim_start_matches("models/").to_lowercase();
+        CAPABILITIES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, capabilities)| *capabilities)
+    }
+
+    /// Returns a warning for each requested option `model` doesn't support.
+    ///
+    /// Requests leave those options out; see [`Self::supported_options`].
+    pub fn warnings(model: &str, thinking: bool, google_search: bool) -> Vec<String> {
+        let Some(capabilities) = Self::of(model) else {
+            return Vec::new();
+        };
+        let mut warnings = Vec::new();
+        if thinking && !capabilities.thinking {
+            warnings.push(format!("{} doesn't support thinking; sent without it", model));
+        }
+        if google_search && !capabilities.search_grounding {
+            warnings.push(format!(
+                "{} doesn't support Google Search grounding; sent without it",
+                model
+            ));
+        }
+        warnings
+    }
+
+    /// Returns the requested thinking and search options, with the ones
+    /// `model` doesn't support turned off.
+    pub fn supported_options(model: &str, thinking: bool, google_search: bool) -> (bool, bool) {
+        match Self::of(model) {
+            Some(capabilities) => (
+                thinking && capabilities.thinking,
+                google_search && capabilities.search_grounding,
+            ),
+            None => (thinking, google_search),
+        }
+    }
+}
+
+/// Lists the models available with the configured credentials.
+///
+/// # Errors
+///
+/// Returns an error if the request fails, or for Vertex AI, whose model
+/// catalog is not listed per project.
+pub async fn list_models(config: &Config) -> Result<Vec<ModelInfo>> {
+    let mut models = if config.openai.is_some() {
+        OpenAiClient::new(config)?.list_models().await?
+    } else if config.vertex.is_some() {
+        return Err(AppError::config("Listing models is not supported for Vertex AI"));
+    } else {
+        GeminiClient::new(config)?.list_models().await?
+    };
+    models.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(models)
+}
+
+/// Resolves an abbreviated model name against the overlay's models.
+///
+/// Returns the model if `query` names exactly one of [`AVAILABLE_MODELS`]
+/// (exactly or as a substring), or `None` so the name is used as given.
+pub fn closest_known(query: &str) -> Option<&'static str> {
+    let query = query.trim().to_lowercase();
+    if let Some(exact) = AVAILABLE_MODELS.iter().copied().find(|model| *model == query) {
+        return Some(exact);
+    }
+    let mut matches = AVAILABLE_MODELS.iter().copied().filter(|model| model.contains(&query));
+    match (matches.next(), matches.next()) {
+        (Some(model), None) => Some(model),
+        _ => None,
+    }
+}
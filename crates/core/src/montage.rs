@@ -0,0 +1,88 @@
+//! Combining several regions into one labelled image.
+//!
+//! Questions about several regions are normally sent as separate images.
+//! Some models compare regions better when they arrive as a single image,
+//! so [`compose`] lays them out on one canvas with a letter above each
+//! ("A", "B", "C", ...) that the prompt can refer to.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::montage;
+//!
+//! let combined = montage::compose(&[first, second]);
+//! let prompt = format!("{}\n\n{}", montage::prompt_note(2), question);
+//! ```
+
+use crate::font;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+/// Space between regions and around the canvas, in pixels.
+const GAP: u32 = 16;
+
+/// Size of a pixel of the label font, in image pixels.
+const LABEL_SCALE: u32 = 4;
+
+/// Height of the strip above each region that holds its label.
+const LABEL_HEIGHT: u32 = font::GLYPH_HEIGHT * LABEL_SCALE + 8;
+
+const BACKGROUND: Rgba<u8> = Rgba([40, 40, 40, 255]);
+const LABEL_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// Returns the label of the region at `index`: `A` to `Z`, then `AA`, `AB`
+/// and so on.
+pub fn label(index: usize) -> String {
+    let mut label = Vec::new();
+    let mut rest = index + 1;
+    while rest > 0 {
+        rest -= 1;
+        label.push(b'A' + (rest % 26) as u8);
+        rest /= 26;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap_or_default()
+}
+
+/// Lays out images in a grid of roughly square shape, in order from left to
+/// right and top to bottom, each below its [`label`].
+///
+/// Rows are as tall as their tallest image; images keep their size.
+pub fn compose(images: &[DynamicImage]) -> DynamicImage {
+    let columns = (images.len() as f64).sqrt().ceil().max(1.0) as usize;
+
+    // Where each image goes, row by row
+    let mut placements = Vec::with_capacity(images.len());
+    let (mut canvas_width, mut top) = (0, GAP);
+    for row in images.chunks(columns) {
+        let mut left = GAP;
+        for image in row {
+            placements.push((left, top));
+            left += image.width() + GAP;
+        }
+        canvas_width = canvas_width.max(left);
+        top += LABEL_HEIGHT + row.iter().map(DynamicImage::height).max().unwrap_or(0) + GAP;
+    }
+
+    let mut canvas = RgbaImage::from_pixel(canvas_width.max(GAP), top, BACKGROUND);
+    for (index, (image, (left, top))) in images.iter().zip(placements).enumerate() {
+        font::draw_text(&mut canvas, &label(index), left, top, LABEL_SCALE, LABEL_COLOR);
+        image::imageops::overlay(
+            &mut canvas,
+            &image.to_rgba8(),
+            i64::from(left),
+            i64::from(top + LABEL_HEIGHT),
+        );
+    }
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/// Explains the layout of a [`compose`]d image of `count` regions, to put
+/// before the question.
+pub fn prompt_note(count: usize) -> String {
+    let labels: Vec<String> = (0..count).map(label).collect();
+    format!(
+        "The image combines {} screen regions, each below its label ({}).",
+        count,
+        labels.join(", ")
+    )
+}
@@ -0,0 +1,188 @@
+//! Local OCR (optical character recognition).
+//!
+//! Extracting text on the user's machine lets a selection be copied as text
+//! directly, or be sent to Gemini as a text-only prompt, which is cheaper and
+//! keeps the image itself private.
+//!
+//! OCR is performed by the [Tesseract](https://github.com/tesseract-ocr/tesseract)
+//! command-line tool, which must be installed and on `PATH` (or configured via
+//! `AI_SHOT_TESSERACT`). The image is piped through stdin, so nothing is
+//! written to disk.
+//!
+//! Besides plain text, [`OcrEngine::extract_words`] reports where each word
+//! is, which [`crate::privacy`] uses to mask sensitive text.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::ocr::OcrEngine;
+//!
+//! let engine = OcrEngine::from_env();
+//! let text = engine.extract_text(&cropped)?;
+//! ```
+
+use crate::error::{AppError, Result};
+use crate::image_processing::PixelRegion;
+use image::{DynamicImage, ImageFormat};
+use std::env;
+use std::io::{Cursor, Write};
+use std::process::{Command, Stdio};
+
+/// Prompt asking a vision model for a plain transcription, for when
+/// Tesseract is not installed.
+pub const MODEL_EXTRACTION_PROMPT: &str = "Transcribe all text in this image exactly as written, \
+    preserving line breaks. Output only the text, without commentary or formatting.";
+
+/// A word found by [`OcrEngine::extract_words`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OcrWord {
+    /// The recognized text.
+    pub text: String,
+    /// Where the word is in the image.
+    pub region: PixelRegion,
+    /// Index of the line the word is on; words of a line are adjacent and
+    /// in reading order.
+    pub line: usize,
+}
+
+/// Tesseract-based OCR engine.
+#[derive(Clone, Debug)]
+pub struct OcrEngine {
+    /// Path or name of the `tesseract` executable.
+    binary: String,
+    /// Tesseract language codes joined with `+` (e.g., "eng+deu").
+    languages: String,
+}
+
+impl OcrEngine {
+    /// Creates an engine with an explicit executable and language list.
+    pub fn new(binary: impl Into<String>, languages: impl Into<String>) -> Self {
+        Self {
+            binary: binary.into(),
+            languages: languages.into(),
+        }
+    }
+
+    /// Creates an engine configured from `AI_SHOT_TESSERACT` and
+    /// `AI_SHOT_OCR_LANG`, defaulting to `tesseract` with English.
+    pub fn from_env() -> Self {
+        Self::new(
+            env::var("AI_SHOT_TESSERACT").unwrap_or_else(|_| "tesseract".to_string()),
+            env::var("AI_SHOT_OCR_LANG").unwrap_or_else(|_| "eng".to_string()),
+        )
+    }
+
+    /// Returns whether the OCR executable can be run.
+    pub fn is_available(&self) -> bool {
+        Command::new(&self.binary)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    /// Extracts text from an image.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Ocr`] if Tesseract is not installed or fails.
+    pub fn extract_text(&self, image: &DynamicImage) -> Result<String> {
+        self.run(image, &[]).map(|text| text.trim().to_string())
+    }
+
+    /// Extracts the words of an image with their positions.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Ocr`] if Tesseract is not installed or fails.
+    pub fn extract_words(&self, image: &DynamicImage) -> Result<Vec<OcrWord>> {
+        let tsv = self.run(image, &["tsv"])?;
+
+        // Columns: level, page, block, paragraph, line, word, left, top,
+        // width, height, confidence, text; level 5 rows are words
+        let mut words: Vec<OcrWord> = Vec::new();
+        let mut current_line = None;
+        for row in tsv.lines().skip(1) {
+            let columns: Vec<&str> = row.split('\t').collect();
+            let [level, page, block, paragraph, line, _, left, top, width, height, _, text] =
+                columns[..]
+            else {
+                continue;
+            };
+            let text = text.trim();
+            if level != "5" || text.is_empty() {
+                continue;
+            }
+            let number = |value: &str| value.parse::<u32>().ok();
+            let (Some(x), Some(y), Some(width), Some(height)) =
+                (number(left), number(top), number(width), number(height))
+            else {
+                continue;
+            };
+
+            let line_key = (page, block, paragraph, line);
+            let line = match (current_line, words.last()) {
+                (Some(key), Some(last)) if key == line_key => last.line,
+                (_, Some(last)) => last.line + 1,
+                (_, None) => 0,
+            };
+            current_line = Some(line_key);
+            words.push(OcrWord {
+                text: text.to_string(),
+                region: PixelRegion {
+                    x,
+                    y,
+                    width,
+                    height,
+                },
+                line,
+            });
+        }
+        Ok(words)
+    }
+
+    /// Runs Tesseract on an image with extra arguments (e.g. an output
+    /// format) and returns what it printed.
+    fn run(&self, image: &DynamicImage, args: &[&str]) -> Result<String> {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .map_err(|e| AppError::image(format!("Failed to encode image for OCR: {}", e)))?;
+
+        let mut child = Command::new(&self.binary)
+            .args(["stdin", "stdout", "-l", &self.languages])
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                AppError::ocr(format!(
+                    "Failed to run '{}' (is Tesseract installed?): {}",
+                    self.binary, e
+                ))
+            })?;
+
+        // Write stdin on the current thread; output is collected afterwards
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&png)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(AppError::ocr(format!(
+                "Tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+impl Default for OcrEngine {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
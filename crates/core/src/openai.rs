@@ -0,0 +1,366 @@
+//! Client for OpenAI-compatible chat-completions gateways.
+//!
+//! Many users route model traffic through gateways such as LiteLLM or
+//! OpenRouter, which expose the OpenAI `/chat/completions` API in front of
+//! many providers (including Gemini). When [`Config::openai`] is set, requests
+//! go to the configured base URL with the configured model string instead of
+//! the Gemini API.
+//!
+//! Responses are streamed via server-sent events and mapped onto
+//! [`GeminiStreamEvent`], so callers can consume either client the same way.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::{openai::OpenAiClient, Config};
+//!
+//! // AI_SHOT_OPENAI_BASE_URL=http://localhost:4000/v1
+//! // GEMINI_MODEL=gemini/gemini-2.5-flash
+//! let config = Config::load()?;
+//! let client = OpenAiClient::new(&config)?;
+//!
+//! let mut stream = client
+//!     .continue_image_stream(image, &[], "Explain this code".to_string(), String::new())
+//!     .await?;
+//! ```
+
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::gemini::{with_stream_deadline, ChatTurn, GeminiEventStream, GeminiStreamEvent};
+use crate::image_processing::EncodedImage;
+use crate::models::ModelInfo;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Client for an OpenAI-compatible chat-completions endpoint.
+pub struct OpenAiClient {
+    http: reqwest::Client,
+    /// Full URL of the chat-completions endpoint.
+    endpoint: String,
+    api_key: String,
+    model: String,
+    /// Overall deadline applied to each request, including streaming.
+    deadline: Option<Duration>,
+}
+
+impl OpenAiClient {
+    /// Creates a client for the gateway configured in [`Config::openai`].
+    ///
+    /// The model string is taken from [`Config::model_name`] unchanged, so
+    /// gateway-specific names like `openrouter/google/gemini-2.5-flash` work.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Config`] if no gateway is configured or the HTTP
+    /// client cannot be built.
+    pub fn new(config: &Config) -> Result<Self> {
+        let openai = config
+            .openai
+            .as_ref()
+            .ok_or_else(|| AppError::config("No OpenAI-compatible base URL configured"))?;
+
+        let timeouts = &config.timeouts;
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(timeouts.connect)
+            .read_timeout(timeouts.read)
+            .tcp_keepalive(timeouts.keepalive);
+        if let Some(proxy_url) = &config.network.proxy {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| AppError::config(format!("Invalid proxy URL '{}': {}", proxy_url, e)))?
+                .no_proxy(
+                    config
+                        .network
+                        .no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+            builder = builder.proxy(proxy);
+        }
+        let http = builder
+            .build()
+            .map_err(|e| AppError::config(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            http,
+            endpoint: format!("{}/chat/completions", openai.base_url.trim_end_matches('/')),
+            api_key: openai.api_key.clone(),
+            model: config.model_name.clone(),
+            deadline: timeouts.deadline,
+        })
+    }
+
+    /// Asks about an image, continuing the conversation in `history`.
+    ///
+    /// # Arguments
+    /// * `image` - The encoded image
+    /// * `history` - Completed exchanges, oldest first (empty for a new chat)
+    /// * `prompt` - The question
+    /// * `system_prompt` - Optional system instructions (empty string to skip)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::OpenAi`] if the stream cannot be established.
+    pub async fn continue_image_stream(
+        &self,
+        image: EncodedImage,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+    ) -> Result<GeminiEventStream> {
+        self.continue_images_stream(vec![image], history, prompt, system_prompt)
+            .await
+    }
+
+    /// Asks about several images at once, attached in order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::OpenAi`] if the stream cannot be established.
+    pub async fn continue_images_stream(
+        &self,
+        images: Vec<EncodedImage>,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+    ) -> Result<GeminiEventStream> {
+        let images = images
+            .into_iter()
+            .map(|image| {
+                json!({
+                    "type": "image_url",
+                    "image_url": {
+                        "url": format!("data:{};base64,{}", image.mime_type, image.data)
+                    }
+                })
+            })
+            .collect();
+        let messages = conversation_messages(&system_prompt, images, history, prompt);
+
+        self.stream_chat(messages).await
+    }
+
+    /// Asks about text extracted from an image (e.g., via OCR).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::OpenAi`] if the stream cannot be established.
+    pub async fn analyze_text_stream(
+        &self,
+        text: String,
+        history: &[ChatTurn],
+        prompt: String,
+        system_prompt: String,
+    ) -> Result<GeminiEventStream> {
+        let context = json!({
+            "type": "text",
+            "text": format!("Text extracted from a screenshot:\n\n{}", text)
+        });
+        let messages = conversation_messages(&system_prompt, vec![context], history, prompt);
+
+        self.stream_chat(messages).await
+    }
+
+    /// Lists the models the gateway serves (`GET /models`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::OpenAi`] if the request fails.
+    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let url = format!("{}/models", self.endpoint.trim_end_matches("/chat/completions"));
+        let mut request = self.http.get(url);
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+
+        let send = request.send();
+        let response = match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, send).await.map_err(|_| {
+                AppError::Timeout(format!("no response within {}s", deadline.as_secs()))
+            })?,
+            None => send.await,
+        }
+        .map_err(|e| http_error("Listing models failed", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::openai(format!("{}: {}", status, body.trim())));
+        }
+        let body: Value = response
+            .json()
+            .await
+            .map_err(|e| http_error("Listing models failed", e))?;
+        let entries = body["data"].as_array().into_iter().flatten();
+        Ok(entries.filter_map(ModelInfo::from_openai).collect())
+    }
+
+    /// Sends a streaming chat-completions request.
+    async fn stream_chat(&self, messages: Vec<Value>) -> Result<GeminiEventStream> {
+        let body = json!({
+            "model": self.model,
+            "messages": messages,
+            "stream": true,
+            "stream_options": { "include_usage": true },
+        });
+
+        tracing::debug!(
+            endpoint = %self.endpoint,
+            model = %self.model,
+            messages = body["messages"].as_array().map_or(0, Vec::len),
+            "openai-compatible request"
+        );
+
+        let mut request = self.http.post(&self.endpoint).json(&body);
+        if !self.api_key.is_empty() {
+            request = request.bearer_auth(&self.api_key);
+        }
+
+        let started = tokio::time::Instant::now();
+        let send = request.send();
+        let response = match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, send).await.map_err(|_| {
+                AppError::Timeout(format!("no response within {}s", deadline.as_secs()))
+            })?,
+            None => send.await,
+        }
+        .map_err(|e| http_error("Request failed", e))?;
+
+        let status = response.status();
+        tracing::debug!(
+            %status,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "openai-compatible response"
+        );
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(AppError::RateLimited);
+        }
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::openai(format!("{}: {}", status, body.trim())));
+        }
+
+        // Split the byte stream into SSE lines and parse each `data:` payload
+        // (buffered as bytes so multi-byte characters split across chunks survive)
+        let state = (response.bytes_stream(), Vec::new());
+        let events = futures::stream::unfold(state, |(mut bytes, mut buffer)| async move {
+            loop {
+                if let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = buffer.drain(..=newline).collect();
+                    match parse_sse_line(String::from_utf8_lossy(&line).trim()) {
+                        SseLine::Events(events) => return Some((Ok(events), (bytes, buffer))),
+                        SseLine::Done => return None,
+                        SseLine::Skip => continue,
+                    }
+                }
+
+                match bytes.next().await {
+                    Some(Ok(chunk)) => buffer.extend_from_slice(&chunk),
+                    Some(Err(e)) => {
+                        return Some((Err(http_error("Stream error", e)), (bytes, Vec::new())));
+                    }
+                    None => return None,
+                }
+            }
+        });
+
+        let events: GeminiEventStream = Box::pin(events);
+        Ok(match self.deadline {
+            Some(deadline) => with_stream_deadline(events, started + deadline, deadline),
+            None => events,
+        })
+    }
+}
+
+/// Result of parsing one server-sent event line.
+enum SseLine {
+    /// Response events carried by the line.
+    Events(Vec<GeminiStreamEvent>),
+    /// The end-of-stream marker.
+    Done,
+    /// A comment, keepalive, or chunk without content.
+    Skip,
+}
+
+/// Parses a `data:` line from a chat-completions stream.
+fn parse_sse_line(line: &str) -> SseLine {
+    let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+        return SseLine::Skip;
+    };
+    if data == "[DONE]" {
+        return SseLine::Done;
+    }
+    let Ok(chunk) = serde_json::from_str::<Value>(data) else {
+        return SseLine::Skip;
+    };
+
+    let delta = &chunk["choices"][0]["delta"];
+    let mut events = Vec::new();
+    if let Some(reason) = chunk["choices"][0]["finish_reason"].as_str() {
+        tracing::debug!(finish_reason = reason, "openai-compatible stream finished");
+        events.push(GeminiStreamEvent::Finish(reason.to_string()));
+    }
+
+    // Gateways expose reasoning under different names
+    for key in ["reasoning_content", "reasoning"] {
+        if let Some(thought) = delta[key].as_str().filter(|t| !t.is_empty()) {
+            events.push(GeminiStreamEvent::Thought(thought.to_string()));
+        }
+    }
+    if let Some(text) = delta["content"].as_str().filter(|t| !t.is_empty()) {
+        events.push(GeminiStreamEvent::Text(text.to_string()));
+    }
+
+    // Sent in a final chunk when `stream_options.include_usage` is honored
+    let usage = &chunk["usage"];
+    if usage.is_object() {
+        events.push(GeminiStreamEvent::Usage {
+            input_tokens: usage["prompt_tokens"].as_u64().unwrap_or(0) as u32,
+            output_tokens: usage["completion_tokens"].as_u64().unwrap_or(0) as u32,
+        });
+    }
+
+    if events.is_empty() {
+        SseLine::Skip
+    } else {
+        SseLine::Events(events)
+    }
+}
+
+/// Builds chat messages with `context` attached to the first user turn.
+fn conversation_messages(
+    system_prompt: &str,
+    context: Vec<Value>,
+    history: &[ChatTurn],
+    prompt: String,
+) -> Vec<Value> {
+    let mut messages = Vec::with_capacity(history.len() * 2 + 2);
+    let mut context = Some(context);
+
+    if !system_prompt.trim().is_empty() {
+        messages.push(json!({ "role": "system", "content": system_prompt }));
+    }
+
+    let user = |prompt: &str, context: Option<Vec<Value>>| {
+        let mut content = vec![json!({ "type": "text", "text": prompt })];
+        content.extend(context.into_iter().flatten());
+        json!({ "role": "user", "content": content })
+    };
+
+    for turn in history {
+        messages.push(user(&turn.prompt, context.take()));
+        messages.push(json!({ "role": "assistant", "content": turn.answer }));
+    }
+    messages.push(user(&prompt, context.take()));
+
+    messages
+}
+
+/// Converts an HTTP error into an [`AppError`], detecting timeouts.
+fn http_error(context: &str, error: reqwest::Error) -> AppError {
+    if error.is_timeout() {
+        AppError::Timeout(format!("{}: {}", context, error))
+    } else {
+        AppError::openai(format!("{}: {}", context, error))
+    }
+}
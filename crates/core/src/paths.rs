@@ -0,0 +1,145 @@
+//! Where ai-shot keeps its files.
+//!
+//! Every location comes from here so they move together. Each directory is
+//! resolved in this order:
+//!
+//! 1. A portable data directory set with `--data-dir` (see [`set_data_dir`])
+//!    or `AI_SHOT_DATA_DIR`, holding `config/`, `data/` and `cache/`
+//! 2. `XDG_CONFIG_HOME`, `XDG_DATA_HOME` or `XDG_CACHE_HOME`, joined with
+//!    `ai-shot`, on every platform
+//! 3. The platform's directories (e.g. `~/.config/ai-shot`,
+//!    `~/Library/Application Support/ai-shot`,
+//!    `%APPDATA%\antigravity\ai-shot\config`)
+//!
+//! | File | Directory |
+//! |------|-----------|
+//! | `config.toml`, `profiles.toml`, `settings.json` | config |
+//! | `history/`, `logs/`, `checkpoints/` | data |
+//! | The daemon's socket | `XDG_RUNTIME_DIR`, else cache |
+
+use directories::ProjectDirs;
+use std::env;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+/// Name of the directory inside XDG base directories.
+const APP_DIR: &str = "ai-shot";
+
+/// Portable data directory set by [`set_data_dir`].
+static DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keeps every file under `dir` instead of the user's directories, e.g. to
+/// run from a USB stick.
+///
+/// Must be called before any file is read; returns `false` (and changes
+/// nothing) if a directory was already set.
+pub fn set_data_dir(dir: impl Into<PathBuf>) -> bool {
+    DATA_DIR.set(dir.into()).is_ok()
+}
+
+/// Returns the portable data directory, if one is set.
+pub fn portable_dir() -> Option<PathBuf> {
+    DATA_DIR.get().cloned().or_else(|| {
+        env::var_os("AI_SHOT_DATA_DIR")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+/// Returns `$name/ai-shot` if the variable holds an absolute path.
+fn xdg(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join(APP_DIR))
+}
+
+fn project_dirs() -> Option<ProjectDirs> {
+    ProjectDirs::from("", "antigravity", "ai-shot")
+}
+
+/// Returns the directory of the configuration files.
+pub fn config_dir() -> Option<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Some(dir.join("config")),
+        None => xdg("XDG_CONFIG_HOME")
+            .or_else(|| project_dirs().map(|dirs| dirs.config_dir().to_path_buf())),
+    }
+}
+
+/// Returns the directory of history, logs and other kept data.
+pub fn data_dir() -> Option<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Some(dir.join("data")),
+        None => xdg("XDG_DATA_HOME")
+            .or_else(|| project_dirs().map(|dirs| dirs.data_dir().to_path_buf())),
+    }
+}
+
+/// Returns the directory of files that can be deleted at any time.
+pub fn cache_dir() -> Option<PathBuf> {
+    match portable_dir() {
+        Some(dir) => Some(dir.join("cache")),
+        None => xdg("XDG_CACHE_HOME")
+            .or_else(|| project_dirs().map(|dirs| dirs.cache_dir().to_path_buf())),
+    }
+}
+
+/// Returns the path of `config.toml`.
+pub fn config_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("config.toml"))
+}
+
+/// Returns the path of `profiles.toml`.
+pub fn profiles_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("profiles.toml"))
+}
+
+/// Returns the path of the overlay's `settings.json`.
+pub fn settings_file() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("settings.json"))
+}
+
+/// Returns the directory of the answer history.
+pub fn history_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("history"))
+}
+
+/// Returns the path of the log of tokens used per request.
+pub fn usage_file() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("usage.jsonl"))
+}
+
+/// Returns the directory of the debug log files.
+pub fn log_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("logs"))
+}
+
+/// Returns the directory of the checkpoints of answers being streamed, one
+/// per overlay process.
+pub fn checkpoint_dir() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join("checkpoints"))
+}
+
+/// Expands a leading `~/` to the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Returns the path of the local socket the running daemon listens on for
+/// `ai-shot trigger` (on Windows, a file holding its loopback port).
+///
+/// Uses `XDG_RUNTIME_DIR` unless a portable data directory is set, so
+/// portable copies don't see each other's daemons.
+pub fn daemon_socket() -> Option<PathBuf> {
+    let runtime_dir = env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute() && portable_dir().is_none());
+    match runtime_dir {
+        Some(dir) => Some(dir.join("ai-shot.sock")),
+        None => cache_dir().map(|dir| dir.join("daemon.sock")),
+    }
+}
@@ -0,0 +1,187 @@
+//! Post-processing actions run by external programs.
+//!
+//! A plugin is any executable declared as a `[[plugins]]` table in
+//! `config.toml`. Each one appears as a button below the answer in the
+//! overlay; clicking it runs the program with the exchange as one JSON
+//! object on stdin, so it can post to Slack, open a ticket or append to a
+//! notes file:
+//!
+//! ```toml
+//! [[plugins]]
+//! name = "Append to Obsidian"
+//! command = "~/bin/obsidian-append"
+//! args = ["--vault", "Work"]
+//! timeout = 30
+//! ```
+//!
+//! The input holds the prompt, model, answer, thinking output and the
+//! selection as a Base64 PNG, stamped like saved screenshots:
+//!
+//! ```json
+//! {
+//!   "prompt": "What does this regex match?",
+//!   "model": "gemini-2.5-pro",
+//!   "answer": "It matches ...",
+//!   "thoughts": "",
+//!   "image": { "mimeType": "image/png", "width": 640, "height": 120, "data": "iVBORw0..." }
+//! }
+//! ```
+//!
+//! The last line the program prints is shown in the overlay (e.g. "Created
+//! PROJ-123"). A non-zero exit status fails the action with the last line
+//! of its stderr, and a program running longer than its timeout is killed.
+
+use crate::error::{AppError, Result};
+use crate::image_processing::{ImageEncoding, ImageProcessor};
+use crate::paths;
+use image::DynamicImage;
+use serde::Deserialize;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How long a plugin may run unless it sets `timeout`.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a running plugin is checked for having exited.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An external program the answer can be handed to.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Plugin {
+    /// Label of the plugin's button.
+    pub name: String,
+    /// Program to run, looked up in `PATH` unless it is a path; a leading
+    /// `~/` is expanded.
+    pub command: String,
+    /// Arguments passed to the program.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Seconds the program may run before it is killed (60 if unset).
+    #[serde(default)]
+    pub timeout: Option<u64>,
+}
+
+/// The exchange a plugin is run on.
+#[derive(Clone, Copy, Debug)]
+pub struct PluginInput<'a> {
+    /// The user's prompt.
+    pub prompt: &'a str,
+    /// Model that produced the answer.
+    pub model: &'a str,
+    /// The model's answer (Markdown).
+    pub answer: &'a str,
+    /// Thinking output, empty if thinking mode was off.
+    pub thoughts: &'a str,
+    /// The selected region the prompt was about.
+    pub image: Option<&'a DynamicImage>,
+}
+
+impl PluginInput<'_> {
+    /// Returns the JSON object written to the plugin's stdin.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::ImageProcessing`] if the image cannot be encoded.
+    pub fn to_json(&self) -> Result<serde_json::Value> {
+        let image = match self.image {
+            Some(image) => {
+                let encoded = ImageProcessor::encode_image(image, ImageEncoding::Png)?;
+                serde_json::json!({
+                    "mimeType": encoded.mime_type,
+                    "width": encoded.width,
+                    "height": encoded.height,
+                    "data": encoded.data,
+                })
+            }
+            None => serde_json::Value::Null,
+        };
+        Ok(serde_json::json!({
+            "prompt": self.prompt,
+            "model": self.model,
+            "answer": self.answer,
+            "thoughts": self.thoughts,
+            "image": image,
+        }))
+    }
+}
+
+impl Plugin {
+    /// Runs the plugin on `input` and waits for it to exit.
+    ///
+    /// Returns the last non-empty line it printed, or an empty string.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Plugin`] if the program cannot be started, exits
+    /// with a failure status, or runs past its timeout.
+    pub fn run(&self, input: &PluginInput<'_>) -> Result<String> {
+        let mut json = serde_json::to_vec(&input.to_json()?)?;
+        json.push(b'\n');
+
+        let mut child = Command::new(paths::expand_home(&self.command))
+            .args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| AppError::plugin(format!("Failed to start {}: {}", self.command, e)))?;
+
+        // Written and read on threads so neither side blocks on a full pipe
+        if let Some(mut stdin) = child.stdin.take() {
+            thread::spawn(move || {
+                // A plugin that ignores its input closes the pipe early
+                let _ = stdin.write_all(&json);
+            });
+        }
+        let stdout = read_to_end(child.stdout.take());
+        let stderr = read_to_end(child.stderr.take());
+
+        let timeout = self.timeout.map_or(DEFAULT_TIMEOUT, Duration::from_secs);
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(AppError::plugin(format!(
+                    "{} did not finish within {} seconds",
+                    self.name,
+                    timeout.as_secs()
+                )));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        let stdout = stdout.join().unwrap_or_default();
+        if !status.success() {
+            let stderr = stderr.join().unwrap_or_default();
+            let reason = last_line(&stderr).unwrap_or_else(|| status.to_string());
+            return Err(AppError::plugin(format!("{} failed: {}", self.name, reason)));
+        }
+        Ok(last_line(&stdout).unwrap_or_default())
+    }
+}
+
+/// Reads a pipe to its end on a thread, lossily as UTF-8.
+fn read_to_end(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// Returns the last line of `text` that isn't blank, trimmed.
+fn last_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,186 @@
+//! Built-in system prompt presets.
+//!
+//! Presets package well-tested instructions for common tasks so users don't
+//! have to write their own system prompt. A selected preset is combined with
+//! the user's own system prompt via [`merge_system_prompt`], with the user's
+//! instructions placed last so they can refine or override the preset.
+//!
+//! A response language can be added on top via [`with_response_language`],
+//! so answers stay in the user's language even when the screenshot's text is
+//! in another one.
+
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A built-in system prompt for a common task.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SystemPromptPreset {
+    /// Reviews code for bugs, style, and improvements.
+    CodeReviewer,
+    /// Translates visible text.
+    Translator,
+    /// Critiques user interfaces and interaction design.
+    UiUxCritic,
+    /// Audits interfaces for accessibility issues.
+    AccessibilityAuditor,
+    /// Extracts structured data from tables, forms, and documents.
+    DataExtractor,
+}
+
+impl SystemPromptPreset {
+    /// All presets, in display order.
+    pub const ALL: &'static [SystemPromptPreset] = &[
+        Self::CodeReviewer,
+        Self::Translator,
+        Self::UiUxCritic,
+        Self::AccessibilityAuditor,
+        Self::DataExtractor,
+    ];
+
+    /// Stable identifier used in settings files and on the command line.
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::CodeReviewer => "code-reviewer",
+            Self::Translator => "translator",
+            Self::UiUxCritic => "ui-ux-critic",
+            Self::AccessibilityAuditor => "accessibility-auditor",
+            Self::DataExtractor => "data-extractor",
+        }
+    }
+
+    /// Human-readable name for the settings UI.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::CodeReviewer => "Code reviewer",
+            Self::Translator => "Translator",
+            Self::UiUxCritic => "UI/UX critic",
+            Self::AccessibilityAuditor => "Accessibility auditor",
+            Self::DataExtractor => "Data extractor",
+        }
+    }
+
+    /// The system instructions for this preset.
+    pub fn instructions(self) -> &'static str {
+        match self {
+            Self::CodeReviewer => {
+                "You are a senior software engineer reviewing code shown in a screenshot. \
+                 Identify bugs, edge cases, security issues, and readability problems. \
+                 Be specific: reference the relevant lines and suggest concrete fixes \
+                 with corrected code snippets."
+            }
+            Self::Translator => {
+                "You are a professional translator. Transcribe the text visible in the image \
+                 and translate it into the user's language (English if unspecified). \
+                 Preserve formatting, tone, and meaning; note any ambiguous phrases."
+            }
+            Self::UiUxCritic => {
+                "You are an experienced UI/UX designer. Critique the interface in the image: \
+                 visual hierarchy, consistency, spacing, typography, affordances, and user flow. \
+                 Prioritize issues by impact and propose actionable improvements."
+            }
+            Self::AccessibilityAuditor => {
+                "You are an accessibility auditor familiar with WCAG 2.2. Review the interface \
+                 in the image for contrast, text size, target size, labeling, and other barriers. \
+                 Cite the relevant WCAG success criteria and suggest remediations."
+            }
+            Self::DataExtractor => {
+                "You extract structured data from images. Return the tables, forms, or lists \
+                 visible in the image as clean Markdown tables or JSON, preserving every value \
+                 exactly. Do not add commentary unless data is illegible."
+            }
+        }
+    }
+}
+
+impl fmt::Display for SystemPromptPreset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl FromStr for SystemPromptPreset {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized = s.trim().to_ascii_lowercase().replace('_', "-");
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|preset| preset.id() == normalized)
+            .ok_or_else(|| {
+                let known: Vec<&str> = Self::ALL.iter().map(|p| p.id()).collect();
+                AppError::config(format!(
+                    "Unknown preset '{}' (expected one of: {})",
+                    s,
+                    known.join(", ")
+                ))
+            })
+    }
+}
+
+/// Languages with native-language response instructions: (code, name, instruction).
+pub const RESPONSE_LANGUAGES: &[(&str, &str, &str)] = &[
+    ("en", "English", "Always answer in English, regardless of the language shown in the image."),
+    ("de", "Deutsch", "Antworte immer auf Deutsch, unabhängig von der Sprache im Bild."),
+    ("es", "Español", "Responde siempre en español, sin importar el idioma que aparezca en la imagen."),
+    ("fr", "Français", "Réponds toujours en français, quelle que soit la langue visible dans l'image."),
+    ("it", "Italiano", "Rispondi sempre in italiano, indipendentemente dalla lingua presente nell'immagine."),
+    ("pt", "Português", "Responda sempre em português, independentemente do idioma mostrado na imagem."),
+    ("pl", "Polski", "Zawsze odpowiadaj po polsku, niezależnie od języka widocznego na obrazie."),
+    ("uk", "Українська", "Завжди відповідай українською, незалежно від мови на зображенні."),
+    ("ru", "Русский", "Всегда отвечай на русском языке, независимо от языка на изображении."),
+    ("tr", "Türkçe", "Görseldeki dil ne olursa olsun her zaman Türkçe yanıt ver."),
+    ("ja", "日本語", "画像内の言語に関係なく、常に日本語で回答してください。"),
+    ("zh", "中文", "无论图片中是什么语言，请始终用中文回答。"),
+];
+
+/// Returns the instruction that pins answers to `language`.
+///
+/// `language` may be a code ("de") or a name ("German", "Deutsch"); known
+/// languages get an instruction written in that language, others an English
+/// one naming the language.
+pub fn language_instruction(language: &str) -> String {
+    let language = language.trim();
+    let known = RESPONSE_LANGUAGES.iter().find(|(code, name, _)| {
+        code.eq_ignore_ascii_case(language) || name.eq_ignore_ascii_case(language)
+    });
+
+    match known {
+        Some((_, _, instruction)) => instruction.to_string(),
+        None => format!(
+            "Always answer in {}, regardless of the language shown in the image.",
+            language
+        ),
+    }
+}
+
+/// Appends the response-language instruction to a system prompt.
+///
+/// Returns `system_prompt` unchanged when `language` is empty.
+pub fn with_response_language(system_prompt: String, language: &str) -> String {
+    if language.trim().is_empty() {
+        return system_prompt;
+    }
+
+    let instruction = language_instruction(language);
+    if system_prompt.is_empty() {
+        instruction
+    } else {
+        format!("{}\n\n{}", system_prompt, instruction)
+    }
+}
+
+/// Combines a preset with the user's own system prompt.
+///
+/// The preset comes first so the user's instructions can refine it.
+pub fn merge_system_prompt(preset: Option<SystemPromptPreset>, user_prompt: &str) -> String {
+    let user_prompt = user_prompt.trim();
+    match preset {
+        Some(preset) if user_prompt.is_empty() => preset.instructions().to_string(),
+        Some(preset) => format!("{}\n\n{}", preset.instructions(), user_prompt),
+        None => user_prompt.to_string(),
+    }
+}
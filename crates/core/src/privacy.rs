@@ -0,0 +1,275 @@
+//! Finding and masking sensitive data before an image is uploaded.
+//!
+//! [`scan`] reads the image with the local OCR engine ([`crate::ocr`]) and
+//! matches each line of text against patterns for email addresses, API keys
+//! and payment card numbers (which must also pass the Luhn check). [`mask`]
+//! pixelates whatever was found.
+//!
+//! Detection is best effort: text the OCR misreads, and secrets in formats
+//! not listed here, stay visible. The overlay therefore lists what it masked
+//! so it can be reviewed before sending.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::ocr::OcrEngine;
+//! use ai_shot_core::privacy;
+//!
+//! let findings = privacy::scan(&OcrEngine::from_env(), &crop)?;
+//! privacy::mask(&mut crop, &findings);
+//! ```
+
+use crate::error::Result;
+use crate::image_processing::{ImageProcessor, PixelRegion, RedactionStyle};
+use crate::ocr::{OcrEngine, OcrWord};
+use regex::Regex;
+use std::fmt;
+use std::ops::Range;
+use std::sync::LazyLock;
+
+/// Pixels added around found text, so the edges of letters are masked too.
+const MASK_PADDING: u32 = 2;
+
+/// What kind of sensitive data was found.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SensitiveKind {
+    /// An email address.
+    Email,
+    /// An API key or token with a well-known prefix (Google, OpenAI,
+    /// Anthropic, GitHub, GitLab, Slack, AWS).
+    ApiKey,
+    /// A payment card number.
+    CardNumber,
+}
+
+impl SensitiveKind {
+    /// Every kind, in the order they are reported.
+    pub const ALL: [SensitiveKind; 3] =
+        [SensitiveKind::Email, SensitiveKind::ApiKey, SensitiveKind::CardNumber];
+
+    /// Returns how the kind is named in messages.
+    pub fn label(self) -> &'static str {
+        match self {
+            SensitiveKind::Email => "email address",
+            SensitiveKind::ApiKey => "API key",
+            SensitiveKind::CardNumber => "card number",
+        }
+    }
+
+    fn pattern(self) -> &'static Regex {
+        match self {
+            SensitiveKind::Email => &EMAIL,
+            SensitiveKind::ApiKey => &API_KEY,
+            SensitiveKind::CardNumber => &CARD_NUMBER,
+        }
+    }
+}
+
+impl fmt::Display for SensitiveKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}")
+        .expect("email pattern is valid")
+});
+
+static API_KEY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"\b(?:AIza[0-9A-Za-z_-]{35}",
+        r"|sk-(?:proj-|ant-[a-z0-9]+-)?[A-Za-z0-9_-]{20,}",
+        r"|gh[pousr]_[A-Za-z0-9]{36,}",
+        r"|github_pat_[A-Za-z0-9_]{22,}",
+        r"|glpat-[A-Za-z0-9_-]{20,}",
+        r"|xox[abprs]-[A-Za-z0-9-]{10,}",
+        r"|AKIA[0-9A-Z]{16})",
+    ))
+    .expect("API key pattern is valid")
+});
+
+/// 13 to 19 digits, optionally grouped with spaces or dashes.
+///
+/// A match can run on into neighbouring numbers, such as an expiry date, so
+/// [`card_numbers`] picks the card numbers out of it.
+static CARD_NUMBER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("card number pattern is valid")
+});
+
+/// Sensitive text found in an image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// What was found.
+    pub kind: SensitiveKind,
+    /// Where it is in the image, covering every word it touches.
+    pub region: PixelRegion,
+    /// The text with most of it hidden, for showing what was masked.
+    pub preview: String,
+}
+
+/// Reads the text of an image and finds sensitive data in it.
+///
+/// # Errors
+///
+/// Returns [`crate::AppError::Ocr`] if Tesseract is not installed or fails.
+pub fn scan(engine: &OcrEngine, image: &image::DynamicImage) -> Result<Vec<Finding>> {
+    Ok(find(&engine.extract_words(image)?))
+}
+
+/// Finds sensitive data in OCR output.
+///
+/// Each line is matched as a whole, so card numbers split into groups of
+/// digits are found too, also when other numbers follow them.
+pub fn find(words: &[OcrWord]) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    for line in words.chunk_by(|a, b| a.line == b.line) {
+        // The line's words joined by spaces, with where each one is
+        let mut text = String::new();
+        let mut spans = Vec::with_capacity(line.len());
+        for word in line {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let start = text.len();
+            text.push_str(&word.text);
+            spans.push(start..text.len());
+        }
+
+        for kind in SensitiveKind::ALL {
+            let matches = kind.pattern().find_iter(&text).flat_map(|found| {
+                let offset = found.start();
+                match kind {
+                    SensitiveKind::CardNumber => card_numbers(found.as_str())
+                        .into_iter()
+                        .map(|number| offset + number.start..offset + number.end)
+                        .collect(),
+                    _ => vec![found.range()],
+                }
+            });
+            for found in matches {
+                let region = line
+                    .iter()
+                    .zip(&spans)
+                    .filter(|(_, span)| span.start < found.end && found.start < span.end)
+                    .map(|(word, _)| word.region)
+                    .reduce(union);
+                if let Some(region) = region {
+                    findings.push(Finding {
+                        kind,
+                        region,
+                        preview: preview(kind, &text[found]),
+                    });
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// Pixelates the findings in place.
+pub fn mask(image: &mut image::DynamicImage, findings: &[Finding]) {
+    let regions: Vec<PixelRegion> = findings
+        .iter()
+        .map(|finding| PixelRegion {
+            x: finding.region.x.saturating_sub(MASK_PADDING),
+            y: finding.region.y.saturating_sub(MASK_PADDING),
+            width: finding.region.width + 2 * MASK_PADDING,
+            height: finding.region.height + 2 * MASK_PADDING,
+        })
+        .collect();
+    ImageProcessor::mask_regions(image, &regions, RedactionStyle::Pixelate);
+}
+
+/// Summarizes findings for a status line, e.g. "2 email addresses, 1 API key".
+pub fn summary(findings: &[Finding]) -> String {
+    SensitiveKind::ALL
+        .iter()
+        .filter_map(|&kind| {
+            let count = findings.iter().filter(|finding| finding.kind == kind).count();
+            match count {
+                0 => None,
+                1 => Some(format!("1 {}", kind)),
+                count if kind == SensitiveKind::Email => {
+                    Some(format!("{} email addresses", count))
+                }
+                count => Some(format!("{} {}s", count, kind)),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Finds the card numbers in a run of digit groups.
+///
+/// Card numbers start and end at group edges, so only those windows of 13
+/// to 19 digits are checked; at each start the longest one passing the Luhn
+/// check wins. An ungrouped run is checked as a whole.
+fn card_numbers(run: &str) -> Vec<Range<usize>> {
+    let separators: Vec<usize> = run.match_indices([' ', '-']).map(|(at, _)| at).collect();
+    let starts = std::iter::once(0).chain(separators.iter().map(|at| at + 1));
+    let ends: Vec<usize> = separators.iter().copied().chain([run.len()]).collect();
+
+    let mut numbers: Vec<Range<usize>> = Vec::new();
+    for start in starts {
+        if numbers.last().is_some_and(|number| start < number.end) {
+            continue;
+        }
+        let windows = ends.iter().rev().filter(|&&end| end > start);
+        let number = windows.map(|&end| start..end).find(|window| {
+            let digits = run[window.clone()].chars().filter(char::is_ascii_digit).count();
+            (13..=19).contains(&digits) && luhn_valid(&run[window.clone()])
+        });
+        numbers.extend(number);
+    }
+    numbers
+}
+
+/// Returns whether digits (with separators) pass the Luhn checksum used by
+/// payment cards.
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Hides most of a match, keeping enough to recognize it.
+fn preview(kind: SensitiveKind, text: &str) -> String {
+    match kind {
+        SensitiveKind::Email => match text.split_once('@') {
+            Some((user, domain)) => {
+                format!("{}•••@{}", user.chars().next().unwrap_or_default(), domain)
+            }
+            None => "•••".to_string(),
+        },
+        SensitiveKind::ApiKey => format!("{}•••", text.chars().take(4).collect::<String>()),
+        SensitiveKind::CardNumber => {
+            let digits: Vec<char> = text.chars().filter(char::is_ascii_digit).collect();
+            let last: String = digits[digits.len().saturating_sub(4)..].iter().collect();
+            format!("•••• {}", last)
+        }
+    }
+}
+
+/// Returns the smallest region covering both.
+fn union(a: PixelRegion, b: PixelRegion) -> PixelRegion {
+    let x = a.x.min(b.x);
+    let y = a.y.min(b.y);
+    let right = (a.x + a.width).max(b.x + b.width);
+    let bottom = (a.y + a.height).max(b.y + b.height);
+    PixelRegion {
+        x,
+        y,
+        width: right - x,
+        height: bottom - y,
+    }
+}
@@ -0,0 +1,175 @@
+//! Named configuration profiles.
+//!
+//! Profiles bundle a backend, credentials and prompt settings under a name,
+//! so switching between, say, a work setup on Vertex AI with a strict
+//! system prompt and a personal free-tier key is a single `--profile` flag
+//! or a pick in the settings window. All profiles live in one TOML file next
+//! to `settings.json` (e.g. `~/.config/ai-shot/profiles.toml` on Linux):
+//!
+//! ```toml
+//! default = "personal"
+//!
+//! [profiles.personal]
+//! api_key = "AIza..."
+//! model = "gemini-flash-latest"
+//!
+//! [profiles.work]
+//! model = "gemini-2.5-pro"
+//! preset = "code-reviewer"
+//! system_prompt = "Flag anything that looks like a secret."
+//!
+//! [profiles.work.vertex]
+//! project = "acme-prod"
+//! location = "europe-west4"
+//! ```
+//!
+//! Every field of a profile is optional and overrides the environment;
+//! explicit builder values (command-line flags) override the profile.
+//! Without a selected profile, `AI_SHOT_PROFILE` and then `default` apply.
+
+use crate::config::{OpenAiConfig, VertexConfig};
+use crate::error::{AppError, Result};
+use crate::paths;
+use crate::presets::SystemPromptPreset;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// Overrides applied when a profile is active.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    /// Model name.
+    pub model: Option<String>,
+    /// Gemini API key.
+    pub api_key: Option<String>,
+    /// Built-in system prompt preset.
+    pub preset: Option<SystemPromptPreset>,
+    /// Custom system prompt, combined with the preset.
+    pub system_prompt: Option<String>,
+    /// Language answers are written in.
+    pub response_language: Option<String>,
+    /// Send requests to Vertex AI instead of the Gemini API.
+    pub vertex: Option<ProfileVertex>,
+    /// Send requests to an OpenAI-compatible gateway.
+    pub openai: Option<ProfileOpenAi>,
+}
+
+/// Vertex AI settings of a profile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileVertex {
+    /// Google Cloud project ID.
+    pub project: String,
+    /// Region of the endpoint (`us-central1` if omitted).
+    pub location: Option<String>,
+    /// Service-account key file (Application Default Credentials if omitted).
+    pub credentials: Option<PathBuf>,
+}
+
+/// OpenAI-compatible gateway settings of a profile.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileOpenAi {
+    /// Base URL of the API, including the version.
+    pub base_url: String,
+    /// Bearer token for the gateway.
+    pub api_key: Option<String>,
+}
+
+impl From<ProfileVertex> for VertexConfig {
+    fn from(vertex: ProfileVertex) -> Self {
+        Self {
+            project: vertex.project,
+            location: vertex.location.unwrap_or_else(|| "us-central1".to_string()),
+            credentials: vertex.credentials,
+        }
+    }
+}
+
+impl From<ProfileOpenAi> for OpenAiConfig {
+    fn from(openai: ProfileOpenAi) -> Self {
+        Self {
+            base_url: openai.base_url,
+            api_key: openai.api_key.unwrap_or_default(),
+        }
+    }
+}
+
+/// The profiles file.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profiles {
+    /// Profile used when none is selected.
+    pub default: Option<String>,
+    /// Profiles by name.
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Profiles {
+    /// Returns the path of the profiles file, if a config directory exists.
+    pub fn path() -> Option<PathBuf> {
+        paths::profiles_file()
+    }
+
+    /// Loads the profiles file; a missing file has no profiles.
+    ///
+    /// # Errors
+    /// Returns an error if the file cannot be read or is not valid.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e.into()),
+        };
+        toml::from_str(&text)
+            .map_err(|e| AppError::config(format!("Invalid {}: {}", path.display(), e)))
+    }
+
+    /// Returns the profile names, sorted.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
+    /// Returns whether the file defines no profiles.
+    pub fn is_empty(&self) -> bool {
+        self.profiles.is_empty()
+    }
+
+    /// Picks the active profile.
+    ///
+    /// Uses `name` if given (and not empty), else `AI_SHOT_PROFILE`, else
+    /// the file's `default`. Returns `None` when none of them is set.
+    ///
+    /// # Errors
+    /// Returns an error if the chosen profile doesn't exist.
+    pub fn select(&self, name: Option<&str>) -> Result<Option<(String, Profile)>> {
+        let from_env = env::var("AI_SHOT_PROFILE").ok();
+        let name = [name, from_env.as_deref(), self.default.as_deref()]
+            .into_iter()
+            .flatten()
+            .map(str::trim)
+            .find(|name| !name.is_empty());
+        let Some(name) = name else {
+            return Ok(None);
+        };
+
+        match self.profiles.get(name) {
+            Some(profile) => Ok(Some((name.to_string(), profile.clone()))),
+            None => {
+                let known: Vec<&str> = self.names().collect();
+                Err(AppError::config(format!(
+                    "Unknown profile '{}' (available: {})",
+                    name,
+                    if known.is_empty() { "none".to_string() } else { known.join(", ") }
+                )))
+            }
+        }
+    }
+}
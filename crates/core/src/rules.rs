@@ -0,0 +1,188 @@
+//! Rules that adjust the daemon's captures to where they were taken.
+//!
+//! Each `[[rules]]` table in `config.toml` pairs conditions with actions.
+//! When a hotkey is pressed, the daemon looks at the focused window, the
+//! monitor the pointer is on and the hotkey's action, and the first rule
+//! whose conditions all hold changes how that capture is answered:
+//!
+//! ```toml
+//! [[rules]]
+//! name = "Code review in the IDE"
+//! when = { app = ["code", "idea"], hotkey = ["select", "active-window"] }
+//! then = { preset = "code-reviewer", model = "gemini-2.5-pro" }
+//!
+//! [[rules]]
+//! name = "Meeting notes"
+//! when = { title = ["zoom", "meet"], monitor = "DP-2" }
+//! then = { copy = true, save_answer = "~/notes/%Y-%m-%d_%H%M%S.md" }
+//! ```
+//!
+//! Conditions left out hold for every capture; `app` and `title` match
+//! when any of their entries is part of the focused window's application
+//! name or title, ignoring case. `monitor` takes an index or an output name
+//! like `--monitor`. Actions set the system prompt preset, the model,
+//! whether answers are copied, and the `--save-image` / `--save-answer`
+//! templates (see [`crate::archive`]); the ones left out keep the
+//! configured value. Answers of hotkeys without the overlay are always
+//! copied.
+
+use crate::archive::Archive;
+use crate::capture::{MonitorInfo, MonitorSelector};
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::hotkey::HotkeyAction;
+use crate::models;
+use crate::presets::SystemPromptPreset;
+use serde::Deserialize;
+
+/// A `[[rules]]` table.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    /// Shown in the daemon's output when the rule applies.
+    #[serde(default)]
+    pub name: String,
+    /// What must hold for the rule to apply.
+    #[serde(default)]
+    pub when: Conditions,
+    /// What changes when it applies.
+    #[serde(default)]
+    pub then: Actions,
+}
+
+/// The `when` table of a rule; all given conditions must hold.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Conditions {
+    /// Parts of the focused window's application name, e.g. `code`.
+    pub app: Vec<String>,
+    /// Parts of the focused window's title.
+    pub title: Vec<String>,
+    /// Monitor the pointer is on, as an index or output name.
+    pub monitor: Option<String>,
+    /// Hotkey actions by name, e.g. `select` or `active-window`.
+    pub hotkey: Vec<String>,
+}
+
+/// The `then` table of a rule.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Actions {
+    /// System prompt preset, e.g. `code-reviewer`.
+    pub preset: Option<SystemPromptPreset>,
+    /// Model to ask; a close match of a known model is corrected.
+    pub model: Option<String>,
+    /// Copy each finished answer in the overlay to the clipboard.
+    pub copy: Option<bool>,
+    /// Template the crop is saved to, as `--save-image`.
+    pub save_image: Option<String>,
+    /// Template the answer is saved to, as `--save-answer`.
+    pub save_answer: Option<String>,
+}
+
+/// Where and how a capture was taken, to match rules against.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RuleContext {
+    /// The hotkey's action.
+    pub action: HotkeyAction,
+    /// Application name of the focused window, if any has the focus.
+    pub app: Option<String>,
+    /// Title of the focused window.
+    pub title: Option<String>,
+    /// Monitor the pointer is on, if its position is known.
+    pub monitor: Option<MonitorInfo>,
+}
+
+impl Rule {
+    /// Returns the rule's name, or a description if it has none.
+    pub fn label(&self) -> String {
+        if self.name.trim().is_empty() {
+            "unnamed rule".to_string()
+        } else {
+            self.name.clone()
+        }
+    }
+
+    /// Checks that the rule's hotkey actions and monitor can be read.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Config`] naming the rule if one can't.
+    pub fn validate(&self) -> Result<()> {
+        let invalid = |e: AppError| AppError::config(format!("Rule '{}': {}", self.label(), e));
+        for action in &self.when.hotkey {
+            action.parse::<HotkeyAction>().map_err(invalid)?;
+        }
+        if let Some(monitor) = &self.when.monitor {
+            monitor.parse::<MonitorSelector>().map_err(invalid)?;
+        }
+        Ok(())
+    }
+
+    /// Returns whether all of the rule's conditions hold for `context`.
+    pub fn matches(&self, context: &RuleContext) -> bool {
+        let when = &self.when;
+        let contains_any = |value: &Option<String>, parts: &[String]| {
+            if parts.is_empty() {
+                return true;
+            }
+            let Some(value) = value else {
+                return false;
+            };
+            let value = value.to_lowercase();
+            parts.iter().any(|part| value.contains(&part.to_lowercase()))
+        };
+        let action_matches = when.hotkey.is_empty()
+            || when
+                .hotkey
+                .iter()
+                .any(|name| name.parse::<HotkeyAction>().ok() == Some(context.action));
+        let monitor_matches = match when.monitor.as_deref().map(str::parse) {
+            None => true,
+            Some(Ok(selector)) => context
+                .monitor
+                .as_ref()
+                .is_some_and(|monitor| is_selected(&selector, monitor)),
+            Some(Err(_)) => false,
+        };
+
+        action_matches
+            && monitor_matches
+            && contains_any(&context.app, &when.app)
+            && contains_any(&context.title, &when.title)
+    }
+
+    /// Applies the rule's actions to the configuration of a capture.
+    pub fn apply(&self, config: &mut Config) {
+        let then = &self.then;
+        if let Some(preset) = then.preset {
+            config.preset = Some(preset);
+        }
+        if let Some(model) = &then.model {
+            config.model_name = models::closest_known(model).map_or(model.clone(), str::to_string);
+        }
+        if let Some(copy) = then.copy {
+            config.copy_answers = copy;
+        }
+        config.archive = Archive {
+            image: then.save_image.clone().or(config.archive.image.take()),
+            answer: then.save_answer.clone().or(config.archive.answer.take()),
+        };
+    }
+}
+
+/// Returns the first rule that applies to `context`.
+pub fn first_match<'a>(rules: &'a [Rule], context: &RuleContext) -> Option<&'a Rule> {
+    rules.iter().find(|rule| rule.matches(context))
+}
+
+/// Returns whether `selector` picks `monitor`, as `--monitor` would.
+fn is_selected(selector: &MonitorSelector, monitor: &MonitorInfo) -> bool {
+    match selector {
+        MonitorSelector::Index(index) => *index == monitor.index,
+        MonitorSelector::Cursor => true,
+        MonitorSelector::Name(name) => monitor.name.as_ref().is_some_and(|output| {
+            output.to_lowercase().starts_with(&name.to_lowercase())
+        }),
+    }
+}
@@ -0,0 +1,282 @@
+//! Shared task scheduling with concurrency limits and backpressure.
+//!
+//! Long-running modes (batch analysis, the daemon's hotkeys and D-Bus
+//! interface, the MCP server) all submit API work through the process-wide
+//! [`TaskScheduler::shared`] so they can coexist without overloading the API
+//! or the machine. Each provider (see [`provider`]) gets its own concurrency
+//! limit, optional spacing between task starts, and bounded queue; when the
+//! queue is full, new work is rejected immediately with
+//! [`AppError::QueueFull`] so callers can slow down instead of piling up
+//! unbounded futures.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::scheduler::{self, SchedulerConfig, TaskScheduler};
+//!
+//! // Before any work is submitted, e.g. from command line flags
+//! TaskScheduler::configure_shared(SchedulerConfig::default().with_provider_limit("gemini", 2));
+//!
+//! let answer = TaskScheduler::shared()
+//!     .run(scheduler::provider(&config), async { client.analyze_image(image, prompt).await })
+//!     .await?;
+//! ```
+
+use crate::config::Config;
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+/// Longest spacing between task starts; longer ones are cut to this.
+pub const MAX_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The scheduler returned by [`TaskScheduler::shared`].
+static SHARED: OnceLock<TaskScheduler> = OnceLock::new();
+
+/// Returns the name `config`'s requests are scheduled under: `"openai"`
+/// for an OpenAI-compatible gateway, `"vertex"` for Vertex AI, else
+/// `"gemini"`.
+pub fn provider(config: &Config) -> &'static str {
+    if config.openai.is_some() {
+        "openai"
+    } else if config.vertex.is_some() {
+        "vertex"
+    } else {
+        "gemini"
+    }
+}
+
+/// Limits applied by a [`TaskScheduler`].
+#[derive(Clone, Debug)]
+pub struct SchedulerConfig {
+    /// Maximum number of tasks running at once for a provider.
+    pub max_concurrent: usize,
+    /// Maximum number of tasks waiting for a slot before new work is rejected.
+    pub max_queue_depth: usize,
+    /// Per-provider overrides of `max_concurrent`.
+    pub provider_limits: HashMap<String, usize>,
+    /// Per-provider minimum time between task starts.
+    pub provider_intervals: HashMap<String, Duration>,
+}
+
+impl SchedulerConfig {
+    /// Overrides the concurrency limit for a single provider.
+    pub fn with_provider_limit(mut self, provider: impl Into<String>, limit: usize) -> Self {
+        self.provider_limits.insert(provider.into(), limit.max(1));
+        self
+    }
+
+    /// Spaces the starts of a provider's tasks at least `interval` apart,
+    /// at most [`MAX_INTERVAL`].
+    pub fn with_provider_interval(
+        mut self,
+        provider: impl Into<String>,
+        interval: Duration,
+    ) -> Self {
+        self.provider_intervals.insert(provider.into(), interval.min(MAX_INTERVAL));
+        self
+    }
+
+    /// Returns the concurrency limit for a provider.
+    fn limit_for(&self, provider: &str) -> usize {
+        self.provider_limits
+            .get(provider)
+            .copied()
+            .unwrap_or(self.max_concurrent)
+            .max(1)
+    }
+
+    /// Returns the minimum time between task starts for a provider.
+    fn interval_for(&self, provider: &str) -> Duration {
+        self.provider_intervals
+            .get(provider)
+            .copied()
+            .unwrap_or_default()
+            .min(MAX_INTERVAL)
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent: 4,
+            max_queue_depth: 64,
+            provider_limits: HashMap::new(),
+            provider_intervals: HashMap::new(),
+        }
+    }
+}
+
+/// Snapshot of a provider's load, used for backpressure decisions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProviderLoad {
+    /// Tasks currently running.
+    pub running: usize,
+    /// Tasks waiting for a slot.
+    pub queued: usize,
+    /// Maximum concurrent tasks.
+    pub capacity: usize,
+    /// Maximum queued tasks.
+    pub queue_capacity: usize,
+}
+
+impl ProviderLoad {
+    /// Returns whether new submissions would currently be rejected.
+    pub fn is_saturated(&self) -> bool {
+        self.queued >= self.queue_capacity
+    }
+}
+
+/// Concurrency state for a single provider.
+struct ProviderSlot {
+    semaphore: Arc<Semaphore>,
+    capacity: usize,
+    queued: AtomicUsize,
+    interval: Duration,
+    /// Earliest time the next task may start.
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl ProviderSlot {
+    /// Reserves the next start time, spacing starts by `interval`; returns
+    /// `None` if starts aren't spaced.
+    fn reserve_start(&self) -> Option<Instant> {
+        if self.interval.is_zero() {
+            return None;
+        }
+        let mut next_start = self
+            .next_start
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let start = next_start.map_or(now, |next| next.max(now));
+        *next_start = Some(start + self.interval);
+        Some(start)
+    }
+}
+
+/// Decrements a provider's queue counter when dropped.
+struct QueuePosition<'a>(&'a AtomicUsize);
+
+impl Drop for QueuePosition<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Scheduler that bounds concurrent and queued work per provider.
+///
+/// Cloning is cheap; clones share the same limits and counters.
+#[derive(Clone)]
+pub struct TaskScheduler {
+    config: Arc<SchedulerConfig>,
+    providers: Arc<Mutex<HashMap<String, Arc<ProviderSlot>>>>,
+}
+
+impl TaskScheduler {
+    /// Creates a scheduler with the given limits.
+    pub fn new(config: SchedulerConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            providers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Returns the scheduler shared by everything in this process that
+    /// sends API work.
+    ///
+    /// It has the default limits unless [`TaskScheduler::configure_shared`]
+    /// ran first.
+    pub fn shared() -> Self {
+        SHARED.get_or_init(Self::default).clone()
+    }
+
+    /// Sets the limits of the shared scheduler.
+    ///
+    /// Returns `false`, leaving the limits as they are, if the shared
+    /// scheduler is already in use.
+    pub fn configure_shared(config: SchedulerConfig) -> bool {
+        SHARED.set(Self::new(config)).is_ok()
+    }
+
+    /// Runs a task once a slot for `provider` is available and, if the
+    /// provider's starts are spaced, its turn has come.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::QueueFull`] without running the task when the
+    /// provider's queue is at capacity; otherwise returns the task's result.
+    /// Tasks may fail with any error an [`AppError`] converts into.
+    pub async fn run<F, T, E>(&self, provider: &str, task: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+        E: From<AppError>,
+    {
+        let slot = self.slot(provider);
+
+        // Reserve a queue position, rejecting if the queue is full
+        let reserved = slot
+            .queued
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |queued| {
+                (queued < self.config.max_queue_depth).then_some(queued + 1)
+            });
+        if reserved.is_err() {
+            return Err(AppError::QueueFull(provider.to_string()).into());
+        }
+
+        // Release the queue position even if this future is dropped while waiting
+        let queue_position = QueuePosition(&slot.queued);
+        let permit = slot.semaphore.clone().acquire_owned().await;
+        drop(queue_position);
+        let _permit = permit.map_err(|_| AppError::Unknown("Scheduler was shut down".into()))?;
+
+        if let Some(start) = slot.reserve_start() {
+            tokio::time::sleep_until(start).await;
+        }
+        task.await
+    }
+
+    /// Returns the current load of a provider.
+    pub fn load(&self, provider: &str) -> ProviderLoad {
+        let slot = self.slot(provider);
+        ProviderLoad {
+            running: slot.capacity - slot.semaphore.available_permits(),
+            queued: slot.queued.load(Ordering::Acquire),
+            capacity: slot.capacity,
+            queue_capacity: self.config.max_queue_depth,
+        }
+    }
+
+    /// Returns the slot for a provider, creating it on first use.
+    fn slot(&self, provider: &str) -> Arc<ProviderSlot> {
+        let mut providers = self
+            .providers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        providers
+            .entry(provider.to_string())
+            .or_insert_with(|| {
+                let capacity = self.config.limit_for(provider);
+                Arc::new(ProviderSlot {
+                    semaphore: Arc::new(Semaphore::new(capacity)),
+                    capacity,
+                    queued: AtomicUsize::new(0),
+                    interval: self.config.interval_for(provider),
+                    next_start: Mutex::new(None),
+                })
+            })
+            .clone()
+    }
+}
+
+impl Default for TaskScheduler {
+    fn default() -> Self {
+        Self::new(SchedulerConfig::default())
+    }
+}
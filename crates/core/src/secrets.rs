@@ -0,0 +1,66 @@
+//! API key storage in the operating system's keyring.
+//!
+//! Keys saved from the settings window or `ai-shot config set api_key` go to
+//! the Secret Service on Linux, the Keychain on macOS and the Credential
+//! Manager on Windows instead of `settings.json`. A key still found in
+//! `settings.json` is moved to the keyring the next time settings are
+//! loaded.
+//!
+//! Set `use_keyring` to `false` in the settings (e.g. on systems without a
+//! Secret Service daemon) to keep the key in `settings.json` as before.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::secrets;
+//!
+//! secrets::store(secrets::GEMINI_API_KEY, "AIza...")?;
+//! let key = secrets::load(secrets::GEMINI_API_KEY)?;
+//! ```
+
+use crate::error::{AppError, Result};
+use keyring::Entry;
+
+/// Service name the secrets are filed under.
+const SERVICE: &str = "ai-shot";
+
+/// Account name of the Gemini API key.
+pub const GEMINI_API_KEY: &str = "gemini-api-key";
+
+/// Returns the keyring entry of `account`.
+fn entry(account: &str) -> Result<Entry> {
+    Entry::new(SERVICE, account).map_err(|e| AppError::secrets(e.to_string()))
+}
+
+/// Reads a secret, or `None` if none is stored.
+///
+/// # Errors
+/// Returns an error if the keyring is unavailable or locked.
+pub fn load(account: &str) -> Result<Option<String>> {
+    match entry(account)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(AppError::secrets(e.to_string())),
+    }
+}
+
+/// Stores a secret, replacing any stored before.
+///
+/// # Errors
+/// Returns an error if the keyring is unavailable or refuses the write.
+pub fn store(account: &str, secret: &str) -> Result<()> {
+    entry(account)?
+        .set_password(secret)
+        .map_err(|e| AppError::secrets(e.to_string()))
+}
+
+/// Removes a secret; removing one that isn't stored is not an error.
+///
+/// # Errors
+/// Returns an error if the keyring is unavailable.
+pub fn delete(account: &str) -> Result<()> {
+    match entry(account)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(AppError::secrets(e.to_string())),
+    }
+}
@@ -0,0 +1,162 @@
+//! Text-to-speech output of responses.
+//!
+//! Answers are read aloud with the platform's speech synthesizer, which makes
+//! responses accessible without reading them and suits "read me this
+//! article" use cases:
+//!
+//! - **macOS**: `say`
+//! - **Windows**: `System.Speech` via PowerShell
+//! - **Linux**: `espeak-ng`
+//!
+//! A different synthesizer can be configured via `AI_SHOT_TTS`; it must read
+//! the text to speak from stdin. Markdown is converted to plain text first so
+//! formatting characters aren't read out.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::speech::Speaker;
+//!
+//! let mut speech = Speaker::from_env().speak(&answer)?;
+//! // ...
+//! speech.stop();
+//! ```
+
+use crate::error::{AppError, Result};
+use pulldown_cmark::{Event, Parser, TagEnd};
+use std::env;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+
+/// PowerShell script that speaks stdin with the built-in Windows synthesizer.
+#[cfg(target_os = "windows")]
+const WINDOWS_SPEAK_SCRIPT: &str = "Add-Type -AssemblyName System.Speech; \
+    (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak([Console]::In.ReadToEnd())";
+
+/// Platform text-to-speech synthesizer.
+#[derive(Clone, Debug, Default)]
+pub struct Speaker {
+    /// Custom synthesizer command, if configured.
+    command: Option<String>,
+}
+
+impl Speaker {
+    /// Creates a speaker using a custom synthesizer command that reads stdin.
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: Some(command.into()),
+        }
+    }
+
+    /// Creates a speaker configured from `AI_SHOT_TTS`, defaulting to the
+    /// platform synthesizer.
+    pub fn from_env() -> Self {
+        Self {
+            command: env::var("AI_SHOT_TTS").ok().filter(|c| !c.trim().is_empty()),
+        }
+    }
+
+    /// Starts reading `markdown` aloud.
+    ///
+    /// Speech runs in the background; the returned [`Speech`] can be polled or
+    /// stopped, and stops automatically when dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppError::Speech`] if the synthesizer cannot be started.
+    pub fn speak(&self, markdown: &str) -> Result<Speech> {
+        let text = plain_text(markdown);
+
+        let mut child = self
+            .command()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| AppError::speech(format!("Failed to start speech synthesizer: {}", e)))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(text.as_bytes())?;
+            // Dropping stdin signals end of input
+        }
+
+        Ok(Speech { child })
+    }
+
+    /// Builds the synthesizer command for this platform.
+    fn command(&self) -> Command {
+        if let Some(command) = &self.command {
+            let mut words = command.split_whitespace();
+            let mut cmd = Command::new(words.next().unwrap_or_default());
+            cmd.args(words);
+            return cmd;
+        }
+
+        #[cfg(target_os = "macos")]
+        {
+            let mut cmd = Command::new("say");
+            cmd.args(["-f", "-"]);
+            cmd
+        }
+        #[cfg(target_os = "windows")]
+        {
+            let mut cmd = Command::new("powershell");
+            cmd.args(["-NoProfile", "-Command", WINDOWS_SPEAK_SCRIPT]);
+            cmd
+        }
+        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        {
+            let mut cmd = Command::new("espeak-ng");
+            cmd.arg("--stdin");
+            cmd
+        }
+    }
+}
+
+/// Speech in progress.
+pub struct Speech {
+    child: Child,
+}
+
+impl Speech {
+    /// Returns whether the synthesizer is still speaking.
+    pub fn is_speaking(&mut self) -> bool {
+        matches!(self.child.try_wait(), Ok(None))
+    }
+
+    /// Stops speaking immediately.
+    pub fn stop(&mut self) {
+        if self.is_speaking() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+impl Drop for Speech {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Converts Markdown to plain text suitable for reading aloud.
+///
+/// Keeps text and inline code, drops formatting markers and link targets,
+/// and separates blocks with newlines so the synthesizer pauses between them.
+pub fn plain_text(markdown: &str) -> String {
+    let mut text = String::with_capacity(markdown.len());
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Text(t) | Event::Code(t) => text.push_str(&t),
+            Event::SoftBreak => text.push(' '),
+            Event::HardBreak
+            | Event::End(TagEnd::Paragraph | TagEnd::Heading(_) | TagEnd::Item | TagEnd::CodeBlock) => {
+                text.push('\n')
+            }
+            _ => {}
+        }
+    }
+
+    text.trim().to_string()
+}
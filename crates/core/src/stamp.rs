@@ -0,0 +1,124 @@
+//! Stamping saved screenshots with the time, the computer and a note.
+//!
+//! Screenshots kept as evidence (e.g. for compliance) often need to show
+//! when and where they were taken. A [`Stamp`] adds a strip below the image
+//! with that line, so nothing in the screenshot is covered. It is applied
+//! to images that are saved, copied or exported, never to what is uploaded
+//! to the model.
+//!
+//! The stamp is set in the overlay's settings (`stamp_timestamp`,
+//! `stamp_hostname` and `stamp_text` in `settings.json`). Letters are drawn
+//! as capitals.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::stamp::Stamp;
+//!
+//! let stamp = Stamp {
+//!     timestamp: true,
+//!     hostname: true,
+//!     text: "Ticket 4711".to_string(),
+//! };
+//! stamp.apply(&crop).save("evidence.png")?;
+//! ```
+
+use crate::font;
+use chrono::{DateTime, Local};
+use image::{DynamicImage, Rgba, RgbaImage};
+use std::env;
+use std::fs;
+use std::process::Command;
+
+/// Format of the time in the stamp, e.g. `2025-03-14 09:26:53 +01:00`.
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+
+/// Between the parts of the stamp.
+const SEPARATOR: &str = "  |  ";
+
+/// Image width per step of the stamp's font scale, so the stamp stays
+/// legible on large screenshots without dwarfing small crops.
+const SCALE_STEP: u32 = 600;
+
+/// Smallest and largest size of a font pixel, in image pixels.
+const SCALE_RANGE: (u32, u32) = (2, 4);
+
+const BACKGROUND: Rgba<u8> = Rgba([24, 24, 24, 255]);
+const TEXT_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// What a stamp below saved screenshots shows.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stamp {
+    /// The local date and time, with the UTC offset.
+    pub timestamp: bool,
+    /// The computer's host name.
+    pub hostname: bool,
+    /// A note of the user's, e.g. a ticket number; empty for none.
+    pub text: String,
+}
+
+impl Stamp {
+    /// Returns whether the stamp shows anything.
+    pub fn is_enabled(&self) -> bool {
+        self.timestamp || self.hostname || !self.text.trim().is_empty()
+    }
+
+    /// Returns the line stamped at `time`.
+    pub fn line(&self, time: DateTime<Local>) -> String {
+        let mut parts = Vec::new();
+        if self.timestamp {
+            parts.push(time.format(TIMESTAMP_FORMAT).to_string());
+        }
+        if let Some(name) = self.hostname.then(hostname).flatten() {
+            parts.push(name);
+        }
+        let text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !text.is_empty() {
+            parts.push(text);
+        }
+        parts.join(SEPARATOR)
+    }
+
+    /// Returns `image` with the stamp for the current time below it, or
+    /// unchanged if the stamp shows nothing.
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        self.apply_at(image, Local::now())
+    }
+
+    /// Like [`Self::apply`], for the given time.
+    ///
+    /// The image is widened if the line doesn't fit below it.
+    pub fn apply_at(&self, image: &DynamicImage, time: DateTime<Local>) -> DynamicImage {
+        let line = self.line(time);
+        if line.is_empty() {
+            return image.clone();
+        }
+
+        let scale = (image.width() / SCALE_STEP).clamp(SCALE_RANGE.0, SCALE_RANGE.1);
+        let padding = 3 * scale;
+        let strip = font::GLYPH_HEIGHT * scale + 2 * padding;
+        let width = image.width().max(font::text_width(&line, scale) + 2 * padding);
+
+        let mut canvas = RgbaImage::from_pixel(width, image.height() + strip, BACKGROUND);
+        image::imageops::replace(&mut canvas, &image.to_rgba8(), 0, 0);
+        font::draw_text(&mut canvas, &line, padding, image.height() + padding, scale, TEXT_COLOR);
+        DynamicImage::ImageRgba8(canvas)
+    }
+}
+
+/// Returns the computer's host name, if it can be found.
+fn hostname() -> Option<String> {
+    env::var("COMPUTERNAME")
+        .or_else(|_| env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .or_else(|| {
+            let output = Command::new("hostname").output().ok()?;
+            output
+                .status
+                .success()
+                .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
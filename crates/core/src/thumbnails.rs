@@ -0,0 +1,192 @@
+//! Thumbnail storage with lazy, memory-bounded full-image decoding.
+//!
+//! Browsing hundreds of past captures must stay fast and must not hold every
+//! screenshot in memory. Each stored image is therefore written twice: the
+//! full-resolution PNG and a small pre-generated thumbnail. Thumbnails are read
+//! through a memory map and decoded straight from the mapped pages; full images
+//! are decoded only on demand and kept in an LRU cache with a byte budget.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::thumbnails::ImageStore;
+//!
+//! let mut store = ImageStore::new(history_dir.join("images"), 256 * 1024 * 1024);
+//! store.insert("2024-05-01T10-00-00", &crop)?;
+//!
+//! // Cheap: decodes a ~256px image from a memory map
+//! let thumb = store.thumbnail("2024-05-01T10-00-00")?;
+//!
+//! // Expensive the first time, cached afterwards
+//! let full = store.full_image("2024-05-01T10-00-00")?;
+//! ```
+
+use crate::error::{AppError, Result};
+use image::{DynamicImage, ImageFormat};
+use memmap2::Mmap;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Longest edge of generated thumbnails, in pixels.
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// On-disk image store with pre-generated thumbnails and an LRU full-image cache.
+pub struct ImageStore {
+    dir: PathBuf,
+    capacity_bytes: usize,
+    used_bytes: usize,
+    cache: HashMap<String, Arc<DynamicImage>>,
+    /// Cache keys from least to most recently used.
+    recency: VecDeque<String>,
+}
+
+impl ImageStore {
+    /// Creates a store rooted at `dir`, caching at most `capacity_bytes` of
+    /// decoded full images.
+    pub fn new(dir: impl Into<PathBuf>, capacity_bytes: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            capacity_bytes,
+            used_bytes: 0,
+            cache: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    /// Returns the path of the full-resolution image for `id`.
+    pub fn image_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.png", id))
+    }
+
+    /// Returns the path of the thumbnail for `id`.
+    pub fn thumbnail_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.thumb.png", id))
+    }
+
+    /// Writes the full image and its thumbnail to disk.
+    ///
+    /// # Errors
+    /// Returns an error if the directory cannot be created or encoding fails.
+    pub fn insert(&mut self, id: &str, image: &DynamicImage) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+
+        save_replacing(image, &self.image_path(id))
+            .map_err(|e| AppError::image(format!("Failed to save image: {}", e)))?;
+        save_replacing(
+            &image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE),
+            &self.thumbnail_path(id),
+        )
+        .map_err(|e| AppError::image(format!("Failed to save thumbnail: {}", e)))?;
+
+        // A re-inserted id must not serve a stale cached image
+        self.evict(id);
+        Ok(())
+    }
+
+    /// Decodes the thumbnail for `id` from a memory-mapped file.
+    ///
+    /// Falls back to generating the thumbnail from the full image when it is
+    /// missing (e.g. for entries written before thumbnails existed).
+    ///
+    /// # Errors
+    /// Returns an error if neither the thumbnail nor the full image can be read.
+    pub fn thumbnail(&self, id: &str) -> Result<DynamicImage> {
+        let path = self.thumbnail_path(id);
+        if path.exists() {
+            return decode_mapped(&path);
+        }
+
+        let full = decode_mapped(&self.image_path(id))?;
+        let thumb = full.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+        let _ = save_replacing(&thumb, &path);
+        Ok(thumb)
+    }
+
+    /// Returns the full-resolution image for `id`, decoding it on first use.
+    ///
+    /// # Errors
+    /// Returns an error if the image file cannot be read or decoded.
+    pub fn full_image(&mut self, id: &str) -> Result<Arc<DynamicImage>> {
+        if let Some(image) = self.cache.get(id).cloned() {
+            self.touch(id);
+            return Ok(image);
+        }
+
+        let image = Arc::new(decode_mapped(&self.image_path(id))?);
+        let size = image.as_bytes().len();
+
+        // Evict least recently used images until the new one fits
+        while self.used_bytes + size > self.capacity_bytes {
+            let Some(oldest) = self.recency.front().cloned() else {
+                break;
+            };
+            self.evict(&oldest);
+        }
+
+        self.used_bytes += size;
+        self.cache.insert(id.to_string(), image.clone());
+        self.recency.push_back(id.to_string());
+        Ok(image)
+    }
+
+    /// Deletes the image and thumbnail for `id` from disk and the cache.
+    pub fn remove(&mut self, id: &str) {
+        self.evict(id);
+        let _ = fs::remove_file(self.image_path(id));
+        let _ = fs::remove_file(self.thumbnail_path(id));
+    }
+
+    /// Returns the number of bytes currently held by decoded full images.
+    pub fn cached_bytes(&self) -> usize {
+        self.used_bytes
+    }
+
+    /// Marks `id` as most recently used.
+    fn touch(&mut self, id: &str) {
+        if let Some(pos) = self.recency.iter().position(|k| k == id)
+            && let Some(key) = self.recency.remove(pos)
+        {
+            self.recency.push_back(key);
+        }
+    }
+
+    /// Drops `id` from the in-memory cache.
+    fn evict(&mut self, id: &str) {
+        if let Some(image) = self.cache.remove(id) {
+            self.used_bytes = self.used_bytes.saturating_sub(image.as_bytes().len());
+        }
+        self.recency.retain(|k| k != id);
+    }
+}
+
+/// Writes `image` as a PNG next to `path` and moves it over `path`.
+///
+/// The daemon, overlays and CLI commands share the store, so files are never
+/// rewritten in place: a process that mapped the old file keeps reading it
+/// whole. The temporary name carries the process id so concurrent writers
+/// don't clobber each other's half-written files.
+fn save_replacing(image: &DynamicImage, path: &Path) -> image::ImageResult<()> {
+    let temporary = path.with_extension(format!("{}.tmp", std::process::id()));
+    if let Err(e) = image.save_with_format(&temporary, ImageFormat::Png) {
+        let _ = fs::remove_file(&temporary);
+        return Err(e);
+    }
+    fs::rename(&temporary, path).map_err(|e| {
+        let _ = fs::remove_file(&temporary);
+        e.into()
+    })
+}
+
+/// Decodes an image file through a read-only memory map.
+fn decode_mapped(path: &Path) -> Result<DynamicImage> {
+    let file = File::open(path)?;
+    // SAFETY: files in the store are only replaced by renaming a complete new
+    // file over them (see `save_replacing`) or unlinked, neither of which
+    // changes the pages of a file that is already open; nothing truncates or
+    // writes to a stored file in place.
+    let mapped = unsafe { Mmap::map(&file)? };
+    image::load_from_memory(&mapped)
+        .map_err(|e| AppError::image(format!("Failed to decode {}: {}", path.display(), e)))
+}
@@ -0,0 +1,33 @@
+//! Accessibility helpers.
+//!
+//! Many overlay controls are icon-only buttons or text fields identified
+//! only by their hint text, which screen readers announce poorly or not at
+//! all. These helpers attach readable names and roles through egui's
+//! AccessKit integration; they do nothing while no assistive technology is
+//! connected.
+
+use eframe::egui::{self, accesskit};
+
+/// Names a widget for assistive technologies.
+pub(crate) trait AccessibleName {
+    /// Sets the name announced for the widget in place of its visible text.
+    fn accessible_name(self, name: &str) -> Self;
+}
+
+impl AccessibleName for egui::Response {
+    fn accessible_name(self, name: &str) -> Self {
+        self.ctx
+            .accesskit_node_builder(self.id, |node| node.set_label(name));
+        self
+    }
+}
+
+/// Marks `ui` as a named region with the given role.
+///
+/// Must be called before adding widgets, so they become its children.
+pub(crate) fn mark_region(ui: &egui::Ui, role: accesskit::Role, name: &str) {
+    ui.ctx().accesskit_node_builder(ui.unique_id(), |node| {
+        node.set_role(role);
+        node.set_label(name);
+    });
+}
@@ -0,0 +1,100 @@
+//! Inline form for entering a missing API key.
+//!
+//! Shown in the prompt popup when no Gemini API key, Vertex AI project or
+//! OpenAI-compatible gateway is configured, so the problem surfaces before a
+//! request fails rather than after. The key is checked against the API
+//! before it is accepted.
+
+use super::settings_window::{check_key, key_format_problem};
+use eframe::egui;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+/// Inline "Set API key" form.
+pub(crate) struct ApiKeyForm {
+    key: String,
+    /// Running check of `key`
+    check: Option<Receiver<Result<(), String>>>,
+    error: Option<String>,
+}
+
+impl ApiKeyForm {
+    /// Creates an empty form.
+    pub fn new() -> Self {
+        Self {
+            key: String::new(),
+            check: None,
+            error: None,
+        }
+    }
+
+    /// Renders the form; returns the key once the API has accepted it.
+    ///
+    /// # Arguments
+    /// * `ui` - The UI to render into
+    /// * `model` - Model the key is checked against
+    pub fn show(&mut self, ui: &mut egui::Ui, model: &str) -> Option<String> {
+        let accepted = self.poll_check(ui.ctx());
+
+        ui.label(egui::RichText::new("No API key is set").strong());
+        ui.horizontal(|ui| {
+            let checking = self.check.is_some();
+            let response = ui.add_enabled(
+                !checking,
+                egui::TextEdit::singleline(&mut self.key)
+                    .password(true)
+                    .desired_width(220.0)
+                    .hint_text("Paste Gemini API key"),
+            );
+            let enter_pressed =
+                response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            let can_save = !checking && !self.key.trim().is_empty();
+            if (ui.add_enabled(can_save, egui::Button::new("Set API key")).clicked() || enter_pressed)
+                && can_save
+            {
+                self.start_check(model);
+            }
+            if checking {
+                ui.spinner();
+            }
+        });
+        ui.hyperlink_to(
+            egui::RichText::new("Get a key from Google AI Studio").small(),
+            "https://aistudio.google.com/apikey",
+        );
+        if let Some(error) = &self.error {
+            ui.colored_label(egui::Color32::RED, format!("✖ {}", error));
+        }
+
+        accepted
+    }
+
+    /// Validates the key locally, then against the API.
+    fn start_check(&mut self, model: &str) {
+        self.key = self.key.trim().to_string();
+        self.error = key_format_problem(&self.key).map(str::to_string);
+        if self.error.is_none() {
+            self.check = Some(check_key(&self.key, model));
+        }
+    }
+
+    /// Picks up the result of a running check; returns the accepted key.
+    fn poll_check(&mut self, ctx: &egui::Context) -> Option<String> {
+        let result = match self.check.as_ref()?.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Disconnected) => Err("Check did not finish".to_string()),
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(100));
+                return None;
+            }
+        };
+        self.check = None;
+        match result {
+            Ok(()) => Some(self.key.clone()),
+            Err(error) => {
+                self.error = Some(error);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Running the selection UI without blocking the caller.
+//!
+//! [`run_selection_ui`](super::run_selection_ui) runs eframe's event loop on
+//! the calling thread until the overlay closes. GUI hosts embedding
+//! ai-shot-core usually own that thread, so [`spawn_selection_ui`] runs the
+//! overlay on a thread of its own and hands back a [`SelectionHandle`] to
+//! poll or wait on; [`run_selection_ui_with`] calls back instead.
+//!
+//! The overlay's event loop must be the only one in the process while it
+//! runs, and on macOS it can only run on the main thread, where these
+//! functions return an error.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut handle = ui::spawn_selection_ui(screenshot, config)?;
+//!
+//! // In the host's own event loop:
+//! if let Some(result) = handle.try_result() {
+//!     if let Some((selection, screen_size, prompt)) = result? {
+//!         println!("Selected: {:?}", selection);
+//!     }
+//! }
+//! ```
+
+use super::snipping_tool;
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use eframe::egui;
+use image::DynamicImage;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread::{self, JoinHandle};
+
+/// The last sent selection, its screen size, and its prompt.
+pub type Selection = (egui::Rect, egui::Vec2, Option<String>);
+
+/// A selection UI running on its own thread.
+pub struct SelectionHandle {
+    result: Receiver<Result<Option<Selection>>>,
+    thread: JoinHandle<()>,
+    /// Whether the outcome was already handed out
+    taken: bool,
+}
+
+impl SelectionHandle {
+    /// Returns whether the overlay has closed.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Returns the outcome if the overlay has closed, without blocking.
+    ///
+    /// The outcome is handed out once; later calls return `None`.
+    pub fn try_result(&mut self) -> Option<Result<Option<Selection>>> {
+        if self.taken {
+            return None;
+        }
+        let result = match self.result.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(thread_stopped()),
+        };
+        self.taken = true;
+        Some(result)
+    }
+
+    /// Blocks until the overlay closes and returns its outcome.
+    ///
+    /// # Errors
+    /// Returns an error if the UI failed or its thread panicked.
+    pub fn wait(self) -> Result<Option<Selection>> {
+        let result = self.result.recv();
+        let _ = self.thread.join();
+        result.unwrap_or_else(|_| Err(thread_stopped()))
+    }
+}
+
+/// Error reported when the UI thread ends without an outcome (a panic).
+fn thread_stopped() -> AppError {
+    AppError::ui("Selection UI thread stopped unexpectedly")
+}
+
+/// Launches the selection UI on a new thread.
+///
+/// # Arguments
+/// * `screenshot` - The captured screen as a [`DynamicImage`]
+/// * `config` - Application configuration with API keys and settings
+///
+/// # Errors
+/// Returns an error on macOS, or if the thread cannot be started.
+pub fn spawn_selection_ui(screenshot: DynamicImage, config: Config) -> Result<SelectionHandle> {
+    let (sender, result) = channel();
+    let thread = spawn_ui_thread(screenshot, config, move |outcome| {
+        let _ = sender.send(outcome);
+    })?;
+    Ok(SelectionHandle {
+        result,
+        thread,
+        taken: false,
+    })
+}
+
+/// Launches the selection UI on a new thread and calls `callback` with the
+/// outcome when it closes.
+///
+/// The callback runs on the UI thread, so hosts typically forward the
+/// outcome to their own event loop from there.
+///
+/// # Errors
+/// Returns an error on macOS, or if the thread cannot be started.
+pub fn run_selection_ui_with<F>(screenshot: DynamicImage, config: Config, callback: F) -> Result<()>
+where
+    F: FnOnce(Result<Option<Selection>>) + Send + 'static,
+{
+    spawn_ui_thread(screenshot, config, callback).map(drop)
+}
+
+/// Starts the thread running the overlay.
+fn spawn_ui_thread<F>(screenshot: DynamicImage, config: Config, done: F) -> Result<JoinHandle<()>>
+where
+    F: FnOnce(Result<Option<Selection>>) + Send + 'static,
+{
+    if cfg!(target_os = "macos") {
+        return Err(AppError::ui("The UI can only run on the main thread on macOS"));
+    }
+
+    thread::Builder::new()
+        .name("ai-shot-ui".to_string())
+        .spawn(move || done(snipping_tool::run(screenshot, None, Vec::new(), config, true)))
+        .map_err(|e| AppError::ui(format!("Failed to start UI thread: {}", e)))
+}
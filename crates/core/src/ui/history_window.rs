@@ -0,0 +1,203 @@
+//! History browser.
+//!
+//! Lists earlier answers from the [`HistoryStore`], newest first, with a
+//! thumbnail of the selection they were about. The search field narrows the
+//! list to entries whose prompt, answer or model contain every word typed.
+//! An entry can be re-opened to read the answer again (and ask follow-ups),
+//! or re-asked to send the same question about the same image again.
+
+use crate::history::{HistoryEntry, HistoryQuery, HistoryStore};
+use crate::image_processing::ImageProcessor;
+use eframe::egui;
+use image::DynamicImage;
+use std::collections::HashMap;
+
+/// Height of one history row, in points.
+const ROW_HEIGHT: f32 = 72.0;
+
+/// What the user chose to do in the history browser.
+pub(crate) enum HistoryAction {
+    /// The window was closed.
+    Close,
+    /// Show an earlier answer with its image.
+    Reopen(HistoryEntry, DynamicImage),
+    /// Ask the same question about the same image again.
+    Reask(HistoryEntry, DynamicImage),
+}
+
+/// Window listing earlier answers.
+pub(crate) struct HistoryWindow {
+    store: Option<HistoryStore>,
+    entries: Vec<HistoryEntry>,
+    /// Text typed into the search field
+    search: String,
+    /// Indices into `entries` that match the search, newest first
+    visible: Vec<usize>,
+    /// Whether the search field should take the focus (once, on opening)
+    focus_search: bool,
+    /// Thumbnails loaded so far, by entry id (`None` if unavailable)
+    thumbnails: HashMap<String, Option<egui::TextureHandle>>,
+    error: Option<String>,
+}
+
+impl HistoryWindow {
+    /// Opens the default history store and reads its entries.
+    pub fn new() -> Self {
+        let store = HistoryStore::open_default();
+        let (entries, error) = match store.as_ref().map(HistoryStore::entries) {
+            Some(Ok(entries)) => (entries, None),
+            Some(Err(e)) => (Vec::new(), Some(e.to_string())),
+            None => (Vec::new(), Some("No data directory available".to_string())),
+        };
+
+        Self {
+            store,
+            visible: (0..entries.len()).collect(),
+            entries,
+            search: String::new(),
+            focus_search: true,
+            thumbnails: HashMap::new(),
+            error,
+        }
+    }
+
+    /// Shows the window for one frame.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<HistoryAction> {
+        let mut open = true;
+        let mut action = None;
+
+        egui::Window::new("History")
+            .collapsible(false)
+            .default_size([520.0, 480.0])
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if let Some(error) = &self.error {
+                    ui.colored_label(egui::Color32::RED, error);
+                } else if self.entries.is_empty() {
+                    ui.label("No answers yet. Answers are added here as they finish.");
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Search:");
+                    let field = egui::TextEdit::singleline(&mut self.search)
+                        .hint_text("Words in the question, answer or model")
+                        .desired_width(f32::INFINITY);
+                    let response = ui.add(field);
+                    if std::mem::take(&mut self.focus_search) {
+                        response.request_focus();
+                    }
+                    if response.changed() {
+                        self.filter();
+                    }
+                });
+                if self.visible.is_empty() && !self.entries.is_empty() {
+                    ui.label("No answers match the search.");
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().show_rows(
+                    ui,
+                    ROW_HEIGHT,
+                    self.visible.len(),
+                    |ui, rows| {
+                        for row in rows {
+                            if let Some(chosen) = self.render_row(ui, self.visible[row]) {
+                                action = Some(chosen);
+                            }
+                        }
+                    },
+                );
+            });
+
+        if !open {
+            return Some(HistoryAction::Close);
+        }
+        let (reask, index) = action?;
+        let entry = self.entries[index].clone();
+        let image = self
+            .store
+            .as_mut()
+            .and_then(|store| store.image(&entry.id).ok())
+            .map(|image| (*image).clone());
+        match image {
+            Some(image) if reask => Some(HistoryAction::Reask(entry, image)),
+            Some(image) => Some(HistoryAction::Reopen(entry, image)),
+            None => {
+                self.error = Some("The image of this entry is missing".to_string());
+                None
+            }
+        }
+    }
+
+    /// Recomputes which entries match the search.
+    fn filter(&mut self) {
+        let query = HistoryQuery {
+            text: Some(self.search.clone()),
+            ..HistoryQuery::default()
+        };
+        self.visible = (0..self.entries.len())
+            .filter(|&index| query.matches(&self.entries[index]))
+            .collect();
+    }
+
+    /// Renders one entry; returns `(reask, index)` if an action was chosen.
+    fn render_row(&mut self, ui: &mut egui::Ui, index: usize) -> Option<(bool, usize)> {
+        let entry = &self.entries[index];
+        let thumbnail = self
+            .thumbnails
+            .entry(entry.id.clone())
+            .or_insert_with(|| {
+                let image = self.store.as_ref()?.thumbnail(&entry.id).ok()?;
+                Some(ui.ctx().load_texture(
+                    format!("history_{}", entry.id),
+                    ImageProcessor::to_color_image(&image),
+                    egui::TextureOptions::LINEAR,
+                ))
+            });
+
+        let mut action = None;
+        ui.push_id(&entry.id, |ui| {
+            ui.set_height(ROW_HEIGHT);
+            ui.horizontal(|ui| {
+                match thumbnail {
+                    Some(texture) => {
+                        ui.add(
+                            egui::Image::new(&*texture)
+                                .max_size(egui::vec2(96.0, ROW_HEIGHT - 8.0))
+                                .corner_radius(2.0),
+                        );
+                    }
+                    None => {
+                        ui.add_sized([96.0, ROW_HEIGHT - 8.0], egui::Label::new("—"));
+                    }
+                }
+
+                ui.vertical(|ui| {
+                    ui.label(egui::RichText::new(&entry.prompt).strong())
+                        .on_hover_text(&entry.prompt);
+                    let first_line = entry.answer.lines().find(|l| !l.trim().is_empty());
+                    ui.label(
+                        egui::RichText::new(first_line.unwrap_or_default())
+                            .small()
+                            .color(egui::Color32::GRAY),
+                    );
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            egui::RichText::new(format!("{} · {}", entry.time(), entry.model))
+                                .small()
+                                .color(egui::Color32::GRAY),
+                        );
+                        if ui.small_button("Re-open").clicked() {
+                            action = Some((false, index));
+                        }
+                        if ui.small_button("Re-ask").clicked() {
+                            action = Some((true, index));
+                        }
+                    });
+                });
+            });
+        });
+        action
+    }
+}
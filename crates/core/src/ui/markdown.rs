@@ -0,0 +1,293 @@
+//! Markdown helpers for streamed responses.
+//!
+//! Gemini streams its answer in arbitrary chunks, which frequently split
+//! markdown constructs in half (an opening code fence without its closing
+//! fence, a lone backtick, half of a `**` marker). Rendering such text directly
+//! makes the viewer flash broken formatting until the rest of the construct
+//! arrives. The sanitizer in this module sits between the stream channel and
+//! the renderer and produces a provisionally balanced version of the text.
+//!
+//! [`MarkdownBlocks`] renders long answers block by block so only the part
+//! still streaming in is re-parsed each frame, and gives code blocks their
+//! own copy buttons.
+
+use super::accessibility::AccessibleName;
+use eframe::egui;
+use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Returns a copy of partially streamed markdown with open constructs closed.
+///
+/// - An unterminated trailing line that looks like the start of a fence
+///   (backticks or tildes) is held back until its newline arrives.
+/// - An open fenced code block is closed provisionally.
+/// - Unbalanced inline code and bold markers on the last line are closed.
+///
+/// The original text is returned unchanged (borrowed) when nothing needs fixing.
+pub fn sanitize_partial(text: &str) -> Cow<'_, str> {
+    // Hold back an incomplete fence marker (e.g. "``" or "```ru") on the last line
+    let (complete, tail) = match text.rfind('\n') {
+        Some(idx) => (&text[..=idx], &text[idx + 1..]),
+        None => ("", text),
+    };
+    let tail_trimmed = tail.trim_start();
+    let visible = if tail_trimmed.starts_with('`') || tail_trimmed.starts_with('~') {
+        complete
+    } else {
+        text
+    };
+
+    let mut suffix = String::new();
+
+    if let Some(fence) = open_fence(visible) {
+        if !visible.ends_with('\n') {
+            suffix.push('\n');
+        }
+        suffix.push_str(&fence);
+    } else {
+        let last_line = visible.lines().last().unwrap_or("");
+        if last_line.matches('`').count() % 2 == 1 {
+            suffix.push('`');
+        }
+        if last_line.matches("**").count() % 2 == 1 {
+            suffix.push_str("**");
+        }
+    }
+
+    if suffix.is_empty() && visible.len() == text.len() {
+        Cow::Borrowed(text)
+    } else {
+        Cow::Owned(format!("{}{}", visible, suffix))
+    }
+}
+
+/// Returns the fence marker of a code block left open at the end of `text`.
+fn open_fence(text: &str) -> Option<String> {
+    let mut open: Option<(char, usize)> = None;
+    for line in text.lines() {
+        track_fence(&mut open, line);
+    }
+
+    open.map(|(marker, run)| marker.to_string().repeat(run))
+}
+
+/// Updates the open fence (marker character and length) after `line`.
+fn track_fence(open: &mut Option<(char, usize)>, line: &str) {
+    let trimmed = line.trim_start();
+    let Some(marker) = trimmed.chars().next().filter(|c| *c == '`' || *c == '~') else {
+        return;
+    };
+    let run = trimmed.chars().take_while(|c| *c == marker).count();
+    if run < 3 {
+        return;
+    }
+
+    match *open {
+        None => *open = Some((marker, run)),
+        Some((open_marker, open_run)) => {
+            // A closing fence uses the same character, is at least as long,
+            // and carries no info string
+            if marker == open_marker
+                && run >= open_run
+                && trimmed[run * marker.len_utf8()..].trim().is_empty()
+            {
+                *open = None;
+            }
+        }
+    }
+}
+
+/// Renders markdown with the configured code highlighting theme.
+///
+/// An empty `syntax_theme` keeps the viewer's defaults, which follow the
+/// light or dark mode.
+pub fn show_markdown(ui: &mut egui::Ui, cache: &mut CommonMarkCache, text: &str, syntax_theme: &str) {
+    let mut viewer = CommonMarkViewer::new();
+    if !syntax_theme.is_empty() {
+        viewer = viewer
+            .syntax_theme_dark(syntax_theme)
+            .syntax_theme_light(syntax_theme);
+    }
+    viewer.show(ui, cache, text);
+}
+
+/// Returns the contents of all fenced and indented code blocks, separated
+/// by blank lines.
+pub fn extract_code(markdown: &str) -> String {
+    let mut blocks = Vec::new();
+    let mut current: Option<String> = None;
+
+    for event in Parser::new(markdown) {
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => current = Some(String::new()),
+            Event::Text(text) => {
+                if let Some(code) = &mut current {
+                    code.push_str(&text);
+                }
+            }
+            Event::End(TagEnd::CodeBlock) => blocks.extend(current.take()),
+            _ => {}
+        }
+    }
+
+    blocks
+        .iter()
+        .map(|code| code.trim_end_matches('\n'))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// A finished block of a [`MarkdownBlocks`] answer.
+struct Block {
+    /// Byte range in the answer.
+    range: Range<usize>,
+    /// Whether the block is a fenced code block.
+    code: bool,
+    /// Height when last rendered at [`MarkdownBlocks::layout`].
+    height: Option<f32>,
+}
+
+/// Markdown rendered as independent blocks, for long streamed answers.
+///
+/// Re-parsing and laying out a whole answer every frame makes long
+/// responses stutter. Instead the text is split at blank lines and around
+/// fenced code blocks; a finished block can no longer change, so only the
+/// growing tail is rescanned and sanitized as chunks arrive. Finished
+/// blocks remember their height, so those scrolled out of view are skipped
+/// without being parsed at all. Code blocks get their own "Copy" button.
+#[derive(Default)]
+pub struct MarkdownBlocks {
+    blocks: Vec<Block>,
+    /// Width and body font size the block heights were measured at.
+    layout: (f32, f32),
+    /// Length of the text the blocks were split from.
+    len: usize,
+}
+
+impl MarkdownBlocks {
+    /// Renders `text` in `ui`, which should be inside a scroll area.
+    ///
+    /// When `streaming` is set, constructs left open in the tail are closed
+    /// provisionally with [`sanitize_partial`].
+    pub fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        cache: &mut CommonMarkCache,
+        text: &str,
+        streaming: bool,
+        syntax_theme: &str,
+    ) {
+        self.update(text);
+
+        // Heights depend on wrapping and text size
+        let width = ui.available_width();
+        let layout = (width, egui::TextStyle::Body.resolve(ui.style()).size);
+        if layout != self.layout {
+            self.layout = layout;
+            for block in &mut self.blocks {
+                block.height = None;
+            }
+        }
+
+        for (index, block) in self.blocks.iter_mut().enumerate() {
+            if let Some(height) = block.height {
+                let rect = egui::Rect::from_min_size(ui.cursor().min, egui::vec2(width, height));
+                if !ui.is_rect_visible(rect) {
+                    ui.add_space(height);
+                    continue;
+                }
+            }
+
+            let top = ui.cursor().top();
+            let source = &text[block.range.clone()];
+            if block.code {
+                ui.push_id(("code_block", index), |ui| {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
+                        let copy = ui.small_button("📋 Copy").accessible_name("Copy code block");
+                        if copy.clicked()
+                            && let Ok(mut clipboard) = arboard::Clipboard::new()
+                        {
+                            let _ = clipboard.set_text(fence_contents(source));
+                        }
+                    });
+                });
+            }
+            show_markdown(ui, cache, source, syntax_theme);
+            block.height = Some(ui.cursor().top() - top);
+        }
+
+        let tail = &text[self.tail_start()..];
+        if streaming {
+            show_markdown(ui, cache, &sanitize_partial(tail), syntax_theme);
+        } else {
+            show_markdown(ui, cache, tail, syntax_theme);
+        }
+    }
+
+    /// Splits newly arrived text into finished blocks.
+    fn update(&mut self, text: &str) {
+        // Text that didn't grow is a different answer
+        if text.len() < self.len || !text.is_char_boundary(self.tail_start()) {
+            *self = Self::default();
+        }
+        self.len = text.len();
+
+        // The tail always starts outside a fence
+        let mut start = self.tail_start();
+        let mut offset = start;
+        let mut fence = None;
+        for line in text[start..].split_inclusive('\n') {
+            if !line.ends_with('\n') {
+                break;
+            }
+            let line_start = offset;
+            offset += line.len();
+
+            let was_open = fence.is_some();
+            track_fence(&mut fence, line);
+            match (was_open, fence.is_some()) {
+                // A code block starts: finish the text before it
+                (false, true) => {
+                    self.push(text, start..line_start, false);
+                    start = line_start;
+                }
+                // A code block ends
+                (true, false) => {
+                    self.push(text, start..offset, true);
+                    start = offset;
+                }
+                (false, false) if line.trim().is_empty() => {
+                    self.push(text, start..offset, false);
+                    start = offset;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Adds a finished block unless it is blank.
+    fn push(&mut self, text: &str, range: Range<usize>, code: bool) {
+        if !text[range.clone()].trim().is_empty() {
+            self.blocks.push(Block {
+                range,
+                code,
+                height: None,
+            });
+        }
+    }
+
+    /// Byte offset where the unfinished tail begins.
+    fn tail_start(&self) -> usize {
+        self.blocks.last().map_or(0, |block| block.range.end)
+    }
+}
+
+/// Returns the lines between the opening and closing fence of a code block.
+fn fence_contents(block: &str) -> &str {
+    let block = block.trim_start_matches(['\r', '\n']);
+    let body = block.split_once('\n').map_or("", |(_, body)| body);
+    let body = body.trim_end();
+    body.rsplit_once('\n').map_or("", |(code, _)| code)
+}
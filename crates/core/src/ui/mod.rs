@@ -8,9 +8,15 @@
 //! The UI is split into focused submodules:
 //! - [`state`]: State machine types and event definitions
 //! - [`settings`]: User preferences and persistence
+//! - [`settings_window`]: Tabbed settings modal with validation
+//! - [`api_key_form`]: Inline prompt for a missing API key
+//! - [`history_window`]: Browser for earlier answers
+//! - [`accessibility`]: Screen-reader names and roles for widgets
 //! - [`rendering`]: Drawing utilities for overlays and borders
+//! - [`markdown`]: Sanitizing of partially streamed markdown
 //! - [`selection`]: User interaction handling
 //! - [`snipping_tool`]: Main application logic
+//! - [`handle`]: Running the UI on its own thread
 //!
 //! # Usage
 //!
@@ -27,19 +33,28 @@
 //! }
 //! ```
 
+mod accessibility;
+mod api_key_form;
+mod handle;
+mod history_window;
+mod markdown;
 mod rendering;
 mod selection;
 mod settings;
+mod settings_window;
 mod snipping_tool;
 mod state;
 
 // Public API exports
-pub use settings::{Settings, AVAILABLE_MODELS};
+pub use settings::{Settings, AVAILABLE_MODELS, DEFAULT_HOTKEY};
+pub use handle::{run_selection_ui_with, spawn_selection_ui, Selection, SelectionHandle};
 pub use snipping_tool::SnippingTool;
 pub use state::{SelectionResult, UiState};
 
+use crate::capture::{ScreenGeometry, WindowInfo};
 use crate::config::Config;
 use crate::error::Result;
+use crate::image_processing::{ImageProcessor, PixelRegion};
 use image::DynamicImage;
 
 /// Launches the selection UI and returns the user's selection.
@@ -52,8 +67,9 @@ use image::DynamicImage;
 /// * `config` - Application configuration with API keys and settings
 ///
 /// # Returns
-/// - `Ok(Some((rect, size, prompt)))` - User made a valid selection
-/// - `Ok(None)` - User cancelled (pressed Escape)
+/// - `Ok(Some((rect, size, prompt)))` - The last selection the user sent,
+///   with its prompt (`None` for local OCR)
+/// - `Ok(None)` - User closed the overlay without sending a selection
 /// - `Err(e)` - An error occurred launching or running the UI
 ///
 /// # Example
@@ -67,5 +83,73 @@ pub fn run_selection_ui(
     screenshot: DynamicImage,
     config: Config,
 ) -> Result<Option<(eframe::egui::Rect, eframe::egui::Vec2, Option<String>)>> {
-    snipping_tool::run(screenshot, config)
+    snipping_tool::run(screenshot, None, Vec::new(), config, false)
+}
+
+/// Launches the selection UI with window geometry for snapping.
+///
+/// Like [`run_selection_ui`], but pressing `Space` snaps the selection to
+/// the window under the cursor (see [`crate::capture::ScreenCapturer::windows_on_screen`]).
+///
+/// # Arguments
+/// * `screenshot` - The captured screen as a [`DynamicImage`]
+/// * `windows` - Windows on the captured screen, frontmost first
+/// * `config` - Application configuration with API keys and settings
+pub fn run_selection_ui_with_windows(
+    screenshot: DynamicImage,
+    windows: Vec<WindowInfo>,
+    config: Config,
+) -> Result<Option<(eframe::egui::Rect, eframe::egui::Vec2, Option<String>)>> {
+    snipping_tool::run(screenshot, None, windows, config, false)
+}
+
+/// Launches the selection UI over a known screen.
+///
+/// Like [`run_selection_ui_with_windows`], but the overlay's placement is
+/// checked against `screen`; if the window manager doesn't put the
+/// fullscreen window exactly over it, a borderless window of the screen's
+/// size is used instead (see [`crate::config::OverlayMode`]).
+///
+/// # Arguments
+/// * `screenshot` - The captured screen as a [`DynamicImage`]
+/// * `screen` - Desktop geometry of the captured screen, if known
+/// * `windows` - Windows on the captured screen, frontmost first
+/// * `config` - Application configuration with API keys and settings
+pub fn run_selection_ui_on_screen(
+    screenshot: DynamicImage,
+    screen: Option<ScreenGeometry>,
+    windows: Vec<WindowInfo>,
+    config: Config,
+) -> Result<Option<(eframe::egui::Rect, eframe::egui::Vec2, Option<String>)>> {
+    snipping_tool::run(screenshot, screen, windows, config, false)
+}
+
+/// Launches the overlay to pick a region without asking anything about it.
+///
+/// The overlay closes as soon as a selection is made, like `slurp`. Window
+/// snapping and keyboard selection work as in [`run_selection_ui_on_screen`].
+///
+/// # Arguments
+/// * `screenshot` - The captured screen as a [`DynamicImage`]
+/// * `screen` - Desktop geometry of the captured screen, if known
+/// * `windows` - Windows on the captured screen, frontmost first
+/// * `config` - Application configuration (for the overlay mode and theme)
+///
+/// # Returns
+/// - `Ok(Some(region))` - The selection in screenshot pixels
+/// - `Ok(None)` - User closed the overlay without selecting
+/// - `Err(e)` - An error occurred launching or running the UI
+pub fn pick_region(
+    screenshot: DynamicImage,
+    screen: Option<ScreenGeometry>,
+    windows: Vec<WindowInfo>,
+    config: Config,
+) -> Result<Option<PixelRegion>> {
+    let (width, height) = (screenshot.width(), screenshot.height());
+    match snipping_tool::pick(screenshot, screen, windows, config)? {
+        Some((selection, ui_size)) => {
+            ImageProcessor::map_selection(selection, ui_size, width, height).map(Some)
+        }
+        None => Ok(None),
+    }
 }
@@ -3,6 +3,7 @@
 //! This module contains reusable rendering functions for the snipping tool UI,
 //! including the selection overlay, dark cutout effect, and popup windows.
 
+use crate::image_processing::{Redaction, RedactionStyle, PIXELATE_BLOCK};
 use eframe::egui;
 
 /// Draws the dark overlay with a transparent "cutout" for the selection area.
@@ -14,15 +15,13 @@ use eframe::egui;
 /// * `painter` - The egui painter to draw with
 /// * `screen_rect` - The full screen rectangle
 /// * `selection_rect` - The selected area to keep clear
-/// * `alpha` - Darkness level (0-255, higher = darker)
+/// * `color` - Dim color (its alpha controls how dark the rest of the screen gets)
 pub fn draw_selection_overlay(
     painter: &egui::Painter,
     screen_rect: egui::Rect,
     selection_rect: egui::Rect,
-    alpha: u8,
+    color: egui::Color32,
 ) {
-    let color = egui::Color32::from_black_alpha(alpha);
-
     // Top region (above selection)
     painter.rect_filled(
         egui::Rect::from_min_max(
@@ -124,3 +123,125 @@ pub fn calculate_popup_position(
 
     (window_x, window_y, pivot)
 }
+
+/// Side length of the magnifier loupe, in UI pixels.
+pub const MAGNIFIER_SIZE: f32 = 128.0;
+
+/// Zoom factor of the magnifier loupe.
+pub const MAGNIFIER_ZOOM: f32 = 8.0;
+
+/// Draws a magnifier loupe next to the cursor with a caption below it.
+///
+/// Shows a zoomed view of the screenshot around `cursor` with a crosshair on
+/// the pixel under it, so small elements can be selected precisely. The
+/// loupe flips to the other side of the cursor near screen edges.
+///
+/// # Arguments
+/// * `painter` - The egui painter to draw with
+/// * `texture` - The screenshot texture covering `screen_rect`
+/// * `screen_rect` - The full screen rectangle
+/// * `cursor` - The current pointer position
+/// * `caption` - Text shown under the loupe (e.g., selection dimensions)
+pub fn draw_magnifier(
+    painter: &egui::Painter,
+    texture: egui::TextureId,
+    screen_rect: egui::Rect,
+    cursor: egui::Pos2,
+    caption: &str,
+) {
+    const OFFSET: f32 = 24.0;
+    const CAPTION_HEIGHT: f32 = 20.0;
+
+    // Place the loupe below-right of the cursor, flipping near edges
+    let mut min = cursor + egui::vec2(OFFSET, OFFSET);
+    if min.x + MAGNIFIER_SIZE > screen_rect.max.x {
+        min.x = cursor.x - OFFSET - MAGNIFIER_SIZE;
+    }
+    if min.y + MAGNIFIER_SIZE + CAPTION_HEIGHT > screen_rect.max.y {
+        min.y = cursor.y - OFFSET - MAGNIFIER_SIZE - CAPTION_HEIGHT;
+    }
+    let loupe = egui::Rect::from_min_size(min, egui::vec2(MAGNIFIER_SIZE, MAGNIFIER_SIZE));
+
+    // Region of the screenshot to magnify, in texture UV coordinates
+    let source_size = egui::Vec2::splat(MAGNIFIER_SIZE / MAGNIFIER_ZOOM);
+    let source = egui::Rect::from_center_size(cursor, source_size);
+    let to_uv = |pos: egui::Pos2| {
+        egui::pos2(
+            (pos.x - screen_rect.min.x) / screen_rect.width(),
+            (pos.y - screen_rect.min.y) / screen_rect.height(),
+        )
+    };
+    let uv = egui::Rect::from_min_max(to_uv(source.min), to_uv(source.max));
+
+    painter.rect_filled(loupe, 0.0, egui::Color32::BLACK);
+    painter.image(texture, loupe, uv, egui::Color32::WHITE);
+
+    // Crosshair on the pixel under the cursor
+    let crosshair = egui::Stroke::new(1.0, egui::Color32::from_rgba_unmultiplied(255, 0, 0, 160));
+    let center = loupe.center();
+    painter.line_segment(
+        [egui::pos2(loupe.min.x, center.y), egui::pos2(loupe.max.x, center.y)],
+        crosshair,
+    );
+    painter.line_segment(
+        [egui::pos2(center.x, loupe.min.y), egui::pos2(center.x, loupe.max.y)],
+        crosshair,
+    );
+    painter.rect_stroke(
+        loupe,
+        0.0,
+        egui::Stroke::new(1.0, egui::Color32::WHITE),
+        egui::StrokeKind::Outside,
+    );
+
+    // Caption bar below the loupe
+    let caption_rect = egui::Rect::from_min_size(
+        egui::pos2(loupe.min.x, loupe.max.y),
+        egui::vec2(MAGNIFIER_SIZE, CAPTION_HEIGHT),
+    );
+    painter.rect_filled(caption_rect, 0.0, egui::Color32::from_black_alpha(200));
+    painter.text(
+        caption_rect.center(),
+        egui::Align2::CENTER_CENTER,
+        caption,
+        egui::FontId::monospace(12.0),
+        egui::Color32::WHITE,
+    );
+}
+
+/// Draws redaction previews over the selection.
+///
+/// Filled regions are drawn solid black as they will be sent; pixelated
+/// regions are shown as a gray grid, since the real pixelation is only
+/// applied to the uploaded image.
+///
+/// # Arguments
+/// * `painter` - The egui painter to draw with
+/// * `selection_rect` - The selected area (redactions are clipped to it)
+/// * `redactions` - Regions to draw
+pub fn draw_redactions(painter: &egui::Painter, selection_rect: egui::Rect, redactions: &[Redaction]) {
+    let painter = painter.with_clip_rect(selection_rect);
+
+    for redaction in redactions {
+        let rect = redaction.rect;
+        match redaction.style {
+            RedactionStyle::Fill => {
+                painter.rect_filled(rect, 0.0, egui::Color32::BLACK);
+            }
+            RedactionStyle::Pixelate => {
+                painter.rect_filled(rect, 0.0, egui::Color32::from_gray(110));
+                let stroke = egui::Stroke::new(1.0, egui::Color32::from_gray(80));
+                let mut x = rect.min.x + PIXELATE_BLOCK;
+                while x < rect.max.x {
+                    painter.vline(x, rect.y_range(), stroke);
+                    x += PIXELATE_BLOCK;
+                }
+                let mut y = rect.min.y + PIXELATE_BLOCK;
+                while y < rect.max.y {
+                    painter.hline(rect.x_range(), y, stroke);
+                    y += PIXELATE_BLOCK;
+                }
+            }
+        }
+    }
+}
@@ -99,17 +99,187 @@ pub fn process_drag_event(
         return SelectionEvent::Dragging;
     }
 
-    if response.drag_stopped() && !is_finalized {
-        if let (Some(s), Some(e)) = (*start, *current) {
-            if is_valid_selection(s, e) {
-                return SelectionEvent::Completed;
-            } else {
-                *start = None;
-                *current = None;
-                return SelectionEvent::Cancelled;
-            }
+    if response.drag_stopped()
+        && !is_finalized
+        && let (Some(s), Some(e)) = (*start, *current)
+    {
+        if is_valid_selection(s, e) {
+            return SelectionEvent::Completed;
+        } else {
+            *start = None;
+            *current = None;
+            return SelectionEvent::Cancelled;
         }
     }
 
     SelectionEvent::None
 }
+
+/// Size (in pixels) of the grab area around selection edges and corners.
+pub const HANDLE_SIZE: f32 = 8.0;
+
+/// Part of a finalized selection that can be dragged to adjust it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionHandle {
+    /// Drag inside the selection to move it.
+    Move,
+    /// Left edge.
+    Left,
+    /// Right edge.
+    Right,
+    /// Top edge.
+    Top,
+    /// Bottom edge.
+    Bottom,
+    /// Top-left corner.
+    TopLeft,
+    /// Top-right corner.
+    TopRight,
+    /// Bottom-left corner.
+    BottomLeft,
+    /// Bottom-right corner.
+    BottomRight,
+}
+
+impl SelectionHandle {
+    /// Returns the mouse cursor shown when hovering this handle.
+    pub fn cursor(self) -> egui::CursorIcon {
+        match self {
+            Self::Move => egui::CursorIcon::Move,
+            Self::Left | Self::Right => egui::CursorIcon::ResizeHorizontal,
+            Self::Top | Self::Bottom => egui::CursorIcon::ResizeVertical,
+            Self::TopLeft | Self::BottomRight => egui::CursorIcon::ResizeNwSe,
+            Self::TopRight | Self::BottomLeft => egui::CursorIcon::ResizeNeSw,
+        }
+    }
+}
+
+/// Finds the handle of `rect` under `pos`, if any.
+///
+/// Corners take precedence over edges, and edges over the interior.
+pub fn handle_at(rect: egui::Rect, pos: egui::Pos2) -> Option<SelectionHandle> {
+    if !rect.expand(HANDLE_SIZE).contains(pos) {
+        return None;
+    }
+
+    let near = |a: f32, b: f32| (a - b).abs() <= HANDLE_SIZE;
+    let (left, right) = (near(pos.x, rect.min.x), near(pos.x, rect.max.x));
+    let (top, bottom) = (near(pos.y, rect.min.y), near(pos.y, rect.max.y));
+
+    Some(match (left, right, top, bottom) {
+        (true, _, true, _) => SelectionHandle::TopLeft,
+        (_, true, true, _) => SelectionHandle::TopRight,
+        (true, _, _, true) => SelectionHandle::BottomLeft,
+        (_, true, _, true) => SelectionHandle::BottomRight,
+        (true, ..) => SelectionHandle::Left,
+        (_, true, ..) => SelectionHandle::Right,
+        (_, _, true, _) => SelectionHandle::Top,
+        (_, _, _, true) => SelectionHandle::Bottom,
+        _ if rect.contains(pos) => SelectionHandle::Move,
+        _ => return None,
+    })
+}
+
+/// Applies a pointer drag of `delta` on `handle` to `rect`.
+///
+/// Moves are clamped so the selection stays within `bounds`; resizes are
+/// normalized so dragging an edge past the opposite one flips the rectangle.
+pub fn apply_handle_drag(
+    rect: egui::Rect,
+    handle: SelectionHandle,
+    delta: egui::Vec2,
+    bounds: egui::Rect,
+) -> egui::Rect {
+    let (mut min, mut max) = (rect.min, rect.max);
+
+    match handle {
+        SelectionHandle::Move => {
+            // Keep the whole rectangle on screen (min/max rather than clamp,
+            // which panics if the rectangle is larger than the bounds)
+            let dx = delta.x.min(bounds.max.x - max.x).max(bounds.min.x - min.x);
+            let dy = delta.y.min(bounds.max.y - max.y).max(bounds.min.y - min.y);
+            return rect.translate(egui::vec2(dx, dy));
+        }
+        SelectionHandle::Left => min.x += delta.x,
+        SelectionHandle::Right => max.x += delta.x,
+        SelectionHandle::Top => min.y += delta.y,
+        SelectionHandle::Bottom => max.y += delta.y,
+        SelectionHandle::TopLeft => min += delta,
+        SelectionHandle::TopRight => {
+            max.x += delta.x;
+            min.y += delta.y;
+        }
+        SelectionHandle::BottomLeft => {
+            min.x += delta.x;
+            max.y += delta.y;
+        }
+        SelectionHandle::BottomRight => max += delta,
+    }
+
+    egui::Rect::from_two_pos(min, max).intersect(bounds)
+}
+
+/// Aspect ratio a new selection is constrained to while dragging.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AspectRatio {
+    /// Any shape (hold Shift for a square).
+    #[default]
+    Free,
+    /// 1:1.
+    Square,
+    /// 16:9.
+    Widescreen,
+    /// 4:3.
+    Standard,
+}
+
+impl AspectRatio {
+    /// All ratios, in the order shown in the toolbar.
+    pub const ALL: [AspectRatio; 4] = [
+        AspectRatio::Free,
+        AspectRatio::Square,
+        AspectRatio::Widescreen,
+        AspectRatio::Standard,
+    ];
+
+    /// Width divided by height, or `None` when unconstrained.
+    pub fn ratio(self) -> Option<f32> {
+        match self {
+            AspectRatio::Free => None,
+            AspectRatio::Square => Some(1.0),
+            AspectRatio::Widescreen => Some(16.0 / 9.0),
+            AspectRatio::Standard => Some(4.0 / 3.0),
+        }
+    }
+
+    /// Short label for the toolbar.
+    pub fn label(self) -> &'static str {
+        match self {
+            AspectRatio::Free => "Free",
+            AspectRatio::Square => "1:1",
+            AspectRatio::Widescreen => "16:9",
+            AspectRatio::Standard => "4:3",
+        }
+    }
+}
+
+/// Moves `end` so the rectangle from `start` has the given aspect ratio.
+///
+/// The dragged direction is kept and the larger of the two spans (after
+/// scaling by the ratio) decides the size, so the selection never shrinks
+/// away from the cursor.
+///
+/// # Arguments
+/// * `start` - Fixed corner of the selection
+/// * `end` - Corner being dragged
+/// * `ratio` - Width divided by height
+pub fn constrain_aspect(start: egui::Pos2, end: egui::Pos2, ratio: f32) -> egui::Pos2 {
+    let delta = end - start;
+    let height = delta.y.abs().max(delta.x.abs() / ratio);
+    let width = height * ratio;
+
+    egui::pos2(
+        start.x + width.copysign(delta.x),
+        start.y + height.copysign(delta.y),
+    )
+}
@@ -1,11 +1,25 @@
 //! User settings persistence and UI configuration.
 //!
 //! This module handles loading and saving user preferences,
-//! including model selection, API keys, and feature toggles.
+//! including model selection, API keys, feature toggles, and appearance.
+//!
+//! The model, API key and prompt options saved here are one layer of the
+//! request configuration: the environment, a profile or a command-line flag
+//! takes precedence over them (see [`Config::with_settings`]).
+//!
+//! [`Config::with_settings`]: crate::config::Config::with_settings
 
-use crate::error::Result;
-use directories::ProjectDirs;
+use crate::error::{AppError, Result};
+use crate::hotkey::Hotkey;
+use crate::image_processing::ImageEncoding;
+use crate::paths;
+use crate::presets::SystemPromptPreset;
+use crate::profiles::Profile;
+use crate::secrets::{self, GEMINI_API_KEY};
+use crate::stamp::Stamp;
+use eframe::egui;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fs;
 use std::path::PathBuf;
 
@@ -16,6 +30,68 @@ pub const AVAILABLE_MODELS: &[&str] = &[
     "gemini-flash-lite-latest",
 ];
 
+/// Syntax highlighting themes bundled with the markdown viewer.
+pub const SYNTAX_THEMES: &[&str] = &[
+    "base16-ocean.dark",
+    "base16-eighties.dark",
+    "base16-mocha.dark",
+    "Solarized (dark)",
+    "base16-ocean.light",
+    "InspiredGitHub",
+    "Solarized (light)",
+];
+
+/// Default size of answer text, in points.
+pub const DEFAULT_RESPONSE_FONT_SIZE: f32 = 14.0;
+
+/// Smallest and largest answer text size, in points.
+pub const RESPONSE_FONT_SIZE_RANGE: std::ops::RangeInclusive<f32> = 8.0..=40.0;
+
+/// Default dim color drawn over everything outside the selection.
+pub const DEFAULT_OVERLAY_COLOR: [u8; 4] = [0, 0, 0, 150];
+
+/// Default accent color used for the selection border and highlights.
+pub const DEFAULT_ACCENT_COLOR: [u8; 3] = [255, 255, 255];
+
+/// Default global hotkey of the daemon mode.
+pub const DEFAULT_HOTKEY: &str = "ctrl+alt+x";
+
+/// Color scheme of the overlay and popups.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Dark widgets (the original look).
+    #[default]
+    Dark,
+    /// Light widgets.
+    Light,
+    /// Follow the operating system's preference.
+    System,
+}
+
+impl Theme {
+    /// All themes, in the order shown in the settings panel.
+    pub const ALL: [Theme; 3] = [Theme::Dark, Theme::Light, Theme::System];
+
+    /// Human-readable name for the settings panel.
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Dark => "Dark",
+            Theme::Light => "Light",
+            Theme::System => "System",
+        }
+    }
+
+    /// Converts to egui's theme preference.
+    pub fn preference(self) -> egui::ThemePreference {
+        match self {
+            Theme::Dark => egui::ThemePreference::Dark,
+            Theme::Light => egui::ThemePreference::Light,
+            Theme::System => egui::ThemePreference::System,
+        }
+    }
+}
+
 /// User-configurable settings persisted between sessions.
 ///
 /// Settings are stored as JSON in the user's config directory
@@ -26,13 +102,105 @@ pub struct Settings {
     pub model: String,
     /// System prompt prepended to all requests.
     pub system_prompt: String,
+    /// Built-in preset combined with `system_prompt`.
+    #[serde(default)]
+    pub preset: Option<SystemPromptPreset>,
     /// Enable "thinking" mode (Gemini 2.0+ models).
     pub thinking_enabled: bool,
     /// Enable Google Search grounding for responses.
     pub google_search: bool,
     /// API key override (takes precedence over environment).
+    ///
+    /// Kept in the OS keyring rather than the file unless `use_keyring`
+    /// is off.
     #[serde(default)]
     pub api_key: String,
+    /// Store the API key in the OS keyring instead of `settings.json`.
+    #[serde(default = "default_true")]
+    pub use_keyring: bool,
+    /// Allow the model to call local tools (open URLs, clipboard, shell).
+    #[serde(default)]
+    pub tools_enabled: bool,
+    /// Extract text locally and send it instead of the image.
+    #[serde(default)]
+    pub ocr_text_only: bool,
+    /// Mask email addresses, API keys and card numbers found by local OCR
+    /// in each selection.
+    #[serde(default)]
+    pub mask_sensitive: bool,
+    /// Stamp saved, copied and exported screenshots with the time.
+    #[serde(default)]
+    pub stamp_timestamp: bool,
+    /// Stamp saved, copied and exported screenshots with the host name.
+    #[serde(default)]
+    pub stamp_hostname: bool,
+    /// Note stamped on saved, copied and exported screenshots; empty for
+    /// none.
+    #[serde(default)]
+    pub stamp_text: String,
+    /// Language answers are written in; empty lets the model decide.
+    #[serde(default)]
+    pub response_language: String,
+    /// Color scheme of the overlay and popups.
+    #[serde(default)]
+    pub theme: Theme,
+    /// Unmultiplied RGBA color dimming the screen outside the selection.
+    #[serde(default = "default_overlay_color")]
+    pub overlay_color: [u8; 4],
+    /// RGB accent color for the selection border and highlights.
+    #[serde(default = "default_accent_color")]
+    pub accent_color: [u8; 3],
+    /// Syntax highlighting theme for code blocks; empty follows the theme.
+    #[serde(default)]
+    pub syntax_theme: String,
+    /// Size of answer text, in points.
+    #[serde(default = "default_response_font_size")]
+    pub response_font_size: f32,
+    /// Global hotkey of the daemon mode.
+    #[serde(default = "default_hotkey")]
+    pub hotkey: String,
+    /// Prompt sent when the prompt box is left empty; empty picks one for
+    /// the detected content ("Explain this code.", ...).
+    #[serde(default)]
+    pub default_prompt: String,
+    /// Whether Enter in an empty prompt box sends the default prompt.
+    #[serde(default = "default_true")]
+    pub empty_enter_submits: bool,
+    /// Whether the prompt box starts out with the last prompt typed.
+    #[serde(default)]
+    pub prefill_last_prompt: bool,
+    /// The last prompt typed, for `prefill_last_prompt`.
+    #[serde(default)]
+    pub last_prompt: String,
+    /// How images are encoded for upload (`jpeg`, `png`, `webp`); empty
+    /// leaves it to `config.toml`.
+    #[serde(default)]
+    pub image_encoding: String,
+    /// Profile selected for this session, by name (see [`crate::profiles`]).
+    ///
+    /// Not saved: the profiles file's `default` picks the profile at startup.
+    #[serde(skip)]
+    pub profile: Option<(String, Profile)>,
+}
+
+fn default_overlay_color() -> [u8; 4] {
+    DEFAULT_OVERLAY_COLOR
+}
+
+fn default_accent_color() -> [u8; 3] {
+    DEFAULT_ACCENT_COLOR
+}
+
+fn default_response_font_size() -> f32 {
+    DEFAULT_RESPONSE_FONT_SIZE
+}
+
+fn default_hotkey() -> String {
+    DEFAULT_HOTKEY.to_string()
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Settings {
@@ -40,24 +208,66 @@ impl Settings {
     ///
     /// Creates the config directory if it doesn't exist.
     fn config_path() -> Option<PathBuf> {
-        ProjectDirs::from("", "antigravity", "ai-shot").map(|dirs| {
-            let config_dir = dirs.config_dir();
-            if !config_dir.exists() {
+        paths::settings_file().inspect(|path| {
+            if let Some(config_dir) = path.parent().filter(|dir| !dir.exists()) {
                 let _ = fs::create_dir_all(config_dir);
             }
-            config_dir.join("settings.json")
         })
     }
 
     /// Loads settings from disk, falling back to defaults if not found.
     ///
+    /// The API key is read from the keyring; a key still in the file is
+    /// moved there.
+    ///
     /// # Arguments
     /// * `default_model` - The model to use if no settings file exists.
     pub fn load(default_model: &str) -> Self {
-        Self::config_path()
+        let mut settings: Self = Self::config_path()
             .and_then(|path| fs::read_to_string(&path).ok())
             .and_then(|content| serde_json::from_str(&content).ok())
-            .unwrap_or_else(|| Self::with_defaults(default_model))
+            .unwrap_or_else(|| Self::with_defaults(default_model));
+        if settings.use_keyring {
+            settings.load_api_key();
+        }
+        settings
+    }
+
+    /// Reads the API key from the keyring, first moving a key found in the
+    /// settings file there.
+    fn load_api_key(&mut self) {
+        if !self.api_key.is_empty() {
+            // Saving stores the key in the keyring and drops it from the file
+            if let Err(e) = self.save() {
+                eprintln!("Warning: Failed to move the API key to the keyring: {}", e);
+            }
+            return;
+        }
+        match secrets::load(GEMINI_API_KEY) {
+            Ok(key) => self.api_key = key.unwrap_or_default(),
+            Err(e) => eprintln!("Warning: Failed to read the API key from the keyring: {}", e),
+        }
+    }
+
+    /// Brings the keyring up to date with the API key, removing it there
+    /// once cleared.
+    ///
+    /// Returns `false` if the keyring is unavailable, in which case the key
+    /// is kept in the settings file.
+    fn store_api_key(&self) -> bool {
+        let result = match secrets::load(GEMINI_API_KEY) {
+            Ok(stored) if stored.as_deref().unwrap_or_default() == self.api_key => Ok(()),
+            Ok(_) if self.api_key.is_empty() => secrets::delete(GEMINI_API_KEY),
+            Ok(_) => secrets::store(GEMINI_API_KEY, &self.api_key),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("Warning: Keeping the API key in settings.json: {}", e);
+                false
+            }
+        }
     }
 
     /// Creates default settings with the specified model.
@@ -65,30 +275,170 @@ impl Settings {
         Self {
             model: model.to_string(),
             system_prompt: String::new(),
+            preset: None,
             thinking_enabled: false,
             google_search: false,
             api_key: String::new(),
+            use_keyring: true,
+            tools_enabled: false,
+            ocr_text_only: false,
+            mask_sensitive: false,
+            stamp_timestamp: false,
+            stamp_hostname: false,
+            stamp_text: String::new(),
+            response_language: String::new(),
+            theme: Theme::default(),
+            overlay_color: DEFAULT_OVERLAY_COLOR,
+            accent_color: DEFAULT_ACCENT_COLOR,
+            syntax_theme: String::new(),
+            response_font_size: DEFAULT_RESPONSE_FONT_SIZE,
+            hotkey: default_hotkey(),
+            default_prompt: String::new(),
+            empty_enter_submits: true,
+            prefill_last_prompt: false,
+            last_prompt: String::new(),
+            image_encoding: String::new(),
+            profile: None,
+        }
+    }
+
+    /// Returns the stamp for saved screenshots these settings describe.
+    pub fn stamp(&self) -> Stamp {
+        Stamp {
+            timestamp: self.stamp_timestamp,
+            hostname: self.stamp_hostname,
+            text: self.stamp_text.clone(),
+        }
+    }
+
+    /// Returns the path of the settings file, if a config directory exists.
+    pub fn path() -> Option<PathBuf> {
+        Self::config_path()
+    }
+
+    /// Returns every setting as a key and its value in JSON notation.
+    ///
+    /// Keys are the field names used in `settings.json`.
+    pub fn entries(&self) -> Vec<(String, String)> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(fields)) => fields
+                .into_iter()
+                .map(|(key, value)| (key, display_value(&value)))
+                .collect(),
+            _ => Vec::new(),
         }
     }
 
+    /// Returns the value of one setting in JSON notation.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let value = serde_json::to_value(self).ok()?;
+        value.get(key).map(display_value)
+    }
+
+    /// Changes one setting, parsing `value` for the setting's type.
+    ///
+    /// Text settings take `value` verbatim; others are parsed as JSON
+    /// (`true`, `14`, `[0, 0, 0, 150]`), and an empty value clears optional
+    /// settings.
+    ///
+    /// # Errors
+    /// Returns an error if `key` is unknown or `value` does not fit it.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let mut fields = serde_json::to_value(&*self)?;
+        let field = fields
+            .get_mut(key)
+            .ok_or_else(|| AppError::config(format!("Unknown setting '{}'", key)))?;
+        *field = match field {
+            Value::String(_) => Value::String(value.to_string()),
+            Value::Null if value.is_empty() => Value::Null,
+            _ => serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string())),
+        };
+        let updated: Self = serde_json::from_value(fields)
+            .map_err(|e| AppError::config(format!("Invalid value for '{}': {}", key, e)))?;
+        if key == "hotkey" {
+            updated.hotkey.parse::<Hotkey>()?;
+        }
+        if key == "image_encoding" && !updated.image_encoding.trim().is_empty() {
+            updated.image_encoding.parse::<ImageEncoding>()?;
+        }
+        *self = updated;
+        Ok(())
+    }
+
     /// Persists settings to disk.
     ///
+    /// With `use_keyring`, the API key goes to the keyring instead of the
+    /// file, unless the keyring is unavailable.
+    ///
     /// # Errors
     /// Returns an error if serialization or file writing fails.
     pub fn save(&self) -> Result<()> {
         if let Some(path) = Self::config_path() {
-            let json = serde_json::to_string_pretty(self)?;
+            let json = if self.use_keyring && self.store_api_key() {
+                let without_key = Self {
+                    api_key: String::new(),
+                    ..self.clone()
+                };
+                serde_json::to_string_pretty(&without_key)?
+            } else {
+                serde_json::to_string_pretty(self)?
+            };
             fs::write(path, json)?;
         }
         Ok(())
     }
 
+    /// Returns the overlay dim color.
+    pub fn overlay_color(&self) -> egui::Color32 {
+        let [r, g, b, a] = self.overlay_color;
+        egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+    }
+
+    /// Returns the accent color.
+    pub fn accent_color(&self) -> egui::Color32 {
+        let [r, g, b] = self.accent_color;
+        egui::Color32::from_rgb(r, g, b)
+    }
+
+    /// Selects a profile, taking over its model and prompt settings.
+    pub fn apply_profile(&mut self, name: &str, profile: &Profile) {
+        if let Some(model) = &profile.model {
+            self.model = model.clone();
+        }
+        if profile.preset.is_some() {
+            self.preset = profile.preset;
+        }
+        if let Some(system_prompt) = &profile.system_prompt {
+            self.system_prompt = system_prompt.clone();
+        }
+        if let Some(language) = &profile.response_language {
+            self.response_language = language.clone();
+        }
+        self.profile = Some((name.to_string(), profile.clone()));
+    }
+
+    /// Returns whether the selected profile brings its own credentials
+    /// (an API key, Vertex AI, or a gateway).
+    pub fn profile_has_credentials(&self) -> bool {
+        self.profile.as_ref().is_some_and(|(_, profile)| {
+            profile.api_key.is_some() || profile.vertex.is_some() || profile.openai.is_some()
+        })
+    }
+
     /// Returns whether the API key is set (either from settings or will use env).
     pub fn has_api_key(&self) -> bool {
         !self.api_key.is_empty()
     }
 }
 
+/// Formats a setting value: text as-is, everything else as JSON.
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
 impl Default for Settings {
     fn default() -> Self {
         Self::with_defaults("gemini-flash-latest")
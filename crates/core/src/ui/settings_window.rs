@@ -0,0 +1,688 @@
+//! Tabbed settings window.
+//!
+//! Shown as a modal over the overlay. Settings are edited in place and
+//! checked as the user types; the API key can be verified against the API
+//! with the "Test key" button before any screenshot is sent.
+
+use super::settings::{
+    Settings, Theme, AVAILABLE_MODELS, DEFAULT_ACCENT_COLOR, DEFAULT_HOTKEY,
+    DEFAULT_OVERLAY_COLOR, RESPONSE_FONT_SIZE_RANGE, SYNTAX_THEMES,
+};
+use crate::config::{Config, ConfigSource};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::gemini::GeminiClient;
+use crate::hotkey::Hotkey;
+use crate::image_processing::ImageEncoding;
+use crate::presets::{SystemPromptPreset, RESPONSE_LANGUAGES};
+use crate::profiles::Profiles;
+use crate::usage::{self, Budget, ModelUsage, UsageLog};
+use eframe::egui;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// Keyboard shortcuts of the overlay, listed on the Hotkeys tab.
+const OVERLAY_SHORTCUTS: &[(&str, &str)] = &[
+    ("F", "Select the full screen"),
+    ("Space", "Snap to the window under the cursor"),
+    ("Arrows", "Move the crosshair, or nudge the selection"),
+    ("Shift+Arrows", "Grow a keyboard selection"),
+    ("Enter", "Finalize a keyboard selection, or send"),
+    ("Tab / Shift+Tab", "Move between the prompt and buttons"),
+    ("Ctrl+drag", "Add another region"),
+    ("Ctrl+C / Ctrl+S", "Copy or save the selection image"),
+    ("Ctrl+V / drop a file", "Ask about another image"),
+    ("H", "Browse earlier answers"),
+    ("D", "Hide or show the dimming outside the selection"),
+    ("Ctrl+scroll / Ctrl+± / Ctrl+0", "Zoom the answer text"),
+    ("Esc", "Go back, or close the overlay"),
+];
+
+/// Tabs of the settings window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum SettingsTab {
+    #[default]
+    Model,
+    ApiKeys,
+    Behavior,
+    Hotkeys,
+    Privacy,
+    Usage,
+}
+
+impl SettingsTab {
+    const ALL: [SettingsTab; 6] = [
+        SettingsTab::Model,
+        SettingsTab::ApiKeys,
+        SettingsTab::Behavior,
+        SettingsTab::Hotkeys,
+        SettingsTab::Privacy,
+        SettingsTab::Usage,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            SettingsTab::Model => "Model",
+            SettingsTab::ApiKeys => "API keys",
+            SettingsTab::Behavior => "Behavior",
+            SettingsTab::Hotkeys => "Hotkeys",
+            SettingsTab::Privacy => "Privacy",
+            SettingsTab::Usage => "Usage",
+        }
+    }
+}
+
+/// State of the "Test key" action.
+enum KeyCheck {
+    /// Not tested since the key last changed.
+    Untested,
+    /// Waiting for the API to answer.
+    Running(Receiver<Result<(), String>>),
+    /// The API accepted the key for the selected model.
+    Passed,
+    /// The API rejected the key, or could not be reached.
+    Failed(String),
+}
+
+/// State of the configuration check.
+enum ConfigCheck {
+    /// Not run since the window opened.
+    NotRun,
+    /// Validating in the background.
+    Running(Receiver<Vec<Diagnostic>>),
+    /// Problems found by the last check.
+    Done(Vec<Diagnostic>),
+}
+
+/// Modal window for editing [`Settings`].
+pub(crate) struct SettingsWindow {
+    tab: SettingsTab,
+    key_check: KeyCheck,
+    /// Key and model the last check was run with
+    checked: (String, String),
+    /// Profiles offered in the Model tab
+    profiles: Profiles,
+    /// Configuration the overlay was started with, layered over the settings
+    config: Config,
+    config_check: ConfigCheck,
+    /// This month's requests per model, shown in the Usage tab
+    usage: Vec<ModelUsage>,
+}
+
+impl SettingsWindow {
+    /// Creates the window, opened on the Model tab.
+    pub fn new(config: &Config) -> Self {
+        Self {
+            tab: SettingsTab::default(),
+            key_check: KeyCheck::Untested,
+            checked: (String::new(), String::new()),
+            profiles: Profiles::load().unwrap_or_else(|e| {
+                eprintln!("Warning: Failed to load profiles: {}", e);
+                Profiles::default()
+            }),
+            config: config.clone(),
+            config_check: ConfigCheck::NotRun,
+            usage: load_usage(),
+        }
+    }
+
+    /// Shows the window for one frame.
+    ///
+    /// Returns `false` once the user closes it; the caller should then
+    /// persist the settings.
+    pub fn show(&mut self, ctx: &egui::Context, settings: &mut Settings) -> bool {
+        self.poll_key_check(ctx, settings);
+        self.poll_config_check(ctx, settings);
+
+        let mut open = true;
+        let modal = egui::Modal::new(egui::Id::new("settings_modal")).show(ctx, |ui| {
+            ui.set_width(460.0);
+            ui.heading("Settings");
+
+            ui.horizontal(|ui| {
+                for tab in SettingsTab::ALL {
+                    ui.selectable_value(&mut self.tab, tab, tab.label());
+                }
+            });
+            ui.separator();
+
+            match self.tab {
+                SettingsTab::Model => render_model_tab(ui, settings, &self.profiles),
+                SettingsTab::ApiKeys => self.render_api_keys_tab(ui, settings),
+                SettingsTab::Behavior => render_behavior_tab(ui, settings),
+                SettingsTab::Hotkeys => render_hotkeys_tab(ui, settings),
+                SettingsTab::Privacy => render_privacy_tab(ui, settings),
+                SettingsTab::Usage => render_usage_tab(ui, &self.usage, &self.config.budget),
+            }
+
+            ui.separator();
+            let problems = validate(settings);
+            for problem in &problems {
+                ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", problem));
+            }
+            self.render_config_check(ui, settings);
+            ui.horizontal(|ui| {
+                // An unusable model name would break every request
+                let model_valid = !settings.model.trim().is_empty();
+                if ui.add_enabled(model_valid, egui::Button::new("Done")).clicked() {
+                    open = false;
+                }
+            });
+        });
+
+        if modal.should_close() && !settings.model.trim().is_empty() {
+            open = false;
+        }
+        open
+    }
+
+    /// Renders the API keys tab with the "Test key" action.
+    fn render_api_keys_tab(&mut self, ui: &mut egui::Ui, settings: &mut Settings) {
+        ui.label("Gemini API key:");
+        ui.add(
+            egui::TextEdit::singleline(&mut settings.api_key)
+                .password(true)
+                .desired_width(f32::INFINITY)
+                .hint_text("Paste Gemini API Key (leave empty to use GEMINI_API_KEY)"),
+        );
+        ui.checkbox(&mut settings.use_keyring, "Store the key in the system keyring")
+            .on_hover_text("Off: the key is saved in plain text in settings.json");
+
+        ui.horizontal(|ui| {
+            let running = matches!(self.key_check, KeyCheck::Running(_));
+            if ui.add_enabled(!running, egui::Button::new("Test key")).clicked() {
+                self.start_key_check(settings);
+            }
+
+            match &self.key_check {
+                KeyCheck::Untested => {}
+                KeyCheck::Running(_) => {
+                    ui.spinner();
+                }
+                KeyCheck::Passed => {
+                    ui.colored_label(egui::Color32::GREEN, format!("✔ Works with {}", settings.model));
+                }
+                KeyCheck::Failed(error) => {
+                    ui.colored_label(egui::Color32::RED, format!("✖ {}", error));
+                }
+            }
+        });
+    }
+
+    /// Lists the problems found in the configuration the settings produce,
+    /// with a button to check again after editing.
+    fn render_config_check(&mut self, ui: &mut egui::Ui, settings: &Settings) {
+        match &self.config_check {
+            ConfigCheck::NotRun => {}
+            ConfigCheck::Running(_) => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Checking configuration…");
+                });
+            }
+            ConfigCheck::Done(diagnostics) => {
+                for diagnostic in diagnostics {
+                    let (color, icon) = match diagnostic.severity {
+                        Severity::Error => (egui::Color32::RED, "✖"),
+                        Severity::Warning => (egui::Color32::YELLOW, "⚠"),
+                    };
+                    ui.colored_label(color, format!("{} {}", icon, diagnostic.message))
+                        .on_hover_text(diagnostic.hint.as_deref().unwrap_or(diagnostic.key));
+                }
+                if ui.small_button("Check configuration again").clicked() {
+                    self.config_check = ConfigCheck::Running(check_config(&self.config, settings));
+                }
+            }
+        }
+    }
+
+    /// Starts a configuration check when the window opens and picks up its
+    /// result.
+    fn poll_config_check(&mut self, ctx: &egui::Context, settings: &Settings) {
+        match &self.config_check {
+            ConfigCheck::NotRun => {
+                self.config_check = ConfigCheck::Running(check_config(&self.config, settings));
+            }
+            ConfigCheck::Running(rx) => match rx.try_recv() {
+                Ok(diagnostics) => self.config_check = ConfigCheck::Done(diagnostics),
+                Err(TryRecvError::Disconnected) => {
+                    self.config_check = ConfigCheck::Done(Vec::new());
+                }
+                Err(TryRecvError::Empty) => ctx.request_repaint_after(Duration::from_millis(100)),
+            },
+            ConfigCheck::Done(_) => {}
+        }
+    }
+
+    /// Checks the key against the API in the background.
+    fn start_key_check(&mut self, settings: &Settings) {
+        self.checked = (settings.api_key.clone(), settings.model.clone());
+        self.key_check = KeyCheck::Running(check_key(&settings.api_key, &settings.model));
+    }
+
+    /// Picks up the result of a running key check.
+    fn poll_key_check(&mut self, ctx: &egui::Context, settings: &Settings) {
+        // A result for a different key or model no longer applies
+        if !matches!(self.key_check, KeyCheck::Untested)
+            && self.checked != (settings.api_key.clone(), settings.model.clone())
+        {
+            self.key_check = KeyCheck::Untested;
+            return;
+        }
+
+        let KeyCheck::Running(rx) = &self.key_check else {
+            return;
+        };
+        self.key_check = match rx.try_recv() {
+            Ok(Ok(())) => KeyCheck::Passed,
+            Ok(Err(error)) => KeyCheck::Failed(error),
+            Err(TryRecvError::Disconnected) => KeyCheck::Failed("Check did not finish".to_string()),
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(Duration::from_millis(100));
+                return;
+            }
+        };
+    }
+}
+
+/// Renders profile and model selection and prompt options.
+fn render_model_tab(ui: &mut egui::Ui, settings: &mut Settings, profiles: &Profiles) {
+    // Switching profiles takes over their model and prompts for this session
+    if !profiles.is_empty() {
+        let current = settings.profile.as_ref().map(|(name, _)| name.clone());
+        egui::ComboBox::from_label("Profile")
+            .selected_text(current.as_deref().unwrap_or("None"))
+            .show_ui(ui, |ui| {
+                for (name, profile) in &profiles.profiles {
+                    if ui.selectable_label(current.as_ref() == Some(name), name).clicked() {
+                        settings.apply_profile(name, profile);
+                    }
+                }
+            });
+    }
+
+    egui::ComboBox::from_label("Model")
+        .selected_text(&settings.model)
+        .show_ui(ui, |ui| {
+            for model in AVAILABLE_MODELS {
+                ui.selectable_value(&mut settings.model, model.to_string(), *model);
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.label("Custom model:");
+        ui.text_edit_singleline(&mut settings.model);
+    });
+
+    ui.checkbox(&mut settings.thinking_enabled, "Enable Thinking");
+    ui.checkbox(&mut settings.google_search, "Use Google Search");
+
+    // Preset combined with the custom system prompt
+    egui::ComboBox::from_label("Preset")
+        .selected_text(settings.preset.map_or("None", SystemPromptPreset::label))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut settings.preset, None, "None");
+            for preset in SystemPromptPreset::ALL {
+                ui.selectable_value(&mut settings.preset, Some(*preset), preset.label());
+            }
+        });
+
+    ui.label("System Instructions:");
+    ui.add(
+        egui::TextEdit::multiline(&mut settings.system_prompt)
+            .desired_rows(3)
+            .desired_width(f32::INFINITY),
+    );
+
+    ui.horizontal(|ui| {
+        ui.label("Default prompt:");
+        ui.add(
+            egui::TextEdit::singleline(&mut settings.default_prompt)
+                .desired_width(f32::INFINITY)
+                .hint_text("Chosen for the content (e.g. \"Explain this code.\")"),
+        );
+    });
+    ui.checkbox(
+        &mut settings.empty_enter_submits,
+        "Enter with an empty prompt sends the default prompt",
+    );
+    ui.checkbox(&mut settings.prefill_last_prompt, "Start with the last prompt typed");
+}
+
+/// Renders answer language, tools, and appearance options.
+fn render_behavior_tab(ui: &mut egui::Ui, settings: &mut Settings) {
+    // Response language, independent of the screenshot's language
+    egui::ComboBox::from_label("Answer language")
+        .selected_text(if settings.response_language.is_empty() {
+            "Auto"
+        } else {
+            settings.response_language.as_str()
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut settings.response_language, String::new(), "Auto");
+            for (_, name, _) in RESPONSE_LANGUAGES {
+                ui.selectable_value(&mut settings.response_language, name.to_string(), *name);
+            }
+        });
+
+    ui.checkbox(&mut settings.tools_enabled, "Allow local actions (tools)");
+
+    // Saved as the names config.toml uses, so `jpeg:90` set there shows up
+    let encodings = [("", "Default"), ("jpeg", "JPEG"), ("png", "PNG"), ("webp", "WebP")];
+    let selected = encodings
+        .iter()
+        .find(|(name, _)| *name == settings.image_encoding)
+        .map_or(settings.image_encoding.as_str(), |(_, label)| *label);
+    egui::ComboBox::from_label("Upload format")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            for (name, label) in encodings {
+                ui.selectable_value(&mut settings.image_encoding, name.to_string(), label);
+            }
+        })
+        .response
+        .on_hover_text("PNG and WebP keep small text sharp; JPEG uploads fastest");
+
+    ui.add_space(8.0);
+    egui::ComboBox::from_label("Theme")
+        .selected_text(settings.theme.label())
+        .show_ui(ui, |ui| {
+            for theme in Theme::ALL {
+                ui.selectable_value(&mut settings.theme, theme, theme.label());
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.label("Overlay:");
+        ui.color_edit_button_srgba_unmultiplied(&mut settings.overlay_color);
+        ui.label("Accent:");
+        ui.color_edit_button_srgb(&mut settings.accent_color);
+        if ui.small_button("Reset").clicked() {
+            settings.overlay_color = DEFAULT_OVERLAY_COLOR;
+            settings.accent_color = DEFAULT_ACCENT_COLOR;
+        }
+    });
+    ui.add(
+        egui::Slider::new(&mut settings.overlay_color[3], 0..=255)
+            .text("Dimming")
+            .custom_formatter(|alpha, _| format!("{:.0}%", alpha / 2.55))
+            .custom_parser(|text| {
+                let percent = text.trim().trim_end_matches('%').trim().parse::<f64>().ok()?;
+                Some(percent * 2.55)
+            }),
+    )
+    .on_hover_text("How dark the screen outside the selection gets (toggle with D)");
+    egui::ComboBox::from_label("Code highlighting")
+        .selected_text(if settings.syntax_theme.is_empty() {
+            "Automatic"
+        } else {
+            settings.syntax_theme.as_str()
+        })
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut settings.syntax_theme, String::new(), "Automatic");
+            for theme in SYNTAX_THEMES {
+                ui.selectable_value(&mut settings.syntax_theme, theme.to_string(), *theme);
+            }
+        });
+    ui.add(
+        egui::Slider::new(&mut settings.response_font_size, RESPONSE_FONT_SIZE_RANGE)
+            .step_by(1.0)
+            .text("Answer text size"),
+    );
+}
+
+/// Renders the daemon's hotkey and the list of keyboard shortcuts.
+fn render_hotkeys_tab(ui: &mut egui::Ui, settings: &mut Settings) {
+    ui.horizontal(|ui| {
+        ui.label("Daemon hotkey:");
+        ui.add(
+            egui::TextEdit::singleline(&mut settings.hotkey)
+                .desired_width(160.0)
+                .hint_text(DEFAULT_HOTKEY),
+        );
+    });
+    ui.label(
+        egui::RichText::new("e.g. ctrl+shift+s or super+prtsc; the daemon picks up changes on save")
+            .small()
+            .color(egui::Color32::GRAY),
+    );
+    ui.separator();
+
+    egui::Grid::new("shortcuts_grid").striped(true).show(ui, |ui| {
+        for (keys, action) in OVERLAY_SHORTCUTS {
+            ui.label(egui::RichText::new(*keys).monospace());
+            ui.label(*action);
+            ui.end_row();
+        }
+    });
+}
+
+/// Renders options controlling what leaves the machine and what saved
+/// screenshots are stamped with.
+fn render_privacy_tab(ui: &mut egui::Ui, settings: &mut Settings) {
+    ui.checkbox(&mut settings.ocr_text_only, "Send OCR text instead of image");
+    ui.label(
+        egui::RichText::new(
+            "Text is extracted locally and only the text is uploaded. \
+             Use the redaction tools to mask parts of the image itself.",
+        )
+        .small()
+        .color(egui::Color32::GRAY),
+    );
+
+    ui.add_space(8.0);
+    ui.checkbox(
+        &mut settings.mask_sensitive,
+        "Mask email addresses, API keys and card numbers",
+    );
+    ui.label(
+        egui::RichText::new(
+            "Each selection is read locally with Tesseract, which must be installed. \
+             Masked text is listed for review before sending; detection can miss things.",
+        )
+        .small()
+        .color(egui::Color32::GRAY),
+    );
+
+    ui.separator();
+    ui.label("Stamp saved, copied and exported screenshots with:");
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut settings.stamp_timestamp, "Date and time");
+        ui.checkbox(&mut settings.stamp_hostname, "Computer name");
+    });
+    ui.horizontal(|ui| {
+        ui.label("Note:");
+        ui.add(
+            egui::TextEdit::singleline(&mut settings.stamp_text)
+                .desired_width(240.0)
+                .hint_text("e.g. Internal - ticket 4711"),
+        );
+    });
+    ui.label(
+        egui::RichText::new(
+            "The stamp goes below the image; what is sent to the model is left as is.",
+        )
+        .small()
+        .color(egui::Color32::GRAY),
+    );
+}
+
+/// Renders this month's requests, tokens and estimated spend per model,
+/// and how much of the monthly budget is spent.
+fn render_usage_tab(ui: &mut egui::Ui, models: &[ModelUsage], budget: &Budget) {
+    let money = |cost: Option<f64>| cost.map_or("?".to_string(), |cost| format!("${:.2}", cost));
+    let spent: f64 = models.iter().filter_map(|model| model.cost).sum();
+
+    ui.label("This month:");
+    if models.is_empty() {
+        ui.label(egui::RichText::new("No requests yet").color(egui::Color32::GRAY));
+    } else {
+        egui::Grid::new("usage_grid").striped(true).show(ui, |ui| {
+            for header in ["Model", "Requests", "Input tokens", "Output tokens", "Est. cost"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for model in models {
+                ui.label(&model.model);
+                ui.label(model.requests.to_string());
+                ui.label(model.input_tokens.to_string());
+                ui.label(model.output_tokens.to_string());
+                ui.label(money(model.cost));
+                ui.end_row();
+            }
+        });
+    }
+
+    ui.separator();
+    match budget.monthly {
+        Some(limit) => {
+            ui.label(format!("Monthly budget: {} of ${:.2} spent", money(Some(spent)), limit));
+            ui.add(egui::ProgressBar::new((spent / limit).min(1.0) as f32).show_percentage());
+        }
+        None => {
+            ui.label(format!("Estimated spend: {}", money(Some(spent))));
+        }
+    }
+    ui.label(
+        egui::RichText::new(
+            "Estimated from list prices; models marked ? have no known price. \
+             Set a budget with monthly_budget in the [usage] table of config.toml.",
+        )
+        .small()
+        .color(egui::Color32::GRAY),
+    );
+}
+
+/// Reads this month's usage for the Usage tab.
+fn load_usage() -> Vec<ModelUsage> {
+    let Some(log) = UsageLog::open_default() else {
+        return Vec::new();
+    };
+    log.summary(usage::month_start()).unwrap_or_else(|e| {
+        eprintln!("Warning: Failed to read the usage log: {}", e);
+        Vec::new()
+    })
+}
+
+/// Checks in the background whether the API accepts `api_key` for `model`.
+///
+/// The receiver gets `Ok(())` or the error message once the API answers.
+pub(super) fn check_key(api_key: &str, model: &str) -> Receiver<Result<(), String>> {
+    let (tx, rx) = channel();
+    let api_key = api_key.to_string();
+    let model = model.to_string();
+
+    thread::spawn(move || {
+        let result = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| e.to_string())
+            .and_then(|runtime| {
+                runtime
+                    .block_on(async {
+                        let config = Config::builder()
+                            .with_api_key(&api_key)
+                            .with_model(&model)
+                            .build()?;
+                        GeminiClient::connect(&config).await?.check_access().await
+                    })
+                    .map_err(|e| e.to_string())
+            });
+        let _ = tx.send(result);
+    });
+    rx
+}
+
+/// Validates the configuration requests would be made with in the
+/// background, since reaching the proxy can take seconds.
+///
+/// Also reports saved settings that the environment, a profile or a flag
+/// takes precedence over.
+fn check_config(config: &Config, settings: &Settings) -> Receiver<Vec<Diagnostic>> {
+    let (tx, rx) = channel();
+    let builder = config.with_settings(settings);
+    let saved_key = !settings.api_key.trim().is_empty();
+
+    thread::spawn(move || {
+        let diagnostics = match builder.build() {
+            Ok(config) => {
+                let mut diagnostics = config.validate();
+                diagnostics.extend(overridden_settings(&config, saved_key));
+                diagnostics
+            }
+            Err(e) => vec![Diagnostic::error("config", e.to_string())],
+        };
+        let _ = tx.send(diagnostics);
+    });
+    rx
+}
+
+/// Returns a warning for the model and the saved API key if a higher layer
+/// replaced them.
+fn overridden_settings(config: &Config, saved_key: bool) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let overridden = |key| {
+        !matches!(
+            config.source(key),
+            ConfigSource::Default | ConfigSource::File | ConfigSource::Settings
+        )
+    };
+    if overridden("model") {
+        diagnostics.push(Diagnostic::warning(
+            "model",
+            format!("Using {} from {}", config.model_name, config.source("model")),
+        ));
+    }
+    if saved_key && overridden("api_key") {
+        diagnostics.push(Diagnostic::warning(
+            "api_key",
+            format!("The saved API key is replaced by one from {}", config.source("api_key")),
+        ));
+    }
+    diagnostics
+}
+
+/// Returns why `key` can't be a Gemini API key, if it obviously can't.
+pub(super) fn key_format_problem(key: &str) -> Option<&'static str> {
+    // Gemini API keys are 39 characters starting with "AIza"
+    let trimmed = key.trim();
+    let looks_valid = trimmed.starts_with("AIza") && trimmed.len() == 39;
+    if !trimmed.is_empty() && !looks_valid {
+        Some("This doesn't look like a Gemini API key")
+    } else if trimmed.len() != key.len() {
+        Some("The API key has leading or trailing spaces")
+    } else {
+        None
+    }
+}
+
+/// Returns human-readable problems with the settings.
+fn validate(settings: &Settings) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let model = settings.model.trim();
+    if model.is_empty() {
+        problems.push("Choose a model".to_string());
+    } else if model.contains(char::is_whitespace) {
+        problems.push("Model names cannot contain spaces".to_string());
+    }
+
+    if let Some(problem) = key_format_problem(&settings.api_key) {
+        problems.push(problem.to_string());
+    }
+
+    match settings.hotkey.parse::<Hotkey>() {
+        Ok(hotkey) => {
+            if let Some(owner) = hotkey.conflicts() {
+                problems.push(format!("{} is usually taken by {}", hotkey, owner));
+            }
+        }
+        Err(e) => problems.push(e.to_string()),
+    }
+
+    if !settings.image_encoding.trim().is_empty()
+        && let Err(e) = settings.image_encoding.parse::<ImageEncoding>()
+    {
+        problems.push(e.to_string());
+    }
+
+    problems
+}
@@ -3,20 +3,74 @@
 //! This module contains the `SnippingTool` struct which implements the
 //! `eframe::App` trait for the fullscreen selection overlay.
 
-use super::rendering::{calculate_popup_position, draw_selection_border, draw_selection_overlay};
-use super::selection::{process_drag_event, SelectionEvent};
-use super::settings::{Settings, AVAILABLE_MODELS};
-use super::state::{SelectionResult, StreamEvent, UiState};
-use crate::config::Config;
+use super::accessibility::{mark_region, AccessibleName};
+use super::api_key_form::ApiKeyForm;
+use super::history_window::{HistoryAction, HistoryWindow};
+use super::markdown::{extract_code, sanitize_partial, show_markdown, MarkdownBlocks};
+use super::rendering::{
+    calculate_popup_position, draw_magnifier, draw_redactions, draw_selection_border,
+    draw_selection_overlay,
+};
+use super::selection::{
+    apply_handle_drag, constrain_aspect, handle_at, is_valid_selection, process_drag_event,
+    AspectRatio, SelectionEvent, SelectionHandle,
+};
+use super::settings::{
+    Settings, Theme, DEFAULT_RESPONSE_FONT_SIZE, RESPONSE_FONT_SIZE_RANGE,
+};
+use super::settings_window::SettingsWindow;
+use super::state::{PayloadEstimate, SelectionResult, StreamEvent, UiState};
+use crate::analysis;
+use crate::capture::{ScreenGeometry, WindowInfo};
+use crate::checkpoint::{Checkpoint, CheckpointStore};
+use crate::classifier::{self, ContentKind};
+use crate::config::{Config, OverlayMode};
 use crate::error::{AppError, Result};
-use crate::gemini::{GeminiClient, GeminiStreamEvent};
-use crate::image_processing::ImageProcessor;
-use eframe::egui;
+use crate::export::{Conversation, ExportFormat};
+use crate::gemini::tools::ToolRegistry;
+use crate::gemini::{
+    ChatTurn, GeminiClient, GeminiEventStream, GeminiStreamEvent, IMAGE_EDIT_MODEL,
+};
+use crate::history::{HistoryEntry, HistoryStore};
+use crate::image_processing::{
+    ImageProcessor, Redaction, RedactionStyle, UploadOptions, DEFAULT_UPSCALE_MIN_EDGE,
+    DOWNSCALE_MAX_EDGE, LARGE_PAYLOAD_BYTES,
+};
+use crate::models::ModelCapabilities;
+use crate::montage;
+use crate::ocr::OcrEngine;
+use crate::openai::OpenAiClient;
+use crate::plugins::{Plugin, PluginInput};
+use crate::upload::{self, Share};
+use crate::usage;
+use crate::privacy::{self, Finding};
+use crate::profiles::Profiles;
+use crate::speech::{Speaker, Speech};
+use directories::UserDirs;
+use eframe::egui::{self, accesskit};
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use image::DynamicImage;
+use std::path::PathBuf;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a streaming response is checkpointed to disk.
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How long a first Escape press waits for the confirming second press.
+const ESCAPE_CONFIRM_WINDOW: Duration = Duration::from_secs(2);
+
+/// How long settings changed by zooming wait before being saved.
+const SETTINGS_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+/// Frames given to the window manager to place the overlay before its
+/// geometry is checked against the captured screen.
+const GEOMETRY_CHECK_FRAMES: u32 = 20;
+
+/// Largest size of the crop preview thumbnail, in pixels.
+const PREVIEW_SIZE: (u32, u32) = (240, 120);
 
 /// The main snipping tool application.
 ///
@@ -28,29 +82,129 @@ pub struct SnippingTool {
     /// Pre-converted image data for fast texture upload
     color_image: Option<egui::ColorImage>,
     screenshot: DynamicImage,
+    /// Windows on the captured screen (frontmost first), for snapping
+    windows: Vec<WindowInfo>,
+    /// Desktop geometry of the captured screen, if known
+    screen: Option<ScreenGeometry>,
+    /// Frames left before the overlay geometry is checked
+    geometry_check_frames: u32,
+    /// Whether the borderless fallback window was requested
+    borderless_fallback: bool,
 
     // Selection state
     selection_start: Option<egui::Pos2>,
     current_pos: Option<egui::Pos2>,
     is_selection_finalized: bool,
+    /// Handle being dragged to move or resize a finalized selection
+    active_handle: Option<SelectionHandle>,
+    /// Earlier regions added with Ctrl+drag, sent along with the selection
+    extra_selections: Vec<egui::Rect>,
+    /// Send the regions as one labelled image instead of one image each
+    combine_regions: bool,
+    /// Crosshair position while selecting with the keyboard
+    keyboard_cursor: Option<egui::Pos2>,
+    /// Aspect ratio new selections are constrained to
+    aspect_ratio: AspectRatio,
+    /// Exact-size entry (width, height in image pixels)
+    exact_size_input: (String, String),
+    /// Regions masked before the selection leaves the machine
+    redactions: Vec<Redaction>,
+    /// Sensitive text masked automatically, with its redaction's rectangle
+    masked_findings: Vec<(Finding, egui::Rect)>,
+    /// Selection being checked for sensitive text in the background
+    sensitive_scan: Option<egui::Rect>,
+    /// Why the last check for sensitive text failed; nothing is sent until
+    /// a check succeeds
+    sensitive_scan_error: Option<String>,
+    /// Active redaction tool; while set, drags inside the selection mark regions
+    redaction_tool: Option<RedactionStyle>,
+    /// Start of the redaction currently being drawn
+    redaction_start: Option<egui::Pos2>,
     pub result: Arc<Mutex<SelectionResult>>,
 
     // Chat state
     chat_input: String,
+    /// Detected content of the current selection, used for the default prompt
+    content_kind: ContentKind,
+    /// Earlier exchanges about the current selection, oldest first
+    conversation: Vec<ChatTurn>,
+    follow_up_input: String,
+    /// Upload size of the current selection, computed in the background
+    payload_estimate: Option<PayloadEstimate>,
+    /// Thumbnail of the pixels that will be sent for the current selection
+    selection_preview: Option<egui::TextureHandle>,
+    /// Downscale the selection before sending (offered for large selections)
+    downscale_upload: bool,
+    /// Enlarge the selection before sending, if chosen in the overlay rather
+    /// than left to [`Config::upscale_min_edge`] (offered for tiny selections)
+    upscale_upload: Option<bool>,
+    /// Grayscale, stretch the contrast and sharpen before sending, for text
+    enhance_text: bool,
 
     // API state
     #[allow(dead_code)]
     config: Config,
     state: UiState,
+    /// Whether a response is still streaming in
+    is_streaming: bool,
+    /// When the current request was sent, and how long it took once finished
+    request_started: Option<Instant>,
+    request_duration: Option<Duration>,
+    /// Input and output tokens reported for the current request
+    usage: Option<(u32, u32)>,
+    /// Parameters of the last request, kept so it can be retried
+    last_request: Option<(egui::Rect, egui::Vec2, String)>,
+    /// Model the last request was sent to, which the environment or a flag
+    /// may have chosen over the saved setting
+    answer_model: String,
+    /// Transient feedback shown below the response (e.g., export result)
+    status_message: Option<String>,
+    /// Answer currently being read aloud
+    speech: Option<Speech>,
+    /// Image returned by an "Edit with AI" request, with its texture
+    edited_image: Option<(DynamicImage, egui::TextureHandle)>,
     rx: Receiver<StreamEvent>,
     tx: Sender<StreamEvent>,
 
+    /// Tool call awaiting user approval, with the channel to send the answer
+    pending_tool_confirmation: Option<(String, Sender<bool>)>,
+
+    // Checkpointing of in-progress responses
+    checkpoints: Option<CheckpointStore>,
+    active_checkpoint: Option<Checkpoint>,
+    last_checkpoint_at: Instant,
+    /// Unfinished answer from a previous session, offered for recovery
+    recovered_checkpoint: Option<Checkpoint>,
+
     // Markdown rendering
     markdown_cache: CommonMarkCache,
+    /// Latest answer split into blocks, so long answers render incrementally
+    answer_blocks: MarkdownBlocks,
 
     // Settings
     settings: Settings,
-    show_settings: bool,
+    /// Settings window, while open
+    settings_window: Option<SettingsWindow>,
+    /// History browser, while open
+    history_window: Option<HistoryWindow>,
+    /// Whether the answer streaming in should be added to the history
+    record_history: bool,
+    /// Whether the dimming outside the selection is hidden (toggled with `D`)
+    dimming_hidden: bool,
+    /// Inline form asking for a missing API key
+    api_key_form: Option<ApiKeyForm>,
+    /// Request held back until an API key is set
+    pending_request: Option<PendingRequest>,
+    /// When settings changed outside the settings window are due to be saved
+    settings_save_due: Option<Instant>,
+    /// Theme and accent color currently applied to the egui style
+    applied_appearance: Option<(Theme, [u8; 3])>,
+    /// Whether the answer is shown in a small pinned window instead of the overlay
+    pinned: bool,
+    /// When Escape was pressed in a state where closing would lose work
+    escape_pressed_at: Option<Instant>,
+    /// Whether the overlay only picks a region and closes once one is made
+    pick_only: bool,
 }
 
 impl SnippingTool {
@@ -59,63 +213,211 @@ impl SnippingTool {
     /// # Arguments
     /// * `screenshot` - The captured screen image
     /// * `result` - Shared result container for returning selection to caller
+    /// * `screen` - Desktop geometry of the captured screen, if known
+    /// * `windows` - Windows on the captured screen, frontmost first
     /// * `config` - Application configuration
-    pub fn new(screenshot: DynamicImage, result: Arc<Mutex<SelectionResult>>, config: Config) -> Self {
+    pub fn new(
+        screenshot: DynamicImage,
+        result: Arc<Mutex<SelectionResult>>,
+        screen: Option<ScreenGeometry>,
+        windows: Vec<WindowInfo>,
+        config: Config,
+    ) -> Self {
         let (tx, rx) = channel();
 
-        // Load settings, using config's API key as fallback
+        // Requests layer these under the environment, profile and flags
+        // (see `Config::with_settings`), so they are not overwritten here
         let mut initial_settings = Settings::load(&config.model_name);
-        if initial_settings.api_key.is_empty() {
-            initial_settings.api_key = config.gemini_api_key.clone();
+        if let Some(name) = &config.profile {
+            match Profiles::load().and_then(|profiles| profiles.select(Some(name))) {
+                Ok(Some((name, profile))) => initial_settings.apply_profile(&name, &profile),
+                Ok(None) => {}
+                Err(e) => eprintln!("Warning: Failed to load profile '{}': {}", name, e),
+            }
         }
 
+        let chat_input = if initial_settings.prefill_last_prompt {
+            initial_settings.last_prompt.clone()
+        } else {
+            String::new()
+        };
+
         // Pre-convert screenshot to ColorImage for fast texture upload
         // This is the expensive operation - do it before the UI loop starts
-        let image_buffer = screenshot.to_rgba8();
-        let size = [screenshot.width() as usize, screenshot.height() as usize];
-        let pixels = image_buffer.as_flat_samples();
-        let color_image = egui::ColorImage::from_rgba_unmultiplied(size, pixels.as_slice());
+        let color_image = ImageProcessor::to_color_image(&screenshot);
+
+        // Look for an answer interrupted by a crash or power loss
+        let checkpoints = CheckpointStore::open_default();
+        let recovered_checkpoint = checkpoints.as_ref().and_then(CheckpointStore::load);
 
         Self {
             image_texture: None,
             color_image: Some(color_image),
             screenshot,
+            windows,
+            screen,
+            geometry_check_frames: GEOMETRY_CHECK_FRAMES,
+            borderless_fallback: config.overlay_mode == OverlayMode::Borderless,
             selection_start: None,
             current_pos: None,
+            active_handle: None,
+            extra_selections: Vec::new(),
+            combine_regions: false,
+            keyboard_cursor: None,
+            aspect_ratio: AspectRatio::default(),
+            exact_size_input: (String::new(), String::new()),
+            redactions: Vec::new(),
+            masked_findings: Vec::new(),
+            sensitive_scan: None,
+            sensitive_scan_error: None,
+            redaction_tool: None,
+            redaction_start: None,
             result,
-            chat_input: String::new(),
+            chat_input,
+            content_kind: ContentKind::General,
+            conversation: Vec::new(),
+            follow_up_input: String::new(),
+            payload_estimate: None,
+            selection_preview: None,
+            downscale_upload: false,
+            upscale_upload: None,
+            enhance_text: false,
             is_selection_finalized: false,
             config,
             state: UiState::Idle,
+            is_streaming: false,
+            request_started: None,
+            request_duration: None,
+            usage: None,
+            last_request: None,
+            answer_model: initial_settings.model.clone(),
+            status_message: None,
+            speech: None,
+            edited_image: None,
             rx,
             tx,
+            pending_tool_confirmation: None,
+            checkpoints,
+            active_checkpoint: None,
+            last_checkpoint_at: Instant::now(),
+            recovered_checkpoint,
             markdown_cache: CommonMarkCache::default(),
+            answer_blocks: MarkdownBlocks::default(),
             settings: initial_settings,
-            show_settings: false,
+            settings_window: None,
+            history_window: None,
+            record_history: false,
+            dimming_hidden: false,
+            api_key_form: None,
+            pending_request: None,
+            settings_save_due: None,
+            applied_appearance: None,
+            pinned: false,
+            escape_pressed_at: None,
+            pick_only: false,
         }
     }
 
     /// Submits a request to the Gemini API for image analysis.
     ///
     /// Spawns a background thread to handle the async API call and streams
-    /// results back through the channel.
+    /// results back through the channel. Earlier exchanges in
+    /// `self.conversation` are sent along so follow-ups keep their context,
+    /// and regions added with Ctrl+drag are attached as separate images
+    /// before `selection`.
     fn submit_request(&mut self, selection: egui::Rect, ui_size: egui::Vec2, prompt: String) {
+        self.record_result(selection, ui_size, Some(prompt.clone()));
+        self.last_request = Some((selection, ui_size, prompt.clone()));
+        self.record_history = true;
+        self.status_message = None;
+        self.speech = None;
+        self.edited_image = None;
+
         // Save settings before making request
         if let Err(e) = self.settings.save() {
             eprintln!("Warning: Failed to save settings: {}", e);
         }
 
+        // Flags, profiles and the environment take precedence over the settings
+        let task_config = match self.config.with_settings(&self.settings).build() {
+            Ok(config) => config,
+            Err(e) => {
+                self.status_message = Some(format!("Configuration error: {}", e));
+                return;
+            }
+        };
+        if let Err(e) = usage::enforce_budget(&task_config.budget) {
+            self.status_message = Some(e.to_string());
+            return;
+        }
+
         self.state = UiState::Response {
             text: String::new(),
             thoughts: String::new(),
         };
+        self.is_streaming = true;
+        self.start_request_timer();
+        self.active_checkpoint = Some(Checkpoint::new(&prompt, &task_config.model_name));
+        self.answer_model = task_config.model_name.clone();
+        self.last_checkpoint_at = Instant::now();
 
         let tx = self.tx.clone();
-        let screenshot = self.screenshot.clone();
+        let screenshot = self.redacted_screenshot(ui_size);
         let settings = self.settings.clone();
+        let system_prompt = analysis::system_prompt(&task_config);
+        let history = self.conversation.clone();
+        let capabilities = ModelCapabilities::of(&task_config.model_name);
+        let upload_options = UploadOptions {
+            max_edge: self
+                .downscale_upload
+                .then_some(DOWNSCALE_MAX_EDGE)
+                .into_iter()
+                .chain(capabilities.map(|capabilities| capabilities.max_image_edge))
+                .min(),
+            max_pixels: task_config.max_image_pixels,
+            min_edge: upscale_min_edge(&task_config, self.upscale_upload),
+            upscale_filter: task_config.upscale_filter,
+            enhance_text: self.enhance_text,
+            encoding: task_config.image_encoding,
+        };
+        let mut warnings = ModelCapabilities::warnings(
+            &task_config.model_name,
+            settings.thinking_enabled,
+            settings.google_search,
+        );
+        if settings.tools_enabled && settings.ocr_text_only {
+            warnings.push("Local actions are off while only OCR text is sent".to_string());
+        }
+        if !warnings.is_empty() {
+            self.status_message = Some(warnings.join("\n"));
+        }
+        let regions: Vec<egui::Rect> =
+            self.extra_selections.iter().copied().chain([selection]).collect();
+        let combine = self.combine_regions && regions.len() > 1 && !settings.ocr_text_only;
+        let prompt = if combine {
+            format!("{}\n\n{}", montage::prompt_note(regions.len()), prompt)
+        } else {
+            prompt
+        };
 
         // Spawn background thread for async work
         thread::spawn(move || {
+            // Checked again here, so a send that beat the background check
+            // still masks what it would have found
+            let mut screenshot = screenshot;
+            let masked = if settings.mask_sensitive {
+                mask_sensitive(&mut screenshot, &regions, ui_size)
+            } else {
+                Ok(())
+            };
+            if let Err(e) = masked {
+                let _ = tx.send(StreamEvent::Error(format!(
+                    "Could not check the selection for sensitive text, so nothing was sent: {}",
+                    e
+                )));
+                return;
+            }
+
             let runtime = tokio::runtime::Builder::new_current_thread()
                 .enable_all()
                 .build();
@@ -123,11 +425,56 @@ impl SnippingTool {
             match runtime {
                 Ok(rt) => {
                     rt.block_on(async {
-                        // Process image to base64
-                        let base64_img =
-                            match ImageProcessor::process_selection(&screenshot, selection, ui_size)
-                            {
-                                Ok(img) => img,
+                        // In OCR text-only mode only the locally extracted text
+                        // leaves the machine, so the regions are never encoded
+                        let ocr_text = if settings.ocr_text_only {
+                            match ocr_regions(&screenshot, &regions, ui_size) {
+                                Ok(text) => Some(text),
+                                Err(e) => {
+                                    let _ = tx.send(StreamEvent::Error(format!(
+                                        "Text extraction failed: {}",
+                                        e
+                                    )));
+                                    return;
+                                }
+                            }
+                        } else {
+                            None
+                        };
+
+                        // Encode each region, or all of them as one
+                        let images = if ocr_text.is_some() {
+                            Ok(Vec::new())
+                        } else if combine {
+                            regions
+                                .iter()
+                                .map(|region| {
+                                    ImageProcessor::crop_selection(&screenshot, *region, ui_size)
+                                })
+                                .collect::<Result<Vec<_>>>()
+                                .and_then(|crops| {
+                                    let combined = ImageProcessor::prepare_upload(
+                                        montage::compose(&crops),
+                                        upload_options,
+                                    );
+                                    ImageProcessor::encode_image(&combined, upload_options.encoding)
+                                })
+                                .map(|image| vec![image])
+                        } else {
+                            regions
+                                .iter()
+                                .map(|region| {
+                                    ImageProcessor::process_selection_with_options(
+                                        &screenshot,
+                                        *region,
+                                        ui_size,
+                                        upload_options,
+                                    )
+                                })
+                                .collect::<Result<Vec<_>>>()
+                        };
+                        let images = match images {
+                                Ok(images) => images,
                                 Err(e) => {
                                     let _ = tx.send(StreamEvent::Error(format!(
                                         "Image processing failed: {}",
@@ -137,24 +484,26 @@ impl SnippingTool {
                                 }
                             };
 
-                        // Create Gemini client with current settings
-                        let task_config = Config::builder()
-                            .with_api_key(&settings.api_key)
-                            .with_model(&settings.model)
-                            .build();
-
-                        let task_config = match task_config {
-                            Ok(c) => c,
-                            Err(e) => {
-                                let _ = tx.send(StreamEvent::Error(format!(
-                                    "Configuration error: {}",
-                                    e
-                                )));
-                                return;
-                            }
-                        };
+                        // Route through an OpenAI-compatible gateway when configured
+                        if task_config.openai.is_some() {
+                            let stream = match (OpenAiClient::new(&task_config), ocr_text) {
+                                (Ok(client), Some(text)) => {
+                                    client
+                                        .analyze_text_stream(text, &history, prompt, system_prompt)
+                                        .await
+                                }
+                                (Ok(client), None) => {
+                                    client
+                                        .continue_images_stream(images, &history, prompt, system_prompt)
+                                        .await
+                                }
+                                (Err(e), _) => Err(e),
+                            };
+                            forward_stream(&tx, stream).await;
+                            return;
+                        }
 
-                        let client = match GeminiClient::new(&task_config) {
+                        let client = match GeminiClient::connect(&task_config).await {
                             Ok(c) => c,
                             Err(e) => {
                                 let _ = tx.send(StreamEvent::Error(format!(
@@ -165,50 +514,69 @@ impl SnippingTool {
                             }
                         };
 
-                        // Stream response from Gemini
-                        match client
-                            .analyze_image_stream(
-                                base64_img,
-                                prompt,
-                                settings.system_prompt,
-                                settings.thinking_enabled,
-                                settings.google_search,
-                            )
-                            .await
-                        {
-                            Ok(mut stream) => {
-                                use futures::StreamExt;
-
-                                while let Some(result) = stream.next().await {
-                                    match result {
-                                        Ok(events) => {
-                                            for event in events {
-                                                match event {
-                                                    GeminiStreamEvent::Text(text) => {
-                                                        let _ = tx.send(StreamEvent::Chunk(text));
-                                                    }
-                                                    GeminiStreamEvent::Thought(thought) => {
-                                                        let _ =
-                                                            tx.send(StreamEvent::Thought(thought));
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            let _ = tx.send(StreamEvent::Error(format!(
-                                                "Stream error: {}",
-                                                e
-                                            )));
-                                        }
-                                    }
+                        // Run a tool-use session when local actions are enabled;
+                        // the session sends the image, so not in OCR text-only mode
+                        if settings.tools_enabled && ocr_text.is_none() {
+                            let confirm_tx = tx.clone();
+                            let tools = ToolRegistry::builtin().with_confirmation(move |call| {
+                                let (reply_tx, reply_rx) = channel();
+                                let request = format!("{}({})", call.name, call.args);
+                                if confirm_tx
+                                    .send(StreamEvent::ConfirmTool(request, reply_tx))
+                                    .is_err()
+                                {
+                                    return false;
                                 }
-                                let _ = tx.send(StreamEvent::Done);
-                            }
-                            Err(e) => {
-                                let _ =
-                                    tx.send(StreamEvent::Error(format!("Gemini API error: {}", e)));
-                            }
+                                reply_rx.recv().unwrap_or(false)
+                            });
+
+                            let result = client
+                                .analyze_images_with_tools(
+                                    images,
+                                    &history,
+                                    prompt,
+                                    system_prompt,
+                                    &tools,
+                                    |event| {
+                                        forward_gemini_event(&tx, event);
+                                    },
+                                )
+                                .await;
+
+                            let _ = match result {
+                                Ok(()) => tx.send(StreamEvent::Done),
+                                Err(e) => tx.send(StreamEvent::Error(format!("Gemini API error: {}", e))),
+                            };
+                            return;
                         }
+
+                        // Stream response from Gemini, sending locally extracted
+                        // text instead of the image in OCR text-only mode
+                        let stream = if let Some(text) = ocr_text {
+                            client
+                                .analyze_text_stream(
+                                    text,
+                                    &history,
+                                    prompt,
+                                    system_prompt,
+                                    settings.thinking_enabled,
+                                    settings.google_search,
+                                )
+                                .await
+                        } else {
+                            client
+                                .continue_images_stream(
+                                    images,
+                                    &history,
+                                    prompt,
+                                    system_prompt,
+                                    settings.thinking_enabled,
+                                    settings.google_search,
+                                )
+                                .await
+                        };
+
+                        forward_stream(&tx, stream).await;
                     });
                 }
                 Err(e) => {
@@ -221,261 +589,2667 @@ impl SnippingTool {
         });
     }
 
-    /// Processes stream events from the background thread.
-    fn process_stream_events(&mut self, ctx: &egui::Context) {
-        while let Ok(event) = self.rx.try_recv() {
-            match event {
-                StreamEvent::Chunk(text) => {
-                    if let UiState::Response {
-                        text: current_text,
-                        ..
-                    } = &mut self.state
-                    {
-                        current_text.push_str(&text);
-                    } else {
-                        self.state = UiState::Response {
-                            text,
-                            thoughts: String::new(),
-                        };
-                    }
-                    ctx.request_repaint();
-                }
-                StreamEvent::Thought(thought) => {
-                    if let UiState::Response { thoughts, .. } = &mut self.state {
-                        thoughts.push_str(&thought);
-                    } else {
-                        self.state = UiState::Response {
-                            text: String::new(),
-                            thoughts: thought,
-                        };
-                    }
-                    ctx.request_repaint();
-                }
-                StreamEvent::Error(err) => {
-                    self.state = UiState::Error(err);
-                }
-                StreamEvent::Done => {
-                    // Stream completed - could trigger analytics or logging here
-                }
-            }
-        }
-    }
+    /// Sends the selection to an image-capable model for editing.
+    ///
+    /// The returned image is shown in the response view along with any text
+    /// the model produced.
+    fn submit_edit_request(&mut self, selection: egui::Rect, ui_size: egui::Vec2, prompt: String) {
+        self.record_result(selection, ui_size, Some(prompt.clone()));
+        self.record_history = false;
+        self.status_message = None;
+        self.speech = None;
+        self.edited_image = None;
+        self.conversation.clear();
+        self.last_request = None;
+        self.state = UiState::Response {
+            text: String::new(),
+            thoughts: String::new(),
+        };
+        self.is_streaming = true;
+        self.start_request_timer();
 
-    /// Renders the idle state UI (prompt input).
-    fn render_idle_ui(&mut self, ui: &mut egui::Ui, selection_rect: egui::Rect) {
-        ui.horizontal(|ui| {
-            ui.label("Ask Gemini:");
-            let response = ui.add(
-                egui::TextEdit::singleline(&mut self.chat_input)
-                    .desired_width(200.0)
-                    .hint_text("e.g., Explain this code")
-                    .lock_focus(true),
-            );
+        let tx = self.tx.clone();
+        let screenshot = self.redacted_screenshot(ui_size);
+        let config = self.config.with_settings(&self.settings).with_model(IMAGE_EDIT_MODEL);
+        let upscale_upload = self.upscale_upload;
+        let mask = self.settings.mask_sensitive;
 
-            if !self.show_settings {
-                response.request_focus();
+        thread::spawn(move || {
+            let mut screenshot = screenshot;
+            let masked = if mask {
+                mask_sensitive(&mut screenshot, &[selection], ui_size)
+            } else {
+                Ok(())
+            };
+            if let Err(e) = masked {
+                let _ = tx.send(StreamEvent::Error(format!(
+                    "Could not check the selection for sensitive text, so nothing was sent: {}",
+                    e
+                )));
+                return;
             }
 
-            let enter_pressed = response.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
-            if ui.button("➤").clicked() || enter_pressed {
-                let prompt = if self.chat_input.trim().is_empty() {
-                    "Explain this image in detail.".to_string()
-                } else {
-                    self.chat_input.clone()
-                };
+            let runtime = match tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            {
+                Ok(rt) => rt,
+                Err(e) => {
+                    let _ = tx.send(StreamEvent::Error(format!(
+                        "Failed to create async runtime: {}",
+                        e
+                    )));
+                    return;
+                }
+            };
 
-                self.submit_request(selection_rect, ui.ctx().viewport_rect().size(), prompt);
-            }
+            let result = runtime.block_on(async {
+                let config = config.build()?;
+                let options = UploadOptions {
+                    max_pixels: config.max_image_pixels,
+                    min_edge: upscale_min_edge(&config, upscale_upload),
+                    upscale_filter: config.upscale_filter,
+                    encoding: config.image_encoding,
+                    ..UploadOptions::default()
+                };
+                let image = ImageProcessor::process_selection_with_options(
+                    &screenshot,
+                    selection,
+                    ui_size,
+                    options,
+                )?;
+                let client = GeminiClient::connect(&config).await?;
+                client.edit_image(image, prompt).await
+            });
 
-            if ui.button("⚙").clicked() {
-                self.show_settings = !self.show_settings;
+            match result {
+                Ok(edit) => {
+                    if let Some(image) = edit.image {
+                        let _ = tx.send(StreamEvent::Image(image));
+                    }
+                    if !edit.text.is_empty() {
+                        let _ = tx.send(StreamEvent::Chunk(edit.text));
+                    }
+                    let _ = tx.send(StreamEvent::Done);
+                }
+                Err(e) => {
+                    let _ = tx.send(StreamEvent::Error(format!("Image editing failed: {}", e)));
+                }
             }
         });
-
-        if self.show_settings {
-            self.render_settings_ui(ui);
-        }
     }
 
-    /// Renders the settings panel.
-    fn render_settings_ui(&mut self, ui: &mut egui::Ui) {
-        ui.separator();
-        ui.label("Settings");
+    /// Estimates the upload size of the finalized selection in the background.
+    ///
+    /// The encoded size and a thumbnail of the crop are reported first; the
+    /// token count follows once the `countTokens` endpoint answers (Gemini
+    /// only, and only with an API key).
+    fn estimate_payload(&mut self, ctx: &egui::Context) {
+        let (Some(start), Some(end)) = (self.selection_start, self.current_pos) else {
+            return;
+        };
 
-        // Model selector
-        egui::ComboBox::from_label("Model")
-            .selected_text(&self.settings.model)
-            .show_ui(ui, |ui| {
-                for model in AVAILABLE_MODELS {
-                    ui.selectable_value(&mut self.settings.model, model.to_string(), *model);
-                }
+        let selection = egui::Rect::from_two_pos(start, end);
+        let ui_size = ctx.viewport_rect().size();
+        let tx = self.tx.clone();
+        let screenshot = self.redacted_screenshot(ui_size);
+        let config = self.config.with_settings(&self.settings);
+        let prompt = self.default_prompt();
+        let upscale_upload = self.upscale_upload;
+        self.payload_estimate = None;
+
+        thread::spawn(move || {
+            let Ok(cropped) = ImageProcessor::crop_selection(&screenshot, selection, ui_size) else {
+                return;
+            };
+            let thumbnail = cropped.thumbnail(PREVIEW_SIZE.0, PREVIEW_SIZE.1);
+            let _ = tx.send(StreamEvent::Preview {
+                selection,
+                image: ImageProcessor::to_color_image(&thumbnail),
             });
 
-        // Feature toggles
-        ui.checkbox(&mut self.settings.thinking_enabled, "Enable Thinking");
-        ui.checkbox(&mut self.settings.google_search, "Use Google Search");
+            // Scaled and encoded the way the request will be, for a true size
+            let config = config.build();
+            let (encoding, max_pixels, min_edge, filter) = config
+                .as_ref()
+                .map(|config| {
+                    (
+                        config.image_encoding,
+                        config.max_image_pixels,
+                        upscale_min_edge(config, upscale_upload),
+                        config.upscale_filter,
+                    )
+                })
+                .unwrap_or_default();
+            let downscaled = max_pixels
+                .and_then(|max_pixels| ImageProcessor::downscale_to_pixels(&cropped, max_pixels));
+            let downscaled_from = downscaled.as_ref().map(|_| (cropped.width(), cropped.height()));
+            let cropped = downscaled.unwrap_or(cropped);
+            let tiny_edge = config
+                .as_ref()
+                .ok()
+                .and_then(|config| config.upscale_min_edge)
+                .unwrap_or(DEFAULT_UPSCALE_MIN_EDGE);
+            let tiny = cropped.width().min(cropped.height()) < tiny_edge;
+            let upscaled = min_edge.and_then(|min_edge| {
+                ImageProcessor::upscale_to_min_edge(&cropped, min_edge, filter)
+            });
+            let upscaled_from = upscaled.as_ref().map(|_| (cropped.width(), cropped.height()));
+            let cropped = upscaled.unwrap_or(cropped);
+            let Ok(encoded) = ImageProcessor::encode_image(&cropped, encoding) else {
+                return;
+            };
 
-        // API Key
-        ui.label("API Key:");
-        ui.add(
-            egui::TextEdit::singleline(&mut self.settings.api_key)
-                .password(true)
-                .hint_text("Paste Gemini API Key"),
-        );
+            let mut estimate = PayloadEstimate {
+                selection,
+                width: encoded.width,
+                height: encoded.height,
+                downscaled_from,
+                upscaled_from,
+                tiny,
+                bytes: encoded.byte_len,
+                tokens: None,
+            };
+            let _ = tx.send(StreamEvent::Estimate(estimate));
 
-        // System prompt
-        ui.label("System Instructions:");
-        ui.add(
-            egui::TextEdit::multiline(&mut self.settings.system_prompt)
-                .desired_rows(3)
-                .desired_width(f32::INFINITY),
-        );
-    }
+            let Ok(config) = config else {
+                return;
+            };
+            if config.openai.is_some() || (config.vertex.is_none() && config.gemini_api_key.is_empty()) {
+                return;
+            }
 
-    /// Renders the response state UI.
-    fn render_response_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, text: &str, thoughts: &str) {
-        ui.horizontal(|ui| {
-            ui.heading("Gemini says:");
-            if text.is_empty() && thoughts.is_empty() {
-                ui.spinner();
+            let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+            else {
+                return;
+            };
+            let tokens = runtime.block_on(async {
+                let client = GeminiClient::connect(&config).await?;
+                client.count_tokens(encoded, prompt).await
+            });
+            if let Ok(tokens) = tokens {
+                estimate.tokens = Some(tokens);
+                let _ = tx.send(StreamEvent::Estimate(estimate));
             }
         });
+    }
 
-        // Display thoughts if available
-        if !thoughts.is_empty() {
-            egui::CollapsingHeader::new("Thinking Process")
-                .default_open(true)
-                .show(ui, |ui| {
-                    egui::ScrollArea::vertical()
-                        .max_height(150.0)
-                        .id_salt("thoughts_scroll")
-                        .show(ui, |ui| {
-                            ui.label(
-                                egui::RichText::new(thoughts)
-                                    .monospace()
-                                    .small()
-                                    .color(egui::Color32::LIGHT_GRAY),
-                            );
-                        });
-                });
-            ui.add_space(8.0);
+    /// Moves or resizes a finalized selection via its handles or the keyboard.
+    ///
+    /// Returns `true` if the pointer interaction was consumed, in which case
+    /// it must not start a new selection.
+    fn adjust_selection(&mut self, ctx: &egui::Context, response: &egui::Response) -> bool {
+        if !self.is_selection_finalized || !matches!(self.state, UiState::Idle) {
+            return false;
         }
+        let (Some(start), Some(end)) = (self.selection_start, self.current_pos) else {
+            return false;
+        };
+        let rect = egui::Rect::from_two_pos(start, end);
+        let bounds = response.rect;
 
-        // Display response with markdown
-        egui::ScrollArea::vertical()
-            .max_height(300.0)
-            .show(ui, |ui| {
-                CommonMarkViewer::new().show(ui, &mut self.markdown_cache, text);
-            });
+        if self.active_handle.is_none()
+            && let Some(handle) = response.hover_pos().and_then(|pos| handle_at(rect, pos))
+        {
+            ctx.set_cursor_icon(handle.cursor());
+        }
 
-        ui.separator();
+        if response.drag_started() {
+            let origin = ctx.input(|i| i.pointer.press_origin());
+            self.active_handle = origin.and_then(|pos| handle_at(rect, pos));
+        }
 
-        // Action buttons
-        let mut should_go_back = false;
-        ui.horizontal(|ui| {
-            if ui.button("Copy").clicked() {
-                if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                    let _ = clipboard.set_text(text);
-                }
+        // Arrow keys nudge the selection (Alt works even while typing a prompt)
+        let typing = ctx.wants_keyboard_input();
+        let nudge = ctx.input(|i| {
+            if typing && !i.modifiers.alt {
+                return egui::Vec2::ZERO;
             }
-            if ui.button("Close").clicked() {
-                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            let step = if i.modifiers.shift { 10.0 } else { 1.0 };
+            let mut delta = egui::Vec2::ZERO;
+            if i.key_pressed(egui::Key::ArrowLeft) {
+                delta.x -= step;
             }
-            if ui.button("Back").clicked() {
-                should_go_back = true;
+            if i.key_pressed(egui::Key::ArrowRight) {
+                delta.x += step;
             }
+            if i.key_pressed(egui::Key::ArrowUp) {
+                delta.y -= step;
+            }
+            if i.key_pressed(egui::Key::ArrowDown) {
+                delta.y += step;
+            }
+            delta
         });
 
-        if should_go_back {
-            self.state = UiState::Idle;
+        let Some(handle) = self.active_handle else {
+            if nudge != egui::Vec2::ZERO {
+                self.set_selection(apply_handle_drag(rect, SelectionHandle::Move, nudge, bounds));
+                self.on_selection_changed(ctx);
+            }
+            return false;
+        };
+
+        ctx.set_cursor_icon(handle.cursor());
+        if response.dragged() {
+            self.set_selection(apply_handle_drag(rect, handle, response.drag_delta(), bounds));
         }
+        if response.drag_stopped() {
+            self.active_handle = None;
+            self.on_selection_changed(ctx);
+        }
+        true
     }
 
-    /// Renders the error state UI.
-    fn render_error_ui(&mut self, ui: &mut egui::Ui, error: &str) {
-        ui.label(egui::RichText::new(format!("Error: {}", error)).color(egui::Color32::RED));
-        if ui.button("Back").clicked() {
-            self.state = UiState::Idle;
+    /// Handles keyboard-only selection before a selection is finalized.
+    ///
+    /// Arrow keys move a crosshair (Ctrl for 10px steps), Shift+arrows grow
+    /// the selection from where the crosshair was, and Enter finalizes it.
+    fn keyboard_select(&mut self, ctx: &egui::Context, screen: egui::Rect) {
+        if self.is_selection_finalized
+            || !matches!(self.state, UiState::Idle)
+            || ctx.wants_keyboard_input()
+        {
+            return;
         }
-    }
-}
 
-impl eframe::App for SnippingTool {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Enforce dark mode
-        ctx.set_visuals(egui::Visuals::dark());
+        let (delta, grow, confirm) = ctx.input(|i| {
+            let step = if i.modifiers.command { 10.0 } else { 1.0 };
+            let mut delta = egui::Vec2::ZERO;
+            if i.key_pressed(egui::Key::ArrowLeft) {
+                delta.x -= step;
+            }
+            if i.key_pressed(egui::Key::ArrowRight) {
+                delta.x += step;
+            }
+            if i.key_pressed(egui::Key::ArrowUp) {
+                delta.y -= step;
+            }
+            if i.key_pressed(egui::Key::ArrowDown) {
+                delta.y += step;
+            }
+            (delta, i.modifiers.shift, i.key_pressed(egui::Key::Enter))
+        });
 
-        // Process any pending stream events
-        self.process_stream_events(ctx);
+        if delta != egui::Vec2::ZERO {
+            let cursor = self
+                .keyboard_cursor
+                .or_else(|| ctx.pointer_latest_pos())
+                .unwrap_or_else(|| screen.center());
+            let moved = screen.clamp(cursor + delta);
 
-        // Upload texture on first frame using pre-converted data
-        if self.image_texture.is_none() {
-            if let Some(color_image) = self.color_image.take() {
-                self.image_texture = Some(ctx.load_texture(
-                    "screenshot",
-                    color_image,
-                    egui::TextureOptions::LINEAR,
-                ));
+            if grow {
+                // Anchor the selection where the crosshair started growing
+                if self.keyboard_cursor.is_none() || self.selection_start.is_none() {
+                    self.selection_start = Some(cursor);
+                }
+                self.current_pos = Some(moved);
+            } else {
+                self.selection_start = None;
+                self.current_pos = None;
             }
+            self.keyboard_cursor = Some(moved);
         }
 
-        // Fullscreen panel with no margins
-        let panel_frame = egui::Frame::default()
-            .inner_margin(egui::Margin::same(0.0 as i8))
-            .outer_margin(egui::Margin::same(0.0 as i8));
+        if confirm
+            && self.keyboard_cursor.is_some()
+            && let (Some(start), Some(end)) = (self.selection_start, self.current_pos)
+            && is_valid_selection(start, end)
+        {
+            self.keyboard_cursor = None;
+            self.is_selection_finalized = true;
+            self.on_selection_changed(ctx);
+        }
+    }
 
-        egui::CentralPanel::default()
-            .frame(panel_frame)
-            .show(ctx, |ui| {
-                let rect = ui.max_rect();
+    /// Returns the aspect ratio to hold a new selection to, if any.
+    fn drag_aspect_ratio(&self, ctx: &egui::Context) -> Option<f32> {
+        if ctx.input(|i| i.modifiers.shift) {
+            Some(1.0)
+        } else {
+            self.aspect_ratio.ratio()
+        }
+    }
 
-                // Draw screenshot as background
-                if let Some(texture) = &self.image_texture {
-                    ui.painter().image(
-                        texture.id(),
-                        rect,
-                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                        egui::Color32::WHITE,
-                    );
-                }
+    /// Renders the toolbar for aspect ratio and exact-size selections.
+    ///
+    /// Shown at the top of the screen until a selection is finalized.
+    fn render_selection_toolbar(&mut self, ctx: &egui::Context, screen: egui::Rect) {
+        let mut exact_size = None;
+        let mut paste = false;
+        let mut history = false;
 
-                // Handle selection input (unless loading)
-                if !matches!(self.state, UiState::Loading) {
-                    let response = ui.interact(rect, ui.id(), egui::Sense::drag());
+        egui::Area::new(egui::Id::new("selection_toolbar"))
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 12.0))
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        for ratio in AspectRatio::ALL {
+                            ui.selectable_value(&mut self.aspect_ratio, ratio, ratio.label());
+                        }
+                        ui.separator();
 
-                    let event = process_drag_event(
-                        &response,
-                        &mut self.selection_start,
-                        &mut self.current_pos,
-                        self.is_selection_finalized,
-                    );
+                        let (width, height) = &mut self.exact_size_input;
+                        ui.label("Size:");
+                        ui.add(egui::TextEdit::singleline(width).desired_width(48.0).hint_text("w"))
+                            .accessible_name("Selection width in pixels");
+                        ui.label("×");
+                        ui.add(egui::TextEdit::singleline(height).desired_width(48.0).hint_text("h"))
+                            .accessible_name("Selection height in pixels");
 
-                    match event {
-                        SelectionEvent::Started => {
-                            self.is_selection_finalized = false;
-                            self.chat_input.clear();
-                            if matches!(self.state, UiState::Response { .. } | UiState::Error(_)) {
-                                self.state = UiState::Idle;
-                            }
+                        let parsed = width
+                            .trim()
+                            .parse::<u32>()
+                            .ok()
+                            .zip(height.trim().parse::<u32>().ok())
+                            .filter(|&(w, h)| w > 0 && h > 0);
+                        let button = ui
+                            .add_enabled(parsed.is_some(), egui::Button::new("Select"))
+                            .on_hover_text("Select exactly this many image pixels, centered");
+                        if button.clicked() {
+                            exact_size = parsed;
                         }
-                        SelectionEvent::Completed => {
-                            self.is_selection_finalized = true;
+                        ui.separator();
+
+                        if ui
+                            .button("📥")
+                            .accessible_name("Paste image")
+                            .on_hover_text("Use the image on the clipboard (Ctrl+V)")
+                            .clicked()
+                        {
+                            paste = true;
                         }
-                        _ => {}
+                        if ui
+                            .button("🕘")
+                            .accessible_name("History")
+                            .on_hover_text("History (H)")
+                            .clicked()
+                        {
+                            history = true;
+                        }
+                    });
+
+                    if let Some(message) = &self.status_message {
+                        ui.label(egui::RichText::new(message).small().color(egui::Color32::GRAY));
                     }
-                }
+                });
+            });
 
-                // Handle escape to close
-                if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
-                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
-                }
+        if let Some((width, height)) = exact_size {
+            // Convert image pixels to UI points (HiDPI captures are larger)
+            let size = egui::vec2(
+                width as f32 * screen.width() / self.screenshot.width() as f32,
+                height as f32 * screen.height() / self.screenshot.height() as f32,
+            );
+            let rect = egui::Rect::from_center_size(screen.center(), size).intersect(screen);
 
-                // Get current interaction position for drawing
-                let current_interaction_pos = if self.is_selection_finalized {
-                    self.current_pos
-                } else {
-                    ctx.pointer_interact_pos().or(self.current_pos)
-                };
+            self.keyboard_cursor = None;
+            self.content_kind = ContentKind::General;
+            self.conversation.clear();
+            self.set_selection(rect);
+            self.is_selection_finalized = true;
+            self.on_selection_changed(ctx);
+        }
+
+        if paste {
+            self.load_image(ctx, clipboard_image());
+        }
+        if history {
+            self.history_window = Some(HistoryWindow::new());
+        }
+    }
+
+    /// Replaces the screenshot with an image dropped onto the overlay or
+    /// pasted with Ctrl+V.
+    ///
+    /// Only done at the prompt, so an answer being read is never replaced.
+    fn handle_image_input(&mut self, ctx: &egui::Context) {
+        if !matches!(self.state, UiState::Idle) || self.is_streaming || self.settings_window.is_some() {
+            return;
+        }
+
+        let (dropped, paste) = ctx.input(|i| {
+            // Clipboard text is pasted into the prompt instead
+            let pasted_text = i.events.iter().any(|event| matches!(event, egui::Event::Paste(_)));
+            let paste_pressed = i.events.iter().any(|event| {
+                matches!(
+                    event,
+                    egui::Event::Key { key: egui::Key::V, pressed: true, modifiers, .. }
+                        if modifiers.command
+                )
+            });
+            (i.raw.dropped_files.first().cloned(), paste_pressed && !pasted_text)
+        });
+
+        if let Some(file) = dropped {
+            self.load_image(ctx, dropped_image(&file));
+        } else if paste {
+            self.load_image(ctx, clipboard_image());
+        }
+
+        // H opens the history unless text is being typed
+        if self.history_window.is_none()
+            && !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::H))
+        {
+            self.history_window = Some(HistoryWindow::new());
+        }
+    }
+
+    /// Shows a loaded image in place of the screenshot, or the error.
+    ///
+    /// Returns where the image appears on screen, in UI coordinates.
+    fn load_image(&mut self, ctx: &egui::Context, image: Result<DynamicImage>) -> Option<egui::Rect> {
+        let image = match image {
+            Ok(image) => image,
+            Err(e) => {
+                self.status_message = Some(e.to_string());
+                return None;
+            }
+        };
+
+        // Pad to the screen's shape so the image isn't stretched
+        let ui_size = ctx.viewport_rect().size();
+        let original_size = egui::vec2(image.width() as f32, image.height() as f32);
+        let image = ImageProcessor::letterbox(&image, ui_size.x / ui_size.y);
+        let scale = ui_size.x / image.width() as f32;
+        let image_rect = egui::Rect::from_center_size(
+            (ui_size * 0.5).to_pos2(),
+            original_size * scale,
+        );
+        self.color_image = Some(ImageProcessor::to_color_image(&image));
+        self.image_texture = None;
+        self.screenshot = image;
+        self.windows.clear();
+
+        self.selection_start = None;
+        self.current_pos = None;
+        self.is_selection_finalized = false;
+        self.active_handle = None;
+        self.extra_selections.clear();
+        self.keyboard_cursor = None;
+        self.redactions.clear();
+        self.masked_findings.clear();
+        self.sensitive_scan = None;
+        self.sensitive_scan_error = None;
+        self.content_kind = ContentKind::General;
+        self.conversation.clear();
+        self.edited_image = None;
+        self.payload_estimate = None;
+        self.selection_preview = None;
+        self.pending_request = None;
+        self.downscale_upload = false;
+        self.upscale_upload = None;
+        self.status_message = Some("Image loaded, select a region to ask about it".to_string());
+        ctx.request_repaint();
+        Some(image_rect)
+    }
+
+    /// Handles the `F` (full screen) and `Space` (snap to window) shortcuts.
+    fn handle_snap_shortcuts(&mut self, ctx: &egui::Context, screen: egui::Rect) {
+        if ctx.wants_keyboard_input() || !matches!(self.state, UiState::Idle) {
+            return;
+        }
+
+        let (full_screen, snap) =
+            ctx.input(|i| (i.key_pressed(egui::Key::F), i.key_pressed(egui::Key::Space)));
+
+        let target = if full_screen {
+            Some(screen)
+        } else if snap {
+            ctx.pointer_latest_pos().and_then(|pos| self.next_window_at(pos, screen))
+        } else {
+            None
+        };
+
+        if let Some(rect) = target {
+            self.keyboard_cursor = None;
+            self.content_kind = ContentKind::General;
+            self.conversation.clear();
+            self.set_selection(rect);
+            self.is_selection_finalized = true;
+            self.on_selection_changed(ctx);
+        }
+    }
+
+    /// Returns the window under `pos` to snap to, in UI coordinates.
+    ///
+    /// Repeated presses cycle from the frontmost window under the cursor to
+    /// the ones behind it.
+    fn next_window_at(&self, pos: egui::Pos2, screen: egui::Rect) -> Option<egui::Rect> {
+        let scale_x = screen.width() / self.screenshot.width() as f32;
+        let scale_y = screen.height() / self.screenshot.height() as f32;
+
+        let candidates: Vec<egui::Rect> = self
+            .windows
+            .iter()
+            .map(|window| {
+                egui::Rect::from_min_size(
+                    egui::pos2(window.x as f32 * scale_x, window.y as f32 * scale_y),
+                    egui::vec2(window.width as f32 * scale_x, window.height as f32 * scale_y),
+                )
+                .intersect(screen)
+            })
+            .filter(|rect| rect.contains(pos))
+            .collect();
+
+        let current = self
+            .selection_start
+            .zip(self.current_pos)
+            .map(|(start, end)| egui::Rect::from_two_pos(start, end));
+        let next = current
+            .and_then(|current| candidates.iter().position(|rect| *rect == current))
+            .map_or(0, |index| (index + 1) % candidates.len());
+
+        candidates.get(next).copied()
+    }
+
+    /// Stores the selection that was acted on, returned to the caller of
+    /// [`run`] once the overlay closes.
+    fn record_result(&self, selection: egui::Rect, ui_size: egui::Vec2, prompt: Option<String>) {
+        if let Ok(mut result) = self.result.lock() {
+            result.selected_area = Some(selection);
+            result.screen_size = Some(ui_size);
+            result.user_prompt = prompt;
+        }
+    }
+
+    /// Handles Escape, which steps back one level at a time.
+    ///
+    /// A response goes back to the prompt and the prompt closes the overlay.
+    /// When that would stop a streaming answer or discard a chat, the first
+    /// press only asks for a second one.
+    fn handle_escape(&mut self, ctx: &egui::Context) {
+        if self.settings_window.is_some() || !ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            return;
+        }
+        if self.history_window.take().is_some() {
+            return;
+        }
+
+        let loses_work = self.is_streaming
+            || (matches!(self.state, UiState::Idle) && !self.conversation.is_empty());
+        let confirmed = self
+            .escape_pressed_at
+            .take()
+            .is_some_and(|pressed| pressed.elapsed() < ESCAPE_CONFIRM_WINDOW);
+        if loses_work && !confirmed {
+            self.escape_pressed_at = Some(Instant::now());
+            self.status_message = Some(if self.is_streaming {
+                "Press Esc again to stop and discard the answer".to_string()
+            } else {
+                "Press Esc again to close and discard the chat".to_string()
+            });
+            return;
+        }
+
+        if matches!(self.state, UiState::Idle) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        } else {
+            self.status_message = None;
+            self.go_back();
+        }
+    }
+
+    /// Returns from the response to the prompt.
+    ///
+    /// A finished exchange is kept so asking again continues the chat; an
+    /// answer still streaming in is stopped and discarded.
+    fn go_back(&mut self) {
+        if self.is_streaming {
+            self.cancel_request();
+        } else if let (UiState::Response { text, .. }, Some((_, _, prompt))) =
+            (&self.state, &self.last_request)
+            && !text.is_empty()
+        {
+            self.conversation.push(ChatTurn {
+                prompt: prompt.clone(),
+                answer: text.clone(),
+            });
+        }
+        self.speech = None;
+        self.state = UiState::Idle;
+    }
+
+    /// Returns a copy of the screenshot with all redactions applied.
+    ///
+    /// Everything that leaves the overlay (uploads, OCR text, exports) is
+    /// derived from this copy rather than the raw screenshot.
+    fn redacted_screenshot(&self, ui_size: egui::Vec2) -> DynamicImage {
+        let mut screenshot = self.screenshot.clone();
+        ImageProcessor::apply_redactions(&mut screenshot, &self.redactions, ui_size);
+        screenshot
+    }
+
+    /// Draws a redaction when a redaction tool is active.
+    ///
+    /// Returns `true` if the drag was used for a redaction, in which case it
+    /// must not move the selection or start a new one.
+    fn draw_redaction(&mut self, ctx: &egui::Context, response: &egui::Response) -> bool {
+        let Some(style) = self.redaction_tool.filter(|_| self.is_selection_finalized) else {
+            return false;
+        };
+        let (Some(start), Some(end)) = (self.selection_start, self.current_pos) else {
+            return false;
+        };
+        let selection = egui::Rect::from_two_pos(start, end);
+
+        if response.drag_started() {
+            self.redaction_start = response
+                .interact_pointer_pos()
+                .filter(|pos| selection.contains(*pos));
+        }
+        let Some(redaction_start) = self.redaction_start else {
+            return false;
+        };
+
+        if response.drag_stopped() {
+            self.redaction_start = None;
+            let rect = response
+                .interact_pointer_pos()
+                .map(|pos| egui::Rect::from_two_pos(redaction_start, pos).intersect(selection));
+            if let Some(rect) = rect.filter(|r| r.width() >= 2.0 && r.height() >= 2.0) {
+                self.redactions.push(Redaction { rect, style });
+                self.estimate_payload(ctx);
+            }
+        }
+        true
+    }
+
+    /// Replaces the selection rectangle.
+    fn set_selection(&mut self, rect: egui::Rect) {
+        self.selection_start = Some(rect.min);
+        self.current_pos = Some(rect.max);
+    }
+
+    /// Refreshes state derived from the selection after it changes.
+    ///
+    /// When only picking a region, the first finalized selection is the
+    /// result and the overlay closes.
+    fn on_selection_changed(&mut self, ctx: &egui::Context) {
+        if self.pick_only {
+            if let (Some(start), Some(end)) = (self.selection_start, self.current_pos) {
+                let ui_size = ctx.viewport_rect().size();
+                self.record_result(egui::Rect::from_two_pos(start, end), ui_size, None);
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            return;
+        }
+        self.classify_selection(ctx);
+        self.estimate_payload(ctx);
+        if self.settings.mask_sensitive {
+            self.scan_sensitive(ctx);
+        } else {
+            self.sensitive_scan = None;
+        }
+    }
+
+    /// Looks for sensitive text in the finalized selection in the
+    /// background; what is found gets masked once reported.
+    ///
+    /// Requests made while the check runs are held back until it finishes.
+    fn scan_sensitive(&mut self, ctx: &egui::Context) {
+        let (Some(start), Some(end)) = (self.selection_start, self.current_pos) else {
+            return;
+        };
+
+        let selection = egui::Rect::from_two_pos(start, end);
+        let ui_size = ctx.viewport_rect().size();
+        let tx = self.tx.clone();
+        let screenshot = self.redacted_screenshot(ui_size);
+        self.sensitive_scan = Some(selection);
+        self.sensitive_scan_error = None;
+
+        thread::spawn(move || {
+            let findings =
+                find_sensitive(&screenshot, selection, ui_size).map_err(|e| e.to_string());
+            let _ = tx.send(StreamEvent::Sensitive {
+                selection,
+                findings,
+            });
+        });
+    }
+
+    /// Classifies the finalized selection to pick a content-aware default prompt.
+    fn classify_selection(&mut self, ctx: &egui::Context) {
+        let (Some(start), Some(end)) = (self.selection_start, self.current_pos) else {
+            return;
+        };
+
+        let selection = egui::Rect::from_two_pos(start, end);
+        self.content_kind = ImageProcessor::crop_selection(
+            &self.screenshot,
+            selection,
+            ctx.viewport_rect().size(),
+        )
+        .map(|cropped| classifier::classify(&cropped))
+        .unwrap_or_default();
+    }
+
+    /// Extracts the selection's text (and that of any extra regions) locally
+    /// and copies it to the clipboard.
+    ///
+    /// The result is shown in the response view without contacting Gemini.
+    fn run_ocr_only(&mut self, selection: egui::Rect, ui_size: egui::Vec2) {
+        self.record_result(selection, ui_size, None);
+        self.record_history = false;
+        self.state = UiState::Response {
+            text: String::new(),
+            thoughts: String::new(),
+        };
+        self.is_streaming = true;
+        self.start_request_timer();
+
+        let tx = self.tx.clone();
+        let screenshot = self.redacted_screenshot(ui_size);
+        let regions: Vec<egui::Rect> =
+            self.extra_selections.iter().copied().chain([selection]).collect();
+
+        thread::spawn(move || {
+            match ocr_regions(&screenshot, &regions, ui_size) {
+                Ok(text) => {
+                    if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                        let _ = clipboard.set_text(text.clone());
+                    }
+                    let _ = tx.send(StreamEvent::Chunk(text));
+                    let _ = tx.send(StreamEvent::Done);
+                }
+                Err(e) => {
+                    let _ = tx.send(StreamEvent::Error(e.to_string()));
+                }
+            }
+        });
+    }
+
+    /// Processes stream events from the background thread.
+    fn process_stream_events(&mut self, ctx: &egui::Context) {
+        while let Ok(event) = self.rx.try_recv() {
+            match event {
+                StreamEvent::Chunk(text) => {
+                    if let UiState::Response {
+                        text: current_text,
+                        ..
+                    } = &mut self.state
+                    {
+                        current_text.push_str(&text);
+                    } else {
+                        self.state = UiState::Response {
+                            text,
+                            thoughts: String::new(),
+                        };
+                    }
+                    ctx.request_repaint();
+                }
+                StreamEvent::Thought(thought) => {
+                    if let UiState::Response { thoughts, .. } = &mut self.state {
+                        thoughts.push_str(&thought);
+                    } else {
+                        self.state = UiState::Response {
+                            text: String::new(),
+                            thoughts: thought,
+                        };
+                    }
+                    ctx.request_repaint();
+                }
+                StreamEvent::Estimate(estimate) => {
+                    let current = self
+                        .selection_start
+                        .zip(self.current_pos)
+                        .map(|(start, end)| egui::Rect::from_two_pos(start, end));
+                    // Ignore estimates for a selection that has since changed
+                    if current == Some(estimate.selection) {
+                        if self.payload_estimate.is_none() {
+                            self.downscale_upload = estimate.bytes > LARGE_PAYLOAD_BYTES;
+                        }
+                        self.payload_estimate = Some(estimate);
+                        ctx.request_repaint();
+                    }
+                }
+                StreamEvent::Preview { selection, image } => {
+                    let current = self
+                        .selection_start
+                        .zip(self.current_pos)
+                        .map(|(start, end)| egui::Rect::from_two_pos(start, end));
+                    if current == Some(selection) {
+                        self.selection_preview = Some(ctx.load_texture(
+                            "selection_preview",
+                            image,
+                            egui::TextureOptions::LINEAR,
+                        ));
+                        ctx.request_repaint();
+                    }
+                }
+                StreamEvent::Sensitive {
+                        selection,
+                        findings,
+                    } => {
+                        // Results of a check for an earlier selection are dropped
+                        if self.sensitive_scan != Some(selection) {
+                            continue;
+                        }
+                        self.sensitive_scan = None;
+                        match findings {
+                            Ok(findings) => {
+                                for (_, rect) in &findings {
+                                    self.redactions.push(Redaction {
+                                        rect: *rect,
+                                        style: RedactionStyle::Pixelate,
+                                    });
+                                }
+                                if !findings.is_empty() {
+                                    self.masked_findings.extend(findings);
+                                    self.estimate_payload(ctx);
+                                }
+                                if let Some(request) = self.pending_request.take() {
+                                    self.submit_or_hold(request);
+                                }
+                            }
+                            Err(e) => {
+                                self.pending_request = None;
+                                self.status_message = Some(sensitive_scan_failed(&e));
+                                self.sensitive_scan_error = Some(e);
+                            }
+                        }
+                        ctx.request_repaint();
+                    }
+                StreamEvent::Image(image) => {
+                    let texture = ctx.load_texture(
+                        "edited_image",
+                        ImageProcessor::to_color_image(&image),
+                        egui::TextureOptions::LINEAR,
+                    );
+                    self.edited_image = Some((image, texture));
+                    ctx.request_repaint();
+                }
+                StreamEvent::ConfirmTool(request, reply) => {
+                    self.pending_tool_confirmation = Some((request, reply));
+                    ctx.request_repaint();
+                }
+                StreamEvent::Usage {
+                    input_tokens,
+                    output_tokens,
+                } => {
+                    self.usage = Some((input_tokens, output_tokens));
+                    ctx.request_repaint();
+                }
+                StreamEvent::Status(message) => {
+                    self.status_message = Some(message);
+                    ctx.request_repaint();
+                }
+                StreamEvent::Uploaded(link) => {
+                    let copied = arboard::Clipboard::new()
+                        .and_then(|mut clipboard| clipboard.set_text(link.as_str()));
+                    self.status_message = Some(match copied {
+                        Ok(()) => format!("Link copied: {}", link),
+                        Err(_) => format!("Shared at {}", link),
+                    });
+                    ctx.request_repaint();
+                }
+                StreamEvent::Error(err) => {
+                    self.state = UiState::Error(err);
+                    self.is_streaming = false;
+                    self.stop_request_timer();
+                }
+                StreamEvent::Done => {
+                    self.is_streaming = false;
+                    self.stop_request_timer();
+                    if let Some((input_tokens, output_tokens)) = self.usage {
+                        self.record_usage(input_tokens, output_tokens);
+                    }
+                    if std::mem::take(&mut self.record_history) {
+                        self.save_to_history();
+                    }
+                    if self.config.copy_answers {
+                        self.copy_answer();
+                    }
+                    if self.config.archive.is_enabled() {
+                        self.archive_answer();
+                    }
+                    self.active_checkpoint = None;
+                    if let Some(store) = &self.checkpoints {
+                        store.clear();
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        }
+
+        if self.is_streaming && self.last_checkpoint_at.elapsed() >= CHECKPOINT_INTERVAL {
+            self.write_checkpoint();
+        }
+    }
+
+    /// Copies the finished answer to the clipboard (`--copy`).
+    fn copy_answer(&mut self) {
+        let UiState::Response { text, .. } = &self.state else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        let copied = arboard::Clipboard::new().and_then(|mut clipboard| clipboard.set_text(text.as_str()));
+        match copied {
+            Ok(()) => {
+                println!("Answer copied to clipboard");
+                self.status_message = Some("Answer copied to clipboard".to_string());
+            }
+            Err(e) => eprintln!("Warning: Failed to copy answer: {}", e),
+        }
+    }
+
+    /// Makes sure the overlay covers exactly the captured screen.
+    ///
+    /// Some tiling window managers ignore or tile fullscreen windows, which
+    /// would show the screenshot offset from the real screen. After giving
+    /// the window manager a few frames, a misplaced fullscreen window is
+    /// replaced by a borderless window with the screen's exact geometry;
+    /// if even that doesn't fit, a warning is printed.
+    fn verify_window_geometry(&mut self, ctx: &egui::Context) {
+        let Some(screen) = self.screen.filter(|_| self.geometry_check_frames > 0 && !self.pinned)
+        else {
+            return;
+        };
+        self.geometry_check_frames -= 1;
+        ctx.request_repaint();
+        if self.geometry_check_frames > 0 {
+            return;
+        }
+
+        let expected = screen_rect(&screen);
+        let Some(actual) = ctx.input(|i| i.viewport().inner_rect) else {
+            return;
+        };
+        // Allow for rounding between physical and logical pixels
+        let fits = (actual.min - expected.min).length() <= 2.0
+            && (actual.size() - expected.size()).length() <= 2.0;
+        if fits {
+            return;
+        }
+
+        if self.borderless_fallback {
+            eprintln!(
+                "Warning: Overlay at {:?} does not cover the captured screen at {:?}; \
+                 selections may be offset",
+                actual, expected
+            );
+        } else {
+            self.borderless_fallback = true;
+            self.geometry_check_frames = GEOMETRY_CHECK_FRAMES;
+            for command in borderless_commands(expected) {
+                ctx.send_viewport_cmd(command);
+            }
+        }
+    }
+
+    /// Adds the finished answer and its selection to the history, then
+    /// removes what the retention policy no longer keeps.
+    ///
+    /// Cropping and writing the images happens in the background.
+    fn save_to_history(&self) {
+        let (Some((selection, ui_size, prompt)), UiState::Response { text, thoughts }) =
+            (self.last_request.clone(), &self.state)
+        else {
+            return;
+        };
+        if text.trim().is_empty() {
+            return;
+        }
+
+        let (input_tokens, output_tokens) = self.usage.unwrap_or_default();
+        let entry = HistoryEntry::new(prompt, &self.answer_model, text.as_str())
+            .with_thoughts(thoughts.as_str())
+            .with_usage(input_tokens, output_tokens);
+        let retention = self.config.history;
+        let screenshot = self.redacted_screenshot(ui_size);
+        thread::spawn(move || {
+            let Some(mut store) = HistoryStore::open_default() else {
+                return;
+            };
+            let result = ImageProcessor::crop_selection(&screenshot, selection, ui_size)
+                .and_then(|crop| store.record(&entry, &crop))
+                .and_then(|()| store.apply_retention(&retention));
+            if let Err(e) = result {
+                eprintln!("Warning: Failed to save history: {}", e);
+            }
+        });
+    }
+
+    /// Adds a finished request to the usage log, warning in the status line
+    /// once the monthly budget is nearly spent.
+    fn record_usage(&mut self, input_tokens: u32, output_tokens: u32) {
+        if let Err(e) = usage::record(&self.answer_model, input_tokens, output_tokens) {
+            eprintln!("Warning: Failed to record usage: {}", e);
+        }
+        match usage::budget_status(&self.config.budget) {
+            Ok(status) => {
+                if let Some(warning) = status.warning() {
+                    self.status_message = Some(warning);
+                }
+            }
+            Err(e) => eprintln!("Warning: Failed to read usage: {}", e),
+        }
+    }
+
+    /// Saves the crop and the answer to the `--save-image`/`--save-answer` paths.
+    fn archive_answer(&self) {
+        let (Some((selection, ui_size, _)), UiState::Response { text, .. }) =
+            (self.last_request.clone(), &self.state)
+        else {
+            return;
+        };
+
+        let archive = self.config.archive.clone();
+        let answer = text.clone();
+        let model = self.answer_model.clone();
+        let screenshot = self.redacted_screenshot(ui_size);
+        let stamp = self.settings.stamp();
+        thread::spawn(move || {
+            let result = ImageProcessor::crop_selection(&screenshot, selection, ui_size)
+                .and_then(|crop| archive.save(&stamp.apply(&crop), &answer, &model));
+            match result {
+                Ok(paths) => {
+                    for path in paths {
+                        println!("Saved {}", path.display());
+                    }
+                }
+                Err(e) => eprintln!("Warning: Failed to archive answer: {}", e),
+            }
+        });
+    }
+
+    /// Re-opens or re-asks an entry chosen in the history browser.
+    fn apply_history_action(&mut self, ctx: &egui::Context, action: HistoryAction) {
+        let (entry, image, reask) = match action {
+            HistoryAction::Close => {
+                self.history_window = None;
+                return;
+            }
+            HistoryAction::Reopen(entry, image) => (entry, image, false),
+            HistoryAction::Reask(entry, image) => (entry, image, true),
+        };
+        self.history_window = None;
+        if self.is_streaming {
+            self.cancel_request();
+        }
+
+        let Some(rect) = self.load_image(ctx, Ok(image)) else {
+            return;
+        };
+        self.set_selection(rect);
+        self.is_selection_finalized = true;
+        self.status_message = None;
+
+        let ui_size = ctx.viewport_rect().size();
+        if reask {
+            self.submit_request(rect, ui_size, entry.prompt);
+        } else {
+            self.last_request = Some((rect, ui_size, entry.prompt));
+            self.usage = None;
+            self.request_duration = None;
+            self.state = UiState::Response {
+                text: entry.answer,
+                thoughts: String::new(),
+            };
+        }
+    }
+
+    /// Starts timing a new request.
+    fn start_request_timer(&mut self) {
+        self.request_started = Some(Instant::now());
+        self.request_duration = None;
+        self.usage = None;
+    }
+
+    /// Records how long the current request took.
+    fn stop_request_timer(&mut self) {
+        self.request_duration = self.request_started.map(|started| started.elapsed());
+    }
+
+    /// Cancels the request that is streaming in, keeping any partial answer.
+    ///
+    /// The background task notices on its next event that nobody is
+    /// listening and drops the connection.
+    fn cancel_request(&mut self) {
+        let (tx, rx) = channel();
+        self.tx = tx;
+        self.rx = rx;
+        self.is_streaming = false;
+        self.pending_tool_confirmation = None;
+        self.stop_request_timer();
+        self.active_checkpoint = None;
+        if let Some(store) = &self.checkpoints {
+            store.clear();
+        }
+        self.status_message = Some("Cancelled".to_string());
+    }
+
+    /// Renders elapsed time, token usage, and a cancel button for a request.
+    fn render_request_progress(&mut self, ui: &mut egui::Ui) {
+        let elapsed = match (self.request_duration, self.request_started) {
+            (Some(duration), _) => duration,
+            (None, Some(started)) => started.elapsed(),
+            (None, None) => return,
+        };
+
+        let mut summary = format!("{:.1}s", elapsed.as_secs_f32());
+        if let Some((input_tokens, output_tokens)) = self.usage {
+            summary.push_str(&format!(" · {} in / {} out tokens", input_tokens, output_tokens));
+        }
+        ui.label(egui::RichText::new(summary).small().color(egui::Color32::GRAY));
+
+        if self.is_streaming {
+            // Keep the timer ticking even when no chunks arrive
+            ui.ctx().request_repaint_after(Duration::from_millis(100));
+            if ui.small_button("Cancel").clicked() {
+                self.cancel_request();
+            }
+        }
+    }
+
+    /// Saves the partial response to the checkpoint store.
+    fn write_checkpoint(&mut self) {
+        self.last_checkpoint_at = Instant::now();
+
+        let (Some(store), Some(checkpoint)) = (&self.checkpoints, &mut self.active_checkpoint) else {
+            return;
+        };
+        let UiState::Response { text, thoughts } = &self.state else {
+            return;
+        };
+
+        checkpoint.text.clone_from(text);
+        checkpoint.thoughts.clone_from(thoughts);
+        if let Err(e) = store.save(checkpoint) {
+            eprintln!("Warning: Failed to write response checkpoint: {}", e);
+        }
+    }
+
+    /// Renders the window offering an answer recovered from a previous session.
+    fn render_recovered_window(&mut self, ctx: &egui::Context) {
+        let Some(checkpoint) = &self.recovered_checkpoint else {
+            return;
+        };
+
+        let mut keep_open = true;
+        let mut dismiss = false;
+        egui::Window::new("Recovered unfinished answer")
+            .collapsible(false)
+            .default_width(500.0)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 20.0))
+            .open(&mut keep_open)
+            .show(ctx, |ui| {
+                ui.label(format!("{} — \"{}\"", checkpoint.model, checkpoint.prompt));
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .id_salt("recovered_scroll")
+                    .show(ui, |ui| {
+                        show_markdown(
+                            ui,
+                            &mut self.markdown_cache,
+                            &sanitize_partial(&checkpoint.text),
+                            &self.settings.syntax_theme,
+                        );
+                    });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Copy").clicked()
+                        && let Ok(mut clipboard) = arboard::Clipboard::new()
+                    {
+                        let _ = clipboard.set_text(checkpoint.text.clone());
+                    }
+                    if ui.button("Discard").clicked() {
+                        dismiss = true;
+                    }
+                });
+            });
+
+        if dismiss {
+            let checkpoint = self.recovered_checkpoint.take();
+            if let (Some(store), Some(checkpoint)) = (&self.checkpoints, checkpoint) {
+                store.discard(&checkpoint);
+                // Offer the next interrupted answer, if several were left behind
+                self.recovered_checkpoint = store.load();
+            }
+        } else if !keep_open {
+            self.recovered_checkpoint = None;
+        }
+    }
+
+    /// Returns the prompt sent when the prompt box is left empty.
+    fn default_prompt(&self) -> String {
+        match self.settings.default_prompt.trim() {
+            "" => self.content_kind.default_prompt().to_string(),
+            prompt => prompt.to_string(),
+        }
+    }
+
+    /// Returns what the prompt box starts out with for a new selection.
+    fn initial_prompt(&self) -> String {
+        if self.settings.prefill_last_prompt {
+            self.settings.last_prompt.clone()
+        } else {
+            String::new()
+        }
+    }
+
+    /// Returns whether requests would fail for lack of credentials.
+    ///
+    /// Vertex AI and OpenAI-compatible gateways bring their own credentials.
+    fn needs_api_key(&self) -> bool {
+        self.settings.api_key.trim().is_empty()
+            && self.config.gemini_api_key.trim().is_empty()
+            && self.config.vertex.is_none()
+            && self.config.openai.is_none()
+            && !self.settings.profile_has_credentials()
+    }
+
+    /// Sends a request, or holds it back until the check for sensitive
+    /// text finishes and the missing API key is set.
+    ///
+    /// Nothing is sent while the last check for sensitive text failed.
+    fn submit_or_hold(&mut self, request: PendingRequest) {
+        let masking = self.settings.mask_sensitive;
+        if let Some(error) = self.sensitive_scan_error.as_ref().filter(|_| masking) {
+            self.status_message = Some(sensitive_scan_failed(error));
+        } else if masking && self.sensitive_scan.is_some() {
+            self.pending_request = Some(request);
+            self.status_message = Some(
+                "Checking the selection for sensitive text; your question is sent once it's done"
+                    .to_string(),
+            );
+        } else if self.needs_api_key() {
+            self.pending_request = Some(request);
+            self.status_message =
+                Some("Set an API key above; your question is sent once it's accepted".to_string());
+        } else {
+            self.submit_pending(request);
+        }
+    }
+
+    /// Sends a request that was built in the prompt popup.
+    fn submit_pending(&mut self, request: PendingRequest) {
+        let PendingRequest { selection, ui_size, prompt, edit } = request;
+        if edit {
+            self.submit_edit_request(selection, ui_size, prompt);
+        } else {
+            self.submit_request(selection, ui_size, prompt);
+        }
+    }
+
+    /// Renders the idle state UI (prompt input).
+    fn render_idle_ui(&mut self, ui: &mut egui::Ui, selection_rect: egui::Rect) {
+        // Ask for a missing key before the request fails, then send what was held back
+        if self.needs_api_key() {
+            let form = self.api_key_form.get_or_insert_with(ApiKeyForm::new);
+            if let Some(key) = form.show(ui, &self.settings.model) {
+                self.api_key_form = None;
+                self.settings.api_key = key;
+                if let Err(e) = self.settings.save() {
+                    eprintln!("Warning: Failed to save settings: {}", e);
+                }
+                if let Some(request) = self.pending_request.take() {
+                    self.submit_or_hold(request);
+                    return;
+                }
+                self.status_message = Some("API key saved".to_string());
+            }
+            ui.separator();
+        }
+
+        ui.horizontal(|ui| {
+            let label = ui.label("Ask Gemini:");
+            let hint = self.default_prompt();
+            let response = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.chat_input)
+                        .desired_width(200.0)
+                        .hint_text(hint),
+                )
+                .labelled_by(label.id);
+
+            // Focus the prompt when nothing else is, so Tab can still move
+            // on to the buttons in reading order
+            if self.settings_window.is_none() && ui.memory(|m| m.focused().is_none()) {
+                response.request_focus();
+            }
+
+            let enter_pressed = (response.has_focus() || response.lost_focus())
+                && ui.input(|i| i.key_pressed(egui::Key::Enter))
+                && (self.settings.empty_enter_submits || !self.chat_input.trim().is_empty());
+            if ui.button("➤").accessible_name("Send").clicked() || enter_pressed {
+                let prompt = if self.chat_input.trim().is_empty() {
+                    self.default_prompt()
+                } else {
+                    self.settings.last_prompt = self.chat_input.clone();
+                    self.chat_input.clone()
+                };
+
+                self.submit_or_hold(PendingRequest {
+                    selection: selection_rect,
+                    ui_size: ui.ctx().viewport_rect().size(),
+                    prompt,
+                    edit: false,
+                });
+            }
+
+            let edit_button = ui
+                .add_enabled(!self.chat_input.trim().is_empty(), egui::Button::new("🎨"))
+                .accessible_name("Edit with AI")
+                .on_hover_text("Edit with AI (e.g. \"remove the background\")");
+            if edit_button.clicked() {
+                self.submit_or_hold(PendingRequest {
+                    selection: selection_rect,
+                    ui_size: ui.ctx().viewport_rect().size(),
+                    prompt: self.chat_input.clone(),
+                    edit: true,
+                });
+            }
+
+            if ui.button("OCR").on_hover_text("Copy the selection's text without asking Gemini").clicked() {
+                self.run_ocr_only(selection_rect, ui.ctx().viewport_rect().size());
+            }
+
+            // Plain screenshot actions that never contact Gemini
+            let ui_size = ui.ctx().viewport_rect().size();
+            let (copy_pressed, save_pressed) = ui.input_mut(|i| {
+                let copy = self.chat_input.is_empty()
+                    && i.events.iter().any(|event| matches!(event, egui::Event::Copy));
+                (copy, i.consume_key(egui::Modifiers::COMMAND, egui::Key::S))
+            });
+            if ui
+                .button("📋")
+                .accessible_name("Copy image")
+                .on_hover_text("Copy image (Ctrl+C)")
+                .clicked()
+                || copy_pressed
+            {
+                self.status_message = Some(match self.copy_selection_image(selection_rect, ui_size) {
+                    Ok(()) => "Image copied to clipboard".to_string(),
+                    Err(e) => format!("Copy failed: {}", e),
+                });
+            }
+            if ui
+                .button("💾")
+                .accessible_name("Save as PNG")
+                .on_hover_text("Save as PNG (Ctrl+S)")
+                .clicked()
+                || save_pressed
+            {
+                self.status_message = Some(match self.save_selection_image(selection_rect, ui_size) {
+                    Ok(path) => format!("Saved to {}", path.display()),
+                    Err(e) => format!("Save failed: {}", e),
+                });
+            }
+            if self.config.upload.is_some()
+                && ui
+                    .button("🔗")
+                    .accessible_name("Share link")
+                    .on_hover_text("Upload the selection and copy its link")
+                    .clicked()
+            {
+                self.share_selection(ui.ctx(), selection_rect, ui_size, String::new(), None);
+            }
+
+            if ui
+                .button("🕘")
+                .accessible_name("History")
+                .on_hover_text("History (H)")
+                .clicked()
+            {
+                self.history_window = Some(HistoryWindow::new());
+            }
+            if ui.button("⚙").accessible_name("Settings").clicked() {
+                self.settings_window = Some(SettingsWindow::new(&self.config));
+            }
+        });
+
+        // Redaction tools: drag inside the selection to mask a region
+        ui.horizontal(|ui| {
+            ui.label(egui::RichText::new("Redact:").small());
+            for (style, label, hint) in [
+                (RedactionStyle::Pixelate, "▦", "Pixelate a region"),
+                (RedactionStyle::Fill, "■", "Black out a region"),
+            ] {
+                let active = self.redaction_tool == Some(style);
+                let response = ui.selectable_label(active, label).accessible_name(hint);
+                if response.on_hover_text(hint).clicked() {
+                    self.redaction_tool = if active { None } else { Some(style) };
+                }
+            }
+            ui.separator();
+            if ui
+                .selectable_label(self.dimming_hidden, "◐")
+                .accessible_name("Hide the dimming")
+                .on_hover_text("Hide the dimming to read what it covers (D)")
+                .clicked()
+            {
+                self.dimming_hidden = !self.dimming_hidden;
+            }
+            if !self.redactions.is_empty() {
+                if ui
+                    .small_button("↶")
+                    .accessible_name("Undo last redaction")
+                    .on_hover_text("Undo last redaction")
+                    .clicked()
+                {
+                    self.redactions.pop();
+                    self.estimate_payload(ui.ctx());
+                }
+                ui.label(
+                    egui::RichText::new(format!("{} masked", self.redactions.len()))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                );
+            }
+        });
+
+        // Automatic masks are listed so a wrong guess can be undone
+        if !self.masked_findings.is_empty() {
+            ui.horizontal(|ui| {
+                let findings: Vec<Finding> =
+                    self.masked_findings.iter().map(|(finding, _)| finding.clone()).collect();
+                let previews: Vec<String> = findings
+                    .iter()
+                    .map(|finding| format!("{}: {}", finding.kind, finding.preview))
+                    .collect();
+                ui.label(
+                    egui::RichText::new(format!("🔒 Masked {}", privacy::summary(&findings)))
+                        .small()
+                        .color(egui::Color32::GRAY),
+                )
+                .on_hover_text(previews.join("\n"));
+                if ui
+                    .small_button("Unmask")
+                    .on_hover_text("Send the detected text as it is")
+                    .clicked()
+                {
+                    let masked: Vec<egui::Rect> =
+                        self.masked_findings.drain(..).map(|(_, rect)| rect).collect();
+                    self.redactions.retain(|redaction| !masked.contains(&redaction.rect));
+                    self.estimate_payload(ui.ctx());
+                }
+            });
+        }
+
+        // Asking again after going back continues the same chat
+        if !self.conversation.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new(format!(
+                        "Continuing chat ({} earlier answers)",
+                        self.conversation.len()
+                    ))
+                    .small()
+                    .color(egui::Color32::GRAY),
+                );
+                if ui
+                    .small_button("✖")
+                    .accessible_name("Start a new chat")
+                    .on_hover_text("Start a new chat")
+                    .clicked()
+                {
+                    self.conversation.clear();
+                }
+            });
+        }
+
+        if let Some(message) = &self.status_message {
+            ui.label(egui::RichText::new(message).small().color(egui::Color32::GRAY));
+        }
+
+        // Regions added with Ctrl+drag are sent as separate images
+        if !self.extra_selections.is_empty() {
+            ui.horizontal(|ui| {
+                ui.label(
+                    egui::RichText::new(format!("{} regions", self.extra_selections.len() + 1))
+                        .small()
+                        .color(egui::Color32::LIGHT_BLUE),
+                );
+                if ui
+                    .small_button("✖")
+                    .accessible_name("Keep only the current region")
+                    .on_hover_text("Keep only the current region")
+                    .clicked()
+                {
+                    self.extra_selections.clear();
+                }
+                ui.checkbox(&mut self.combine_regions, "As one image").on_hover_text(
+                    "Send the regions side by side in a single image, labelled A, B, C in \
+                     the order they were added (the current region is last)",
+                );
+            });
+        }
+
+        // Exactly what will be uploaded, to catch clipping or missed redactions
+        if let Some(preview) = &self.selection_preview {
+            ui.add(
+                egui::Image::new(preview)
+                    .max_size(egui::vec2(PREVIEW_SIZE.0 as f32, PREVIEW_SIZE.1 as f32))
+                    .corner_radius(2.0),
+            )
+            .on_hover_text("Preview of the image that will be sent");
+        }
+
+        ui.checkbox(&mut self.enhance_text, "Sharpen text").on_hover_text(
+            "Send the selection in grayscale with more contrast and sharper edges, \
+             which helps with small or faint text and code",
+        );
+
+        // Payload size, with an offer to downscale oversized selections
+        if let Some(estimate) = self.payload_estimate {
+            let mut summary = format!(
+                "{}×{} · {:.1} MB",
+                estimate.width,
+                estimate.height,
+                estimate.bytes as f64 / (1024.0 * 1024.0)
+            );
+            if let Some((width, height)) =
+                estimate.downscaled_from.or(estimate.upscaled_from)
+            {
+                summary = format!("{}×{} → {}", width, height, summary);
+            }
+            if let Some(tokens) = estimate.tokens {
+                summary.push_str(&format!(" · {} tokens", tokens));
+            }
+            ui.label(egui::RichText::new(summary).small().color(egui::Color32::GRAY));
+
+            let oversized = estimate.bytes > LARGE_PAYLOAD_BYTES
+                || estimate.width.max(estimate.height) > DOWNSCALE_MAX_EDGE * 2;
+            if oversized {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new("⚠ Large selection, upload may be slow")
+                            .small()
+                            .color(egui::Color32::YELLOW),
+                    );
+                    ui.checkbox(
+                        &mut self.downscale_upload,
+                        format!("Downscale to {}px", DOWNSCALE_MAX_EDGE),
+                    );
+                });
+            }
+
+            // Tiny selections give the model too few pixels to read
+            if estimate.tiny {
+                ui.horizontal(|ui| {
+                    let mut enlarge = estimate.upscaled_from.is_some();
+                    let label = match estimate.upscaled_from {
+                        Some((width, _)) => {
+                            format!("🔍 Enlarged ×{}", estimate.width / width.max(1))
+                        }
+                        None => "🔍 Enlarge tiny selection".to_string(),
+                    };
+                    if ui
+                        .checkbox(&mut enlarge, label)
+                        .on_hover_text(
+                            "Scale the selection up before sending so the model gets more \
+                             pixels to work with; set upscale_min_edge and upscale_filter in \
+                             config.toml to do this by default",
+                        )
+                        .changed()
+                    {
+                        self.upscale_upload = Some(enlarge);
+                        self.estimate_payload(ui.ctx());
+                    }
+                });
+            }
+        }
+    }
+
+    /// Renders the response state UI.
+    fn render_response_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, text: &str, thoughts: &str) {
+        ui.horizontal(|ui| {
+            ui.heading("Gemini says:");
+            if self.is_streaming && text.is_empty() && thoughts.is_empty() {
+                ui.spinner();
+            }
+            self.render_request_progress(ui);
+        });
+
+        // Display thoughts if available
+        if !thoughts.is_empty() {
+            egui::CollapsingHeader::new("Thinking Process")
+                .default_open(true)
+                .show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(150.0)
+                        .id_salt("thoughts_scroll")
+                        .show(ui, |ui| {
+                            ui.label(
+                                egui::RichText::new(thoughts)
+                                    .monospace()
+                                    .small()
+                                    .color(egui::Color32::LIGHT_GRAY),
+                            );
+                        });
+                });
+            ui.add_space(8.0);
+        }
+
+        // Ask before running confirmation-gated tools
+        if let Some((request, _)) = &self.pending_tool_confirmation {
+            let request = request.clone();
+            let mut decision = None;
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.label("Gemini wants to run:");
+                ui.label(egui::RichText::new(&request).monospace());
+                ui.horizontal(|ui| {
+                    if ui.button("Allow").clicked() {
+                        decision = Some(true);
+                    }
+                    if ui.button("Deny").clicked() {
+                        decision = Some(false);
+                    }
+                });
+            });
+            if let Some(allowed) = decision
+                && let Some((_, reply)) = self.pending_tool_confirmation.take()
+            {
+                let _ = reply.send(allowed);
+            }
+            ui.add_space(8.0);
+        }
+
+        // Display the image returned by an "Edit with AI" request
+        if let Some((_, texture)) = &self.edited_image {
+            let size = texture.size_vec2();
+            let scale = (ui.available_width() / size.x).min(300.0 / size.y).min(1.0);
+            ui.image((texture.id(), size * scale));
+            ui.add_space(8.0);
+        }
+
+        // Display response with markdown, closing constructs split mid-stream
+        self.render_transcript(ui, text, 400.0);
+
+        ui.separator();
+
+        // Follow-up question in the same chat
+        let mut follow_up = None;
+        ui.add_enabled_ui(!self.is_streaming, |ui| {
+            ui.horizontal(|ui| {
+                let response = ui
+                    .add(
+                        egui::TextEdit::singleline(&mut self.follow_up_input)
+                            .desired_width(ui.available_width() - 40.0)
+                            .hint_text("Ask a follow-up..."),
+                    )
+                    .accessible_name("Follow-up question");
+                let enter_pressed =
+                    response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if (ui.button("➤").accessible_name("Send follow-up").clicked() || enter_pressed)
+                    && !self.follow_up_input.trim().is_empty()
+                {
+                    follow_up = Some(std::mem::take(&mut self.follow_up_input));
+                }
+            });
+        });
+
+        // Action buttons
+        let mut should_go_back = false;
+        let mut export_format = None;
+        let mut save_image = false;
+        let mut plugin = None;
+        let mut share = false;
+        ui.horizontal(|ui| {
+            if ui.button("Copy").clicked()
+                && let Ok(mut clipboard) = arboard::Clipboard::new()
+            {
+                let _ = clipboard.set_text(text);
+            }
+            let has_code = text.contains("```") || text.contains("~~~");
+            if ui
+                .add_enabled(has_code, egui::Button::new("Copy code"))
+                .on_hover_text("Copy only the code blocks")
+                .clicked()
+                && let Ok(mut clipboard) = arboard::Clipboard::new()
+            {
+                let _ = clipboard.set_text(extract_code(text));
+            }
+            let speaking = self.speech.as_mut().is_some_and(Speech::is_speaking);
+            if speaking {
+                // Poll so the button resets when speech ends
+                ctx.request_repaint_after(Duration::from_millis(250));
+                if ui
+                    .button("⏹")
+                    .accessible_name("Stop reading")
+                    .on_hover_text("Stop reading")
+                    .clicked()
+                {
+                    self.speech = None;
+                }
+            } else if ui
+                .add_enabled(!self.is_streaming && !text.is_empty(), egui::Button::new("🔊"))
+                .accessible_name("Read the answer aloud")
+                .on_hover_text("Read the answer aloud")
+                .clicked()
+            {
+                match Speaker::from_env().speak(text) {
+                    Ok(speech) => self.speech = Some(speech),
+                    Err(e) => self.status_message = Some(e.to_string()),
+                }
+            }
+            ui.add_enabled_ui(!self.is_streaming, |ui| {
+                ui.menu_button("Save conversation", |ui| {
+                    if ui.button("Markdown").clicked() {
+                        export_format = Some(ExportFormat::Markdown);
+                        ui.close();
+                    }
+                    if ui.button("HTML").clicked() {
+                        export_format = Some(ExportFormat::Html);
+                        ui.close();
+                    }
+                });
+            });
+            if self.edited_image.is_some() && ui.button("Save image").clicked() {
+                save_image = true;
+            }
+            ui.add_enabled_ui(!self.is_streaming && !text.is_empty(), |ui| {
+                for configured in &self.config.plugins {
+                    if ui
+                        .button(&configured.name)
+                        .on_hover_text(format!("Run {}", configured.command))
+                        .clicked()
+                    {
+                        plugin = Some(configured.clone());
+                    }
+                }
+            });
+            if self.config.upload.is_some()
+                && ui
+                    .add_enabled(!self.is_streaming, egui::Button::new("🔗 Share"))
+                    .on_hover_text("Upload the selection and copy its link")
+                    .clicked()
+            {
+                share = true;
+            }
+            if ui
+                .button("📌")
+                .accessible_name("Pin the answer")
+                .on_hover_text("Pin the answer in a small window")
+                .clicked()
+            {
+                self.set_pinned(ctx, true);
+            }
+            if ui.button("Close").clicked() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            if ui.button("Back").clicked() {
+                should_go_back = true;
+            }
+        });
+
+        if save_image {
+            self.status_message = Some(match self.save_edited_image() {
+                Ok(path) => format!("Saved to {}", path.display()),
+                Err(e) => format!("Save failed: {}", e),
+            });
+        }
+
+        if let Some(plugin) = plugin {
+            self.run_plugin(ctx, plugin, text, thoughts);
+        }
+
+        if let Some((selection, ui_size, prompt)) = self.last_request.clone().filter(|_| share) {
+            self.share_selection(ctx, selection, ui_size, prompt, Some(text.to_string()));
+        }
+
+        if let Some(format) = export_format {
+            self.status_message = Some(match self.export_conversation(text, thoughts, format) {
+                Ok(path) => format!("Saved to {}", path.display()),
+                Err(e) => format!("Export failed: {}", e),
+            });
+        }
+
+        if let Some(message) = &self.status_message {
+            ui.label(egui::RichText::new(message).small().color(egui::Color32::GRAY));
+        }
+
+        if let Some(prompt) = follow_up
+            && let Some((selection, ui_size, previous_prompt)) = self.last_request.clone()
+        {
+            self.conversation.push(ChatTurn {
+                prompt: previous_prompt,
+                answer: text.to_string(),
+            });
+            self.submit_request(selection, ui_size, prompt);
+        }
+
+        if should_go_back {
+            self.go_back();
+        }
+    }
+
+    /// Renders the chat transcript with `text` as the latest answer.
+    fn render_transcript(&mut self, ui: &mut egui::Ui, text: &str, max_height: f32) {
+        let font_size = self.settings.response_font_size;
+        let response = egui::ScrollArea::vertical()
+            .max_height(max_height)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                mark_region(ui, accesskit::Role::Log, "Conversation");
+
+                // Scale all text styles so headings and code keep their proportions
+                let scale = font_size / egui::TextStyle::Body.resolve(ui.style()).size;
+                for font in ui.style_mut().text_styles.values_mut() {
+                    font.size *= scale;
+                }
+
+                // Earlier exchanges stay above the latest answer
+                for turn in &self.conversation {
+                    render_user_message(ui, &mut self.markdown_cache, &turn.prompt);
+                    show_markdown(
+                        ui,
+                        &mut self.markdown_cache,
+                        &turn.answer,
+                        &self.settings.syntax_theme,
+                    );
+                    ui.add_space(8.0);
+                }
+                if let Some((_, _, prompt)) = &self.last_request {
+                    render_user_message(ui, &mut self.markdown_cache, prompt);
+                }
+                self.answer_blocks.show(
+                    ui,
+                    &mut self.markdown_cache,
+                    text,
+                    self.is_streaming,
+                    &self.settings.syntax_theme,
+                );
+            });
+
+        self.zoom_response_text(ui, response.inner_rect);
+    }
+
+    /// Changes the answer text size with Ctrl+scroll over the transcript or
+    /// Ctrl+plus/minus, and resets it with Ctrl+0.
+    fn zoom_response_text(&mut self, ui: &egui::Ui, transcript: egui::Rect) {
+        use egui::gui_zoom::kb_shortcuts;
+
+        let size = self.settings.response_font_size;
+        let hovered = ui.rect_contains_pointer(transcript);
+        let new_size = ui.input_mut(|i| {
+            if i.consume_shortcut(&kb_shortcuts::ZOOM_RESET) {
+                DEFAULT_RESPONSE_FONT_SIZE
+            } else if i.consume_shortcut(&kb_shortcuts::ZOOM_IN)
+                || i.consume_shortcut(&kb_shortcuts::ZOOM_IN_SECONDARY)
+            {
+                size + 1.0
+            } else if i.consume_shortcut(&kb_shortcuts::ZOOM_OUT) {
+                size - 1.0
+            } else if hovered {
+                size * i.zoom_delta()
+            } else {
+                size
+            }
+        });
+
+        let new_size = new_size.clamp(*RESPONSE_FONT_SIZE_RANGE.start(), *RESPONSE_FONT_SIZE_RANGE.end());
+        if new_size != size {
+            self.settings.response_font_size = new_size;
+            self.settings_save_due = Some(Instant::now() + SETTINGS_SAVE_DELAY);
+        }
+    }
+
+    /// Applies the theme and accent color from the settings to all styles.
+    fn apply_appearance(&mut self, ctx: &egui::Context) {
+        let appearance = (self.settings.theme, self.settings.accent_color);
+        if self.applied_appearance == Some(appearance) {
+            return;
+        }
+
+        ctx.set_theme(self.settings.theme.preference());
+        let accent = self.settings.accent_color();
+        ctx.all_styles_mut(|style| {
+            style.visuals.selection.bg_fill = accent.gamma_multiply(0.6);
+            style.visuals.selection.stroke.color = accent;
+            style.visuals.hyperlink_color = accent;
+        });
+        self.applied_appearance = Some(appearance);
+    }
+
+    /// Switches between the fullscreen overlay and a small pinned window.
+    ///
+    /// The pinned window is the same viewport turned into a decorated,
+    /// always-on-top window, so it stays open after the overlay is gone.
+    fn set_pinned(&mut self, ctx: &egui::Context, pinned: bool) {
+        self.pinned = pinned;
+        if pinned {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(true));
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::vec2(420.0, 520.0)));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title("AI-Shot answer".to_string()));
+        } else if let Some(screen) = self.screen.filter(|_| self.borderless_fallback) {
+            for command in borderless_commands(screen_rect(&screen)) {
+                ctx.send_viewport_cmd(command);
+            }
+        } else {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(false));
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(true));
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(
+            egui::WindowLevel::AlwaysOnTop,
+        ));
+    }
+
+    /// Renders the pinned answer window.
+    fn render_pinned_ui(&mut self, ctx: &egui::Context) {
+        let (text, thoughts) = match &self.state {
+            UiState::Response { text, thoughts } => (text.clone(), thoughts.clone()),
+            UiState::Error(error) => (format!("**Error:** {}", error), String::new()),
+            _ => (String::new(), String::new()),
+        };
+        let mut unpin = false;
+        egui::TopBottomPanel::bottom("pinned_actions").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                if self.is_streaming {
+                    ui.spinner();
+                }
+                if ui.button("Copy").clicked()
+                    && let Ok(mut clipboard) = arboard::Clipboard::new()
+                {
+                    let _ = clipboard.set_text(text.as_str());
+                }
+                if ui.button("Unpin").on_hover_text("Back to the screenshot").clicked() {
+                    unpin = true;
+                }
+                if ui.button("Close").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            if !thoughts.is_empty() {
+                egui::CollapsingHeader::new("Thinking Process").show(ui, |ui| {
+                    ui.label(egui::RichText::new(&thoughts).monospace().small());
+                });
+            }
+            self.render_transcript(ui, &text, f32::INFINITY);
+        });
+
+        if unpin {
+            self.set_pinned(ctx, false);
+        }
+    }
+
+    /// Exports the current exchange to the user's documents directory.
+    fn export_conversation(
+        &self,
+        text: &str,
+        thoughts: &str,
+        format: ExportFormat,
+    ) -> Result<PathBuf> {
+        let path = output_path("ai-shot", format.extension())?;
+
+        let (prompt, image) = match &self.last_request {
+            Some((selection, ui_size, prompt)) => (
+                prompt.as_str(),
+                ImageProcessor::crop_selection(&self.redacted_screenshot(*ui_size), *selection, *ui_size)
+                    .ok()
+                    .map(|crop| self.settings.stamp().apply(&crop)),
+            ),
+            None => ("", None),
+        };
+
+        Conversation {
+            prompt,
+            model: &self.answer_model,
+            answer: text,
+            thoughts,
+            image: image.as_ref(),
+        }
+        .export(&path, format)?;
+
+        Ok(path)
+    }
+
+    /// Returns why the selection may not be handed on yet while sensitive
+    /// text is masked: its check is still running, or it failed.
+    fn sensitive_scan_blocker(&self) -> Option<String> {
+        if !self.settings.mask_sensitive {
+            return None;
+        }
+        match (&self.sensitive_scan_error, self.sensitive_scan) {
+            (Some(error), _) => Some(sensitive_scan_failed(error)),
+            (None, Some(_)) => Some(
+                "Still checking the selection for sensitive text; try again in a moment"
+                    .to_string(),
+            ),
+            (None, None) => None,
+        }
+    }
+
+    /// Runs a plugin on the current exchange in the background; its message
+    /// replaces the status line when it finishes.
+    ///
+    /// Like requests, the selection is checked for sensitive text again
+    /// before the plugin gets it.
+    fn run_plugin(&mut self, ctx: &egui::Context, plugin: Plugin, text: &str, thoughts: &str) {
+        if let Some(blocker) = self.sensitive_scan_blocker() {
+            self.status_message = Some(blocker);
+            return;
+        }
+        self.status_message = Some(format!("Running {}...", plugin.name));
+        let (prompt, selection) = match &self.last_request {
+            Some((selection, ui_size, prompt)) => {
+                let screenshot = self.redacted_screenshot(*ui_size);
+                (prompt.clone(), Some((screenshot, *selection, *ui_size)))
+            }
+            None => (String::new(), None),
+        };
+        let mask = self.settings.mask_sensitive;
+        let stamp = self.settings.stamp();
+        let model = self.answer_model.clone();
+        let answer = text.to_string();
+        let thoughts = thoughts.to_string();
+        let tx = self.tx.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let image = match selection {
+                Some((mut screenshot, selection, ui_size)) => {
+                    let masked = if mask {
+                        mask_sensitive(&mut screenshot, &[selection], ui_size)
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = masked {
+                        let _ = tx.send(StreamEvent::Status(sensitive_scan_failed(&e.to_string())));
+                        ctx.request_repaint();
+                        return;
+                    }
+                    ImageProcessor::crop_selection(&screenshot, selection, ui_size)
+                        .ok()
+                        .map(|crop| stamp.apply(&crop))
+                }
+                None => None,
+            };
+            let input = PluginInput {
+                prompt: &prompt,
+                model: &model,
+                answer: &answer,
+                thoughts: &thoughts,
+                image: image.as_ref(),
+            };
+            let message = match plugin.run(&input) {
+                Ok(message) if message.is_empty() => format!("{} finished", plugin.name),
+                Ok(message) => message,
+                Err(e) => e.to_string(),
+            };
+            let _ = tx.send(StreamEvent::Status(message));
+            ctx.request_repaint();
+        });
+    }
+
+    /// Uploads the cropped selection (with redactions and the stamp) in the
+    /// background; its link is copied to the clipboard when it is done.
+    ///
+    /// Like requests, nothing is uploaded while the check for sensitive text
+    /// runs or after it failed, and the selection is checked again before
+    /// the upload.
+    fn share_selection(
+        &mut self,
+        ctx: &egui::Context,
+        selection: egui::Rect,
+        ui_size: egui::Vec2,
+        prompt: String,
+        answer: Option<String>,
+    ) {
+        if let Some(blocker) = self.sensitive_scan_blocker() {
+            self.status_message = Some(blocker);
+            return;
+        }
+        let mut screenshot = self.redacted_screenshot(ui_size);
+        if let Err(e) = ImageProcessor::crop_selection(&screenshot, selection, ui_size) {
+            self.status_message = Some(format!("Share failed: {}", e));
+            return;
+        }
+        self.status_message = Some("Uploading...".to_string());
+
+        let mask = self.settings.mask_sensitive;
+        let stamp = self.settings.stamp();
+        let config = self.config.clone();
+        let model = self.answer_model.clone();
+        let tx = self.tx.clone();
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            let masked = if mask {
+                mask_sensitive(&mut screenshot, &[selection], ui_size)
+            } else {
+                Ok(())
+            };
+            let crop = match masked
+                .and_then(|()| ImageProcessor::crop_selection(&screenshot, selection, ui_size))
+            {
+                Ok(crop) => stamp.apply(&crop),
+                Err(e) => {
+                    let _ = tx.send(StreamEvent::Status(format!("Share failed: {}", e)));
+                    ctx.request_repaint();
+                    return;
+                }
+            };
+            let share = Share {
+                image: &crop,
+                prompt: &prompt,
+                model: &model,
+                answer: answer.as_deref(),
+            };
+            let uploaded = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(AppError::from)
+                .and_then(|runtime| runtime.block_on(upload::share(&config, share)));
+            let _ = tx.send(match uploaded {
+                Ok(link) => StreamEvent::Uploaded(link),
+                Err(e) => StreamEvent::Status(format!("Share failed: {}", e)),
+            });
+            ctx.request_repaint();
+        });
+    }
+
+    /// Copies the cropped selection (with redactions and the stamp) to the
+    /// clipboard.
+    fn copy_selection_image(&self, selection: egui::Rect, ui_size: egui::Vec2) -> Result<()> {
+        let cropped =
+            ImageProcessor::crop_selection(&self.redacted_screenshot(ui_size), selection, ui_size)?;
+        let rgba = self.settings.stamp().apply(&cropped).to_rgba8();
+
+        let mut clipboard = arboard::Clipboard::new()
+            .map_err(|e| AppError::image(format!("Clipboard unavailable: {}", e)))?;
+        clipboard
+            .set_image(arboard::ImageData {
+                width: rgba.width() as usize,
+                height: rgba.height() as usize,
+                bytes: rgba.into_raw().into(),
+            })
+            .map_err(|e| AppError::image(format!("Failed to copy image: {}", e)))
+    }
+
+    /// Saves the cropped selection (with redactions and the stamp) as a PNG.
+    fn save_selection_image(&self, selection: egui::Rect, ui_size: egui::Vec2) -> Result<PathBuf> {
+        let cropped =
+            ImageProcessor::crop_selection(&self.redacted_screenshot(ui_size), selection, ui_size)?;
+
+        let path = output_path("ai-shot-selection", "png")?;
+        self.settings
+            .stamp()
+            .apply(&cropped)
+            .save(&path)
+            .map_err(|e| AppError::image(format!("Failed to save image: {}", e)))?;
+        Ok(path)
+    }
+
+    /// Saves the image returned by an "Edit with AI" request as a PNG.
+    fn save_edited_image(&self) -> Result<PathBuf> {
+        let Some((image, _)) = &self.edited_image else {
+            return Err(AppError::image("No edited image to save"));
+        };
+
+        let path = output_path("ai-shot-edit", "png")?;
+        image
+            .save(&path)
+            .map_err(|e| AppError::image(format!("Failed to save image: {}", e)))?;
+        Ok(path)
+    }
+
+    /// Renders the error state UI.
+    fn render_error_ui(&mut self, ui: &mut egui::Ui, error: &str) {
+        ui.label(egui::RichText::new(format!("Error: {}", error)).color(egui::Color32::RED));
+        ui.horizontal(|ui| {
+            if let Some((selection, ui_size, prompt)) = self.last_request.clone()
+                && ui.button("Retry").clicked()
+            {
+                self.submit_request(selection, ui_size, prompt);
+            }
+            if ui.button("Back").clicked() {
+                self.state = UiState::Idle;
+            }
+        });
+    }
+}
+
+/// Renders a user message of the transcript as a highlighted bubble.
+fn render_user_message(ui: &mut egui::Ui, cache: &mut CommonMarkCache, prompt: &str) {
+    egui::Frame::new()
+        .fill(ui.visuals().faint_bg_color)
+        .corner_radius(6.0)
+        .inner_margin(6.0)
+        .show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            CommonMarkViewer::new().show(ui, cache, prompt);
+        });
+    ui.add_space(4.0);
+}
+
+/// A request from the prompt popup.
+struct PendingRequest {
+    selection: egui::Rect,
+    ui_size: egui::Vec2,
+    prompt: String,
+    /// Whether it is an "Edit with AI" request
+    edit: bool,
+}
+
+/// Rectangle of a screen on the desktop, in points.
+fn screen_rect(screen: &ScreenGeometry) -> egui::Rect {
+    egui::Rect::from_min_size(
+        egui::pos2(screen.x as f32, screen.y as f32),
+        egui::vec2(screen.width as f32, screen.height as f32),
+    )
+}
+
+/// Viewport commands turning the overlay into a borderless window over `screen`.
+fn borderless_commands(screen: egui::Rect) -> [egui::ViewportCommand; 4] {
+    [
+        egui::ViewportCommand::Fullscreen(false),
+        egui::ViewportCommand::Decorations(false),
+        egui::ViewportCommand::OuterPosition(screen.min),
+        egui::ViewportCommand::InnerSize(screen.size()),
+    ]
+}
+
+/// Lets the event loop be created on a thread other than the main one.
+#[allow(unused_variables)]
+fn allow_any_thread(builder: &mut eframe::EventLoopBuilder<eframe::UserEvent>) {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        use winit::platform::wayland::EventLoopBuilderExtWayland;
+        use winit::platform::x11::EventLoopBuilderExtX11;
+        EventLoopBuilderExtX11::with_any_thread(builder, true);
+        EventLoopBuilderExtWayland::with_any_thread(builder, true);
+    }
+    #[cfg(windows)]
+    {
+        use winit::platform::windows::EventLoopBuilderExtWindows;
+        builder.with_any_thread(true);
+    }
+}
+
+/// Reads an image from the clipboard.
+fn clipboard_image() -> Result<DynamicImage> {
+    let image = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_image())
+        .map_err(|e| AppError::image(format!("No image on the clipboard: {}", e)))?;
+
+    image::RgbaImage::from_raw(image.width as u32, image.height as u32, image.bytes.into_owned())
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| AppError::image("Clipboard image has an unexpected size"))
+}
+
+/// Decodes an image file dropped onto the overlay.
+fn dropped_image(file: &egui::DroppedFile) -> Result<DynamicImage> {
+    let image = match (&file.bytes, &file.path) {
+        (Some(bytes), _) => image::load_from_memory(bytes),
+        (None, Some(path)) => image::open(path),
+        (None, None) => return Err(AppError::image("Dropped item is not a file")),
+    };
+    image.map_err(|e| AppError::image(format!("Failed to open {}: {}", file.name, e)))
+}
+
+/// Returns a timestamped path in the user's documents (or home) directory.
+fn output_path(prefix: &str, extension: &str) -> Result<PathBuf> {
+    let dir = UserDirs::new()
+        .and_then(|dirs| dirs.document_dir().map(|d| d.to_path_buf()))
+        .or_else(|| UserDirs::new().map(|dirs| dirs.home_dir().to_path_buf()))
+        .ok_or_else(|| AppError::config("Could not determine a directory to save to"))?;
+
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    Ok(dir.join(format!("{}-{}.{}", prefix, timestamp, extension)))
+}
+
+/// Extracts the text of a selection with the local OCR engine.
+fn ocr_selection(screenshot: &DynamicImage, selection: egui::Rect, ui_size: egui::Vec2) -> Result<String> {
+    let cropped = ImageProcessor::crop_selection(screenshot, selection, ui_size)?;
+    OcrEngine::from_env().extract_text(&cropped)
+}
+
+/// Returns the shortest edge uploads are enlarged to: the configured one,
+/// unless enlarging was switched on or off in the overlay.
+fn upscale_min_edge(config: &Config, enlarge: Option<bool>) -> Option<u32> {
+    match enlarge {
+        Some(true) => Some(config.upscale_min_edge.unwrap_or(DEFAULT_UPSCALE_MIN_EDGE)),
+        Some(false) => None,
+        None => config.upscale_min_edge,
+    }
+}
+
+/// Extracts text from several regions, labelling each when there are more
+/// than one.
+fn ocr_regions(screenshot: &DynamicImage, regions: &[egui::Rect], ui_size: egui::Vec2) -> Result<String> {
+    if let [region] = regions {
+        return ocr_selection(screenshot, *region, ui_size);
+    }
+
+    let mut text = String::new();
+    for (index, region) in regions.iter().enumerate() {
+        let region_text = ocr_selection(screenshot, *region, ui_size)?;
+        text.push_str(&format!("Region {}:\n{}\n\n", index + 1, region_text.trim()));
+    }
+    Ok(text)
+}
+
+/// Finds sensitive text in `selection`, with where each finding is in UI
+/// coordinates.
+fn find_sensitive(
+    screenshot: &DynamicImage,
+    selection: egui::Rect,
+    ui_size: egui::Vec2,
+) -> Result<Vec<(Finding, egui::Rect)>> {
+    let (width, height) = (screenshot.width(), screenshot.height());
+    let region = ImageProcessor::map_selection(selection, ui_size, width, height)?;
+    let cropped = screenshot.crop_imm(region.x, region.y, region.width, region.height);
+    let findings = privacy::scan(&OcrEngine::from_env(), &cropped)?;
+
+    // Back to UI coordinates, padded to cover the edges of letters
+    let scale = egui::vec2(width as f32 / ui_size.x, height as f32 / ui_size.y);
+    Ok(findings
+        .into_iter()
+        .map(|finding| {
+            let found = finding.region;
+            let min = egui::vec2((region.x + found.x) as f32, (region.y + found.y) as f32);
+            let size = egui::vec2(found.width as f32, found.height as f32);
+            let rect = egui::Rect::from_min_size((min / scale).to_pos2(), size / scale)
+                .expand(2.0)
+                .intersect(selection);
+            (finding, rect)
+        })
+        .collect())
+}
+
+/// Masks the sensitive text found in `regions` of `screenshot`.
+fn mask_sensitive(
+    screenshot: &mut DynamicImage,
+    regions: &[egui::Rect],
+    ui_size: egui::Vec2,
+) -> Result<()> {
+    let mut redactions = Vec::new();
+    for region in regions {
+        let findings = find_sensitive(screenshot, *region, ui_size)?;
+        redactions.extend(findings.into_iter().map(|(_, rect)| Redaction {
+            rect,
+            style: RedactionStyle::Pixelate,
+        }));
+    }
+    ImageProcessor::apply_redactions(screenshot, &redactions, ui_size);
+    Ok(())
+}
+
+/// Returns the status shown when the check for sensitive text failed.
+fn sensitive_scan_failed(error: &str) -> String {
+    format!(
+        "Could not check the selection for sensitive text, so nothing is sent: {}. \
+         Install Tesseract or turn off masking in the Privacy settings.",
+        error
+    )
+}
+
+/// Forwards a response stream to the UI channel until it ends.
+async fn forward_stream(tx: &Sender<StreamEvent>, stream: Result<GeminiEventStream>) {
+    use futures::StreamExt;
+
+    let mut stream = match stream {
+        Ok(stream) => stream,
+        Err(e) => {
+            let _ = tx.send(StreamEvent::Error(format!("API error: {}", e)));
+            return;
+        }
+    };
+
+    while let Some(result) = stream.next().await {
+        match result {
+            Ok(events) => {
+                for event in events {
+                    // Stop (dropping the connection) once the request is cancelled
+                    if !forward_gemini_event(tx, event) {
+                        return;
+                    }
+                }
+            }
+            Err(e) => {
+                let _ = tx.send(StreamEvent::Error(format!("Stream error: {}", e)));
+            }
+        }
+    }
+    let _ = tx.send(StreamEvent::Done);
+}
+
+/// Forwards a Gemini stream event to the UI channel.
+///
+/// Returns `false` if the UI is no longer listening (the request was
+/// cancelled).
+fn forward_gemini_event(tx: &Sender<StreamEvent>, event: GeminiStreamEvent) -> bool {
+    match event {
+        GeminiStreamEvent::Text(text) => tx.send(StreamEvent::Chunk(text)),
+        GeminiStreamEvent::Thought(thought) => tx.send(StreamEvent::Thought(thought)),
+        GeminiStreamEvent::ToolCall { name, output } => {
+            tx.send(StreamEvent::Thought(format!("\n[tool] {} -> {}\n", name, output)))
+        }
+        GeminiStreamEvent::Finish(_) => Ok(()),
+        GeminiStreamEvent::Usage {
+            input_tokens,
+            output_tokens,
+        } => tx.send(StreamEvent::Usage {
+            input_tokens,
+            output_tokens,
+        }),
+    }
+    .is_ok()
+}
+
+impl eframe::App for SnippingTool {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Apply the theme and accent color when they change
+        self.apply_appearance(ctx);
+
+        // Fall back to a borderless window if fullscreen landed elsewhere
+        self.verify_window_geometry(ctx);
+
+        // Process any pending stream events
+        self.process_stream_events(ctx);
+
+        // Persist zoom changes once they settle
+        if let Some(due) = self.settings_save_due {
+            if Instant::now() >= due {
+                self.settings_save_due = None;
+                if let Err(e) = self.settings.save() {
+                    eprintln!("Warning: Failed to save settings: {}", e);
+                }
+            } else {
+                ctx.request_repaint_after(due - Instant::now());
+            }
+        }
+
+        // The pinned window shows only the answer
+        if self.pinned {
+            self.render_pinned_ui(ctx);
+            return;
+        }
+
+        // Offer to recover an answer interrupted in a previous session
+        self.render_recovered_window(ctx);
+
+        // Settings modal, saved when closed
+        if let Some(window) = &mut self.settings_window
+            && !window.show(ctx, &mut self.settings)
+        {
+            self.settings_window = None;
+            if let Err(e) = self.settings.save() {
+                eprintln!("Warning: Failed to save settings: {}", e);
+            }
+        }
+
+        // History browser; re-opening an entry replaces the screenshot
+        if let Some(window) = &mut self.history_window
+            && let Some(action) = window.show(ctx)
+        {
+            self.apply_history_action(ctx, action);
+        }
+
+        // D shows what the dimming covers, unless text is being typed
+        if !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::D))
+        {
+            self.dimming_hidden = !self.dimming_hidden;
+        }
+
+        // Dropped or pasted images replace the screenshot
+        self.handle_image_input(ctx);
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            egui::Area::new(egui::Id::new("drop_hint"))
+                .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.heading("Drop to ask about this image");
+                    });
+                });
+        }
+
+        // Upload texture on first frame (or after an image was loaded)
+        if self.image_texture.is_none()
+            && let Some(color_image) = self.color_image.take()
+        {
+            // Nearest-neighbor magnification keeps pixels crisp in the magnifier
+            self.image_texture = Some(ctx.load_texture(
+                "screenshot",
+                color_image,
+                egui::TextureOptions {
+                    magnification: egui::TextureFilter::Nearest,
+                    ..egui::TextureOptions::LINEAR
+                },
+            ));
+        }
+
+        // Fullscreen panel with no margins
+        let panel_frame = egui::Frame::default()
+            .inner_margin(egui::Margin::same(0.0 as i8))
+            .outer_margin(egui::Margin::same(0.0 as i8));
+
+        egui::CentralPanel::default()
+            .frame(panel_frame)
+            .show(ctx, |ui| {
+                let rect = ui.max_rect();
+
+                // Draw screenshot as background
+                if let Some(texture) = &self.image_texture {
+                    ui.painter().image(
+                        texture.id(),
+                        rect,
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                }
+
+                // Handle selection input
+                let response = ui.interact(rect, ui.id(), egui::Sense::drag());
+
+                // Ctrl+drag keeps the finalized selection as an extra region
+                let previous = self
+                    .selection_start
+                    .zip(self.current_pos)
+                    .filter(|_| self.is_selection_finalized)
+                    .map(|(start, end)| egui::Rect::from_two_pos(start, end));
+                let add_region = ctx.input(|i| i.modifiers.command);
+
+                // Handles on a finalized selection take priority over
+                // starting a new one
+                let event = if self.draw_redaction(ctx, &response)
+                    || self.adjust_selection(ctx, &response)
+                {
+                    SelectionEvent::None
+                } else {
+                    process_drag_event(
+                        &response,
+                        &mut self.selection_start,
+                        &mut self.current_pos,
+                        self.is_selection_finalized,
+                    )
+                };
+
+                // Keep a new selection to the chosen aspect ratio (Shift for a square)
+                if !self.is_selection_finalized
+                    && self.keyboard_cursor.is_none()
+                    && let (Some(start), Some(end), Some(ratio)) =
+                        (self.selection_start, self.current_pos, self.drag_aspect_ratio(ctx))
+                {
+                    self.current_pos = Some(constrain_aspect(start, end, ratio));
+                }
+
+                match event {
+                    SelectionEvent::Started if add_region && previous.is_some() => {
+                        self.extra_selections.extend(previous);
+                        self.is_selection_finalized = false;
+                        self.payload_estimate = None;
+                        self.selection_preview = None;
+                    }
+                    SelectionEvent::Started => {
+                        self.extra_selections.clear();
+                        self.keyboard_cursor = None;
+                        self.is_selection_finalized = false;
+                        self.chat_input = self.initial_prompt();
+                        self.content_kind = ContentKind::General;
+                        self.conversation.clear();
+                        self.edited_image = None;
+                        self.payload_estimate = None;
+                        self.selection_preview = None;
+                        self.downscale_upload = false;
+                        self.upscale_upload = None;
+                        self.redactions.clear();
+                        self.masked_findings.clear();
+                        self.sensitive_scan = None;
+                        self.sensitive_scan_error = None;
+                        self.status_message = None;
+                        if matches!(self.state, UiState::Response { .. } | UiState::Error(_)) {
+                            self.state = UiState::Idle;
+                        }
+                    }
+                    SelectionEvent::Completed => {
+                        self.is_selection_finalized = true;
+                        self.on_selection_changed(ctx);
+                    }
+                    SelectionEvent::Cancelled => {
+                        // A too-small extra region falls back to the last one
+                        if let Some(last) = self.extra_selections.pop() {
+                            self.set_selection(last);
+                            self.is_selection_finalized = true;
+                            self.on_selection_changed(ctx);
+                        }
+                    }
+                    _ => {}
+                }
+
+                // Keyboard shortcuts for full-screen and window selection
+                if self.settings_window.is_none() {
+                    self.handle_snap_shortcuts(ctx, rect);
+                    self.keyboard_select(ctx, rect);
+                }
+
+                // Escape steps back, then closes
+                self.handle_escape(ctx);
+
+                // Get current interaction position for drawing
+                let current_interaction_pos = if self.is_selection_finalized
+                    || self.keyboard_cursor.is_some()
+                    || self.drag_aspect_ratio(ctx).is_some()
+                {
+                    self.current_pos
+                } else {
+                    ctx.pointer_interact_pos().or(self.current_pos)
+                };
+
+                if !self.is_selection_finalized && matches!(self.state, UiState::Idle) {
+                    self.render_selection_toolbar(ctx, rect);
+                }
+
+                // Crosshair for keyboard-only selection
+                if let Some(cursor) = self.keyboard_cursor {
+                    let stroke = egui::Stroke::new(1.0, self.settings.accent_color());
+                    ui.painter().hline(rect.x_range(), cursor.y, stroke);
+                    ui.painter().vline(cursor.x, rect.y_range(), stroke);
+                }
 
                 // Draw selection overlay and UI
                 if let (Some(start), Some(current)) = (self.selection_start, current_interaction_pos)
@@ -484,10 +3258,74 @@ impl eframe::App for SnippingTool {
                     let screen_rect = ui.max_rect();
 
                     // Draw dark overlay with cutout
-                    draw_selection_overlay(ui.painter(), screen_rect, selection_rect, 150);
+                    if !self.dimming_hidden {
+                        draw_selection_overlay(
+                            ui.painter(),
+                            screen_rect,
+                            selection_rect,
+                            self.settings.overlay_color(),
+                        );
+                    }
 
                     // Draw selection border
-                    draw_selection_border(ui.painter(), selection_rect, 2.0, egui::Color32::WHITE);
+                    draw_selection_border(
+                        ui.painter(),
+                        selection_rect,
+                        2.0,
+                        self.settings.accent_color(),
+                    );
+
+                    // Extra regions, numbered in the order they are sent
+                    let to_uv = |pos: egui::Pos2| ((pos - screen_rect.min) / screen_rect.size()).to_pos2();
+                    for (index, region) in self.extra_selections.iter().enumerate() {
+                        if let Some(texture) = &self.image_texture {
+                            let uv = egui::Rect::from_min_max(to_uv(region.min), to_uv(region.max));
+                            ui.painter().image(texture.id(), *region, uv, egui::Color32::WHITE);
+                        }
+                        draw_selection_border(ui.painter(), *region, 2.0, egui::Color32::LIGHT_BLUE);
+                        ui.painter().text(
+                            region.min + egui::vec2(4.0, 4.0),
+                            egui::Align2::LEFT_TOP,
+                            if self.combine_regions {
+                                montage::label(index)
+                            } else {
+                                (index + 1).to_string()
+                            },
+                            egui::FontId::proportional(14.0),
+                            egui::Color32::LIGHT_BLUE,
+                        );
+                    }
+
+                    // Masked regions, plus the one being drawn
+                    draw_redactions(ui.painter(), selection_rect, &self.redactions);
+                    if let (Some(redaction_start), Some(pos)) =
+                        (self.redaction_start, ctx.pointer_latest_pos())
+                    {
+                        draw_selection_border(
+                            &ui.painter().with_clip_rect(selection_rect),
+                            egui::Rect::from_two_pos(redaction_start, pos),
+                            1.0,
+                            egui::Color32::RED,
+                        );
+                    }
+
+                    // Magnifier with the live size in image pixels while dragging
+                    let dragging = !self.is_selection_finalized || self.active_handle.is_some();
+                    if dragging
+                        && ctx.input(|i| i.pointer.primary_down())
+                        && let (Some(texture), Some(cursor)) =
+                            (&self.image_texture, ctx.pointer_latest_pos())
+                    {
+                        let caption = ImageProcessor::map_selection(
+                            selection_rect,
+                            ctx.viewport_rect().size(),
+                            self.screenshot.width(),
+                            self.screenshot.height(),
+                        )
+                        .map(|region| format!("{}×{}", region.width, region.height))
+                        .unwrap_or_else(|_| "0×0".to_string());
+                        draw_magnifier(ui.painter(), texture.id(), screen_rect, cursor, &caption);
+                    }
 
                     // Show interaction window when selection is finalized
                 if self.is_selection_finalized {
@@ -505,8 +3343,10 @@ impl eframe::App for SnippingTool {
                             .pivot(pivot)
                             .show(ctx, |ui| {
                                 egui::Frame::popup(ui.style())
-                                    .fill(egui::Color32::from_rgb(30, 30, 30))
-                                    .stroke(egui::Stroke::new(1.0, egui::Color32::GRAY))
+                                    .stroke(egui::Stroke::new(
+                                        1.0,
+                                        self.settings.accent_color().gamma_multiply(0.5),
+                                    ))
                                     .inner_margin(10.0)
                                     .show(ui, |ui| {
                                         ui.set_width(window_width);
@@ -517,12 +3357,6 @@ impl eframe::App for SnippingTool {
                                             UiState::Idle => {
                                                 self.render_idle_ui(ui, selection_rect);
                                             }
-                                            UiState::Loading => {
-                                                ui.horizontal(|ui| {
-                                                    ui.spinner();
-                                                    ui.label("Analyzing...");
-                                                });
-                                            }
                                             UiState::Response { text, thoughts } => {
                                                 self.render_response_ui(ui, ctx, &text, &thoughts);
                                             }
@@ -542,21 +3376,71 @@ impl eframe::App for SnippingTool {
 ///
 /// # Arguments
 /// * `screenshot` - The captured screen image
+/// * `screen` - Desktop geometry of the captured screen, if known
+/// * `windows` - Windows on the captured screen for `Space` snapping
 /// * `config` - Application configuration
+/// * `any_thread` - Allow running off the main thread (not supported on macOS)
 ///
 /// # Returns
-/// The selected rectangle and screen size, or `None` if cancelled.
+/// The last selection sent, its screen size and prompt, or `None` if the
+/// overlay was closed without sending anything.
 pub fn run(
     screenshot: DynamicImage,
+    screen: Option<ScreenGeometry>,
+    windows: Vec<WindowInfo>,
     config: Config,
+    any_thread: bool,
 ) -> Result<Option<(egui::Rect, egui::Vec2, Option<String>)>> {
-    let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_fullscreen(true)
-            .with_decorations(false)
-            .with_always_on_top(),
+    run_overlay(screenshot, screen, windows, config, any_thread, false)
+}
+
+/// Launches the overlay to pick a region, without prompting or sending it.
+///
+/// The overlay closes as soon as a selection is made (by dragging, keyboard
+/// selection, or window snapping).
+///
+/// # Returns
+/// The selection and its screen size, or `None` if the overlay was closed
+/// without selecting anything.
+pub fn pick(
+    screenshot: DynamicImage,
+    screen: Option<ScreenGeometry>,
+    windows: Vec<WindowInfo>,
+    config: Config,
+) -> Result<Option<(egui::Rect, egui::Vec2)>> {
+    let picked = run_overlay(screenshot, screen, windows, config, false, true)?;
+    Ok(picked.map(|(selection, ui_size, _)| (selection, ui_size)))
+}
+
+/// Runs the overlay's event loop until the window closes.
+fn run_overlay(
+    screenshot: DynamicImage,
+    screen: Option<ScreenGeometry>,
+    windows: Vec<WindowInfo>,
+    config: Config,
+    any_thread: bool,
+    pick_only: bool,
+) -> Result<Option<(egui::Rect, egui::Vec2, Option<String>)>> {
+    let viewport = egui::ViewportBuilder::default()
+        .with_decorations(false)
+        .with_always_on_top();
+    let viewport = match screen {
+        Some(screen) if config.overlay_mode == OverlayMode::Borderless => {
+            let rect = screen_rect(&screen);
+            viewport.with_position(rect.min).with_inner_size(rect.size())
+        }
+        _ => viewport.with_fullscreen(true),
+    };
+    let mut options = eframe::NativeOptions {
+        viewport,
         ..Default::default()
     };
+    if any_thread {
+        if cfg!(target_os = "macos") {
+            return Err(AppError::ui("The UI can only run on the main thread on macOS"));
+        }
+        options.event_loop_builder = Some(Box::new(allow_any_thread));
+    }
 
     let result = Arc::new(Mutex::new(SelectionResult::default()));
     let app_result = result.clone();
@@ -564,8 +3448,12 @@ pub fn run(
     eframe::run_native(
         "Screen Gemini Selection",
         options,
-        Box::new(move |_cc| {
-            Ok(Box::new(SnippingTool::new(screenshot, app_result, config)) as Box<dyn eframe::App>)
+        Box::new(move |cc| {
+            // Ctrl+plus/minus zoom the answer text rather than the whole overlay
+            cc.egui_ctx.options_mut(|o| o.zoom_with_keyboard = false);
+            let mut tool = SnippingTool::new(screenshot, app_result, screen, windows, config);
+            tool.pick_only = pick_only;
+            Ok(Box::new(tool) as Box<dyn eframe::App>)
         }),
     )
     .map_err(|e| AppError::ui(format!("Failed to run UI: {}", e)))?;
@@ -27,9 +27,10 @@ pub struct SelectionResult {
 pub enum UiState {
     /// Waiting for user input (prompt entry).
     Idle,
-    /// Loading/processing request (legacy state, kept for compatibility).
-    Loading,
     /// Displaying streaming or complete response from Gemini.
+    ///
+    /// Shown as the latest entry of a chat transcript; earlier exchanges
+    /// about the same selection are kept by the snipping tool.
     Response {
         /// The accumulated response text.
         text: String,
@@ -40,6 +41,27 @@ pub enum UiState {
     Error(String),
 }
 
+/// Upload size estimate for the current selection.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct PayloadEstimate {
+    /// Selection the estimate was computed for, in UI coordinates.
+    pub selection: egui::Rect,
+    /// Uploaded image width in pixels.
+    pub width: u32,
+    /// Uploaded image height in pixels.
+    pub height: u32,
+    /// Cropped size, if [`crate::Config::max_image_pixels`] downscales it.
+    pub downscaled_from: Option<(u32, u32)>,
+    /// Cropped size, if it is enlarged because it is tiny.
+    pub upscaled_from: Option<(u32, u32)>,
+    /// Whether the selection is small enough to offer enlarging it.
+    pub tiny: bool,
+    /// Encoded image size in bytes (before base64).
+    pub bytes: usize,
+    /// Input tokens reported by the API, once counted.
+    pub tokens: Option<u32>,
+}
+
 /// Events received from the background streaming task.
 ///
 /// These events are sent through a channel from the async Gemini task
@@ -49,6 +71,39 @@ pub(crate) enum StreamEvent {
     Chunk(String),
     /// A chunk of thinking/reasoning text arrived.
     Thought(String),
+    /// Token usage reported so far for the current request.
+    Usage {
+        /// Input (prompt and image) tokens.
+        input_tokens: u32,
+        /// Output tokens generated so far.
+        output_tokens: u32,
+    },
+    /// The upload size of the selection was estimated.
+    Estimate(PayloadEstimate),
+    /// A thumbnail of the pixels that would be sent for a selection.
+    Preview {
+        /// Selection the thumbnail was made for, in UI coordinates.
+        selection: egui::Rect,
+        /// Downsized crop, after scale mapping and redaction.
+        image: egui::ColorImage,
+    },
+    /// A selection was checked for sensitive text.
+    Sensitive {
+        /// Selection that was scanned, in UI coordinates.
+        selection: egui::Rect,
+        /// What was found, with where it is in UI coordinates, or why the
+        /// check failed.
+        findings: std::result::Result<Vec<(crate::privacy::Finding, egui::Rect)>, String>,
+    },
+    /// An image generated by an image editing request arrived.
+    Image(image::DynamicImage),
+    /// A tool call needs user approval; the answer is sent back on the channel.
+    ConfirmTool(String, std::sync::mpsc::Sender<bool>),
+    /// A background action (e.g. a plugin) finished with a message for the
+    /// status line.
+    Status(String),
+    /// A selection was shared; holds its link.
+    Uploaded(String),
     /// An error occurred during streaming.
     Error(String),
     /// The stream has completed.
@@ -0,0 +1,407 @@
+//! Sharing selections through a link.
+//!
+//! With an `[upload]` table in `config.toml`, the overlay gets a share
+//! button: the selection (with redactions and the stamp) is uploaded to the
+//! configured destination and the link is copied to the clipboard. Three
+//! destinations are built in:
+//!
+//! - `imgur`: an anonymous Imgur upload, with a registered application's
+//!   client id
+//! - `s3`: an object in an S3 bucket or an S3-compatible store (MinIO,
+//!   Cloudflare R2), signed with AWS Signature Version 4
+//! - `webhook`: a JSON `POST` to any URL that answers with the link
+//!
+//! ```toml
+//! [upload]
+//! destination = "s3"
+//! include_answer = true
+//!
+//! [upload.s3]
+//! bucket = "team-screenshots"
+//! region = "eu-central-1"
+//! prefix = "ai-shot/"
+//! public_url = "https://screenshots.example.com"
+//! ```
+//!
+//! S3 credentials are read from `access_key_id` and `secret_access_key`, or
+//! from `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and
+//! `AWS_SESSION_TOKEN`. With `include_answer`, the answer goes along as the
+//! Imgur description or the webhook's `answer` field; S3 stores the image
+//! only.
+//!
+//! The webhook receives the image and the exchange as one JSON object:
+//!
+//! ```json
+//! {
+//!   "fileName": "ai-shot-20250314-092653.png",
+//!   "image": { "mimeType": "image/png", "width": 640, "height": 120, "data": "iVBORw0..." },
+//!   "prompt": "What does this regex match?",
+//!   "model": "gemini-2.5-pro",
+//!   "answer": "It matches ..."
+//! }
+//! ```
+//!
+//! and answers with the link as plain text or as JSON, where it is read from
+//! `response_field` (a dotted path, `url` if unset).
+
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::gemini::GeminiClient;
+use crate::image_processing::{ImageEncoding, ImageProcessor};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use chrono::{Local, Utc};
+use hmac::{Hmac, Mac};
+use image::DynamicImage;
+use reqwest::header::AUTHORIZATION;
+use serde::Deserialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+
+/// Imgur's anonymous image upload endpoint.
+const IMGUR_ENDPOINT: &str = "https://api.imgur.com/3/image";
+
+/// Region used for S3 when `region` is unset.
+const DEFAULT_S3_REGION: &str = "us-east-1";
+
+/// Longest error body quoted in an upload error.
+const MAX_ERROR_BODY: usize = 200;
+
+/// Where shared selections go, from the `[upload]` table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UploadConfig {
+    /// The destination named by `destination`.
+    pub destination: Destination,
+    /// Send the answer along, if the destination takes text.
+    pub include_answer: bool,
+}
+
+/// A service selections are uploaded to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Destination {
+    /// Anonymous Imgur uploads.
+    Imgur(ImgurOptions),
+    /// An S3 bucket or S3-compatible store.
+    S3(S3Options),
+    /// A URL that takes a JSON `POST` and answers with the link.
+    Webhook(WebhookOptions),
+}
+
+impl Destination {
+    /// Returns the name of the destination, as in `destination`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Imgur(_) => "imgur",
+            Self::S3(_) => "s3",
+            Self::Webhook(_) => "webhook",
+        }
+    }
+}
+
+/// The `[upload.imgur]` table.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ImgurOptions {
+    /// Client id of a registered Imgur application.
+    pub client_id: String,
+}
+
+/// The `[upload.s3]` table.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct S3Options {
+    /// Name of the bucket.
+    pub bucket: String,
+    /// Region of the bucket (`us-east-1` if unset; `auto` for R2).
+    #[serde(default)]
+    pub region: Option<String>,
+    /// Base URL of an S3-compatible store, addressed path-style; AWS if
+    /// unset.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Prepended to the object names, e.g. `screenshots/`.
+    #[serde(default)]
+    pub prefix: String,
+    /// Base URL the objects are served from (e.g. a CDN); the object's own
+    /// URL if unset.
+    #[serde(default)]
+    pub public_url: Option<String>,
+    /// Canned ACL of the objects, e.g. `public-read`.
+    #[serde(default)]
+    pub acl: Option<String>,
+    /// Access key; `AWS_ACCESS_KEY_ID` if unset.
+    #[serde(default)]
+    pub access_key_id: Option<String>,
+    /// Secret key; `AWS_SECRET_ACCESS_KEY` if unset.
+    #[serde(default)]
+    pub secret_access_key: Option<String>,
+}
+
+/// The `[upload.webhook]` table.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookOptions {
+    /// URL the JSON object is posted to.
+    pub url: String,
+    /// Extra request headers, e.g. `Authorization`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Dotted path of the link in a JSON response (`url` if unset).
+    #[serde(default)]
+    pub response_field: Option<String>,
+}
+
+/// The exchange a shared selection belongs to.
+#[derive(Clone, Copy, Debug)]
+pub struct Share<'a> {
+    /// The selection, as saved.
+    pub image: &'a DynamicImage,
+    /// The user's prompt, empty if nothing was asked yet.
+    pub prompt: &'a str,
+    /// Model that produced the answer.
+    pub model: &'a str,
+    /// The model's answer, if there is one and it should be shared.
+    pub answer: Option<&'a str>,
+}
+
+/// Uploads a selection to the configured destination and returns its link.
+///
+/// The answer is only sent if `include_answer` is set.
+///
+/// # Errors
+///
+/// Returns [`AppError::Config`] if no destination is configured or S3
+/// credentials are missing, and [`AppError::Upload`] if the upload fails or
+/// the response has no link.
+pub async fn share(config: &Config, share: Share<'_>) -> Result<String> {
+    let upload = config.upload.as_ref().ok_or_else(|| {
+        AppError::config("No upload destination is configured (see [upload] in config.toml)")
+    })?;
+    let share = Share {
+        answer: share
+            .answer
+            .filter(|answer| upload.include_answer && !answer.trim().is_empty()),
+        ..share
+    };
+    let png = ImageProcessor::encode(share.image, ImageEncoding::Png)?;
+    let name = format!("ai-shot-{}.png", Local::now().format("%Y%m%d-%H%M%S"));
+    let http = GeminiClient::http_client(config)?
+        .build()
+        .map_err(|e| AppError::config(format!("Failed to create HTTP client: {}", e)))?;
+
+    let link = match &upload.destination {
+        Destination::Imgur(options) => imgur(&http, options, &png, share.answer).await?,
+        Destination::S3(options) => s3(&http, options, &name, png).await?,
+        Destination::Webhook(options) => webhook(&http, options, &name, &png, &share).await?,
+    };
+    tracing::debug!(destination = upload.destination.name(), %link, "uploaded selection");
+    Ok(link)
+}
+
+/// Uploads to Imgur and returns the image's link.
+async fn imgur(
+    http: &reqwest::Client,
+    options: &ImgurOptions,
+    png: &[u8],
+    answer: Option<&str>,
+) -> Result<String> {
+    let data = BASE64.encode(png);
+    let mut form = vec![("image", data.as_str()), ("type", "base64")];
+    if let Some(answer) = answer {
+        form.push(("description", answer));
+    }
+    let request = http
+        .post(IMGUR_ENDPOINT)
+        .header(AUTHORIZATION, format!("Client-ID {}", options.client_id))
+        .form(&form);
+    let body = send(request).await?;
+
+    let json: Value = serde_json::from_str(&body)
+        .map_err(|e| AppError::upload(format!("Unexpected Imgur response: {}", e)))?;
+    json.pointer("/data/link")
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| AppError::upload("Imgur's response has no link"))
+}
+
+/// Puts the PNG into the bucket and returns the object's public link.
+async fn s3(
+    http: &reqwest::Client,
+    options: &S3Options,
+    name: &str,
+    png: Vec<u8>,
+) -> Result<String> {
+    let credential = |value: &Option<String>, variable: &str| {
+        value.clone().or_else(|| env::var(variable).ok()).ok_or_else(|| {
+            AppError::config(format!("S3 uploads need [upload.s3] credentials or {}", variable))
+        })
+    };
+    let access_key = credential(&options.access_key_id, "AWS_ACCESS_KEY_ID")?;
+    let secret_key = credential(&options.secret_access_key, "AWS_SECRET_ACCESS_KEY")?;
+    let session_token = env::var("AWS_SESSION_TOKEN").ok().filter(|token| !token.is_empty());
+    let region = options.region.as_deref().unwrap_or(DEFAULT_S3_REGION);
+
+    let key = encode_key(&format!("{}{}", options.prefix, name));
+    let (host, path, object_url) = match &options.endpoint {
+        Some(endpoint) => {
+            let url = url::Url::parse(endpoint).map_err(|e| {
+                AppError::config(format!("Invalid S3 endpoint '{}': {}", endpoint, e))
+            })?;
+            let mut host = url.host_str().unwrap_or_default().to_string();
+            if let Some(port) = url.port() {
+                host.push_str(&format!(":{}", port));
+            }
+            let base = url.path().trim_end_matches('/');
+            let path = format!("{}/{}/{}", base, options.bucket, key);
+            let object_url = format!("{}://{}{}", url.scheme(), host, path);
+            (host, path, object_url)
+        }
+        None => {
+            let host = format!("{}.s3.{}.amazonaws.com", options.bucket, region);
+            let path = format!("/{}", key);
+            let object_url = format!("https://{}{}", host, path);
+            (host, path, object_url)
+        }
+    };
+
+    let now = Utc::now();
+    let date = now.format("%Y%m%d").to_string();
+    let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let payload_hash = hex::encode(Sha256::digest(&png));
+
+    let mut headers = vec![
+        ("content-type", "image/png".to_string()),
+        ("host", host),
+        ("x-amz-content-sha256", payload_hash.clone()),
+        ("x-amz-date", timestamp.clone()),
+    ];
+    if let Some(acl) = &options.acl {
+        headers.push(("x-amz-acl", acl.clone()));
+    }
+    if let Some(token) = session_token {
+        headers.push(("x-amz-security-token", token));
+    }
+    headers.sort_by_key(|(name, _)| *name);
+
+    // https://docs.aws.amazon.com/IAM/latest/UserGuide/reference_sigv-create-signed-request.html
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{}:{}\n", name, value.trim()))
+        .collect();
+    let signed_headers = headers.iter().map(|(name, _)| *name).collect::<Vec<_>>().join(";");
+    let canonical_request =
+        format!("PUT\n{}\n\n{}\n{}\n{}", path, canonical_headers, signed_headers, payload_hash);
+    let scope = format!("{}/{}/s3/aws4_request", date, region);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        timestamp,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+    let signing_key = [date.as_str(), region, "s3", "aws4_request"]
+        .iter()
+        .fold(format!("AWS4{}", secret_key).into_bytes(), |key, part| {
+            hmac_sha256(&key, part.as_bytes())
+        });
+    let signature = hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        access_key, scope, signed_headers, signature
+    );
+
+    // reqwest sets Host from the URL
+    let mut request = http.put(&object_url).header(AUTHORIZATION, authorization).body(png);
+    for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+        request = request.header(name, value);
+    }
+    send(request).await?;
+
+    Ok(match &options.public_url {
+        Some(base) => format!("{}/{}", base.trim_end_matches('/'), key),
+        None => object_url,
+    })
+}
+
+/// Posts the exchange to the webhook and returns the link it answers with.
+async fn webhook(
+    http: &reqwest::Client,
+    options: &WebhookOptions,
+    name: &str,
+    png: &[u8],
+    share: &Share<'_>,
+) -> Result<String> {
+    let body = serde_json::json!({
+        "fileName": name,
+        "image": {
+            "mimeType": ImageEncoding::Png.mime_type(),
+            "width": share.image.width(),
+            "height": share.image.height(),
+            "data": BASE64.encode(png),
+        },
+        "prompt": share.prompt,
+        "model": share.model,
+        "answer": share.answer,
+    });
+    let mut request = http.post(&options.url).json(&body);
+    for (name, value) in &options.headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+    let body = send(request).await?;
+
+    let link = match serde_json::from_str::<Value>(&body) {
+        Ok(json) => {
+            let field = options.response_field.as_deref().unwrap_or("url");
+            let pointer = format!("/{}", field.replace('.', "/"));
+            json.pointer(&pointer)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .ok_or_else(|| {
+                    AppError::upload(format!("The webhook's response has no '{}' field", field))
+                })?
+        }
+        Err(_) => body.trim().to_string(),
+    };
+    if link.is_empty() {
+        return Err(AppError::upload("The webhook answered without a link"));
+    }
+    Ok(link)
+}
+
+/// Sends a request and returns the response body, failing on an error
+/// status.
+async fn send(request: reqwest::RequestBuilder) -> Result<String> {
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::upload(format!("Request failed: {}", e)))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| AppError::upload(format!("Failed to read the response: {}", e)))?;
+    if !status.is_success() {
+        let body: String = body.trim().chars().take(MAX_ERROR_BODY).collect();
+        return Err(AppError::upload(format!("{}: {}", status, body)));
+    }
+    Ok(body)
+}
+
+/// Percent-encodes an object key for the request path, keeping `/`.
+fn encode_key(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Returns the HMAC-SHA256 of `data` under `key`.
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
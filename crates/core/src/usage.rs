@@ -0,0 +1,380 @@
+//! Usage statistics and the monthly budget.
+//!
+//! Every answered request appends one JSON line to `usage.jsonl` in the
+//! user's data directory with the model and the tokens the API reported,
+//! whether or not the answer is kept in the history. [`UsageLog::summary`]
+//! adds them up per model, with the spend estimated from
+//! [`Pricing`], a built-in table of list prices (`ai-shot stats`, and the
+//! Usage tab of the settings).
+//!
+//! A `[usage]` table in `config.toml` sets a monthly budget in US dollars.
+//! Reaching 80% of it warns after each answer; with `hard_limit`, requests
+//! are refused once it is spent, until the next month starts:
+//!
+//! ```toml
+//! [usage]
+//! monthly_budget = 20.0
+//! hard_limit = true
+//! ```
+//!
+//! Estimates only count models with a known price and ignore free tiers,
+//! cached tokens and long-context surcharges, so the bill may differ.
+//!
+//! # Example
+//!
+//! ```ignore
+//! use ai_shot_core::usage::{self, UsageLog};
+//!
+//! usage::enforce_budget(&config.budget)?;
+//! // ... send the request ...
+//! usage::record(&analysis.model, analysis.input_tokens, analysis.output_tokens)?;
+//!
+//! let log = UsageLog::open_default().expect("no data directory");
+//! for model in log.summary(usage::month_start())? {
+//!     println!("{}: {} requests", model.model, model.requests);
+//! }
+//! ```
+
+use crate::error::{AppError, Result};
+use crate::paths;
+use chrono::{Datelike, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Share of the monthly budget at which answers start to warn.
+const WARN_AT: f64 = 0.8;
+
+/// List prices in US dollars per million input and output tokens, by model
+/// name prefix; the first matching entry applies, so more specific
+/// prefixes come first.
+const PRICES: &[(&str, Pricing)] = &[
+    ("gemini-2.5-flash-image", Pricing::new(0.30, 30.0)),
+    ("gemini-2.5-flash-lite", Pricing::new(0.10, 0.40)),
+    ("gemini-flash-lite", Pricing::new(0.10, 0.40)),
+    ("gemini-2.5-flash", Pricing::new(0.30, 2.50)),
+    ("gemini-flash", Pricing::new(0.30, 2.50)),
+    ("gemini-2.5-pro", Pricing::new(1.25, 10.0)),
+    ("gemini-3-pro", Pricing::new(2.0, 12.0)),
+    ("gemini-2.0-flash-lite", Pricing::new(0.075, 0.30)),
+    ("gemini-2.0-flash", Pricing::new(0.10, 0.40)),
+    ("gemini-1.5-pro", Pricing::new(1.25, 5.0)),
+    ("gemini-1.5-flash", Pricing::new(0.075, 0.30)),
+    ("gemma-", Pricing::new(0.0, 0.0)),
+];
+
+/// Price of a model's tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pricing {
+    /// US dollars per million input tokens.
+    pub input: f64,
+    /// US dollars per million output tokens, including thinking.
+    pub output: f64,
+}
+
+impl Pricing {
+    const fn new(input: f64, output: f64) -> Self {
+        Self { input, output }
+    }
+
+    /// Looks up a model's list price, or returns `None` for models not in
+    /// the table.
+    ///
+    /// A `models/` prefix and surrounding whitespace are ignored.
+    pub fn of(model: &str) -> Option<Self> {
+        let model = model.trim().trim_start_matches("models/").to_lowercase();
+        PRICES
+            .iter()
+            .find(|(prefix, _)| model.starts_with(prefix))
+            .map(|(_, pricing)| *pricing)
+    }
+
+    /// Returns the price of the tokens, in US dollars.
+    pub fn cost(&self, input_tokens: u64, output_tokens: u64) -> f64 {
+        (input_tokens as f64 * self.input + output_tokens as f64 * self.output) / 1_000_000.0
+    }
+}
+
+/// The `[usage]` table.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Budget {
+    /// Estimated spend per calendar month, in US dollars, if limited.
+    pub monthly: Option<f64>,
+    /// Refuse requests once the monthly budget is spent.
+    pub hard_limit: bool,
+}
+
+/// How this month's estimated spend compares to the budget.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BudgetStatus {
+    /// No budget is set.
+    Unlimited,
+    /// Less than 80% of the budget is spent.
+    Within {
+        /// Estimated spend this month, in US dollars.
+        spent: f64,
+        /// The monthly budget.
+        budget: f64,
+    },
+    /// At least 80% of the budget is spent.
+    Near {
+        /// Estimated spend this month, in US dollars.
+        spent: f64,
+        /// The monthly budget.
+        budget: f64,
+    },
+    /// The budget is spent.
+    Exceeded {
+        /// Estimated spend this month, in US dollars.
+        spent: f64,
+        /// The monthly budget.
+        budget: f64,
+    },
+}
+
+impl BudgetStatus {
+    /// Returns the estimated spend and the budget, if a budget is set.
+    pub fn spent(&self) -> Option<(f64, f64)> {
+        match *self {
+            Self::Unlimited => None,
+            Self::Within { spent, budget }
+            | Self::Near { spent, budget }
+            | Self::Exceeded { spent, budget } => Some((spent, budget)),
+        }
+    }
+
+    /// Returns a warning to show after an answer, if the budget is nearly
+    /// or fully spent.
+    pub fn warning(&self) -> Option<String> {
+        match *self {
+            Self::Unlimited | Self::Within { .. } => None,
+            Self::Near { spent, budget } => Some(format!(
+                "About ${:.2} of the ${:.2} monthly budget is spent",
+                spent, budget
+            )),
+            Self::Exceeded { spent, budget } => Some(format!(
+                "The ${:.2} monthly budget is spent (about ${:.2} this month)",
+                budget, spent
+            )),
+        }
+    }
+}
+
+/// One answered request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    /// Unix timestamp (seconds) of the answer.
+    pub time: u64,
+    /// Model that answered.
+    pub model: String,
+    /// Input (prompt and image) tokens.
+    pub input_tokens: u32,
+    /// Output tokens, including thinking.
+    pub output_tokens: u32,
+}
+
+/// Requests and tokens of one model over a period.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelUsage {
+    /// The model.
+    pub model: String,
+    /// Number of answered requests.
+    pub requests: u64,
+    /// Input tokens of all requests.
+    pub input_tokens: u64,
+    /// Output tokens of all requests.
+    pub output_tokens: u64,
+    /// Estimated spend in US dollars, or `None` if the model's price is
+    /// unknown.
+    pub cost: Option<f64>,
+}
+
+/// The usage log in the data directory.
+pub struct UsageLog {
+    /// Path of the JSONL log.
+    path: PathBuf,
+}
+
+impl UsageLog {
+    /// Opens the log in the default data directory.
+    ///
+    /// Returns `None` if the platform data directory cannot be determined.
+    pub fn open_default() -> Option<Self> {
+        paths::usage_file().map(Self::at)
+    }
+
+    /// Opens the log at a specific path.
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Appends a record.
+    ///
+    /// # Errors
+    /// Returns an error if the log cannot be written.
+    pub fn record(&self, record: &UsageRecord) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the records answered at or after `since` (a Unix timestamp),
+    /// oldest first.
+    ///
+    /// Lines that cannot be parsed (e.g. cut short by a crash) are skipped.
+    ///
+    /// # Errors
+    /// Returns an error if the log exists but cannot be read.
+    pub fn records_since(&self, since: u64) -> Result<Vec<UsageRecord>> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<UsageRecord>(line).ok())
+            .filter(|record| record.time >= since)
+            .collect())
+    }
+
+    /// Adds up the records since `since` per model, most requests first.
+    ///
+    /// # Errors
+    /// Returns an error if the log exists but cannot be read.
+    pub fn summary(&self, since: u64) -> Result<Vec<ModelUsage>> {
+        let mut models: Vec<ModelUsage> = Vec::new();
+        for record in self.records_since(since)? {
+            let index = match models.iter().position(|usage| usage.model == record.model) {
+                Some(index) => index,
+                None => {
+                    models.push(ModelUsage {
+                        model: record.model.clone(),
+                        ..ModelUsage::default()
+                    });
+                    models.len() - 1
+                }
+            };
+            let usage = &mut models[index];
+            usage.requests += 1;
+            usage.input_tokens += u64::from(record.input_tokens);
+            usage.output_tokens += u64::from(record.output_tokens);
+        }
+        for usage in &mut models {
+            usage.cost = Pricing::of(&usage.model)
+                .map(|pricing| pricing.cost(usage.input_tokens, usage.output_tokens));
+        }
+        models.sort_by(|a, b| b.requests.cmp(&a.requests).then_with(|| a.model.cmp(&b.model)));
+        Ok(models)
+    }
+
+    /// Returns the estimated spend since the start of the month, in US
+    /// dollars.
+    ///
+    /// # Errors
+    /// Returns an error if the log exists but cannot be read.
+    pub fn spent_this_month(&self) -> Result<f64> {
+        Ok(self
+            .summary(month_start())?
+            .iter()
+            .filter_map(|usage| usage.cost)
+            .sum())
+    }
+
+    /// Compares this month's estimated spend to `budget`.
+    ///
+    /// # Errors
+    /// Returns an error if the log exists but cannot be read.
+    pub fn budget_status(&self, budget: &Budget) -> Result<BudgetStatus> {
+        let Some(limit) = budget.monthly else {
+            return Ok(BudgetStatus::Unlimited);
+        };
+        let spent = self.spent_this_month()?;
+        Ok(if spent >= limit {
+            BudgetStatus::Exceeded { spent, budget: limit }
+        } else if spent >= limit * WARN_AT {
+            BudgetStatus::Near { spent, budget: limit }
+        } else {
+            BudgetStatus::Within { spent, budget: limit }
+        })
+    }
+}
+
+/// Adds a request to the usage log in the default data directory.
+///
+/// Requests without reported tokens (e.g. cancelled ones) are left out.
+///
+/// # Errors
+/// Returns an error if the log cannot be written.
+pub fn record(model: &str, input_tokens: u32, output_tokens: u32) -> Result<()> {
+    if input_tokens == 0 && output_tokens == 0 {
+        return Ok(());
+    }
+    let Some(log) = UsageLog::open_default() else {
+        return Ok(());
+    };
+    log.record(&UsageRecord {
+        time: now(),
+        model: model.to_string(),
+        input_tokens,
+        output_tokens,
+    })
+}
+
+/// Returns how this month's spend compares to `budget`.
+///
+/// # Errors
+/// Returns an error if the usage log exists but cannot be read.
+pub fn budget_status(budget: &Budget) -> Result<BudgetStatus> {
+    match UsageLog::open_default() {
+        Some(log) if budget.monthly.is_some() => log.budget_status(budget),
+        _ => Ok(BudgetStatus::Unlimited),
+    }
+}
+
+/// Refuses a new request if `budget` has a hard limit that is spent.
+///
+/// # Errors
+/// Returns [`AppError::Budget`] if the budget is spent, or an error if the
+/// usage log cannot be read.
+pub fn enforce_budget(budget: &Budget) -> Result<()> {
+    if !budget.hard_limit {
+        return Ok(());
+    }
+    match budget_status(budget)? {
+        BudgetStatus::Exceeded { spent, budget } => Err(AppError::budget(format!(
+            "The ${:.2} monthly budget is spent (about ${:.2}); raise [usage] monthly_budget \
+             or wait for next month",
+            budget, spent
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Returns the Unix timestamp of the start of the current month, in local
+/// time.
+pub fn month_start() -> u64 {
+    let today = Local::now().date_naive();
+    today
+        .with_day(1)
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .and_then(|start| start.and_local_timezone(Local).earliest())
+        .map_or(0, |start| start.timestamp().max(0) as u64)
+}
+
+/// Returns the current Unix timestamp.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -0,0 +1,120 @@
+//! Reloading the configuration when its files change.
+//!
+//! [`Config::subscribe`] starts a background thread that watches
+//! `config.toml`, `profiles.toml` and `settings.json` and sends a rebuilt
+//! [`Config`] after each change, keeping the values that were set on the
+//! builder (command-line flags). Changes to `settings.json` are reported too,
+//! so holders of UI [`Settings`](crate::ui::Settings) know to reload them.
+//!
+//! The files are polled rather than watched through OS notifications, which
+//! also catches editors that replace a file instead of writing it in place.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let updates = config.subscribe()?;
+//! std::thread::spawn(move || {
+//!     for config in updates {
+//!         println!("Now using {}", config.model_name);
+//!     }
+//! });
+//! ```
+
+use crate::config::{Config, ConfigBuilder};
+use crate::error::{AppError, Result};
+use crate::profiles::Profiles;
+use crate::ui::Settings;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// How often the files are checked for changes.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Stream of configurations rebuilt after the files changed.
+///
+/// Iterating blocks until the next change. Dropping the stream stops
+/// watching.
+pub struct ConfigUpdates {
+    receiver: Receiver<Config>,
+    stop: Arc<AtomicBool>,
+}
+
+impl ConfigUpdates {
+    /// Starts watching, rebuilding from `builder` on every change.
+    pub(crate) fn start(builder: ConfigBuilder) -> Result<Self> {
+        let (sender, receiver) = channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+
+        thread::Builder::new()
+            .name("ai-shot-config-watch".to_string())
+            .spawn(move || {
+                let mut seen = snapshot();
+                while !stopped.load(Ordering::Relaxed) {
+                    thread::sleep(POLL_INTERVAL);
+                    let current = snapshot();
+                    if current == seen {
+                        continue;
+                    }
+                    seen = current;
+
+                    match builder.clone().build() {
+                        Ok(config) => {
+                            if sender.send(config).is_err() {
+                                break;
+                            }
+                        }
+                        // Keep the last good configuration until the file is fixed
+                        Err(e) => tracing::warn!(error = %e, "ignoring invalid configuration change"),
+                    }
+                }
+            })
+            .map_err(|e| AppError::config(format!("Failed to start config watcher: {}", e)))?;
+
+        Ok(Self { receiver, stop })
+    }
+
+    /// Returns the newest configuration since the last call, if the files
+    /// changed, without blocking.
+    pub fn try_latest(&self) -> Option<Config> {
+        self.receiver.try_iter().last()
+    }
+}
+
+impl Iterator for ConfigUpdates {
+    type Item = Config;
+
+    fn next(&mut self) -> Option<Config> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl Drop for ConfigUpdates {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Files the configuration is read from.
+fn watched_files() -> Vec<PathBuf> {
+    [Config::file_path(), Profiles::path(), Settings::path()]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Modification time and size of each watched file (`None` if missing).
+fn snapshot() -> Vec<Option<(SystemTime, u64)>> {
+    watched_files()
+        .iter()
+        .map(|path| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
@@ -0,0 +1,144 @@
+//! Property tests for UI-to-image coordinate mapping.
+//!
+//! These generate arbitrary selections, UI sizes, and HiDPI scale factors and
+//! check the invariants the crop pipeline relies on.
+
+use ai_shot_core::image_processing::ImageProcessor;
+use ai_shot_core::AppError;
+use eframe::egui;
+use image::{DynamicImage, RgbaImage};
+use proptest::prelude::*;
+
+/// Common display scale factors, plus fractional ones seen on Linux.
+fn scale_factor() -> impl Strategy<Value = f32> {
+    prop_oneof![
+        Just(1.0f32),
+        Just(1.25),
+        Just(1.5),
+        Just(2.0),
+        Just(3.0),
+        0.5f32..4.0,
+    ]
+}
+
+/// A UI size plus a selection that may extend past the screen edges.
+fn ui_and_selection() -> impl Strategy<Value = (egui::Vec2, egui::Pos2, egui::Pos2)> {
+    (100.0f32..4000.0, 100.0f32..3000.0).prop_flat_map(|(w, h)| {
+        (
+            Just(egui::vec2(w, h)),
+            (-50.0f32..w + 50.0, -50.0f32..h + 50.0).prop_map(|(x, y)| egui::pos2(x, y)),
+            (-50.0f32..w + 50.0, -50.0f32..h + 50.0).prop_map(|(x, y)| egui::pos2(x, y)),
+        )
+    })
+}
+
+proptest! {
+    #[test]
+    fn mapped_region_is_within_image_bounds(
+        (ui_size, start, end) in ui_and_selection(),
+        scale in scale_factor(),
+    ) {
+        let image_width = (ui_size.x * scale).round() as u32;
+        let image_height = (ui_size.y * scale).round() as u32;
+        let selection = egui::Rect::from_two_pos(start, end);
+
+        if let Ok(region) = ImageProcessor::map_selection(selection, ui_size, image_width, image_height) {
+            prop_assert!(region.width > 0 && region.height > 0);
+            prop_assert!(region.x + region.width <= image_width);
+            prop_assert!(region.y + region.height <= image_height);
+        }
+    }
+
+    #[test]
+    fn valid_inner_selection_is_non_empty_and_inverse_mappable(
+        (ui_size, start, end) in ui_and_selection(),
+        scale in scale_factor(),
+    ) {
+        let screen = egui::Rect::from_min_size(egui::Pos2::ZERO, ui_size);
+        let selection = egui::Rect::from_two_pos(start, end).intersect(screen);
+        // Only selections spanning at least two image pixels each way are expected to map
+        prop_assume!(selection.width() * scale >= 2.0 && selection.height() * scale >= 2.0);
+
+        let image_width = (ui_size.x * scale).round() as u32;
+        let image_height = (ui_size.y * scale).round() as u32;
+        let scale_x = image_width as f32 / ui_size.x;
+        let scale_y = image_height as f32 / ui_size.y;
+
+        let region = ImageProcessor::map_selection(selection, ui_size, image_width, image_height);
+        prop_assert!(region.is_ok(), "valid selection {:?} mapped to {:?}", selection, region);
+        let region = region.unwrap();
+
+        // Mapping back to UI space must land within two image pixels of the selection
+        let tolerance_x = 2.0 / scale_x + 1e-3;
+        let tolerance_y = 2.0 / scale_y + 1e-3;
+        let back = egui::Rect::from_min_size(
+            egui::pos2(region.x as f32 / scale_x, region.y as f32 / scale_y),
+            egui::vec2(region.width as f32 / scale_x, region.height as f32 / scale_y),
+        );
+        prop_assert!((back.min.x - selection.min.x).abs() <= tolerance_x);
+        prop_assert!((back.min.y - selection.min.y).abs() <= tolerance_y);
+        prop_assert!((back.max.x - selection.max.x).abs() <= tolerance_x);
+        prop_assert!((back.max.y - selection.max.y).abs() <= tolerance_y);
+    }
+
+    #[test]
+    fn selection_direction_does_not_matter(
+        (ui_size, start, end) in ui_and_selection(),
+        scale in scale_factor(),
+    ) {
+        let image_width = (ui_size.x * scale).round() as u32;
+        let image_height = (ui_size.y * scale).round() as u32;
+
+        let forward = ImageProcessor::map_selection(
+            egui::Rect::from_two_pos(start, end), ui_size, image_width, image_height,
+        );
+        let backward = ImageProcessor::map_selection(
+            egui::Rect::from_two_pos(end, start), ui_size, image_width, image_height,
+        );
+        prop_assert_eq!(forward.ok(), backward.ok());
+    }
+
+    #[test]
+    fn degenerate_selection_is_rejected(
+        (ui_size, start, _) in ui_and_selection(),
+        scale in scale_factor(),
+    ) {
+        let image_width = (ui_size.x * scale).round() as u32;
+        let image_height = (ui_size.y * scale).round() as u32;
+        let selection = egui::Rect::from_two_pos(start, start);
+
+        let result = ImageProcessor::map_selection(selection, ui_size, image_width, image_height);
+        prop_assert!(matches!(result, Err(AppError::EmptySelection)));
+    }
+
+    #[test]
+    fn crop_matches_mapped_region(
+        width in 1u32..64,
+        height in 1u32..64,
+        scale in scale_factor(),
+        (x0, y0, x1, y1) in (0.0f32..1.0, 0.0f32..1.0, 0.0f32..1.0, 0.0f32..1.0),
+    ) {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(width, height));
+        let ui_size = egui::vec2(width as f32 / scale, height as f32 / scale);
+        let selection = egui::Rect::from_two_pos(
+            egui::pos2(x0 * ui_size.x, y0 * ui_size.y),
+            egui::pos2(x1 * ui_size.x, y1 * ui_size.y),
+        );
+
+        let region = ImageProcessor::map_selection(selection, ui_size, width, height);
+        let cropped = ImageProcessor::crop_selection(&image, selection, ui_size);
+        match (region, cropped) {
+            (Ok(region), Ok(cropped)) => {
+                prop_assert_eq!(cropped.width(), region.width);
+                prop_assert_eq!(cropped.height(), region.height);
+            }
+            (Err(_), Err(_)) => {}
+            (region, cropped) => prop_assert!(
+                false,
+                "mapping and crop disagree: {:?} vs {:?}",
+                region,
+                cropped.map(|c| (c.width(), c.height()))
+            ),
+        }
+    }
+}
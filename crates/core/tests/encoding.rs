@@ -0,0 +1,141 @@
+//! Tests that encoded uploads carry no metadata and are byte-for-byte
+//! reproducible.
+
+use ai_shot_core::image_processing::{ImageEncoding, ImageProcessor};
+use image::{DynamicImage, Rgba, RgbaImage};
+
+const ENCODINGS: [ImageEncoding; 4] = [
+    ImageEncoding::Jpeg { quality: 75 },
+    ImageEncoding::Jpeg { quality: 95 },
+    ImageEncoding::Png,
+    ImageEncoding::WebP,
+];
+
+/// A screenshot-like image: a gradient with a sharp-edged box.
+fn sample(alpha: u8) -> DynamicImage {
+    let image = RgbaImage::from_fn(97, 61, |x, y| {
+        if (20..40).contains(&x) && (10..30).contains(&y) {
+            Rgba([20, 20, 20, alpha])
+        } else {
+            Rgba([(x * 2) as u8, (y * 4) as u8, 180, alpha])
+        }
+    });
+    DynamicImage::ImageRgba8(image)
+}
+
+/// Returns the JPEG segment markers before the image data.
+fn jpeg_markers(bytes: &[u8]) -> Vec<u8> {
+    assert_eq!(&bytes[..2], &[0xFF, 0xD8], "missing JPEG start marker");
+    let mut markers = Vec::new();
+    let mut at = 2;
+    while at + 4 <= bytes.len() {
+        assert_eq!(bytes[at], 0xFF, "malformed JPEG segment");
+        let marker = bytes[at + 1];
+        markers.push(marker);
+        if marker == 0xDA {
+            break;
+        }
+        let length = u16::from_be_bytes([bytes[at + 2], bytes[at + 3]]) as usize;
+        at += 2 + length;
+    }
+    markers
+}
+
+/// Returns the PNG chunk types in order.
+fn png_chunks(bytes: &[u8]) -> Vec<String> {
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1a\n", "missing PNG signature");
+    let mut chunks = Vec::new();
+    let mut at = 8;
+    while at + 8 <= bytes.len() {
+        let length = u32::from_be_bytes(bytes[at..at + 4].try_into().unwrap()) as usize;
+        chunks.push(String::from_utf8_lossy(&bytes[at + 4..at + 8]).into_owned());
+        at += 12 + length;
+    }
+    chunks
+}
+
+#[test]
+fn identical_images_encode_to_identical_bytes() {
+    for encoding in ENCODINGS {
+        let first = ImageProcessor::encode(&sample(255), encoding).unwrap();
+        let second = ImageProcessor::encode(&sample(255), encoding).unwrap();
+        assert_eq!(first, second, "{} output differs between runs", encoding);
+    }
+}
+
+#[test]
+fn pixel_layout_does_not_change_the_bytes() {
+    // An opaque RGBA capture and the same pixels as RGB are the same upload
+    let rgba = sample(255);
+    let rgb = DynamicImage::ImageRgb8(rgba.to_rgb8());
+    for encoding in ENCODINGS {
+        assert_eq!(
+            ImageProcessor::encode(&rgba, encoding).unwrap(),
+            ImageProcessor::encode(&rgb, encoding).unwrap(),
+            "{} output depends on the pixel layout",
+            encoding
+        );
+    }
+}
+
+#[test]
+fn base64_encoding_is_deterministic() {
+    let image = sample(255);
+    for encoding in ENCODINGS {
+        assert_eq!(
+            ImageProcessor::encode_to_base64(&image, encoding).unwrap(),
+            ImageProcessor::encode_to_base64(&image, encoding).unwrap()
+        );
+    }
+}
+
+#[test]
+fn jpeg_has_no_metadata_segments() {
+    let bytes = ImageProcessor::encode(&sample(255), ImageEncoding::default()).unwrap();
+    for marker in jpeg_markers(&bytes) {
+        // APP1 to APP15 hold EXIF, XMP and ICC profiles; 0xFE is a comment
+        assert!(
+            !(0xE1..=0xEF).contains(&marker) && marker != 0xFE,
+            "unexpected JPEG segment 0x{:02X}",
+            marker
+        );
+    }
+}
+
+#[test]
+fn png_has_only_image_chunks() {
+    for alpha in [255, 128] {
+        let bytes = ImageProcessor::encode(&sample(alpha), ImageEncoding::Png).unwrap();
+        for chunk in png_chunks(&bytes) {
+            assert!(
+                ["IHDR", "IDAT", "IEND"].contains(&chunk.as_str()),
+                "unexpected PNG chunk {}",
+                chunk
+            );
+        }
+    }
+}
+
+#[test]
+fn webp_has_no_metadata_chunks() {
+    let bytes = ImageProcessor::encode(&sample(128), ImageEncoding::WebP).unwrap();
+    assert_eq!(&bytes[..4], b"RIFF");
+    assert_eq!(&bytes[8..12], b"WEBP");
+    for name in [b"EXIF", b"XMP ", b"ICCP"] {
+        assert!(
+            !bytes.windows(4).any(|window| window == name),
+            "unexpected WebP chunk {}",
+            String::from_utf8_lossy(name)
+        );
+    }
+}
+
+#[test]
+fn transparency_survives_lossless_encodings() {
+    let image = sample(128);
+    for encoding in [ImageEncoding::Png, ImageEncoding::WebP] {
+        let bytes = ImageProcessor::encode(&image, encoding).unwrap();
+        let decoded = image::load_from_memory(&bytes).unwrap();
+        assert_eq!(decoded.to_rgba8(), image.to_rgba8(), "{} is not lossless", encoding);
+    }
+}
@@ -0,0 +1,89 @@
+//! Tests that HTML exports never carry anything from the answer that runs or
+//! loads when the file is opened.
+
+use ai_shot_core::export::{Conversation, ExportFormat};
+use std::fs;
+
+/// Exports `answer` as HTML and returns the written document.
+fn export_html(name: &str, answer: &str) -> String {
+    let path = std::env::temp_dir().join(format!(
+        "ai-shot-export-{}-{}.html",
+        name,
+        std::process::id()
+    ));
+    let conversation = Conversation {
+        prompt: "Explain this",
+        model: "test-model",
+        answer,
+        thoughts: "",
+        image: None,
+    };
+    conversation
+        .export(&path, ExportFormat::Html)
+        .expect("export failed");
+    let html = fs::read_to_string(&path).expect("export not written");
+    let _ = fs::remove_file(&path);
+    html
+}
+
+#[test]
+fn raw_html_is_escaped() {
+    let html = export_html(
+        "script",
+        "Before\n\n<script>alert(1)</script>\n\nand <b onclick=\"x()\">inline</b>",
+    );
+    assert!(
+        !html.contains("<script>"),
+        "script tag passed through: {html}"
+    );
+    assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+    assert!(
+        !html.contains("<b onclick"),
+        "inline HTML passed through: {html}"
+    );
+}
+
+#[test]
+fn unsafe_link_targets_are_dropped() {
+    for (name, target) in [
+        ("javascript", "javascript:alert(1)"),
+        ("upper", "JavaScript:alert(1)"),
+        ("entity", "javascript&#58;alert(1)"),
+        ("data", "data:text/html;base64,PHNjcmlwdD4="),
+        ("vbscript", "vbscript:msgbox(1)"),
+    ] {
+        let html = export_html(name, &format!("[click]({target})"));
+        assert!(
+            html.contains("<a href=\"#\">click</a>"),
+            "{target} was kept: {html}"
+        );
+    }
+}
+
+#[test]
+fn safe_link_targets_are_kept() {
+    for (name, target) in [
+        ("https", "https://example.com/a?b=c"),
+        ("http", "http://example.com"),
+        ("mailto", "mailto:someone@example.com"),
+        ("fragment", "#section"),
+        ("relative", "docs/page.html"),
+    ] {
+        let html = export_html(name, &format!("[click]({target})"));
+        assert!(
+            html.contains(&format!("href=\"{target}\"")),
+            "{target} was dropped: {html}"
+        );
+    }
+}
+
+#[test]
+fn answer_images_become_alt_text() {
+    let html = export_html(
+        "image",
+        "See ![a tracking pixel](https://example.com/pixel.png)",
+    );
+    assert!(!html.contains("<img"), "image passed through: {html}");
+    assert!(!html.contains("example.com"), "remote URL kept: {html}");
+    assert!(html.contains("a tracking pixel"));
+}